@@ -302,8 +302,11 @@ After=graphical-session.target
 Wants=graphical-session.target
 
 [Service]
-Type=simple
+Type=notify
+NotifyAccess=main
 ExecStart=$INSTALL_DIR/swwws-daemon
+ExecReload=/bin/kill -HUP \$MAINPID
+WatchdogSec=60
 Restart=on-failure
 RestartSec=5
 Environment=RUST_LOG=info
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use swwws_common::{CommandBuilder, ErrorReporting, IpcClient, IpcCommand, IpcResponse, ProcessExecutor, SocketLocation};
+use swwws_config::check::{check_config, Severity};
+use swwws_config::Config;
+
+/// Counts what `doctor` found, printing each finding as it goes
+#[derive(Default)]
+struct Doctor {
+    failed: usize,
+    warned: usize,
+}
+
+impl Doctor {
+    fn heading(&self, title: &str) {
+        println!("{}", title);
+    }
+
+    fn pass(&mut self, message: impl AsRef<str>) {
+        println!("  ✓ {}", message.as_ref());
+    }
+
+    fn warn(&mut self, message: impl AsRef<str>, hint: impl AsRef<str>) {
+        self.warned += 1;
+        println!("  ⚠ {}", message.as_ref());
+        println!("    → {}", hint.as_ref());
+    }
+
+    fn fail(&mut self, message: impl AsRef<str>, hint: impl AsRef<str>) {
+        self.failed += 1;
+        println!("  ✗ {}", message.as_ref());
+        println!("    → {}", hint.as_ref());
+    }
+}
+
+/// Check the setup swwws needs, from the config file to the running daemons,
+/// in the order a problem in one would cause the next to fail. Returns whether
+/// every check passed, warnings aside.
+pub fn run(config: Option<PathBuf>) -> bool {
+    let mut doctor = Doctor::default();
+
+    let loaded = check_config_file(&mut doctor, config);
+    let swww = check_swww(&mut doctor, loaded.as_ref());
+    if let Some(swww) = swww {
+        check_swww_daemon(&mut doctor, swww);
+    }
+    check_swwws_daemon(&mut doctor);
+    check_environment(&mut doctor);
+
+    println!();
+    println!("{} failed, {} warnings", doctor.failed, doctor.warned);
+    doctor.failed == 0
+}
+
+/// The config file and the wallpaper paths it names. Returns the config if it loaded.
+fn check_config_file(doctor: &mut Doctor, path: Option<PathBuf>) -> Option<Config> {
+    doctor.heading("Config");
+    let path = match path.map_or_else(Config::config_path, Ok) {
+        Ok(path) => path,
+        Err(e) => {
+            doctor.fail(e.user_friendly_message(), "Pass the config with --config, or set SWWWS_CONFIG");
+            return None;
+        }
+    };
+    if !path.exists() {
+        doctor.fail(format!("No config file at {:?}", path), "Write a starter config with `swwws-cli init`");
+        return None;
+    }
+
+    let report = check_config(&path);
+    let mut sections = report.sections.iter().peekable();
+    // Parsing and validation come first, under "file"
+    if let Some((_, findings)) = sections.next_if(|(section, _)| section == "file") {
+        for finding in findings {
+            match finding.severity {
+                Severity::Info => doctor.pass(&finding.message),
+                Severity::Warning => doctor.warn(&finding.message, "Fine to run with, but probably not what you meant"),
+                Severity::Error => doctor.fail(&finding.message, format!("Fix {:?}, then run `swwws-cli check-config`", path)),
+            }
+        }
+    }
+    if report.has_errors() && sections.peek().is_none() {
+        return None;
+    }
+
+    doctor.heading("Wallpaper paths");
+    for (section, findings) in sections {
+        for finding in findings {
+            let message = format!("[{}] {}", section, finding.message);
+            match finding.severity {
+                Severity::Info => doctor.pass(message),
+                Severity::Warning => doctor.warn(message, format!("See the [{}] section of the config", section)),
+                Severity::Error => doctor.fail(message, format!("Point `path` in [{}] at a directory of images", section)),
+            }
+        }
+    }
+    Config::load_from(&path).ok()
+}
+
+/// The swww binary, and its version. Returns its path if it runs.
+fn check_swww(doctor: &mut Doctor, config: Option<&Config>) -> Option<PathBuf> {
+    doctor.heading("swww");
+    let configured = config.and_then(|config| config.global.swww_binary.as_deref()).map(Path::new);
+    let swww = match ProcessExecutor::find_swww(configured) {
+        Ok(swww) => swww,
+        Err(e) => {
+            doctor.fail(e.user_friendly_message(), "Install swww, or set swww_binary in [global] to where it is");
+            return None;
+        }
+    };
+
+    match Command::new(&swww).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            doctor.pass(format!("{} ({})", swww.display(), version));
+            Some(swww)
+        }
+        Ok(output) => {
+            doctor.fail(
+                format!("`{} --version` failed: {}", swww.display(), String::from_utf8_lossy(&output.stderr).trim()),
+                "Reinstall swww, or set swww_binary in [global] to a working one",
+            );
+            None
+        }
+        Err(e) => {
+            doctor.fail(format!("Can't run {}: {}", swww.display(), e), "Check that swww_binary points at the swww executable");
+            None
+        }
+    }
+}
+
+/// swww-daemon, through `swww query`
+fn check_swww_daemon(doctor: &mut Doctor, swww: PathBuf) {
+    doctor.heading("swww-daemon");
+    match ProcessExecutor::new(CommandBuilder::new(swww)).query_swww_outputs() {
+        Ok(outputs) => {
+            let outputs: Vec<String> = outputs.iter()
+                .map(|output| match output.size {
+                    Some((width, height)) => format!("{} ({}x{})", output.name, width, height),
+                    None => output.name.clone(),
+                })
+                .collect();
+            doctor.pass(format!("Answers `swww query` with {}", outputs.join(", ")));
+        }
+        Err(e) => doctor.fail(
+            format!("`swww query` failed: {}", e.user_friendly_message()),
+            "Start swww-daemon from your compositor's startup, in the same session as swwws",
+        ),
+    }
+}
+
+/// The swwws daemon, through its IPC socket
+fn check_swwws_daemon(doctor: &mut Doctor) {
+    doctor.heading("swwws daemon");
+    let client = IpcClient::new();
+    match client.send_command(IpcCommand::Status) {
+        Ok(IpcResponse::Status { outputs, paused, .. }) => {
+            let managed = outputs.iter().filter(|output| !output.disabled).count();
+            doctor.pass(format!("Answers on {:?}: {} outputs managed{}",
+                client.socket_path(), managed, if paused { ", paused" } else { "" }));
+        }
+        Ok(IpcResponse::Error { message, .. }) => doctor.fail(message, "See the daemon's log"),
+        Ok(_) => doctor.fail("Unexpected answer to a status request", "Restart swwws-daemon so it matches swwws-cli"),
+        Err(e) => doctor.fail(
+            format!("{:#}", e),
+            "Start it with `swwws-cli daemon --detach`, or through your service manager",
+        ),
+    }
+}
+
+fn check_environment(doctor: &mut Doctor) {
+    doctor.heading("Environment");
+    match std::env::var("WAYLAND_DISPLAY").ok().filter(|display| !display.is_empty()) {
+        Some(display) => doctor.pass(format!("WAYLAND_DISPLAY={}", display)),
+        None => doctor.fail(
+            "WAYLAND_DISPLAY is not set; swww and the socket name both fall back to wayland-0",
+            "Run from your Wayland session; for a systemd unit, run `systemctl --user import-environment WAYLAND_DISPLAY` at login",
+        ),
+    }
+    // SWWWS_SOCKET skips the runtime dir, so ask without it
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    let location = SocketLocation::resolve(runtime_dir.clone(), None, Path::new("/tmp"), 0);
+    match (location.fallback_reason, runtime_dir) {
+        (None, Some(dir)) => doctor.pass(format!("XDG_RUNTIME_DIR={}", dir.display())),
+        (reason, _) => doctor.warn(
+            format!("{}; the swwws socket goes under /tmp instead, and swww may not find its own", reason.unwrap_or_default()),
+            "Start swwws from a login session that sets XDG_RUNTIME_DIR",
+        ),
+    }
+}
@@ -0,0 +1,126 @@
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use swwws_common::{ErrorKind, IpcClient, IpcCommand, IpcResponse};
+use crate::verbosity;
+
+/// Something else went wrong, like a file that couldn't be written
+pub const EXIT_FAILED: i32 = 1;
+/// The daemon isn't running, or hung up
+pub const EXIT_UNREACHABLE: i32 = 2;
+/// The daemon refused the command or failed to carry it out
+pub const EXIT_DAEMON: i32 = 3;
+/// The arguments are wrong, including names of outputs and groups the daemon doesn't know
+pub const EXIT_USAGE: i32 = 4;
+/// The daemon didn't answer in time
+pub const EXIT_TIMEOUT: i32 = 5;
+
+/// Set by `--json-errors`
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json(json: bool) {
+    JSON_ERRORS.store(json, Ordering::Relaxed);
+}
+
+/// Why a command failed, and the exit code that tells scripts so. With
+/// `--json-errors` it is printed to stderr as `{"code", "kind", "message"}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Failure {
+    pub code: i32,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(code: i32, kind: &'static str, message: impl Into<String>) -> Self {
+        Self { code, kind, message: message.into() }
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::new(EXIT_USAGE, "usage", message)
+    }
+
+    /// An `IpcResponse::Error` from the daemon
+    pub fn daemon(kind: ErrorKind, message: impl Into<String>) -> Self {
+        match kind {
+            ErrorKind::InvalidArgument => Self::new(EXIT_USAGE, "invalid_argument", message),
+            ErrorKind::NotFound => Self::new(EXIT_USAGE, "not_found", message),
+            ErrorKind::Unavailable => Self::new(EXIT_DAEMON, "unavailable", message),
+            ErrorKind::Failed => Self::new(EXIT_DAEMON, "failed", message),
+        }
+    }
+
+    /// A failure raised as one, or a connection to the daemon that failed in
+    /// a way scripts may want to tell apart; anything else is `EXIT_FAILED`
+    pub fn from_error(error: anyhow::Error) -> Self {
+        if let Some(failure) = error.downcast_ref::<Failure>() {
+            return failure.clone();
+        }
+        let kind = error.chain().find_map(|cause| cause.downcast_ref::<std::io::Error>()).map(std::io::Error::kind);
+        let message = format!("{:#}", error);
+        match kind {
+            Some(std::io::ErrorKind::NotFound
+                | std::io::ErrorKind::ConnectionRefused
+                | std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::BrokenPipe) => Self::new(EXIT_UNREACHABLE, "unreachable", message),
+            Some(std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => Self::new(EXIT_TIMEOUT, "timeout", message),
+            _ => Self::new(EXIT_FAILED, "failed", message),
+        }
+    }
+
+    pub fn with_message(self, message: impl Into<String>) -> Self {
+        Self { message: message.into(), ..self }
+    }
+
+    /// Print it to stderr and exit with its code
+    pub fn exit(self) -> ! {
+        if JSON_ERRORS.load(Ordering::Relaxed) {
+            match serde_json::to_string(&self) {
+                Ok(json) => eprintln!("{}", json),
+                Err(_) => eprintln!("✗ Error: {}", self.message),
+            }
+        } else {
+            eprintln!("✗ Error: {}", self.message);
+        }
+        std::process::exit(self.code);
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Send `command` to the daemon. Its answer comes back unless it is an
+/// `IpcResponse::Error`, which becomes a `Failure` like the connection errors.
+pub fn request(command: IpcCommand) -> anyhow::Result<IpcResponse> {
+    request_with(&IpcClient::new(), command)
+}
+
+/// `request` through `client`, e.g. one with a timeout. With `--verbose` the
+/// socket, the command as sent and how long the answer took go to stderr.
+pub fn request_with(client: &IpcClient, command: IpcCommand) -> anyhow::Result<IpcResponse> {
+    if verbosity::verbose() {
+        verbosity::detail(format_args!("socket {}", client.socket_path().display()));
+        verbosity::detail(format_args!("sending {}", serde_json::to_string(&command)?));
+    }
+    let started = Instant::now();
+    let response = client.send_command(command);
+    match &response {
+        Ok(_) => verbosity::detail(format_args!("answered in {:?}", started.elapsed())),
+        Err(_) => verbosity::detail(format_args!("failed after {:?}", started.elapsed())),
+    }
+    answer(response)
+}
+
+/// The daemon's answer, with `IpcResponse::Error` turned into a `Failure`
+pub fn answer(response: anyhow::Result<IpcResponse>) -> anyhow::Result<IpcResponse> {
+    match response? {
+        IpcResponse::Error { message, kind } => Err(Failure::daemon(kind, message).into()),
+        response => Ok(response),
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{bail, Result};
+use std::io::IsTerminal;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use swwws_common::{IpcClient, IpcCommand, IpcResponse, OutputStatus, StatusReport};
+use crate::failure::request;
+use crate::print_response;
+
+/// How often countdowns are redrawn between fetches
+const TICK: Duration = Duration::from_secs(1);
+
+/// `status --follow`: show the status until Ctrl-C, fetching it again whenever
+/// the daemon reports an event and at least every `interval`. On a terminal
+/// the table is redrawn in place and its countdowns tick down in between;
+/// with `json` each fetch is printed as one line.
+pub fn run(interval: Duration, json: bool) -> Result<()> {
+    exit_on_ctrl_c();
+    let (tx, rx) = mpsc::channel();
+    forward_events(tx);
+
+    let in_place = !json && std::io::stdout().is_terminal();
+    let (mut outputs, mut paused) = fetch()?;
+    let mut fetched = Instant::now();
+    show(&outputs, paused, Duration::ZERO, json, in_place)?;
+
+    loop {
+        let wait = TICK.min(interval.saturating_sub(fetched.elapsed()));
+        let refresh = match rx.recv_timeout(wait) {
+            // Changes on several outputs come in a burst; one fetch covers them
+            Ok(()) => {
+                rx.try_iter().count();
+                true
+            }
+            Err(RecvTimeoutError::Timeout) => fetched.elapsed() >= interval,
+            // The daemon can't send events, so polling is all there is
+            Err(RecvTimeoutError::Disconnected) => {
+                std::thread::sleep(wait);
+                fetched.elapsed() >= interval
+            }
+        };
+
+        if refresh {
+            (outputs, paused) = fetch()?;
+            fetched = Instant::now();
+            show(&outputs, paused, Duration::ZERO, json, in_place)?;
+        } else if in_place {
+            show(&outputs, paused, fetched.elapsed(), json, in_place)?;
+        }
+    }
+}
+
+fn fetch() -> Result<(Vec<OutputStatus>, bool)> {
+    match request(IpcCommand::Status)? {
+        IpcResponse::Status { outputs, paused, .. } => Ok((outputs, paused)),
+        _ => bail!("Unexpected answer to a status request"),
+    }
+}
+
+/// Print the status as it is `elapsed` after it was fetched
+fn show(outputs: &[OutputStatus], paused: bool, elapsed: Duration, json: bool, in_place: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(&StatusReport::new(outputs, paused))?);
+        return Ok(());
+    }
+
+    let outputs = outputs.iter()
+        .cloned()
+        .map(|mut output| {
+            if !output.paused {
+                output.timer_remaining = output.timer_remaining.map(|remaining| remaining.saturating_sub(elapsed.as_secs()));
+            }
+            output
+        })
+        .collect();
+    if in_place {
+        // Cursor home, then clear the screen
+        print!("\x1b[H\x1b[2J");
+    }
+    print_response(IpcResponse::Status { outputs, paused, daemon: None });
+    Ok(())
+}
+
+/// Send a message for each daemon event until the daemon hangs up, then one
+/// more so the next fetch finds out why. Sends nothing if it can't subscribe.
+fn forward_events(tx: Sender<()>) {
+    std::thread::spawn(move || {
+        let Ok((IpcResponse::Success { .. }, events)) = IpcClient::new().subscribe() else { return };
+        for event in events {
+            if event.is_err() || tx.send(()).is_err() {
+                break;
+            }
+        }
+        let _ = tx.send(());
+    });
+}
+
+/// Leave with a clean exit status instead of being killed mid-redraw
+fn exit_on_ctrl_c() {
+    std::thread::spawn(|| {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() else { return };
+        if runtime.block_on(tokio::signal::ctrl_c()).is_ok() {
+            println!();
+            std::process::exit(0);
+        }
+    });
+}
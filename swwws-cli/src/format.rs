@@ -0,0 +1,164 @@
+use anyhow::{bail, Result};
+use std::path::Path;
+use swwws_common::{ErrorKind, IpcCommand, IpcResponse, OutputMode, OutputStatus};
+use crate::failure::{request, Failure};
+use crate::format_duration;
+
+/// What a `{placeholder}` in `status --format` stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Output,
+    Image,
+    Path,
+    Position,
+    Size,
+    Remaining,
+    State,
+    Mode,
+    Group,
+}
+
+impl Field {
+    const ALL: [Field; 9] = [
+        Field::Output, Field::Image, Field::Path, Field::Position, Field::Size,
+        Field::Remaining, Field::State, Field::Mode, Field::Group,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Output => "output",
+            Field::Image => "image",
+            Field::Path => "path",
+            Field::Position => "position",
+            Field::Size => "size",
+            Field::Remaining => "remaining",
+            Field::State => "state",
+            Field::Mode => "mode",
+            Field::Group => "group",
+        }
+    }
+
+    fn value(self, output: &OutputStatus) -> String {
+        match self {
+            Field::Output => output.name.clone(),
+            Field::Image => output.current_image.as_deref()
+                .and_then(|path| Path::new(path).file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            Field::Path => output.current_image.clone().unwrap_or_default(),
+            Field::Position => (output.queue_position + 1).to_string(),
+            Field::Size => output.queue_size.to_string(),
+            Field::Remaining => match output.timer_remaining {
+                _ if output.pinned => "pinned".to_string(),
+                _ if output.static_wallpaper => "static".to_string(),
+                Some(0) => "ready".to_string(),
+                Some(remaining) => format_duration(remaining),
+                None => "no timer".to_string(),
+            },
+            Field::State => match output {
+                _ if output.disabled => "disabled",
+                _ if output.pinned => "pinned",
+                _ if output.paused => "paused",
+                _ => "running",
+            }.to_string(),
+            Field::Mode => match output.mode {
+                OutputMode::Independent => "independent",
+                OutputMode::Synchronized => "synchronized",
+                OutputMode::Group(_) => "group",
+            }.to_string(),
+            Field::Group => match &output.mode {
+                OutputMode::Group(group) => group.clone(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// A `status --format` string, checked before anything is asked of the
+/// daemon. `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(format: &str) -> Result<Self, Failure> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(Failure::usage(format!("Unclosed '{{{}' in --format", name))),
+                        }
+                    }
+                    let Some(field) = Field::ALL.into_iter().find(|field| field.name() == name) else {
+                        let message = format!("Unknown placeholder {{{}}} in --format; valid ones are {}", name, valid_placeholders());
+                        return Err(Failure::usage(message));
+                    };
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field(field));
+                }
+                '}' => return Err(Failure::usage("Unmatched '}' in --format; write '}}' for a literal brace")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Self { pieces })
+    }
+
+    /// The line for one output
+    pub fn render(&self, output: &OutputStatus) -> String {
+        self.pieces.iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => text.clone(),
+                Piece::Field(field) => field.value(output),
+            })
+            .collect()
+    }
+}
+
+/// `status --format`: one line per managed output, or just the line for `output`
+pub fn print(format: &str, output: Option<&str>) -> Result<()> {
+    let template = Template::parse(format)?;
+    let outputs = match request(IpcCommand::Status)? {
+        IpcResponse::Status { outputs, .. } => outputs,
+        _ => bail!("Unexpected answer to a status request"),
+    };
+
+    match output {
+        Some(name) => {
+            let Some(status) = outputs.iter().find(|status| status.name.eq_ignore_ascii_case(name)) else {
+                return Err(Failure::daemon(ErrorKind::NotFound, format!("swwws manages no output named {}", name)).into());
+            };
+            println!("{}", template.render(status));
+        }
+        None => {
+            for status in outputs.iter().filter(|status| !status.disabled) {
+                println!("{}", template.render(status));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn valid_placeholders() -> String {
+    Field::ALL.iter().map(|field| format!("{{{}}}", field.name())).collect::<Vec<_>>().join(", ")
+}
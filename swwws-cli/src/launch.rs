@@ -0,0 +1,105 @@
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions};
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use swwws_common::{DaemonState, SocketLocation};
+use crate::verbosity;
+
+/// How long `--detach` waits for the daemon to answer on its socket
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How `swwws-cli daemon` runs swwws-daemon
+pub struct Launch {
+    /// Fork to the background instead of replacing this process
+    pub detach: bool,
+    /// Where the daemon logs to. With `detach` it defaults to daemon.log next
+    /// to the state file; otherwise the log stays on this terminal.
+    pub log_file: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+}
+
+impl Launch {
+    /// Start swwws-daemon, unless one already answers on the IPC socket. The
+    /// daemon's pid is written next to its socket.
+    pub fn run(self) -> Result<()> {
+        let location = SocketLocation::current();
+        if UnixStream::connect(&location.path).is_ok() {
+            bail!("A swwws daemon is already running on {:?}; stop it with `swwws-cli stop` first", location.path);
+        }
+        location.prepare_dir()?;
+        let pid_file = location.path.with_extension("pid");
+
+        let mut command = Command::new(daemon_binary());
+        if let Some(config) = &self.config {
+            command.arg("--config").arg(config);
+        }
+        // Without RUST_LOG only errors would be logged
+        if std::env::var_os("RUST_LOG").is_none() {
+            command.env("RUST_LOG", "info");
+        }
+
+        let log_file = match (&self.log_file, self.detach) {
+            (Some(path), _) => Some(path.clone()),
+            (None, true) => Some(DaemonState::get_state_file(None).with_file_name("daemon.log")),
+            (None, false) => None,
+        };
+        if let Some(path) = &log_file {
+            let log = open_log(path)?;
+            command.stdout(log.try_clone()?).stderr(log);
+        }
+
+        if !self.detach {
+            // exec keeps this pid, so a supervisor watches the daemon itself
+            write_pid(&pid_file, std::process::id())?;
+            let error = command.exec();
+            return Err(error).with_context(|| format!("Failed to run {:?}", command.get_program()));
+        }
+
+        // Its own process group, so Ctrl-C in this terminal doesn't reach it
+        let mut child = command.stdin(Stdio::null())
+            .process_group(0)
+            .spawn()
+            .with_context(|| format!("Failed to start {:?}", command.get_program()))?;
+        write_pid(&pid_file, child.id())?;
+
+        let log_file = log_file.unwrap_or_default();
+        let started = Instant::now();
+        while started.elapsed() < STARTUP_TIMEOUT {
+            if let Some(status) = child.try_wait()? {
+                bail!("swwws-daemon exited during startup ({}); see {:?}", status, log_file);
+            }
+            if UnixStream::connect(&location.path).is_ok() {
+                verbosity::success(format_args!("Started swwws-daemon (pid {}), logging to {:?}", child.id(), log_file));
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        println!("swwws-daemon (pid {}) is still starting up, logging to {:?}", child.id(), log_file);
+        Ok(())
+    }
+}
+
+/// swwws-daemon from the directory this binary is in, or else from PATH
+fn daemon_binary() -> PathBuf {
+    std::env::current_exe().ok()
+        .map(|exe| exe.with_file_name("swwws-daemon"))
+        .filter(|daemon| daemon.is_file())
+        .unwrap_or_else(|| PathBuf::from("swwws-daemon"))
+}
+
+fn open_log(path: &Path) -> Result<File> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create log directory {:?}", dir))?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+        .with_context(|| format!("Failed to open log file {:?}", path))
+}
+
+fn write_pid(path: &Path, pid: u32) -> Result<()> {
+    std::fs::write(path, format!("{}\n", pid))
+        .with_context(|| format!("Failed to write pid file {:?}", path))
+}
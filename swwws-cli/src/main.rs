@@ -1,154 +1,705 @@
-use clap::{Parser, Subcommand};
-use swwws_common::{IpcClient, IpcCommand, IpcResponse};
+use clap::{ArgGroup, Parser, Subcommand};
+use swwws_common::{IpcClient, IpcCommand, IpcEvent, IpcResponse, DaemonInfo, ErrorReporting, CommandBuilder, ProcessExecutor, ProblemCategory, ProblemReport, OutputMode, OutputSettings, ReloadSummary, StatusReport, parse_duration};
+use swwws_config::check::{check_config, Severity};
+use std::path::PathBuf;
+use std::time::Duration;
+use anyhow::{anyhow, bail, Result};
+use failure::{answer, request, request_with, Failure, EXIT_DAEMON, EXIT_TIMEOUT, EXIT_UNREACHABLE, EXIT_USAGE};
+use launch::Launch;
+use verbosity::Verbosity;
+
+mod doctor;
+mod failure;
+mod follow;
+mod format;
+mod launch;
+mod preview;
+mod verbosity;
+mod waybar;
 
 #[derive(Parser)]
 #[command(name = "swwws-cli")]
 #[command(about = "swwws-cli (swww slideshow daemon control)")]
 #[command(version = "0.1.0")]
 struct Cli {
+    /// On failure, print {"code", "kind", "message"} as JSON to stderr instead of a message
+    #[arg(long, global = true)]
+    json_errors: bool,
+
+    /// Print nothing on success; errors still go to stderr and exit codes are the same
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print more: the socket, each command sent to the daemon and how long it
+    /// took (on stderr), and details some commands leave out
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Start the swwws daemon
-    Daemon,
+    /// Start the swwws daemon, attached to this terminal unless --detach is given
+    Daemon {
+        /// Run in the background, logging to ~/.local/state/swwws/daemon.log
+        /// unless --log-file says otherwise
+        #[arg(long, conflicts_with = "foreground")]
+        detach: bool,
+
+        /// Stay attached, with the daemon taking over this process (the
+        /// default), for supervisors like runit or dinit
+        #[arg(long)]
+        foreground: bool,
+
+        /// Write the daemon's log to this file
+        #[arg(long, value_name = "PATH")]
+        log_file: Option<PathBuf>,
+
+        /// Config file for the daemon to use
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+    },
     
     /// Advance to next wallpaper
     Next {
         /// Specific output to advance
-        #[arg(long)]
+        #[arg(long, conflicts_with = "group")]
         output: Option<String>,
+
+        /// Only this group, by name or index, on all of its outputs
+        #[arg(long)]
+        group: Option<String>,
     },
     
     /// Go to previous wallpaper
     Previous {
         /// Specific output to go back
-        #[arg(long)]
+        #[arg(long, conflicts_with = "group")]
         output: Option<String>,
+
+        /// Only this group, by name or index, on all of its outputs
+        #[arg(long)]
+        group: Option<String>,
     },
     
+    /// Show an image right away, restarting the timer of the queue it goes to
+    Set {
+        /// Image to show
+        path: PathBuf,
+
+        /// Show it on this output only (and the rest of its group)
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Change how long each image stays up until the config changes it
+    Interval {
+        /// New interval, e.g. "10m"
+        #[arg(required_unless_present = "reset")]
+        duration: Option<String>,
+
+        /// Go back to the interval in the config
+        #[arg(long, conflicts_with = "duration")]
+        reset: bool,
+
+        /// Only this output (and the rest of its group)
+        #[arg(long)]
+        output: Option<String>,
+    },
+
     /// Pause the slideshow
-    Pause,
+    Pause {
+        /// Only this group, by name or index; the others carry on
+        #[arg(long)]
+        group: Option<String>,
+    },
     
-    /// Resume the slideshow
-    Resume,
+    /// Resume the slideshow, groups paused on their own included
+    Resume {
+        /// Only this group, by name or index
+        #[arg(long)]
+        group: Option<String>,
+    },
     
     /// Toggle pause state
     TogglePause,
     
-    /// Reload configuration
+    /// Reload configuration; with --verbose, list the settings that changed
+    /// and the queues that were rebuilt
     Reload,
+
+    /// Save state and stop the daemon
+    Stop,
+
+    /// Check whether the daemon is up. Exits with 0 if it answers, 2 if it
+    /// isn't running, 3 if it answers with an error and 5 if it doesn't answer in time
+    Ping {
+        /// How long to wait for the answer, e.g. "500ms"
+        #[arg(long, default_value = "2s", value_parser = parse_duration)]
+        timeout: Duration,
+    },
+
+    /// Switch how outputs share wallpapers, until the next reload
+    Behavior {
+        #[arg(value_parser = ["independent", "synchronized", "grouped"])]
+        behavior: String,
+
+        /// Outputs of one group, comma-separated (e.g. DP-1,DP-2). Repeat for
+        /// more groups; without it the config's groups are used
+        #[arg(long = "group", value_name = "OUTPUTS")]
+        groups: Vec<String>,
+    },
+
+    /// Look through the wallpaper directories again and update the queues
+    Rescan {
+        /// Only the queue this output draws from
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Never show an image again: the one on screen, or PATH
+    Blacklist {
+        /// Image to blacklist instead of the one on screen
+        #[arg(conflicts_with_all = ["output", "list", "remove"])]
+        path: Option<PathBuf>,
+
+        /// Blacklist the image on this output, for when outputs show different ones
+        #[arg(long, conflicts_with_all = ["list", "remove"])]
+        output: Option<String>,
+
+        /// List the blacklisted images
+        #[arg(long, conflicts_with = "remove")]
+        list: bool,
+
+        /// Take an image off the blacklist
+        #[arg(long, value_name = "PATH")]
+        remove: Option<PathBuf>,
+    },
+
+    /// Keep the wallpaper on screen: timers and next/previous without
+    /// --output leave a pinned output alone
+    Pin {
+        /// Only this output; without it every output is pinned
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Let pinned outputs change again
+    Unpin {
+        /// Only this output; without it every pinned output
+        #[arg(long)]
+        output: Option<String>,
+    },
     
-    /// Show current status
-    Status,
+    /// Show current status; with --verbose, also daemon uptime and reload history
+    #[command(group(ArgGroup::new("one_line").args(["waybar", "format"])))]
+    Status {
+        /// Print the status of each output as JSON, for scripts and status bars
+        #[arg(long, conflicts_with = "verbose")]
+        json: bool,
+
+        /// Keep showing the status until Ctrl-C, updated as the daemon reports
+        /// changes. With --json, each update is printed as one line of JSON.
+        #[arg(short, long, conflicts_with = "verbose")]
+        follow: bool,
+
+        /// With --follow, fetch the status at least this often, e.g. "30s"
+        #[arg(long, default_value = "10s", value_parser = parse_duration, requires = "follow")]
+        interval: Duration,
+
+        /// Print one line for a waybar custom module ("return-type": "json"):
+        /// the wallpaper's name, with its path and the time left as tooltip
+        #[arg(long, conflicts_with_all = ["verbose", "json", "follow"])]
+        waybar: bool,
+
+        /// Print one line per output from a template, e.g. '{output}: {image} ({remaining})'.
+        /// Placeholders: {output}, {image}, {path}, {position}, {size}, {remaining},
+        /// {state}, {mode} and {group}; {{ and }} are literal braces.
+        #[arg(long, conflicts_with_all = ["verbose", "json", "follow", "waybar"])]
+        format: Option<String>,
+
+        /// Only this output: the one the waybar module shows (defaults to the
+        /// first), or the single line --format prints
+        #[arg(long, requires = "one_line")]
+        output: Option<String>,
+    },
+
+    /// Print the full path of the current wallpaper, one line per output
+    Current {
+        /// Only this output's wallpaper
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// List the outputs swww reports and what the config and daemon make of each
+    Outputs,
+
+    /// List the latest wallpaper changes, newest first
+    History {
+        /// Only changes on this output
+        #[arg(long)]
+        output: Option<String>,
+
+        /// How many changes to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// List recently shown, current and upcoming images, once per queue
+    Queue {
+        /// Only the queue this output draws from
+        #[arg(long, conflicts_with = "group")]
+        output: Option<String>,
+
+        /// Only this group's queue, by name or index
+        #[arg(long)]
+        group: Option<String>,
+
+        /// How many upcoming images to list
+        #[arg(short = 'n', long, default_value_t = 5)]
+        count: usize,
+
+        /// How many images shown before the current one to list
+        #[arg(long, default_value_t = 3)]
+        history: usize,
+
+        /// Print the queues as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print the images `next` will show, without moving the queues
+    Preview {
+        /// Only the queue this output draws from
+        #[arg(long)]
+        output: Option<String>,
+
+        /// How many upcoming images to print
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+
+        /// Open the first of them with the configured viewer, or xdg-open
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Print the settings each output ends up with once the config is merged
+    Config {
+        /// Only this output's settings
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Print daemon events as they happen: wallpaper changes, pause and resume, reloads
+    Subscribe {
+        /// Print each event as a line of JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Change settings of an output while the daemon runs
+    Output {
+        #[command(subcommand)]
+        command: OutputCommands,
+    },
+
+    /// Show recent failures recorded by the daemon
+    Problems {
+        /// Forget recorded problems instead: all of them, or one category
+        /// (change, discovery or reload)
+        #[arg(long, value_name = "CATEGORY")]
+        clear: Option<Option<ProblemCategory>>,
+    },
+
+    /// Validate a config file without a running daemon
+    CheckConfig {
+        /// Config file to check (defaults to SWWWS_CONFIG or ~/.config/swwws/config.toml)
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+    },
+
+    /// Check the config, swww, both daemons and the environment, with hints for what fails
+    Doctor {
+        /// Config file to check (defaults to SWWWS_CONFIG or ~/.config/swwws/config.toml)
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+    },
+
+    /// Write a commented starter config with a section for each connected output
+    Init {
+        /// Where to write it (defaults to SWWWS_CONFIG or ~/.config/swwws/config.toml)
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum OutputCommands {
+    /// Set an output's wallpaper directory or duration and rebuild its queue
+    Configure {
+        /// Output to configure, as listed by `swww query`
+        output: String,
+
+        /// Wallpaper directory
+        #[arg(long)]
+        path: Option<String>,
 
-    match cli.command {
-        Commands::Daemon => {
-            println!("To start the daemon, run: swwws-daemon");
-            println!("Or use systemctl --user start swwws if installed via install.sh");
+        /// How long each image stays up, e.g. "10m"
+        #[arg(long)]
+        duration: Option<String>,
+
+        /// Also write the settings to the output's section of the config file
+        #[arg(long)]
+        persist: bool,
+    },
+}
+
+fn main() {
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        // --help and --version
+        if !e.use_stderr() {
+            e.exit();
+        }
+        if std::env::args().any(|arg| arg == "--json-errors") {
+            failure::set_json(true);
+            // Just the first line of clap's message, without the usage after it
+            let message = e.render().to_string();
+            let message = message.lines().next().unwrap_or_default().trim_start_matches("error: ");
+            Failure::usage(message).exit();
+        }
+        let _ = e.print();
+        std::process::exit(EXIT_USAGE);
+    });
+    failure::set_json(cli.json_errors);
+    verbosity::set(match (cli.quiet, cli.verbose) {
+        (true, _) => Verbosity::Quiet,
+        (_, true) => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    });
+
+    if let Err(e) = run(cli.command) {
+        Failure::from_error(e).exit();
+    }
+}
+
+fn run(command: Commands) -> Result<()> {
+    match command {
+        Commands::Daemon { detach, foreground: _, log_file, config } => {
+            (Launch { detach, log_file, config }).run()?;
         }
         
-        Commands::Next { output } => {
-            let client = IpcClient::new();
-            let command = IpcCommand::Next { output };
+        Commands::Next { output, group } => {
+            let command = IpcCommand::Next { output, group };
             
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            print_response(request(command)?);
         }
         
-        Commands::Previous { output } => {
-            let client = IpcClient::new();
-            let command = IpcCommand::Previous { output };
+        Commands::Previous { output, group } => {
+            let command = IpcCommand::Previous { output, group };
             
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            print_response(request(command)?);
         }
         
-        Commands::Pause => {
-            let client = IpcClient::new();
-            let command = IpcCommand::Pause;
+        Commands::Set { path, output } => {
+            // The daemon has its own working directory
+            let path = std::path::absolute(&path).unwrap_or(path);
+            let command = IpcCommand::SetImage { output, path };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Interval { duration, reset: _, output } => {
+            let command = IpcCommand::SetInterval { output, duration };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Pause { group } => {
+            let command = match group {
+                Some(group) => IpcCommand::PauseGroup { group },
+                None => IpcCommand::Pause,
+            };
             
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            print_response(request(command)?);
         }
         
-        Commands::Resume => {
-            let client = IpcClient::new();
-            let command = IpcCommand::Resume;
+        Commands::Resume { group } => {
+            let command = match group {
+                Some(group) => IpcCommand::ResumeGroup { group },
+                None => IpcCommand::Resume,
+            };
             
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            print_response(request(command)?);
         }
         
         Commands::TogglePause => {
-            let client = IpcClient::new();
             let command = IpcCommand::TogglePause;
             
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            print_response(request(command)?);
         }
         
         Commands::Reload => {
-            let client = IpcClient::new();
             let command = IpcCommand::Reload;
             
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
+            match request(command)? {
+                IpcResponse::Reloaded { message, summary } if verbosity::verbose() => {
+                    verbosity::success(message);
+                    print_reload_summary(&summary);
                 }
+                response => print_response(response),
             }
         }
         
-        Commands::Status => {
-            let client = IpcClient::new();
-            let command = IpcCommand::Status;
-            
-            match client.send_command(command) {
-                Ok(response) => print_response(response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
+        Commands::Stop => {
+
+            print_response(request(IpcCommand::Shutdown)?);
+        }
+
+        Commands::Behavior { behavior, groups } => {
+            let groups = (!groups.is_empty()).then(|| {
+                groups.iter()
+                    .map(|group| group.split(',').map(str::trim).filter(|output| !output.is_empty()).map(String::from).collect())
+                    .collect()
+            });
+            let command = IpcCommand::SetBehavior { behavior, groups };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Rescan { output } => {
+            let command = IpcCommand::Rescan { output };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Blacklist { path, output, list, remove } => {
+            // The daemon has its own working directory
+            let absolute = |path: PathBuf| std::path::absolute(&path).unwrap_or(path);
+            let command = match (list, remove) {
+                (true, _) => IpcCommand::ListBlacklist,
+                (false, Some(path)) => IpcCommand::Unblacklist { path: absolute(path) },
+                (false, None) => IpcCommand::Blacklist { output, path: path.map(absolute) },
+            };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Pin { output } => print_response(request(IpcCommand::Pin { output })?),
+
+        Commands::Unpin { output } => print_response(request(IpcCommand::Unpin { output })?),
+
+        Commands::Status { waybar: true, output, .. } => waybar::print(output.as_deref()),
+
+        Commands::Status { format: Some(format), output, .. } => format::print(&format, output.as_deref())?,
+
+        Commands::Status { follow: true, interval, json, .. } => follow::run(interval, json)?,
+
+        Commands::Status { json, .. } => {
+            match request(IpcCommand::Status)? {
+                IpcResponse::Status { outputs, paused, .. } if json => {
+                    println!("{}", serde_json::to_string_pretty(&StatusReport::new(&outputs, paused))?);
+                }
+                response => {
+                    let daemon = match &response {
+                        IpcResponse::Status { daemon, .. } => daemon.clone(),
+                        _ => None,
+                    };
+                    print_response(response);
+                    if let Some(info) = daemon {
+                        print_daemon_info(&info, verbosity::verbose());
+                    }
                 }
             }
         }
+
+        Commands::Current { output } => {
+            let command = IpcCommand::GetCurrent { output };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Ping { timeout } => {
+            let client = IpcClient::new().with_timeout(timeout);
+            let failure = match request_with(&client, IpcCommand::Ping) {
+                Ok(IpcResponse::Pong { version, uptime, .. }) => {
+                    if !verbosity::quiet() {
+                        println!("swwws-daemon {} is up ({})", version, format_duration(uptime));
+                    }
+                    return Ok(());
+                }
+                Ok(_) => Failure::new(EXIT_DAEMON, "failed", "Unexpected answer to ping; restart swwws-daemon so it matches swwws-cli"),
+                Err(e) => match Failure::from_error(e) {
+                    failure if failure.code == EXIT_UNREACHABLE => {
+                        failure.with_message(format!("swwws-daemon is not running ({})", client.socket_path().display()))
+                    }
+                    failure if failure.code == EXIT_TIMEOUT => {
+                        failure.with_message(format!("swwws-daemon did not answer within {:?}", timeout))
+                    }
+                    failure => failure,
+                },
+            };
+            if verbosity::quiet() {
+                std::process::exit(failure.code);
+            }
+            failure.exit();
+        }
+
+        Commands::Outputs => {
+            print_response(request(IpcCommand::Outputs)?);
+        }
+
+        Commands::History { output, limit } => {
+            let command = IpcCommand::History { output, limit };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Queue { output, group, count, history, json } => {
+            let command = IpcCommand::QueueList { output, count, group, recent: history };
+
+            match request(command)? {
+                IpcResponse::QueueList { queues } if json => {
+                    println!("{}", serde_json::to_string_pretty(&queues)?);
+                }
+                response => print_response(response),
+            }
+        }
+
+        Commands::Preview { output, count, open } => preview::run(output, count, open)?,
+
+        Commands::Config { output } => {
+            let command = IpcCommand::GetConfig { output };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Subscribe { json } => {
+            let (response, events) = IpcClient::new().subscribe()?;
+            if !matches!(answer(Ok(response))?, IpcResponse::Success { .. }) {
+                return Err(Failure::new(EXIT_DAEMON, "failed", "Unexpected answer to subscribe; restart swwws-daemon so it matches swwws-cli").into());
+            }
+
+            for event in events {
+                match event? {
+                    event if json => println!("{}", serde_json::to_string(&event)?),
+                    event => print_event(&event),
+                }
+            }
+        }
+
+        Commands::Output { command: OutputCommands::Configure { output, path, duration, persist } } => {
+            let command = IpcCommand::ConfigureOutput {
+                output,
+                settings: OutputSettings { path, duration },
+                persist,
+            };
+
+            print_response(request(command)?);
+        }
+
+        Commands::Problems { clear } => {
+            let command = match clear {
+                Some(category) => IpcCommand::ClearProblems { category },
+                None => IpcCommand::Problems,
+            };
+
+            print_response(request(command)?);
+        }
+
+        Commands::CheckConfig { config } => {
+            let path = match config {
+                Some(path) => path,
+                None => swwws_config::Config::config_path().map_err(|e| anyhow!(e.user_friendly_message()))?,
+            };
+
+            let report = check_config(&path);
+            for (section, findings) in &report.sections {
+                println!("[{}]", section);
+                for finding in findings {
+                    match finding.severity {
+                        Severity::Info => println!("  ✓ {}", finding.message),
+                        Severity::Warning => println!("  ⚠ {}", finding.message),
+                        Severity::Error => println!("  ✗ {}", finding.message),
+                    }
+                }
+            }
+
+            println!();
+            println!("{} errors, {} warnings",
+                report.count(Severity::Error), report.count(Severity::Warning));
+            if report.has_errors() {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Doctor { config } => {
+            if !doctor::run(config) {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Init { config, force } => {
+            let path = match config {
+                Some(path) => path,
+                None => swwws_config::Config::config_path().map_err(|e| anyhow!(e.user_friendly_message()))?,
+            };
+
+            if path.exists() && !force {
+                return Err(Failure::usage(format!("{} already exists (use --force to overwrite)", path.display())).into());
+            }
+
+            // An existing config being overwritten may say where swww is
+            let swww_binary = swwws_config::Config::load_from(&path).ok()
+                .and_then(|existing| existing.global.swww_binary);
+            let outputs = ProcessExecutor::find_swww(swww_binary.as_deref().map(std::path::Path::new))
+                .and_then(|swww| ProcessExecutor::new(CommandBuilder::new(swww)).get_swww_outputs())
+                .unwrap_or_else(|e| {
+                    eprintln!("⚠ Could not detect outputs, writing no output sections: {}", e.user_friendly_message());
+                    Vec::new()
+                });
+
+            let written = path.parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(&path, swwws_config::init::starter_config(&outputs)));
+            if let Err(e) = written {
+                bail!("Failed to write {}: {}", path.display(), e);
+            }
+
+            if verbosity::quiet() {
+                return Ok(());
+            }
+            println!("✓ Wrote {}", path.display());
+            if !outputs.is_empty() {
+                println!("  Sections for: {}", outputs.join(", "));
+            }
+            println!();
+            println!("Next steps:");
+            println!("  1. Set `path` under [any] or an output section to a directory of images");
+            println!("  2. Check it with: swwws-cli check-config");
+            println!("  3. Start the daemon: swwws-daemon (or systemctl --user start swwws)");
+        }
     }
 
     Ok(())
 }
 
+/// How long ago something happened, in its largest unit
+fn format_age(seconds: i64) -> String {
+    match seconds {
+        ..=0 => "just now".to_string(),
+        1..=59 => format!("{}s ago", seconds),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
 fn format_duration(seconds: u64) -> String {
     if seconds < 60 {
         format!("{}s", seconds)
@@ -174,16 +725,11 @@ fn format_duration(seconds: u64) -> String {
 
 fn print_response(response: IpcResponse) {
     match response {
-        IpcResponse::Success { message } => {
-            println!("✓ {}", message);
-        }
+        IpcResponse::Success { message } | IpcResponse::Reloaded { message, .. } => verbosity::success(message),
         
-        IpcResponse::Error { message } => {
-            eprintln!("✗ Error: {}", message);
-            std::process::exit(1);
-        }
+        IpcResponse::Error { message, kind } => Failure::daemon(kind, message).exit(),
         
-        IpcResponse::Status { outputs, paused } => {
+        IpcResponse::Status { outputs, paused, .. } => {
             if outputs.is_empty() {
                 println!("No outputs found");
                 return;
@@ -193,10 +739,20 @@ fn print_response(response: IpcResponse) {
             println!("=============");
             println!("Global State: {}", if paused { "PAUSED" } else { "RUNNING" });
             println!();
-            
+
+            // Outputs outside any group are only worth pointing out next to grouped ones
+            let grouped = outputs.iter().any(|output| matches!(output.mode, OutputMode::Group(_)));
             for output in outputs {
-                let status = if output.paused { "PAUSED" } else { "RUNNING" };
-                let timer_str = if let Some(remaining) = output.timer_remaining {
+                if output.disabled {
+                    println!("{}: disabled", output.name);
+                    continue;
+                }
+                let status = if output.pinned { "PINNED" } else if output.paused { "PAUSED" } else { "RUNNING" };
+                let timer_str = if output.pinned {
+                    "until unpinned".to_string()
+                } else if output.static_wallpaper {
+                    "static".to_string()
+                } else if let Some(remaining) = output.timer_remaining {
                     if remaining > 0 {
                         format_duration(remaining)
                     } else {
@@ -215,8 +771,14 @@ fn print_response(response: IpcResponse) {
                     })
                     .unwrap_or("None");
                 
+                let name = match &output.mode {
+                    OutputMode::Independent if grouped => format!("{} (independent)", output.name),
+                    OutputMode::Independent => output.name.clone(),
+                    OutputMode::Synchronized => format!("{} (sync)", output.name),
+                    OutputMode::Group(group) => format!("{} ({})", output.name, group),
+                };
                 println!("{}: {} | {} | {}/{} | {}", 
-                    output.name,
+                    name,
                     status,
                     current_image,
                     output.queue_position + 1,
@@ -225,5 +787,195 @@ fn print_response(response: IpcResponse) {
                 );
             }
         }
+
+        IpcResponse::Problems { categories } => print_problems(&categories),
+
+        IpcResponse::Pong { .. } if verbosity::quiet() => {}
+
+        IpcResponse::Pong { version, uptime, .. } => {
+            println!("swwws-daemon {} is up ({})", version, format_duration(uptime));
+        }
+
+        IpcResponse::Blacklist { paths } => {
+            if paths.is_empty() {
+                println!("No images are blacklisted");
+            }
+            for path in paths {
+                println!("{}", path);
+            }
+        }
+
+        IpcResponse::Outputs { outputs } => {
+            for output in outputs {
+                if !output.connected {
+                    let hint = match &output.near_match {
+                        Some(near_match) => format!(" (swww reports {}; section names are case-sensitive)", near_match),
+                        None => String::new(),
+                    };
+                    println!("{}: configured but not found{}", output.name, hint);
+                    continue;
+                }
+
+                let size = match (output.size, &output.scale) {
+                    (Some((width, height)), Some(scale)) => format!("{}x{} @ {}x", width, height, scale),
+                    (Some((width, height)), None) => format!("{}x{}", width, height),
+                    (None, _) => "size unknown".to_string(),
+                };
+                let section = if output.section { format!("[{}]", output.name) } else { "[any]".to_string() };
+                let section = match &output.group {
+                    Some(group) => format!("{} + group {}", section, group),
+                    None => section,
+                };
+                let queue = match (&output.queue, output.enabled) {
+                    (_, false) => "disabled".to_string(),
+                    (Some(queue), true) => format!("queue: {}", queue),
+                    (None, true) => "no queue".to_string(),
+                };
+                println!("{}: {} | {} | {}", output.name, size, section, queue);
+            }
+        }
+
+        IpcResponse::History { changes } => {
+            if changes.is_empty() {
+                println!("No wallpaper changes recorded yet");
+            }
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |now| now.as_secs() as i64);
+            let width = changes.iter().map(|change| change.output.len()).max().unwrap_or(0);
+            for change in changes {
+                println!("{:>8}  {:<width$}  {} ({})",
+                    format_age(now - change.timestamp.timestamp()),
+                    change.output,
+                    change.image,
+                    change.trigger,
+                    width = width
+                );
+            }
+        }
+
+        // Offsets count from the current image, marked with >
+        IpcResponse::QueueList { queues } => {
+            for (i, listing) in queues.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                let name = if listing.outputs == [listing.queue.clone()] {
+                    listing.queue.clone()
+                } else {
+                    format!("{} on {}", listing.queue, listing.outputs.join(", "))
+                };
+                println!("{} ({}/{}):", name, listing.position, listing.size);
+                for image in &listing.images {
+                    let offset = if image.offset > 0 { format!("+{}", image.offset) } else { image.offset.to_string() };
+                    println!("{} {:>3}  {}", if image.current { ">" } else { " " }, offset, image.path);
+                }
+            }
+        }
+
+        // Printed as TOML, with the queue of each output in a comment
+        IpcResponse::Config { behavior, outputs } => {
+            println!("monitor_behavior = \"{}\"", behavior);
+            for output in outputs {
+                let queue = match &output.mode {
+                    OutputMode::Independent => "its own queue".to_string(),
+                    OutputMode::Synchronized => "the queue shared by all outputs".to_string(),
+                    OutputMode::Group(group) => format!("the queue of group '{}'", group),
+                };
+                println!();
+                println!("# {} uses {}", output.output, queue);
+                print!("{}", output.toml);
+            }
+        }
+
+        // Nothing but the paths, so the output can be piped
+        IpcResponse::Current { images } => {
+            for path in images.into_iter().filter_map(|image| image.path) {
+                println!("{}", path);
+            }
+        }
+    }
+}
+
+fn print_event(event: &IpcEvent) {
+    match event {
+        IpcEvent::WallpaperChanged { output, image, trigger } => println!("{}: {} ({})", output, image, trigger),
+        IpcEvent::Paused => println!("paused"),
+        IpcEvent::Resumed => println!("resumed"),
+        IpcEvent::Reloaded { success: true, message } => println!("reloaded: {}", message),
+        IpcEvent::Reloaded { success: false, message } => println!("reload failed: {}", message),
+    }
+}
+
+fn print_reload_summary(summary: &ReloadSummary) {
+    if !summary.changes.is_empty() {
+        println!();
+        println!("Changed:");
+        for change in &summary.changes {
+            println!("  {}", change);
+        }
+    }
+    if !summary.outputs.is_empty() {
+        println!("Outputs affected: {}", summary.outputs.join(", "));
+    }
+    if !summary.rebuilt.is_empty() {
+        println!("Queues rebuilt: {}", summary.rebuilt.join(", "));
+    }
+}
+
+fn print_problems(categories: &[ProblemReport]) {
+    if categories.is_empty() {
+        println!("No problems recorded");
+        return;
+    }
+
+    for (i, report) in categories.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}: {} total", report.category, report.total);
+        if report.dropped > 0 {
+            println!("  ({} older problems dropped, showing the last {})", report.dropped, report.recent.len());
+        }
+
+        for (output, count) in &report.by_output {
+            let label = if output.is_empty() { "(no output)" } else { output.as_str() };
+            println!("  {} ({}):", label, count);
+            for record in report.recent.iter().filter(|r| r.output.as_deref().unwrap_or_default() == output) {
+                println!("    {} {}", record.timestamp.format("%Y-%m-%d %H:%M:%S UTC"), record.message);
+            }
+        }
+    }
+}
+
+fn print_daemon_info(info: &DaemonInfo, verbose: bool) {
+    if verbose {
+        println!();
+        println!("Daemon:");
+        println!("  Uptime: {}", format_duration(info.uptime));
+        println!("  Config: {}", info.config_path);
+        println!("  Reloads: {} succeeded, {} failed", info.reloads_succeeded, info.reloads_failed);
+        match &info.last_reload {
+            Some(last) => println!("  Last reload: {} at {} ({})",
+                if last.success { "ok" } else { "FAILED" },
+                last.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                last.message
+            ),
+            None => println!("  Last reload: never"),
+        }
+    }
+
+    if !info.runtime_outputs.is_empty() {
+        println!();
+        println!("⚠ Runtime-only settings for {} (use `swwws-cli output configure --persist` to keep them)",
+            info.runtime_outputs.join(", "));
+    }
+
+    if info.config_modified {
+        println!();
+        println!("⚠ Config modified since last reload (run `swwws-cli reload` to apply)");
+    }
+
+    if info.dry_run {
+        println!();
+        println!("⚠ Dry run: swww commands are printed by the daemon instead of run, so wallpapers don't change");
     }
 }
@@ -0,0 +1,55 @@
+use anyhow::{bail, Context, Result};
+use std::process::{Command, Stdio};
+use swwws_common::{ErrorKind, IpcCommand, IpcResponse, QueueListing};
+use swwws_config::Config;
+use crate::failure::{request, Failure};
+
+/// `preview`: the next `count` images of each queue, as `next` will show them.
+/// The daemon only reads its queues for this, so nothing moves. With one
+/// queue only the paths are printed, so they can be piped.
+pub fn run(output: Option<String>, count: usize, open: bool) -> Result<()> {
+    let command = IpcCommand::QueueList { output, count, group: None, recent: 0 };
+    let queues = match request(command)? {
+        IpcResponse::QueueList { queues } => queues,
+        _ => bail!("Unexpected answer to a queue request"),
+    };
+
+    for (i, listing) in queues.iter().enumerate() {
+        if queues.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("{}:", listing.outputs.join(", "));
+        }
+        for path in upcoming(listing) {
+            println!("{}", path);
+        }
+    }
+
+    if open {
+        let Some(image) = queues.iter().find_map(|listing| upcoming(listing).next()) else {
+            return Err(Failure::daemon(ErrorKind::Unavailable, "No upcoming image to open; the queue starts a new cycle next").into());
+        };
+        let viewer = viewer();
+        Command::new(&viewer)
+            .arg(image)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to run {}", viewer))?;
+    }
+    Ok(())
+}
+
+fn upcoming(listing: &QueueListing) -> impl Iterator<Item = &str> {
+    listing.images.iter().filter(|image| image.offset > 0).map(|image| image.path.as_str())
+}
+
+/// `viewer` from the config, or xdg-open when it is unset or the config can't be read
+fn viewer() -> String {
+    Config::config_path().ok()
+        .and_then(|path| Config::load_from(&path).ok())
+        .and_then(|config| config.global.viewer)
+        .unwrap_or_else(|| "xdg-open".to_string())
+}
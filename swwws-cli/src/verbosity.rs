@@ -0,0 +1,49 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much the CLI prints besides what a command is asked to show, from the
+/// global `--quiet` and `--verbose`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Nothing on success; errors still go to stderr
+    Quiet,
+    Normal,
+    /// Also how each request to the daemon went, on stderr
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+pub fn set(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn get() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+pub fn quiet() -> bool {
+    get() == Verbosity::Quiet
+}
+
+pub fn verbose() -> bool {
+    get() == Verbosity::Verbose
+}
+
+/// A `✓` line saying a command did what it was asked, left out with `--quiet`
+pub fn success(message: impl Display) {
+    if !quiet() {
+        println!("✓ {}", message);
+    }
+}
+
+/// A line for debugging with `--verbose`, on stderr so piped output stays clean
+pub fn detail(message: impl Display) {
+    if verbose() {
+        eprintln!("· {}", message);
+    }
+}
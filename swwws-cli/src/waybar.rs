@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::path::Path;
+use swwws_common::{IpcClient, IpcCommand, IpcResponse, OutputStatus};
+use crate::format_duration;
+
+/// One line of JSON for a waybar custom module with `"return-type": "json"`
+#[derive(Debug, Serialize)]
+struct Module {
+    text: String,
+    tooltip: String,
+    /// "running", "paused" or "pinned"; "stopped" when the daemon can't be reached and
+    /// "error" when it can't answer for the output
+    class: String,
+    /// Same as `class`, for `format-icons`
+    alt: String,
+}
+
+impl Module {
+    fn new(text: impl Into<String>, tooltip: impl Into<String>, class: &str) -> Self {
+        Self { text: text.into(), tooltip: tooltip.into(), class: class.to_string(), alt: class.to_string() }
+    }
+
+    /// For `output`, or the first output swwws manages
+    fn from_status(outputs: &[OutputStatus], output: Option<&str>) -> Self {
+        let status = match output {
+            Some(name) => outputs.iter().find(|status| status.name.eq_ignore_ascii_case(name)),
+            None => outputs.iter().find(|status| !status.disabled),
+        };
+        let Some(status) = status else {
+            let tooltip = match output {
+                Some(name) => format!("swwws manages no output named {}", name),
+                None => "swwws manages no outputs".to_string(),
+            };
+            return Self::new("", tooltip, "error");
+        };
+
+        let class = if status.pinned { "pinned" } else if status.paused { "paused" } else { "running" };
+        let Some(image) = &status.current_image else {
+            return Self::new("", format!("{}: no wallpaper yet", status.name), class);
+        };
+        let text = Path::new(image).file_stem().map_or_else(|| image.clone(), |stem| stem.to_string_lossy().to_string());
+        let next = match status.timer_remaining {
+            _ if status.pinned => "pinned".to_string(),
+            _ if status.static_wallpaper => "static wallpaper".to_string(),
+            _ if status.paused => "paused".to_string(),
+            Some(remaining) => format!("next in {}", format_duration(remaining)),
+            None => "no timer".to_string(),
+        };
+        Self::new(text, format!("{}\n{}", image, next), class)
+    }
+}
+
+/// Print the module for the current status. Always succeeds: when the daemon
+/// isn't running the module says so, instead of the bar logging an error on
+/// every poll.
+pub fn print(output: Option<&str>) {
+    let module = match IpcClient::new().send_command(IpcCommand::Status) {
+        Ok(IpcResponse::Status { outputs, .. }) => Module::from_status(&outputs, output),
+        Ok(IpcResponse::Error { message, .. }) => Module::new("", message, "error"),
+        Ok(_) => Module::new("", "Unexpected answer from the swwws daemon", "error"),
+        Err(e) => Module::new("off", format!("The swwws daemon is not running\n{}", e), "stopped"),
+    };
+    match serde_json::to_string(&module) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
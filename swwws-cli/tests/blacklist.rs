@@ -0,0 +1,36 @@
+use swwws_common::IpcResponse;
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+#[test]
+fn test_blacklist_commands() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let success = || IpcResponse::Success { message: "Blacklisted ugly.png; it won't be shown again".to_string() };
+
+    let daemon = mock_daemon(&socket, success());
+    let output = swwws_cli(&socket, &["blacklist", "--output", "DP-1"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(daemon.join().unwrap(), serde_json::json!({ "Blacklist": { "output": "DP-1", "path": null } }));
+    std::fs::remove_file(&socket).unwrap();
+
+    // Paths are made absolute for the daemon
+    let daemon = mock_daemon(&socket, success());
+    let output = swwws_cli(&socket, &["blacklist", "--remove", "walls/ugly.png"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let command = daemon.join().unwrap();
+    let path = command["Unblacklist"]["path"].as_str().unwrap();
+    assert!(path.starts_with('/') && path.ends_with("walls/ugly.png"), "{}", path);
+    std::fs::remove_file(&socket).unwrap();
+
+    let paths = vec!["/walls/a.png".to_string(), "/walls/b.png".to_string()];
+    let daemon = mock_daemon(&socket, IpcResponse::Blacklist { paths });
+    let output = swwws_cli(&socket, &["blacklist", "--list"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "/walls/a.png\n/walls/b.png\n");
+    assert_eq!(daemon.join().unwrap(), serde_json::json!("ListBlacklist"));
+
+    let output = swwws_cli(&socket, &["blacklist", "/walls/a.png", "--output", "DP-1"]);
+    assert_eq!(output.status.code(), Some(4));
+}
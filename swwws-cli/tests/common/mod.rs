@@ -0,0 +1,27 @@
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::thread::JoinHandle;
+use swwws_common::ipc::{read_frame, write_frame, PROTOCOL_VERSION};
+use swwws_common::IpcResponse;
+
+/// Answer one command on `socket` with `response`, as the daemon would, and
+/// hand back the command that was sent
+pub fn mock_daemon(socket: &Path, response: IpcResponse) -> JoinHandle<serde_json::Value> {
+    let listener = UnixListener::bind(socket).unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let command: serde_json::Value = serde_json::from_slice(&read_frame(&mut stream).unwrap()).unwrap();
+        let answer = serde_json::json!({ "version": PROTOCOL_VERSION, "response": response });
+        write_frame(&mut stream, answer.to_string().as_bytes()).unwrap();
+        command["command"].clone()
+    })
+}
+
+pub fn swwws_cli(socket: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_swwws-cli"))
+        .args(args)
+        .env("SWWWS_SOCKET", socket)
+        .output()
+        .unwrap()
+}
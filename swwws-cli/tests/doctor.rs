@@ -0,0 +1,27 @@
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_failures_come_with_hints_and_fail_the_command() {
+    let temp_dir = tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_swwws-cli"))
+        .args(["doctor", "--config"])
+        .arg(temp_dir.path().join("config.toml"))
+        .env("PATH", "")
+        .env("SWWWS_SOCKET", temp_dir.path().join("swwws.sock"))
+        .env("WAYLAND_DISPLAY", "wayland-1")
+        .env("XDG_RUNTIME_DIR", temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let config = stdout.find("✗ No config file at").expect(&stdout);
+    let init = stdout.find("→ Write a starter config with `swwws-cli init`").expect(&stdout);
+    let swww = stdout.find("→ Install swww").expect(&stdout);
+    let daemon = stdout.find("→ Start it with `swwws-cli daemon --detach`").expect(&stdout);
+    // In the order one failure leads to the next
+    assert!(config < init && init < swww && swww < daemon, "{}", stdout);
+    assert!(stdout.contains("✓ WAYLAND_DISPLAY=wayland-1"), "{}", stdout);
+    assert!(stdout.ends_with("3 failed, 0 warnings\n"), "{}", stdout);
+}
@@ -0,0 +1,49 @@
+use swwws_common::{ErrorKind, IpcResponse};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+fn json_error(output: &std::process::Output) -> serde_json::Value {
+    assert!(output.stdout.is_empty());
+    serde_json::from_slice(&output.stderr).unwrap()
+}
+
+#[test]
+fn test_daemon_errors_exit_by_kind() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, IpcResponse::error(ErrorKind::NotFound, "Unknown output 'DP-9'"));
+    let output = swwws_cli(&socket, &["next", "--output", "DP-9", "--json-errors"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(json_error(&output), serde_json::json!({ "code": 4, "kind": "not_found", "message": "Unknown output 'DP-9'" }));
+    daemon.join().unwrap();
+    std::fs::remove_file(&socket).unwrap();
+
+    let daemon = mock_daemon(&socket, IpcResponse::error(ErrorKind::Unavailable, "DP-1 keeps a static wallpaper"));
+    let output = swwws_cli(&socket, &["interval", "5m"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "✗ Error: DP-1 keeps a static wallpaper\n");
+    daemon.join().unwrap();
+}
+
+#[test]
+fn test_connection_and_usage_errors_have_their_own_codes() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let output = swwws_cli(&socket, &["--json-errors", "pause"]);
+    assert_eq!(output.status.code(), Some(2));
+    let error = json_error(&output);
+    assert_eq!(error["kind"], "unreachable");
+    assert!(error["message"].as_str().unwrap().contains("swwws.sock"));
+
+    let output = swwws_cli(&socket, &["next", "--output", "DP-1", "--group", "desk", "--json-errors"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(json_error(&output)["kind"], "usage");
+
+    let output = swwws_cli(&socket, &["previous", "--bogus"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--bogus"));
+}
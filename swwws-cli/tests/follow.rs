@@ -0,0 +1,73 @@
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixListener;
+use std::process::{Command, Stdio};
+use swwws_common::ipc::{read_frame, write_frame, PROTOCOL_VERSION};
+use swwws_common::{IpcEvent, IpcResponse, OutputMode, OutputStatus};
+use tempfile::tempdir;
+
+fn status(image: &str) -> IpcResponse {
+    let output = OutputStatus {
+        name: "DP-1".to_string(),
+        current_image: Some(image.to_string()),
+        queue_position: 0,
+        queue_size: 2,
+        timer_remaining: Some(300),
+        paused: false,
+        static_wallpaper: false,
+        disabled: false,
+        mode: OutputMode::Independent,
+        pinned: false,
+    };
+    IpcResponse::Status { outputs: vec![output], paused: false, daemon: None }
+}
+
+#[test]
+fn test_json_follow_prints_a_line_per_update() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let listener = UnixListener::bind(&socket).unwrap();
+
+    // Answers status with the next image each time, and sends subscribers one change
+    std::thread::spawn(move || {
+        let mut subscribers = Vec::new();
+        let mut images = ["/walls/a.png", "/walls/b.png"].into_iter().cycle();
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let command: serde_json::Value = serde_json::from_slice(&read_frame(&mut stream).unwrap()).unwrap();
+            let response = match command["command"].as_str() {
+                Some("Subscribe") => IpcResponse::Success { message: "Subscribed".to_string() },
+                _ => status(images.next().unwrap()),
+            };
+            let answer = serde_json::json!({ "version": PROTOCOL_VERSION, "response": response });
+            write_frame(&mut stream, answer.to_string().as_bytes()).unwrap();
+            if command["command"] == "Subscribe" {
+                let event = IpcEvent::WallpaperChanged {
+                    output: "DP-1".to_string(),
+                    image: "/walls/b.png".to_string(),
+                    trigger: "timer".to_string(),
+                };
+                write_frame(&mut stream, serde_json::to_string(&event).unwrap().as_bytes()).unwrap();
+                subscribers.push(stream);
+            }
+        }
+    });
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_swwws-cli"))
+        .args(["status", "--follow", "--json"])
+        .env("SWWWS_SOCKET", &socket)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut next_image = || {
+        let line = lines.next().unwrap().unwrap();
+        let report: serde_json::Value = serde_json::from_str(&line).unwrap();
+        report["outputs"][0]["image"].as_str().unwrap().to_string()
+    };
+    let first = next_image();
+    let second = next_image();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!([first, second], ["/walls/a.png", "/walls/b.png"]);
+}
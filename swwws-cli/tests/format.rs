@@ -0,0 +1,69 @@
+use swwws_common::{IpcResponse, OutputMode, OutputStatus};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+fn statuses() -> Vec<OutputStatus> {
+    let output = |name: &str, image: &str, remaining: Option<u64>, mode: OutputMode| OutputStatus {
+        name: name.to_string(),
+        current_image: Some(image.to_string()),
+        queue_position: 2,
+        queue_size: 12,
+        timer_remaining: remaining,
+        paused: false,
+        static_wallpaper: false,
+        disabled: false,
+        mode,
+        pinned: false,
+    };
+    vec![
+        output("DP-1", "/walls/forest.png", Some(125), OutputMode::Group("desk".to_string())),
+        output("HDMI-A-1", "/walls/city.jpg", Some(0), OutputMode::Independent),
+        OutputStatus::disabled("eDP-1"),
+    ]
+}
+
+fn status_format(args: &[&str]) -> std::process::Output {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let daemon = mock_daemon(&socket, IpcResponse::Status { outputs: statuses(), paused: false, daemon: None });
+    let output = swwws_cli(&socket, &[&["status"], args].concat());
+    daemon.join().unwrap();
+    output
+}
+
+#[test]
+fn test_each_managed_output_gets_a_line() {
+    let output = status_format(&["--format", "{output}: {image} ({remaining}) {position}/{size} {state} {mode} {group} {{{path}}}"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "DP-1: forest.png (2m5s) 3/12 running group desk {/walls/forest.png}\n\
+         HDMI-A-1: city.jpg (ready) 3/12 running independent  {/walls/city.jpg}\n",
+    );
+}
+
+#[test]
+fn test_output_picks_a_single_line() {
+    let output = status_format(&["--format", "{image}", "--output", "hdmi-a-1"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "city.jpg\n");
+
+    let output = status_format(&["--format", "{image}", "--output", "DP-9"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no output named DP-9"));
+}
+
+#[test]
+fn test_unknown_placeholders_are_refused_up_front() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    // No daemon listens: the format is checked before it would be asked
+    let output = swwws_cli(&socket, &["status", "--format", "{output} {wallpaper}"]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown placeholder {wallpaper}"), "{}", stderr);
+    assert!(stderr.contains("{output}, {image}, {path}"), "{}", stderr);
+}
@@ -0,0 +1,41 @@
+use swwws_common::{IpcResponse, OutputMode, OutputStatus};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+#[test]
+fn test_pin_commands_and_status() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, IpcResponse::Success { message: "Pinned forest.png on DP-1".to_string() });
+    let output = swwws_cli(&socket, &["pin", "--output", "DP-1"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(daemon.join().unwrap(), serde_json::json!({ "Pin": { "output": "DP-1" } }));
+    std::fs::remove_file(&socket).unwrap();
+
+    let daemon = mock_daemon(&socket, IpcResponse::Success { message: "Unpinned 2 outputs".to_string() });
+    let output = swwws_cli(&socket, &["unpin"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(daemon.join().unwrap(), serde_json::json!({ "Unpin": { "output": null } }));
+    std::fs::remove_file(&socket).unwrap();
+
+    let pinned = OutputStatus {
+        name: "DP-1".to_string(),
+        current_image: Some("/walls/forest.png".to_string()),
+        queue_position: 3,
+        queue_size: 12,
+        timer_remaining: None,
+        paused: false,
+        static_wallpaper: false,
+        disabled: false,
+        mode: OutputMode::Independent,
+        pinned: true,
+    };
+    let daemon = mock_daemon(&socket, IpcResponse::Status { outputs: vec![pinned], paused: false, daemon: None });
+    let output = swwws_cli(&socket, &["status"]);
+    daemon.join().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("DP-1: PINNED | forest.png | 4/12 | until unpinned"), "{}", stdout);
+}
@@ -0,0 +1,34 @@
+use std::os::unix::net::UnixListener;
+use swwws_common::{ErrorKind, IpcResponse};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+#[test]
+fn test_exit_codes_tell_why() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, IpcResponse::Pong { version: "0.1.0".to_string(), uptime: 3900, pid: Some(4242) });
+    let output = swwws_cli(&socket, &["ping"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "swwws-daemon 0.1.0 is up (1h5m)\n");
+    assert_eq!(daemon.join().unwrap(), serde_json::json!("Ping"));
+    std::fs::remove_file(&socket).unwrap();
+
+    let daemon = mock_daemon(&socket, IpcResponse::error(ErrorKind::Failed, "Malformed command"));
+    assert_eq!(swwws_cli(&socket, &["ping"]).status.code(), Some(3));
+    daemon.join().unwrap();
+    std::fs::remove_file(&socket).unwrap();
+
+    let output = swwws_cli(&socket, &["ping", "--quiet"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+
+    // Accepts connections but never answers
+    let _listener = UnixListener::bind(&socket).unwrap();
+    let output = swwws_cli(&socket, &["ping", "--timeout", "200ms"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("did not answer within 200ms"));
+}
@@ -0,0 +1,65 @@
+use std::process::Command;
+use std::time::{Duration, Instant};
+use swwws_common::{IpcResponse, OutputMode, QueueEntry, QueueListing};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+fn listing() -> IpcResponse {
+    let entry = |path: &str, offset: i64| QueueEntry { path: path.to_string(), current: offset == 0, offset };
+    IpcResponse::QueueList {
+        queues: vec![QueueListing {
+            queue: "all outputs (sync)".to_string(),
+            mode: OutputMode::Synchronized,
+            outputs: vec!["DP-1".to_string(), "HDMI-A-1".to_string()],
+            position: 4,
+            size: 20,
+            images: vec![entry("/walls/c.png", 0), entry("/walls/d.png", 1), entry("/walls/e.png", 2)],
+        }],
+    }
+}
+
+#[test]
+fn test_preview_prints_only_upcoming_images() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, listing());
+    let output = swwws_cli(&socket, &["preview", "-n", "2"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "/walls/d.png\n/walls/e.png\n");
+    assert_eq!(
+        daemon.join().unwrap(),
+        serde_json::json!({ "QueueList": { "output": null, "count": 2, "group": null, "recent": 0 } }),
+    );
+}
+
+#[test]
+fn test_preview_opens_the_next_image_with_the_viewer() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let opened = temp_dir.path().join("opened");
+    let viewer = temp_dir.path().join("viewer");
+    // Written aside and moved, so it is never seen half-written
+    std::fs::write(&viewer, format!("#!/bin/sh\necho \"$1\" > {0:?}.part && mv {0:?}.part {0:?}\n", opened)).unwrap();
+    std::fs::set_permissions(&viewer, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let config = temp_dir.path().join("config.toml");
+    std::fs::write(&config, format!("[global]\nviewer = {:?}\n\n[any]\npath = {:?}\n", viewer, temp_dir.path())).unwrap();
+
+    let daemon = mock_daemon(&socket, listing());
+    let output = Command::new(env!("CARGO_BIN_EXE_swwws-cli"))
+        .args(["preview", "--open"])
+        .env("SWWWS_SOCKET", &socket)
+        .env("SWWWS_CONFIG", &config)
+        .output()
+        .unwrap();
+    daemon.join().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let started = Instant::now();
+    while !opened.exists() && started.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(std::fs::read_to_string(&opened).unwrap().trim(), "/walls/d.png");
+}
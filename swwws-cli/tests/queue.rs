@@ -0,0 +1,85 @@
+use swwws_common::{ErrorKind, IpcResponse, OutputMode, QueueEntry, QueueListing};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+fn entry(path: &str, offset: i64) -> QueueEntry {
+    QueueEntry { path: path.to_string(), current: offset == 0, offset }
+}
+
+fn listings() -> Vec<QueueListing> {
+    vec![
+        QueueListing {
+            queue: "all outputs (sync)".to_string(),
+            mode: OutputMode::Synchronized,
+            outputs: vec!["DP-1".to_string(), "HDMI-A-1".to_string()],
+            position: 4,
+            size: 20,
+            images: vec![entry("/walls/b.png", -1), entry("/walls/c.png", 0), entry("/walls/d.png", 1), entry("/walls/e.png", 2)],
+        },
+        QueueListing {
+            queue: "eDP-1".to_string(),
+            mode: OutputMode::Independent,
+            outputs: vec!["eDP-1".to_string()],
+            position: 1,
+            size: 3,
+            images: vec![entry("/laptop/a.png", 0)],
+        },
+    ]
+}
+
+#[test]
+fn test_queues_are_listed_once_each() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let daemon = mock_daemon(&socket, IpcResponse::QueueList { queues: listings() });
+
+    let output = swwws_cli(&socket, &["queue"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "all outputs (sync) on DP-1, HDMI-A-1 (4/20):\n\
+         \x20  -1  /walls/b.png\n\
+         >   0  /walls/c.png\n\
+         \x20  +1  /walls/d.png\n\
+         \x20  +2  /walls/e.png\n\
+         \n\
+         eDP-1 (1/3):\n\
+         >   0  /laptop/a.png\n",
+    );
+
+    let command = daemon.join().unwrap();
+    assert_eq!(command, serde_json::json!({ "QueueList": { "output": null, "count": 5, "group": null, "recent": 3 } }));
+}
+
+#[test]
+fn test_json_and_filters_reach_the_daemon() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let daemon = mock_daemon(&socket, IpcResponse::QueueList { queues: listings() });
+
+    let output = swwws_cli(&socket, &["queue", "--group", "desk", "--count", "2", "--history", "1", "--json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let printed: Vec<QueueListing> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(printed, listings());
+
+    let command = daemon.join().unwrap();
+    assert_eq!(command, serde_json::json!({ "QueueList": { "output": null, "count": 2, "group": "desk", "recent": 1 } }));
+}
+
+#[test]
+fn test_daemon_errors_fail_the_command() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let daemon = mock_daemon(&socket, IpcResponse::error(ErrorKind::NotFound, "Unknown output 'DP-9'"));
+
+    let output = swwws_cli(&socket, &["queue", "--output", "DP-9", "--json"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown output 'DP-9'"));
+    daemon.join().unwrap();
+
+    // Naming both is refused before anything is sent
+    let output = swwws_cli(&socket, &["queue", "--output", "DP-1", "--group", "desk"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,35 @@
+use swwws_common::{IpcResponse, ReloadSummary};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+fn reloaded() -> IpcResponse {
+    IpcResponse::Reloaded {
+        message: "Configuration reloaded: 1 change on 1 output, 1 queue rebuilt".to_string(),
+        summary: ReloadSummary {
+            changes: vec![r#"DP-1: path "/walls" → "/other""#.to_string()],
+            rebuilt: vec!["DP-1".to_string()],
+            outputs: vec!["DP-1".to_string()],
+        },
+    }
+}
+
+#[test]
+fn test_reload_lists_changes_when_verbose() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, reloaded());
+    let output = swwws_cli(&socket, &["reload"]);
+    daemon.join().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "✓ Configuration reloaded: 1 change on 1 output, 1 queue rebuilt\n");
+    std::fs::remove_file(&socket).unwrap();
+
+    let daemon = mock_daemon(&socket, reloaded());
+    let output = swwws_cli(&socket, &["reload", "--verbose"]);
+    assert_eq!(daemon.join().unwrap(), serde_json::json!("Reload"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Changed:\n  DP-1: path \"/walls\" → \"/other\"\n"), "{}", stdout);
+    assert!(stdout.contains("Outputs affected: DP-1\nQueues rebuilt: DP-1\n"), "{}", stdout);
+}
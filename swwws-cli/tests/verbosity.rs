@@ -0,0 +1,40 @@
+use swwws_common::{ErrorKind, IpcResponse};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+#[test]
+fn test_quiet_prints_nothing_on_success_but_keeps_errors() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, IpcResponse::Success { message: "Advanced to the next wallpaper".to_string() });
+    let output = swwws_cli(&socket, &["next", "--quiet"]);
+    daemon.join().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+    std::fs::remove_file(&socket).unwrap();
+
+    let daemon = mock_daemon(&socket, IpcResponse::error(ErrorKind::Unavailable, "DP-1 is disabled"));
+    let output = swwws_cli(&socket, &["-q", "next", "--output", "DP-1"]);
+    daemon.join().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("DP-1 is disabled"));
+}
+
+#[test]
+fn test_verbose_traces_the_request_on_stderr() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+
+    let daemon = mock_daemon(&socket, IpcResponse::Success { message: "Paused".to_string() });
+    let output = swwws_cli(&socket, &["-v", "pause"]);
+    daemon.join().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "✓ Paused\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("socket {}", socket.display())), "{}", stderr);
+    assert!(stderr.contains("sending "), "{}", stderr);
+    assert!(stderr.contains("answered in "), "{}", stderr);
+}
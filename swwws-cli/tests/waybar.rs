@@ -0,0 +1,72 @@
+use swwws_common::{IpcResponse, OutputMode, OutputStatus};
+use tempfile::tempdir;
+
+mod common;
+use common::{mock_daemon, swwws_cli};
+
+fn status(name: &str, image: Option<&str>, remaining: Option<u64>, paused: bool) -> OutputStatus {
+    OutputStatus {
+        name: name.to_string(),
+        current_image: image.map(str::to_string),
+        queue_position: 0,
+        queue_size: 10,
+        timer_remaining: remaining,
+        paused,
+        static_wallpaper: false,
+        disabled: false,
+        mode: OutputMode::Independent,
+        pinned: false,
+    }
+}
+
+fn waybar(response: IpcResponse, args: &[&str]) -> serde_json::Value {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let daemon = mock_daemon(&socket, response);
+
+    let output = swwws_cli(&socket, &[&["status", "--waybar"], args].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    daemon.join().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "not one line: {}", stdout);
+    serde_json::from_str(&stdout).unwrap()
+}
+
+#[test]
+fn test_module_shows_the_chosen_output() {
+    let outputs = vec![
+        status("DP-1", Some("/walls/forest.png"), Some(125), false),
+        status("HDMI-A-1", Some("/walls/city.night.jpg"), None, true),
+    ];
+    let first = waybar(IpcResponse::Status { outputs: outputs.clone(), paused: false, daemon: None }, &[]);
+    assert_eq!(first, serde_json::json!({
+        "text": "forest",
+        "tooltip": "/walls/forest.png\nnext in 2m5s",
+        "class": "running",
+        "alt": "running",
+    }));
+
+    let chosen = waybar(IpcResponse::Status { outputs, paused: false, daemon: None }, &["--output", "hdmi-a-1"]);
+    assert_eq!(chosen["text"], "city.night");
+    assert_eq!(chosen["class"], "paused");
+    assert_eq!(chosen["tooltip"], "/walls/city.night.jpg\npaused");
+}
+
+#[test]
+fn test_missing_output_is_reported_in_the_module() {
+    let outputs = vec![status("DP-1", Some("/walls/forest.png"), Some(60), false)];
+    let module = waybar(IpcResponse::Status { outputs, paused: false, daemon: None }, &["--output", "DP-9"]);
+    assert_eq!(module["class"], "error");
+    assert_eq!(module["tooltip"], "swwws manages no output named DP-9");
+}
+
+#[test]
+fn test_stopped_daemon_still_exits_zero() {
+    let temp_dir = tempdir().unwrap();
+    let output = swwws_cli(&temp_dir.path().join("missing.sock"), &["status", "--waybar"]);
+    assert!(output.status.success());
+    let module: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(module["text"], "off");
+    assert_eq!(module["class"], "stopped");
+    assert!(module["tooltip"].as_str().unwrap().starts_with("The swwws daemon is not running"));
+}
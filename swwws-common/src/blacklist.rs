@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Images never to show again, by canonical path, so a symlink or a relative
+/// path to a blacklisted image is caught too. Kept in the state file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Blacklist {
+    paths: BTreeSet<PathBuf>,
+}
+
+/// `path` with symlinks resolved, or as given if it can't be (e.g. it was deleted)
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Blacklist {
+    /// Returns false if it was blacklisted already
+    pub fn add(&mut self, image: &Path) -> bool {
+        self.paths.insert(canonical(image))
+    }
+
+    /// Returns false if it wasn't blacklisted
+    pub fn remove(&mut self, image: &Path) -> bool {
+        self.paths.remove(image) || self.paths.remove(&canonical(image))
+    }
+
+    pub fn contains(&self, image: &Path) -> bool {
+        !self.paths.is_empty() && (self.paths.contains(image) || self.paths.contains(&canonical(image)))
+    }
+
+    /// `images` without the blacklisted ones
+    pub fn filter(&self, mut images: Vec<PathBuf>) -> Vec<PathBuf> {
+        images.retain(|image| !self.contains(image));
+        images
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// In path order
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.paths.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_links_to_a_blacklisted_image_are_caught() {
+        let temp_dir = tempdir().unwrap();
+        let image = temp_dir.path().join("ugly.png");
+        std::fs::write(&image, b"png").unwrap();
+        let link = temp_dir.path().join("link.png");
+        std::os::unix::fs::symlink(&image, &link).unwrap();
+        let other = temp_dir.path().join("fine.png");
+
+        let mut blacklist = Blacklist::default();
+        assert!(blacklist.add(&link));
+        assert!(!blacklist.add(&image));
+        assert_eq!(blacklist.paths().collect::<Vec<_>>(), [&image.canonicalize().unwrap()]);
+
+        assert!(blacklist.contains(&image));
+        assert!(blacklist.contains(&temp_dir.path().join(".").join("ugly.png")));
+        assert_eq!(blacklist.filter(vec![link, image, other.clone()]), [other]);
+    }
+
+    #[test]
+    fn test_deleted_images_can_be_removed() {
+        let temp_dir = tempdir().unwrap();
+        let image = temp_dir.path().join("ugly.png");
+        std::fs::write(&image, b"png").unwrap();
+
+        let mut blacklist = Blacklist::default();
+        blacklist.add(&image);
+        let listed = blacklist.paths().next().unwrap().clone();
+        std::fs::remove_file(&image).unwrap();
+        assert!(blacklist.remove(&listed));
+        assert!(blacklist.is_empty());
+        assert!(!blacklist.remove(&listed));
+
+        let json = serde_json::to_string(&Blacklist::default()).unwrap();
+        assert_eq!(json, "[]");
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::command_builder::OutputConfig;
+
+/// Why a wallpaper change is happening. Settings that depend on the trigger,
+/// like manual transition overrides, are resolved from this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeReason {
+    /// First wallpaper set when the daemon starts
+    Startup,
+    /// The rotation timer expired
+    Timer,
+    /// Requested over IPC (next, previous)
+    Manual,
+    /// Re-applied after a reload or behavior change
+    Refresh,
+    /// A schedule window opened or closed
+    Schedule,
+    /// The output came back on after being powered off, or swww-daemon restarted;
+    /// re-applied without a transition
+    Wake,
+}
+
+impl std::fmt::Display for ChangeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeReason::Startup => write!(f, "startup"),
+            ChangeReason::Timer => write!(f, "timer"),
+            ChangeReason::Manual => write!(f, "manual"),
+            ChangeReason::Refresh => write!(f, "refresh"),
+            ChangeReason::Schedule => write!(f, "schedule"),
+            ChangeReason::Wake => write!(f, "wake"),
+        }
+    }
+}
+
+/// A single wallpaper change, ready to hand to the backend
+#[derive(Debug, Clone)]
+pub struct ChangeRequest {
+    pub output: String,
+    pub image: PathBuf,
+    pub config: OutputConfig,
+    pub reason: ChangeReason,
+    /// How long the transition keeps running after swww returns. The output's
+    /// next change waits this long so transitions never overlap.
+    pub settle: Duration,
+    /// `on_change` command to run once the change is applied, with `{image}` and
+    /// `{output}` not yet filled in
+    pub on_change: Option<String>,
+}
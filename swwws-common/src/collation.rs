@@ -0,0 +1,232 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+/// How image paths are compared when a queue is ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Collation {
+    /// Plain byte-wise path comparison
+    #[default]
+    Byte,
+    /// Like `Byte`, but runs of digits compare numerically
+    Natural,
+    /// Case-insensitive, accent-folded comparison
+    Unicode,
+    /// Like `Unicode`, but runs of digits compare numerically
+    NaturalUnicode,
+}
+
+impl std::fmt::Display for Collation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Collation::Byte => write!(f, "byte"),
+            Collation::Natural => write!(f, "natural"),
+            Collation::Unicode => write!(f, "unicode"),
+            Collation::NaturalUnicode => write!(f, "natural-unicode"),
+        }
+    }
+}
+
+impl Collation {
+    pub fn compare(&self, a: &Path, b: &Path) -> Ordering {
+        match self {
+            Collation::Byte => a.cmp(b),
+            Collation::Natural => {
+                natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()).then_with(|| a.cmp(b))
+            }
+            Collation::Unicode => {
+                let key_a = fold(&a.to_string_lossy());
+                let key_b = fold(&b.to_string_lossy());
+                // Fall back to bytes so "a.png" and "A.png" still have a stable order
+                key_a.cmp(&key_b).then_with(|| a.cmp(b))
+            }
+            Collation::NaturalUnicode => {
+                let key_a = fold(&a.to_string_lossy());
+                let key_b = fold(&b.to_string_lossy());
+                natural_cmp(&key_a, &key_b).then_with(|| a.cmp(b))
+            }
+        }
+    }
+}
+
+/// Build a lowercase, accent-free sort key.
+///
+/// This is a small approximation of NFKD + case folding that covers the Latin
+/// scripts wallpaper filenames are usually written in, without pulling in ICU.
+pub fn fold(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        for lower in c.to_lowercase() {
+            fold_char(lower, &mut out);
+        }
+    }
+    out
+}
+
+fn fold_char(c: char, out: &mut String) {
+    let folded = match c {
+        // Combining diacritical marks (already decomposed input)
+        '\u{0300}'..='\u{036F}' => return,
+        // Fullwidth digits and letters
+        '\u{FF10}'..='\u{FF19}' => char::from_u32(c as u32 - 0xFF10 + '0' as u32).unwrap_or(c),
+        '\u{FF41}'..='\u{FF5A}' => char::from_u32(c as u32 - 0xFF41 + 'a' as u32).unwrap_or(c),
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' | 'ð' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        'æ' => return out.push_str("ae"),
+        'œ' => return out.push_str("oe"),
+        'ß' => return out.push_str("ss"),
+        'þ' => return out.push_str("th"),
+        other => other,
+    };
+    out.push(folded);
+}
+
+/// Compare two strings treating runs of ASCII digits as numbers
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let run_a = take_digits(&mut a_chars);
+                let run_b = take_digits(&mut b_chars);
+                let trimmed_a = run_a.trim_start_matches('0');
+                let trimmed_b = run_b.trim_start_matches('0');
+
+                let ordering = trimmed_a.len().cmp(&trimmed_b.len())
+                    .then_with(|| trimmed_a.cmp(trimmed_b))
+                    // "01" after "1" so the order is still total
+                    .then_with(|| run_a.len().cmp(&run_b.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(ca), Some(cb)) => {
+                if ca != cb {
+                    return ca.cmp(&cb);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut run = String::new();
+    while let Some(c) = chars.peek().copied() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        run.push(c);
+        chars.next();
+    }
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sorted(names: &[&str], collation: Collation) -> Vec<String> {
+        let mut paths: Vec<PathBuf> = names.iter().map(|n| PathBuf::from(format!("/walls/{}", n))).collect();
+        paths.sort_by(|a, b| collation.compare(a, b));
+        paths.iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_byte_collation_keeps_path_order() {
+        let order = sorted(&["zebra.png", "Ähre.png", "apfel.png"], Collation::Byte);
+        assert_eq!(order, vec!["apfel.png", "zebra.png", "Ähre.png"]);
+    }
+
+    #[test]
+    fn test_unicode_collation_german() {
+        let order = sorted(
+            &["zebra.png", "Ähre.png", "apfel.png", "Über.png", "Straße.png", "strand.png"],
+            Collation::Unicode,
+        );
+        assert_eq!(order, vec!["Ähre.png", "apfel.png", "strand.png", "Straße.png", "Über.png", "zebra.png"]);
+    }
+
+    #[test]
+    fn test_unicode_collation_swedish() {
+        let order = sorted(
+            &["Östersund.jpg", "Åre.jpg", "Bergen.jpg", "älvdalen.jpg", "Oslo.jpg"],
+            Collation::Unicode,
+        );
+        assert_eq!(order, vec!["älvdalen.jpg", "Åre.jpg", "Bergen.jpg", "Oslo.jpg", "Östersund.jpg"]);
+    }
+
+    #[test]
+    fn test_unicode_collation_is_case_insensitive_and_stable() {
+        let order = sorted(&["b.png", "A.png", "a.png", "B.png"], Collation::Unicode);
+        assert_eq!(order, vec!["A.png", "a.png", "B.png", "b.png"]);
+    }
+
+    #[test]
+    fn test_natural_unicode_collation_mixed_case_numeric() {
+        let order = sorted(
+            &["Wall_10.png", "wall_2.png", "WALL_1.png", "wäll_3.png", "wall_02.png"],
+            Collation::NaturalUnicode,
+        );
+        assert_eq!(order, vec!["WALL_1.png", "wall_2.png", "wall_02.png", "wäll_3.png", "Wall_10.png"]);
+    }
+
+    #[test]
+    fn test_natural_collation_numeric_runs() {
+        let order = sorted(
+            &["frame_120.png", "frame_2.png", "frame_10.png", "Frame_3.png", "frame_1.png"],
+            Collation::Natural,
+        );
+        // Case still matters, only the numbers are compared by value
+        assert_eq!(order, vec!["Frame_3.png", "frame_1.png", "frame_2.png", "frame_10.png", "frame_120.png"]);
+    }
+
+    #[test]
+    fn test_fold_decomposed_input() {
+        // "e" followed by a combining acute accent
+        assert_eq!(fold("Cafe\u{0301}"), "cafe");
+        assert_eq!(fold("Ｗａｌｌ１"), "wall1");
+    }
+
+    #[test]
+    fn test_collation_deserialization() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            collation: Collation,
+        }
+
+        let w: Wrapper = toml::from_str(r#"collation = "natural-unicode""#).unwrap();
+        assert_eq!(w.collation, Collation::NaturalUnicode);
+        let w: Wrapper = toml::from_str(r#"collation = "natural""#).unwrap();
+        assert_eq!(w.collation, Collation::Natural);
+        let w: Wrapper = toml::from_str(r#"collation = "byte""#).unwrap();
+        assert_eq!(w.collation, Collation::Byte);
+    }
+}
@@ -19,7 +19,7 @@ pub struct OutputConfig {
     pub transition_wave: Option<String>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct CommandBuilder {
     swww_path: PathBuf,
 }
@@ -29,6 +29,10 @@ impl CommandBuilder {
         Self { swww_path }
     }
 
+    pub fn swww_path(&self) -> &Path {
+        &self.swww_path
+    }
+
     pub fn build_img_command(
         &self,
         image_path: &Path,
@@ -39,34 +43,34 @@ impl CommandBuilder {
         cmd.arg("img");
 
         if let Some(output) = output_name {
-            cmd.args(&["-o", output]);
+            cmd.args(["-o", output]);
         }
         if let Some(transition_type) = &config.transition_type {
-            cmd.args(&["--transition-type", transition_type]);
+            cmd.args(["--transition-type", transition_type]);
         }
         if let Some(transition_step) = config.transition_step {
-            cmd.args(&["--transition-step", &transition_step.to_string()]);
+            cmd.args(["--transition-step", &transition_step.to_string()]);
         }
         if let Some(transition_angle) = config.transition_angle {
-            cmd.args(&["--transition-angle", &transition_angle.to_string()]);
+            cmd.args(["--transition-angle", &transition_angle.to_string()]);
         }
         if let Some(transition_pos) = &config.transition_pos {
-            cmd.args(&["--transition-pos", transition_pos]);
+            cmd.args(["--transition-pos", transition_pos]);
         }
         if let Some(transition_bezier) = &config.transition_bezier {
-            cmd.args(&["--transition-bezier", transition_bezier]);
+            cmd.args(["--transition-bezier", transition_bezier]);
         }
         if let Some(transition_fps) = config.transition_fps {
-            cmd.args(&["--transition-fps", &transition_fps.to_string()]);
+            cmd.args(["--transition-fps", &transition_fps.to_string()]);
         }
         if let Some(resize) = &config.resize {
-            cmd.args(&["--resize", resize]);
+            cmd.args(["--resize", resize]);
         }
         if let Some(fill_color) = &config.fill_color {
-            cmd.args(&["--fill-color", fill_color]);
+            cmd.args(["--fill-color", fill_color]);
         }
         if let Some(filter) = &config.filter {
-            cmd.args(&["-f", filter]);
+            cmd.args(["-f", filter]);
         }
         if let Some(invert_y) = config.invert_y {
             if invert_y {
@@ -74,7 +78,7 @@ impl CommandBuilder {
             }
         }
         if let Some(transition_wave) = &config.transition_wave {
-            cmd.args(&["--transition-wave", transition_wave]);
+            cmd.args(["--transition-wave", transition_wave]);
         }
         cmd.arg(image_path);
         cmd
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, Metadata};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use crate::image_discovery::DiscoveryOptions;
+
+/// Bumped whenever the file layout changes, so an old cache file is dropped
+/// instead of misread
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedScan {
+    /// The scan was made with. Other options may find other images.
+    options: DiscoveryOptions,
+    /// Of every directory in the tree, see `fingerprint`
+    fingerprint: u64,
+    dirs: Vec<PathBuf>,
+    images: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    scans: HashMap<PathBuf, CachedScan>,
+}
+
+/// Images found under each scanned directory, kept with a fingerprint of the
+/// directories in its tree.
+///
+/// Adding, removing or renaming a file changes the modification time of the
+/// directory it is in, so as long as the fingerprint still matches, the tree
+/// holds the same images and `ImageDiscovery` can skip the walk.
+#[derive(Debug, Default)]
+pub struct DiscoveryCache {
+    scans: Mutex<HashMap<PathBuf, CachedScan>>,
+    /// Changed since it was loaded or last saved
+    dirty: AtomicBool,
+}
+
+static GLOBAL: LazyLock<DiscoveryCache> = LazyLock::new(DiscoveryCache::default);
+
+impl DiscoveryCache {
+    /// The cache `ImageDiscovery` uses
+    pub fn global() -> &'static DiscoveryCache {
+        &GLOBAL
+    }
+
+    /// Where the cache for `state_file` is kept: next to it
+    pub fn file_for(state_file: &Path) -> PathBuf {
+        state_file.with_file_name("discovery-cache.json")
+    }
+
+    /// Images found under `root` last time, if it was scanned with the same
+    /// `options` and no directory in its tree has changed since. A stale entry is dropped.
+    pub fn get(&self, root: &Path, options: &DiscoveryOptions) -> Option<Vec<PathBuf>> {
+        let mut scans = self.scans.lock().unwrap();
+        let scan = scans.get(root)?;
+        if scan.options != *options {
+            return None;
+        }
+        let current = fingerprint(scan.dirs.iter().map(|dir| (dir.as_path(), fs::metadata(dir).ok())));
+        if current == Some(scan.fingerprint) {
+            return Some(scan.images.clone());
+        }
+
+        log::debug!("{:?} changed since it was last scanned", root);
+        scans.remove(root);
+        self.dirty.store(true, Ordering::Relaxed);
+        None
+    }
+
+    /// Remember the `images` found under `root`, along with the metadata each
+    /// of its directories had when the walk reached it. Nothing is kept if some
+    /// directory's metadata could not be read.
+    pub fn insert(&self, root: &Path, options: &DiscoveryOptions, dirs: Vec<(PathBuf, Option<Metadata>)>, images: &[PathBuf]) {
+        let Some(fingerprint) = fingerprint(dirs.iter().map(|(dir, metadata)| (dir.as_path(), metadata.clone()))) else {
+            return;
+        };
+        let scan = CachedScan {
+            options: options.clone(),
+            fingerprint,
+            dirs: dirs.into_iter().map(|(dir, _)| dir).collect(),
+            images: images.to_vec(),
+        };
+        self.scans.lock().unwrap().insert(root.to_path_buf(), scan);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Take over the entries saved in `file`. A missing, unreadable or outdated
+    /// file leaves the cache empty, so the next discovery walks the tree.
+    pub fn load(&self, file: &Path) {
+        let Ok(json) = fs::read_to_string(file) else { return };
+        match serde_json::from_str::<CacheFile>(&json) {
+            Ok(cache) if cache.version == CACHE_VERSION => {
+                log::info!("Loaded discovery cache with {} directories from {:?}", cache.scans.len(), file);
+                *self.scans.lock().unwrap() = cache.scans;
+                self.dirty.store(false, Ordering::Relaxed);
+            }
+            Ok(_) => log::info!("Ignoring discovery cache from another swwws version"),
+            Err(e) => log::warn!("Ignoring unreadable discovery cache {:?}: {}", file, e),
+        }
+    }
+
+    /// Write the cache to `file` if it changed. Written to a temporary file
+    /// first, so a crash midway never leaves a truncated cache behind.
+    pub fn save(&self, file: &Path) -> Result<()> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let cache = CacheFile {
+            version: CACHE_VERSION,
+            scans: self.scans.lock().unwrap().clone(),
+        };
+        let json = serde_json::to_string(&cache)
+            .with_context(|| "Failed to serialize discovery cache")?;
+
+        let partial = file.with_extension("json.tmp");
+        let written = fs::write(&partial, json)
+            .and_then(|()| fs::rename(&partial, file))
+            .with_context(|| format!("Failed to write discovery cache: {:?}", file));
+        if written.is_err() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        written
+    }
+}
+
+/// Hash of the path, modification time and size of every directory in `dirs`.
+/// None if any of them is gone or has no readable metadata.
+fn fingerprint<'a>(dirs: impl IntoIterator<Item = (&'a Path, Option<Metadata>)>) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    for (dir, metadata) in dirs {
+        let metadata = metadata?;
+        dir.hash(&mut hasher);
+        metadata.modified().ok()?.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+    }
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn stamp(dir: &Path) -> (PathBuf, Option<Metadata>) {
+        (dir.to_path_buf(), fs::metadata(dir).ok())
+    }
+
+    #[test]
+    fn test_entry_goes_stale_when_a_directory_changes() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        let images = vec![nested.join("a.png")];
+
+        let cache = DiscoveryCache::default();
+        cache.insert(temp_dir.path(), &DiscoveryOptions::default(), vec![stamp(temp_dir.path()), stamp(&nested)], &images);
+        assert_eq!(cache.get(temp_dir.path(), &DiscoveryOptions::default()), Some(images));
+
+        fs::write(nested.join("b.png"), "fake png").unwrap();
+        assert_eq!(cache.get(temp_dir.path(), &DiscoveryOptions::default()), None);
+        // Dropped, not just skipped
+        assert!(cache.scans.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = tempdir().unwrap();
+        let file = DiscoveryCache::file_for(&temp_dir.path().join("state.json"));
+        let walls = temp_dir.path().join("walls");
+        fs::create_dir(&walls).unwrap();
+        let images = vec![walls.join("a.png")];
+
+        let cache = DiscoveryCache::default();
+        cache.insert(&walls, &DiscoveryOptions::default(), vec![stamp(&walls)], &images);
+        cache.save(&file).unwrap();
+        assert!(!temp_dir.path().join("discovery-cache.json.tmp").exists());
+
+        let loaded = DiscoveryCache::default();
+        loaded.load(&file);
+        assert_eq!(loaded.get(&walls, &DiscoveryOptions::default()), Some(images));
+
+        // A cache from another version, or garbage, is ignored
+        fs::write(&file, r#"{"version": 0, "scans": {}}"#).unwrap();
+        let old = DiscoveryCache::default();
+        old.load(&file);
+        assert!(old.scans.lock().unwrap().is_empty());
+        fs::write(&file, "{").unwrap();
+        old.load(&file);
+        assert!(old.scans.lock().unwrap().is_empty());
+    }
+}
@@ -1,9 +1,231 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::str::FromStr;
 use anyhow::{Result, Context};
+use rand::Rng;
+use serde::Serialize;
 
 pub fn parse_duration(duration_str: &str) -> Result<Duration> {
     humantime::Duration::from_str(duration_str)
         .map(|d| d.into())
         .with_context(|| format!("Invalid duration format: {}", duration_str))
 }
+
+/// How long each image stays up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Interval {
+    Fixed(Duration),
+    /// A new random length between `min` and `max` after every change
+    Random { min: Duration, max: Duration },
+}
+
+impl Interval {
+    /// Shortest time an image can stay up
+    pub fn min(&self) -> Duration {
+        match self {
+            Interval::Fixed(duration) => *duration,
+            Interval::Random { min, .. } => *min,
+        }
+    }
+
+    /// Whether `duration` is one this interval could have picked
+    pub fn contains(&self, duration: Duration) -> bool {
+        match self {
+            Interval::Fixed(fixed) => *fixed == duration,
+            Interval::Random { min, max } => (*min..=*max).contains(&duration),
+        }
+    }
+
+    /// Length of the next image: the fixed duration, or a fresh random one
+    pub fn pick(&self) -> Duration {
+        match self {
+            Interval::Fixed(duration) => *duration,
+            Interval::Random { min, max } if min >= max => *min,
+            Interval::Random { min, max } => rand::thread_rng().gen_range(*min..=*max),
+        }
+    }
+}
+
+impl From<Duration> for Interval {
+    fn from(duration: Duration) -> Self {
+        Interval::Fixed(duration)
+    }
+}
+
+impl PartialEq<Duration> for Interval {
+    fn eq(&self, other: &Duration) -> bool {
+        *self == Interval::Fixed(*other)
+    }
+}
+
+/// Target length of each running timer, keyed by output or group. A random
+/// interval is picked once per timer start and kept until the timer restarts,
+/// so the deadline doesn't move between checks.
+#[derive(Debug, Default)]
+pub struct IntervalPicks {
+    picks: Mutex<HashMap<String, (Instant, Duration)>>,
+}
+
+impl IntervalPicks {
+    pub fn target(&self, key: &str, started: Instant, interval: Interval) -> Duration {
+        if let Interval::Fixed(duration) = interval {
+            return duration;
+        }
+
+        let mut picks = self.picks.lock().unwrap();
+        match picks.get(key) {
+            // Re-picked after a reload changed the range, too
+            Some((picked_for, duration)) if *picked_for == started && interval.contains(*duration) => *duration,
+            _ => {
+                let duration = interval.pick();
+                log::debug!("Next change for {} in {:?}", key, duration);
+                picks.insert(key.to_string(), (started, duration));
+                duration
+            }
+        }
+    }
+
+    /// A start for the timer of `key` that makes it fall due at `due`, with a
+    /// fresh pick for a random interval. `due` itself if that start can't be
+    /// represented.
+    pub fn start_due_at(&self, key: &str, due: Instant, interval: Interval) -> Instant {
+        let duration = interval.pick();
+        let Some(started) = due.checked_sub(duration) else { return due };
+        if !matches!(interval, Interval::Fixed(_)) {
+            self.picks.lock().unwrap().insert(key.to_string(), (started, duration));
+        }
+        started
+    }
+
+    /// Keep the pick of the timer of `key` started at `from` for the same
+    /// timer moved to start at `to`, as when a paused timer resumes
+    pub fn carry_over(&self, key: &str, from: Instant, to: Instant) {
+        if let Some(pick) = self.picks.lock().unwrap().get_mut(key) {
+            if pick.0 == from {
+                pick.0 = to;
+            }
+        }
+    }
+}
+
+/// Intervals set at runtime with `swwws-cli interval`, keyed by output. Each
+/// remembers the configured interval it replaced, so a config that changes
+/// that interval takes over again.
+#[derive(Debug, Default)]
+pub struct IntervalOverrides {
+    overrides: HashMap<String, (Interval, Interval)>,
+}
+
+impl IntervalOverrides {
+    pub fn set(&mut self, output: &str, interval: Interval, configured: Interval) {
+        self.overrides.insert(output.to_string(), (interval, configured));
+    }
+
+    /// Go back to the configured interval. Returns false if none was set.
+    pub fn clear(&mut self, output: &str) -> bool {
+        self.overrides.remove(output).is_some()
+    }
+
+    /// The interval set for `output`, or `configured` without one
+    pub fn interval(&self, output: &str, configured: Interval) -> Interval {
+        self.overrides.get(output).map_or(configured, |(interval, _)| *interval)
+    }
+
+    /// Drop the intervals of outputs whose configured interval is no longer
+    /// the one they replaced, and return those outputs
+    pub fn retain_configured(&mut self, configured: impl Fn(&str) -> Interval) -> Vec<String> {
+        let mut dropped = Vec::new();
+        self.overrides.retain(|output, (_, replaced)| {
+            let keep = configured(output) == *replaced;
+            if !keep {
+                dropped.push(output.clone());
+            }
+            keep
+        });
+        dropped.sort();
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_pick_stays_in_range() {
+        let interval = Interval::Random { min: Duration::from_secs(180), max: Duration::from_secs(600) };
+        for _ in 0..1000 {
+            assert!(interval.contains(interval.pick()));
+        }
+        assert_eq!(Interval::Fixed(Duration::from_secs(5)).pick(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_pick_is_kept_until_timer_restarts() {
+        let picks = IntervalPicks::default();
+        let interval = Interval::Random { min: Duration::from_secs(1), max: Duration::from_secs(3600) };
+        let started = Instant::now();
+
+        let first = picks.target("DP-1", started, interval);
+        for _ in 0..10 {
+            assert_eq!(picks.target("DP-1", started, interval), first);
+        }
+
+        // Each restart gets its own pick
+        let restarts: Vec<Duration> = (1..=20)
+            .map(|i| picks.target("DP-1", started + Duration::from_secs(i), interval))
+            .collect();
+        assert!(restarts.iter().any(|d| *d != first));
+
+        // A narrowed range invalidates an out-of-range pick
+        let narrow = Interval::Random { min: Duration::from_secs(1), max: Duration::from_secs(1) };
+        let last = started + Duration::from_secs(20);
+        assert_eq!(picks.target("DP-1", last, narrow), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_start_due_at_keeps_its_pick() {
+        let picks = IntervalPicks::default();
+        let due = Instant::now() + Duration::from_secs(7200);
+        for interval in [
+            Interval::Fixed(Duration::from_secs(300)),
+            Interval::Random { min: Duration::from_secs(1), max: Duration::from_secs(3600) },
+        ] {
+            let started = picks.start_due_at("DP-1", due, interval);
+            assert_eq!(started + picks.target("DP-1", started, interval), due);
+        }
+    }
+
+    #[test]
+    fn test_carried_over_pick_survives_a_moved_start() {
+        let picks = IntervalPicks::default();
+        let interval = Interval::Random { min: Duration::from_secs(1), max: Duration::from_secs(3600) };
+        let started = Instant::now();
+        let picked = picks.target("DP-1", started, interval);
+        let moved = started + Duration::from_secs(600);
+        picks.carry_over("DP-1", started, moved);
+        assert_eq!(picks.target("DP-1", moved, interval), picked);
+    }
+
+    #[test]
+    fn test_override_gives_way_to_config_change() {
+        let minutes = |m: u64| Interval::Fixed(Duration::from_secs(m * 60));
+        let mut overrides = IntervalOverrides::default();
+        overrides.set("DP-1", minutes(10), minutes(5));
+        overrides.set("HDMI-A-1", minutes(10), minutes(5));
+        assert_eq!(overrides.interval("DP-1", minutes(5)), minutes(10));
+        assert_eq!(overrides.interval("eDP-1", minutes(5)), minutes(5));
+
+        // Only HDMI-A-1's configured interval changed
+        let dropped = overrides.retain_configured(|output| if output == "HDMI-A-1" { minutes(3) } else { minutes(5) });
+        assert_eq!(dropped, vec!["HDMI-A-1".to_string()]);
+        assert_eq!(overrides.interval("HDMI-A-1", minutes(3)), minutes(3));
+        assert_eq!(overrides.interval("DP-1", minutes(5)), minutes(10));
+
+        assert!(overrides.clear("DP-1"));
+        assert!(!overrides.clear("DP-1"));
+        assert_eq!(overrides.interval("DP-1", minutes(5)), minutes(5));
+    }
+}
@@ -39,6 +39,9 @@ pub enum ConfigError {
     #[error("Failed to read configuration file: {path:?}")]
     FileRead { path: PathBuf, source: std::io::Error },
 
+    #[error("Failed to write configuration file {path:?}: {source}")]
+    FileWrite { path: PathBuf, source: std::io::Error },
+
     #[error("Failed to parse TOML configuration: {message}")]
     TomlParse { message: String },
 
@@ -53,6 +56,9 @@ pub enum ConfigError {
 
     #[error("Could not determine config directory")]
     NoConfigDir,
+
+    #[error("Undefined environment variable ${variable} in {field}")]
+    UndefinedVariable { field: String, variable: String },
 }
 
 /// Image discovery errors
@@ -292,6 +298,9 @@ impl ErrorReporting for SwwwsError {
             SwwwsError::State(StateError::FileRead { path, .. }) => {
                 format!("Failed to read state file: {:?}", path)
             }
+            SwwwsError::Process(ProcessError::Execution { command, source }) => {
+                format!("Failed to run {}: {}", command, source)
+            }
             _ => self.to_string(),
         }
     }
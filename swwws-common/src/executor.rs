@@ -1,106 +1,132 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use crate::error::{SwwwsError, ProcessError};
 use crate::Result;
 use crate::command_builder::{CommandBuilder, OutputConfig};
+use crate::orientation::{parse_query_scale, parse_query_size, Orientation};
 
-#[derive(Clone)]
-pub struct ProcessExecutor;
+/// Runs swww as a subprocess, using the binary the command builder was made with
+#[derive(Debug, Clone)]
+pub struct ProcessExecutor {
+    swww_path: PathBuf,
+    dry_run: bool,
+}
+
+/// An output listed by `swww query`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueriedOutput {
+    pub name: String,
+    pub size: Option<(u32, u32)>, // width x height as swww reports it
+    pub scale: Option<String>, // as swww prints it, e.g. "1.5"
+}
+
+impl QueriedOutput {
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.size.map(|(width, height)| Orientation::from_size(width, height))
+    }
+}
 
 impl ProcessExecutor {
-    pub fn new(_command_builder: CommandBuilder) -> Self {
-        Self
+    pub fn new(command_builder: CommandBuilder) -> Self {
+        Self { swww_path: command_builder.swww_path().to_path_buf(), dry_run: false }
     }
 
-    pub async fn execute_swww_command(
-        &self,
-        image_path: &Path,
-        config: &OutputConfig,
-        output_name: Option<&str>,
-    ) -> Result<()> {
-        // Validate the image path first
-        crate::image_discovery::ImageDiscovery::validate_image(image_path)?;
+    /// Print each `swww img` and `swww clear` instead of running it, to see what
+    /// the daemon would do. Queries still run, as the daemon needs the outputs.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
 
-        // Use the subprocess approach since socket communication corrupts swww-daemon
-        use std::process::Command;
-        let swww_path = which::which("swww")
-            .map_err(|_| SwwwsError::Process(ProcessError::Execution {
-                command: "swww binary not found".to_string(),
-                source: std::io::Error::new(std::io::ErrorKind::NotFound, "swww not in PATH"),
-            }))?;
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
 
-        let mut cmd = Command::new(&swww_path);
-        cmd.arg("img");
-        if let Some(output) = output_name {
-            cmd.args(&["-o", output]);
-        }
-        cmd.arg(image_path);
-        
-        // Add transition parameters
-        if let Some(transition_type) = &config.transition_type {
-            cmd.args(&["--transition-type", transition_type]);
-        }
-        if let Some(transition_step) = config.transition_step {
-            cmd.args(&["--transition-step", &transition_step.to_string()]);
-        }
-        if let Some(transition_angle) = config.transition_angle {
-            cmd.args(&["--transition-angle", &transition_angle.to_string()]);
-        }
-        if let Some(transition_pos) = &config.transition_pos {
-            cmd.args(&["--transition-pos", transition_pos]);
-        }
-        if let Some(transition_bezier) = &config.transition_bezier {
-            cmd.args(&["--transition-bezier", transition_bezier]);
-        }
-        if let Some(transition_fps) = config.transition_fps {
-            cmd.args(&["--transition-fps", &transition_fps.to_string()]);
-        }
-        if let Some(resize) = &config.resize {
-            cmd.args(&["--resize", resize]);
-        }
-        if let Some(fill_color) = &config.fill_color {
-            cmd.args(&["--fill-color", fill_color]);
-        }
-        if let Some(filter) = &config.filter {
-            cmd.args(&["-f", filter]);
-        }
-        if let Some(invert_y) = config.invert_y {
-            if invert_y {
-                cmd.arg("--invert-y");
-            }
-        }
-        if let Some(transition_wave) = &config.transition_wave {
-            cmd.args(&["--transition-wave", transition_wave]);
+    /// The swww binary to run: `configured` when set (`swww_binary` in the config),
+    /// otherwise `swww` from `PATH`
+    pub fn find_swww(configured: Option<&Path>) -> Result<PathBuf> {
+        match configured {
+            Some(path) => Ok(path.to_path_buf()),
+            None => which::which("swww")
+                .map_err(|e| SwwwsError::Process(ProcessError::Execution {
+                    command: "swww (searched PATH; set swww_binary in [global] to use another location)".to_string(),
+                    source: std::io::Error::new(std::io::ErrorKind::NotFound, e.to_string()),
+                })),
         }
-        
+    }
+
+    pub fn swww_path(&self) -> &Path {
+        &self.swww_path
+    }
+
+    /// `swww <subcommand>` with the session environment swww needs to find its daemon
+    fn swww_command(&self, subcommand: &str) -> Command {
+        let mut cmd = Command::new(&self.swww_path);
+        cmd.arg(subcommand);
+
         // Set environment variables from current session, with fallbacks
         if let Ok(display) = std::env::var("WAYLAND_DISPLAY") {
             cmd.env("WAYLAND_DISPLAY", display);
         } else {
-            cmd.env("WAYLAND_DISPLAY", "wayland-0");
+            cmd.env("WAYLAND_DISPLAY", "wayland-0"); // Common fallback
         }
-        
+
         if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
             cmd.env("XDG_RUNTIME_DIR", runtime_dir);
         } else {
+            // Fallback: construct from current user ID
             let uid = unsafe { libc::getuid() };
             cmd.env("XDG_RUNTIME_DIR", format!("/run/user/{}", uid));
         }
-        
+
         if let Ok(desktop) = std::env::var("XDG_CURRENT_DESKTOP") {
             cmd.env("XDG_CURRENT_DESKTOP", desktop);
         }
-        
+
         if let Ok(session_type) = std::env::var("XDG_SESSION_TYPE") {
             cmd.env("XDG_SESSION_TYPE", session_type);
         } else {
-            cmd.env("XDG_SESSION_TYPE", "wayland");
+            cmd.env("XDG_SESSION_TYPE", "wayland"); // Reasonable default for swww
         }
 
+        cmd
+    }
+
+    /// How a command shows up in errors: the full path of the binary that was tried
+    fn describe(&self, subcommand: &str) -> String {
+        format!("{} {}", self.swww_path.display(), subcommand)
+    }
+
+    /// With `dry_run`, print `cmd` as it would run and tell the caller to skip it
+    fn skipped(&self, cmd: &Command) -> bool {
+        if self.dry_run {
+            let line = command_line(cmd);
+            log::info!("Dry run, not running: {}", line);
+            println!("{}", line);
+        }
+        self.dry_run
+    }
+
+    /// Set `image_path` on `output_name`, or on every output. swww runs as a
+    /// `tokio::process` child, so waiting on it holds up no thread.
+    pub async fn run_swww_command(
+        &self,
+        image_path: &Path,
+        config: &OutputConfig,
+        output_name: Option<&str>,
+    ) -> Result<()> {
+        // Validate the image path first
+        crate::image_discovery::ImageDiscovery::validate_image(image_path)?;
+
+        let cmd = self.img_command(image_path, config, output_name);
+        if self.skipped(&cmd) {
+            return Ok(());
+        }
         log::info!("Executing swww command: {:?}", cmd);
 
-        let output = cmd.output()
+        let output = tokio::process::Command::from(cmd).output().await
             .map_err(|e| SwwwsError::Process(ProcessError::Execution {
-                command: format!("{:?}", cmd),
+                command: self.describe("img"),
                 source: e,
             }))?;
 
@@ -130,44 +156,93 @@ impl ProcessExecutor {
         Ok(())
     }
 
-    pub fn check_swww_daemon() -> Result<()> {
-        use std::process::Command;
-        let mut cmd = Command::new("swww");
-        cmd.arg("query");
-        
-        // Set environment variables from current session, with fallbacks
-        if let Ok(display) = std::env::var("WAYLAND_DISPLAY") {
-            cmd.env("WAYLAND_DISPLAY", display);
-        } else {
-            cmd.env("WAYLAND_DISPLAY", "wayland-0");
+    /// `swww img` for `image_path` on `output_name`, or every output, with the
+    /// transition flags `config` sets
+    fn img_command(&self, image_path: &Path, config: &OutputConfig, output_name: Option<&str>) -> Command {
+        // Use the subprocess approach since socket communication corrupts swww-daemon
+        let mut cmd = self.swww_command("img");
+        if let Some(output) = output_name {
+            cmd.args(["-o", output]);
         }
+        cmd.arg(image_path);
         
-        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
-            cmd.env("XDG_RUNTIME_DIR", runtime_dir);
-        } else {
-            let uid = unsafe { libc::getuid() };
-            cmd.env("XDG_RUNTIME_DIR", format!("/run/user/{}", uid));
+        // Add transition parameters
+        if let Some(transition_type) = &config.transition_type {
+            cmd.args(["--transition-type", transition_type]);
         }
-        
-        if let Ok(desktop) = std::env::var("XDG_CURRENT_DESKTOP") {
-            cmd.env("XDG_CURRENT_DESKTOP", desktop);
+        if let Some(transition_step) = config.transition_step {
+            cmd.args(["--transition-step", &transition_step.to_string()]);
         }
-        
-        if let Ok(session_type) = std::env::var("XDG_SESSION_TYPE") {
-            cmd.env("XDG_SESSION_TYPE", session_type);
-        } else {
-            cmd.env("XDG_SESSION_TYPE", "wayland");
+        if let Some(transition_angle) = config.transition_angle {
+            cmd.args(["--transition-angle", &transition_angle.to_string()]);
+        }
+        if let Some(transition_pos) = &config.transition_pos {
+            cmd.args(["--transition-pos", transition_pos]);
+        }
+        if let Some(transition_bezier) = &config.transition_bezier {
+            cmd.args(["--transition-bezier", transition_bezier]);
+        }
+        if let Some(transition_fps) = config.transition_fps {
+            cmd.args(["--transition-fps", &transition_fps.to_string()]);
+        }
+        if let Some(resize) = &config.resize {
+            cmd.args(["--resize", resize]);
+        }
+        if let Some(fill_color) = &config.fill_color {
+            cmd.args(["--fill-color", fill_color]);
+        }
+        if let Some(filter) = &config.filter {
+            cmd.args(["-f", filter]);
+        }
+        if let Some(invert_y) = config.invert_y {
+            if invert_y {
+                cmd.arg("--invert-y");
+            }
+        }
+        if let Some(transition_wave) = &config.transition_wave {
+            cmd.args(["--transition-wave", transition_wave]);
         }
+
+        cmd
+    }
+
+    /// Clear the wallpaper on `outputs` with `swww clear`, back to swww's default color
+    pub fn clear_outputs(&self, outputs: &[String]) -> Result<()> {
+        let mut cmd = self.swww_command("clear");
+        if !outputs.is_empty() {
+            cmd.args(["-o", &outputs.join(",")]);
+        }
+        if self.skipped(&cmd) {
+            return Ok(());
+        }
+
+        let output = cmd.output()
+            .map_err(|e| SwwwsError::Process(ProcessError::Execution {
+                command: self.describe("clear"),
+                source: e,
+            }))?;
+
+        if !output.status.success() {
+            return Err(SwwwsError::Process(ProcessError::NonZeroExit {
+                code: output.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            }));
+        }
+        Ok(())
+    }
+
+    pub fn check_swww_daemon(&self) -> Result<()> {
+        let mut cmd = self.swww_command("query");
         
         let output = cmd.output()
             .map_err(|e| SwwwsError::Process(ProcessError::Execution {
-                command: "swww query".to_string(),
+                command: self.describe("query"),
                 source: e,
             }))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            log::error!("swww daemon check failed: {}", stderr);
+            log::debug!("swww daemon check failed: {}", stderr);
             return Err(SwwwsError::Process(ProcessError::NonZeroExit {
                 code: output.status.code().unwrap_or(-1),
                 stderr: stderr.to_string(),
@@ -178,42 +253,18 @@ impl ProcessExecutor {
         Ok(())
     }
 
-    pub fn get_swww_outputs() -> Result<Vec<String>> {
-        use std::process::Command;
-        
-        let mut cmd = Command::new("swww");
-        cmd.arg("query");
-        
-        // Set environment variables from current session, with fallbacks
-        if let Ok(display) = std::env::var("WAYLAND_DISPLAY") {
-            cmd.env("WAYLAND_DISPLAY", display);
-        } else {
-            cmd.env("WAYLAND_DISPLAY", "wayland-0"); // Common fallback
-        }
-        
-        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
-            cmd.env("XDG_RUNTIME_DIR", runtime_dir);
-        } else {
-            // Fallback: construct from current user ID
-            let uid = unsafe { libc::getuid() };
-            cmd.env("XDG_RUNTIME_DIR", format!("/run/user/{}", uid));
-        }
-        
-        if let Ok(desktop) = std::env::var("XDG_CURRENT_DESKTOP") {
-            cmd.env("XDG_CURRENT_DESKTOP", desktop);
-        }
-        
-        if let Ok(session_type) = std::env::var("XDG_SESSION_TYPE") {
-            cmd.env("XDG_SESSION_TYPE", session_type);
-        } else {
-            cmd.env("XDG_SESSION_TYPE", "wayland"); // Reasonable default for swww
-        }
-        
-        log::debug!("Executing: swww query with environment set");
+    pub fn get_swww_outputs(&self) -> Result<Vec<String>> {
+        Ok(self.query_swww_outputs()?.into_iter().map(|output| output.name).collect())
+    }
+
+    /// Outputs from `swww query` with their sizes, where swww reported one
+    pub fn query_swww_outputs(&self) -> Result<Vec<QueriedOutput>> {
+        let mut cmd = self.swww_command("query");
+        log::debug!("Executing: {} with environment set", self.describe("query"));
         
         let output = cmd.output()
             .map_err(|e| SwwwsError::Process(ProcessError::Execution {
-                command: "swww query".to_string(),
+                command: self.describe("query"),
                 source: e,
             }))?;
 
@@ -239,11 +290,7 @@ impl ProcessExecutor {
             
             // Handle swww query output format which can be:
             // "OUTPUT_NAME: resolution, scale: ..." or ": OUTPUT_NAME: resolution, scale: ..."
-            let working_line = if line.starts_with(": ") {
-                &line[2..] // Skip the leading ": "
-            } else {
-                line
-            };
+            let working_line = line.strip_prefix(": ").unwrap_or(line);
             
             if let Some(colon_pos) = working_line.find(':') {
                 let output_name = working_line[..colon_pos].trim();
@@ -255,7 +302,11 @@ impl ProcessExecutor {
                         || output_name.to_uppercase().contains("LVDS") 
                         || output_name.to_uppercase().contains("VGA") 
                         || output_name.to_uppercase().contains("DVI") {
-                        outputs.push(output_name.to_string());
+                        outputs.push(QueriedOutput {
+                            name: output_name.to_string(),
+                            size: parse_query_size(line),
+                            scale: parse_query_scale(line),
+                        });
                         log::debug!("Found display output: {}", output_name);
                     }
                 }
@@ -271,7 +322,74 @@ impl ProcessExecutor {
             }));
         }
         
-        log::info!("Found swww outputs: {:?}", outputs);
+        log::info!("Found swww outputs: {:?}", outputs.iter().map(|output| &output.name).collect::<Vec<_>>());
         Ok(outputs)
     }
 }
+
+/// `cmd` as it could be pasted into a shell
+fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./,:=@+".contains(c)) {
+                arg.into_owned()
+            } else {
+                crate::hook::shell_quote(&arg)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorReporting;
+
+    #[test]
+    fn test_configured_swww_is_used_as_is() {
+        let path = Path::new("/opt/swww/bin/swww");
+        assert_eq!(ProcessExecutor::find_swww(Some(path)).unwrap(), path);
+    }
+
+    #[test]
+    fn test_missing_swww_binary_is_named_in_error() {
+        let executor = ProcessExecutor::new(CommandBuilder::new(PathBuf::from("/nonexistent/swww")));
+        let message = executor.query_swww_outputs().unwrap_err().user_friendly_message();
+        assert!(message.contains("/nonexistent/swww query"), "got: {}", message);
+    }
+
+    #[test]
+    fn test_dry_run_command_line_has_every_flag() {
+        let executor = ProcessExecutor::new(CommandBuilder::new(PathBuf::from("/usr/bin/swww"))).with_dry_run(true);
+        assert!(executor.is_dry_run());
+        let config = OutputConfig {
+            path: None,
+            mode: None,
+            transition_type: Some("wipe".to_string()),
+            transition_step: Some(90),
+            transition_angle: Some(30.0),
+            transition_pos: Some("center".to_string()),
+            transition_bezier: Some(".54,0,.34,.99".to_string()),
+            transition_fps: Some(60),
+            resize: Some("crop".to_string()),
+            fill_color: Some("000000".to_string()),
+            filter: Some("Lanczos3".to_string()),
+            invert_y: Some(true),
+            transition_wave: Some("20,20".to_string()),
+        };
+        let cmd = executor.img_command(Path::new("/walls/my forest.png"), &config, Some("DP-1"));
+        assert_eq!(
+            command_line(&cmd),
+            "/usr/bin/swww img -o DP-1 '/walls/my forest.png' --transition-type wipe --transition-step 90 \
+             --transition-angle 30 --transition-pos center --transition-bezier .54,0,.34,.99 --transition-fps 60 \
+             --resize crop --fill-color 000000 -f Lanczos3 --invert-y --transition-wave 20,20",
+        );
+
+        // Nothing is run, so the missing binary is no error
+        let missing = ProcessExecutor::new(CommandBuilder::new(PathBuf::from("/nonexistent/swww"))).with_dry_run(true);
+        assert!(missing.clear_outputs(&["DP-1".to_string()]).is_ok());
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Fill `{image}` and `{output}` into an `on_change` command. Both are quoted
+/// for the shell, so paths with spaces need no quotes in the config.
+pub fn expand(template: &str, image: &Path, output: &str) -> String {
+    template
+        .replace("{image}", &shell_quote(&image.to_string_lossy()))
+        .replace("{output}", &shell_quote(output))
+}
+
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Run an `on_change` command with `sh -c` on a thread of its own, so a slow
+/// hook never holds up the next change. Its output is logged at debug level and
+/// a failure only as a warning.
+pub fn spawn(command: String, output: String) {
+    std::thread::spawn(move || run(&command, &output));
+}
+
+fn run(command: &str, output: &str) -> bool {
+    log::debug!("Running on_change hook for {}: {}", output, command);
+    match Command::new("sh").arg("-c").arg(command).output() {
+        Ok(result) => {
+            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            if !stdout.trim().is_empty() {
+                log::debug!("on_change hook for {} stdout: {}", output, stdout.trim_end());
+            }
+            if !stderr.trim().is_empty() {
+                log::debug!("on_change hook for {} stderr: {}", output, stderr.trim_end());
+            }
+            if !result.status.success() {
+                log::warn!("on_change hook for {} failed ({}): {}", output, result.status, command);
+            }
+            result.status.success()
+        }
+        Err(e) => {
+            log::warn!("Failed to run on_change hook for {}: {}", output, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_expand_quotes_substitutions() {
+        let command = expand("wal -i {image} # {output}", Path::new("/walls/it's here.png"), "DP-1");
+        assert_eq!(command, r"wal -i '/walls/it'\''s here.png' # 'DP-1'");
+    }
+
+    #[test]
+    fn test_run_passes_image_through_the_shell() {
+        let temp_dir = tempdir().unwrap();
+        let out = temp_dir.path().join("out");
+        let template = format!("printf %s {{image}} > {}", out.display());
+
+        assert!(run(&expand(&template, Path::new("/walls/a b.png"), "DP-1"), "DP-1"));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "/walls/a b.png");
+        assert!(!run("exit 3", "DP-1"));
+    }
+}
@@ -1,12 +1,103 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
 use walkdir::WalkDir;
-use crate::error::{SwwwsError, ImageDiscoveryError};
+use crate::discovery_cache::DiscoveryCache;
+use crate::error::{ErrorReporting, SwwwsError, ImageDiscoveryError};
+use crate::resolution::image_dimensions;
 use crate::Result;
 
+/// How often a running scan logs how far it got
+pub const SCAN_PROGRESS_INTERVAL: Duration = Duration::from_secs(3);
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "tiff", "webp", "avif"];
+
+/// Most threads checking discovered files at once
+const SCAN_WORKERS: usize = 8;
+
+/// Extensions of playlist files: plain text with one image path per line
+const PLAYLIST_EXTENSIONS: &[&str] = &["txt", "m3u", "m3u8"];
+
+/// How `ImageDiscovery` walks a directory tree
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DiscoveryOptions {
+    /// Descend into symlinked directories. Each directory is walked once however
+    /// many links lead to it, so links back up the tree can't loop.
+    pub follow_symlinks: bool,
+    /// How many directory levels to descend: 1 only looks at the files directly
+    /// in the path, 2 also at those in its subdirectories. None is unlimited.
+    pub max_depth: Option<usize>,
+    /// Width and height an image needs to be kept. None keeps every size.
+    pub min_resolution: Option<(u32, u32)>,
+    /// Keep images whose size can't be read from their header when
+    /// `min_resolution` is set
+    pub keep_unknown_resolution: bool,
+    /// Leave out files and directories whose name starts with a dot. Hidden
+    /// directories are not walked at all.
+    pub skip_hidden: bool,
+    /// Keep one path of each set of files with identical contents, the first
+    /// in path order
+    pub dedupe: bool,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: true,
+            max_depth: None,
+            min_resolution: None,
+            keep_unknown_resolution: true,
+            skip_hidden: true,
+            dedupe: false,
+        }
+    }
+}
+
 pub struct ImageDiscovery;
 
 impl ImageDiscovery {
-    pub fn discover_images(path: &Path) -> Result<Vec<PathBuf>> {
+    /// Images in `path`, taken from the discovery cache when its tree has not
+    /// changed since the last scan. `force` walks the tree regardless.
+    pub fn discover_images(path: &Path, options: &DiscoveryOptions, force: bool) -> Result<Vec<PathBuf>> {
+        Self::scan(path, options, force, &AtomicUsize::new(0))
+    }
+
+    /// `discover_images` on a blocking thread, so a huge tree doesn't hold up the
+    /// async runtime. Scans that take a while log their progress.
+    pub async fn discover_images_async(path: PathBuf, options: DiscoveryOptions, force: bool) -> Result<Vec<PathBuf>> {
+        let found = Arc::new(AtomicUsize::new(0));
+        let scan = tokio::task::spawn_blocking({
+            let path = path.clone();
+            let found = Arc::clone(&found);
+            move || Self::scan(&path, &options, force, &found)
+        });
+        tokio::pin!(scan);
+
+        let started = Instant::now();
+        let mut progress = tokio::time::interval_at(tokio::time::Instant::now() + SCAN_PROGRESS_INTERVAL, SCAN_PROGRESS_INTERVAL);
+        loop {
+            tokio::select! {
+                scanned = &mut scan => {
+                    return scanned.unwrap_or_else(|e| Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::DirectoryRead {
+                        path,
+                        source: std::io::Error::other(e),
+                    })));
+                }
+                _ = progress.tick() => {
+                    log::info!("Still scanning {:?} after {}s, {} images so far",
+                        path, started.elapsed().as_secs(), found.load(Ordering::Relaxed));
+                }
+            }
+        }
+    }
+
+    /// Walk `path` for images, counting them in `found` as they turn up
+    fn scan(path: &Path, options: &DiscoveryOptions, force: bool, found: &AtomicUsize) -> Result<Vec<PathBuf>> {
         if !path.exists() {
             return Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::DirectoryRead {
                 path: path.to_path_buf(),
@@ -14,39 +105,79 @@ impl ImageDiscovery {
             }));
         }
 
+        if Self::is_playlist(path) {
+            let images = Self::filter_resolution(path, options, Self::read_playlist(path)?)?;
+            let images = if options.dedupe { dedupe(path, images) } else { images };
+            found.store(images.len(), Ordering::Relaxed);
+            return Ok(images);
+        }
+
+        // A single image is a one-image slideshow
+        if path.is_file() {
+            Self::validate_image(path)?;
+            log::info!("Using single image {:?}", path);
+            return Ok(vec![path.to_path_buf()]);
+        }
+
         if !path.is_dir() {
             return Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::DirectoryRead {
                 path: path.to_path_buf(),
-                source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path is not a directory"),
+                source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path is neither a directory nor a file"),
             }));
         }
 
-        let mut images = Vec::new();
-        let supported_extensions = ["jpg", "jpeg", "png", "gif", "bmp", "tiff", "webp", "avif"];
+        if !force {
+            if let Some(images) = DiscoveryCache::global().get(path, options) {
+                found.store(images.len(), Ordering::Relaxed);
+                log::info!("Discovered {} images in {:?} (unchanged since the last scan)", images.len(), path);
+                return Ok(images);
+            }
+        }
 
-        for entry in WalkDir::new(path)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let entry_path = entry.path();
-            
-            if entry_path.is_file() {
-                if let Some(extension) = entry_path.extension() {
-                    if let Some(ext_str) = extension.to_str() {
-                        if supported_extensions.contains(&ext_str.to_lowercase().as_str()) {
-                            // Validate that the file is actually readable
-                            if let Err(e) = std::fs::metadata(entry_path) {
-                                log::warn!("Skipping unreadable file {:?}: {}", entry_path, e);
-                                continue;
-                            }
-                            
-                            images.push(entry_path.to_path_buf());
-                        }
-                    }
+        // Each directory as the walk found it, for the cache
+        let mut dirs = Vec::new();
+
+        // Real paths of the directories walked so far, when following links
+        let mut visited = HashSet::new();
+        let mut walker = WalkDir::new(path).follow_links(options.follow_symlinks);
+        if let Some(max_depth) = options.max_depth {
+            walker = walker.max_depth(max_depth);
+        }
+        // The path itself counts even if it is hidden, only what is below it is skipped
+        let mut walk = walker.into_iter()
+            .filter_entry(|entry| !(options.skip_hidden && entry.depth() > 0 && is_hidden(entry.file_name())));
+
+        // The walk itself stays on this thread, handing out files with an image
+        // extension as it goes; checking each one takes a syscall, which adds up
+        // on network mounts, so that runs on the worker threads
+        let candidates = std::iter::from_fn(|| loop {
+            let Ok(entry) = walk.next()? else { continue };
+            let file_type = entry.file_type();
+            if file_type.is_dir() {
+                if options.follow_symlinks && !visited.insert(real_path(entry.path())) {
+                    log::debug!("Skipping {:?}, already walked through another link", entry.path());
+                    walk.skip_current_dir();
+                    continue;
                 }
+                dirs.push((entry.path().to_path_buf(), entry.metadata().ok()));
+            } else if (file_type.is_file() || file_type.is_symlink()) && has_image_extension(entry.path()) {
+                return Some(entry.into_path());
             }
-        }
+        });
+        let images = parallel_filter_map(candidates, |candidate| {
+            // Also follows links, and makes sure the file is actually readable
+            match std::fs::metadata(&candidate) {
+                Ok(metadata) if metadata.is_file() => {
+                    found.fetch_add(1, Ordering::Relaxed);
+                    Some(candidate)
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    log::warn!("Skipping unreadable file {:?}: {}", candidate, e);
+                    None
+                }
+            }
+        });
 
         if images.is_empty() {
             return Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::NoImagesFound {
@@ -55,6 +186,87 @@ impl ImageDiscovery {
         }
 
         log::info!("Discovered {} images in {:?}", images.len(), path);
+        let images = Self::filter_resolution(path, options, images)?;
+        let images = if options.dedupe { dedupe(path, images) } else { images };
+        DiscoveryCache::global().insert(path, options, dirs, &images);
+        Ok(images)
+    }
+
+    /// `images` without those smaller than `options.min_resolution`, going by
+    /// their headers. Fails like an empty directory if none are left.
+    fn filter_resolution(path: &Path, options: &DiscoveryOptions, images: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+        let Some((min_width, min_height)) = options.min_resolution else { return Ok(images) };
+
+        let total = images.len();
+        let unknown = AtomicUsize::new(0);
+        let kept = parallel_filter_map(images.into_iter(), |image| match image_dimensions(&image) {
+            Some((width, height)) => (width >= min_width && height >= min_height).then_some(image),
+            None => {
+                unknown.fetch_add(1, Ordering::Relaxed);
+                options.keep_unknown_resolution.then_some(image)
+            }
+        });
+        let unknown = unknown.into_inner();
+
+        let dropped = total - kept.len();
+        if dropped > 0 || unknown > 0 {
+            log::info!("Dropped {} of {} images in {:?} below {}x{} ({} of unknown size, {})",
+                dropped, total, path, min_width, min_height, unknown,
+                if options.keep_unknown_resolution { "kept" } else { "dropped" });
+        }
+        if kept.is_empty() {
+            return Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::NoImagesFound {
+                path: path.to_path_buf(),
+            }));
+        }
+        Ok(kept)
+    }
+
+    /// Whether `path` is a playlist file rather than an image or a directory
+    pub fn is_playlist(path: &Path) -> bool {
+        path.is_file() && path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| PLAYLIST_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    }
+
+    /// Images listed in the playlist at `path`, in file order. Blank lines and
+    /// `#` comments are skipped, a leading `~` is the home directory, and a
+    /// relative entry is taken relative to the playlist. Entries that are
+    /// missing or not images are skipped with a warning; only a playlist
+    /// without a single usable entry is an error.
+    fn read_playlist(path: &Path) -> Result<Vec<PathBuf>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SwwwsError::ImageDiscovery(ImageDiscoveryError::FileAccess {
+                path: path.to_path_buf(),
+                source: e,
+            }))?;
+        let base = path.parent().unwrap_or(Path::new("/"));
+
+        let mut images = Vec::new();
+        let mut listed = HashSet::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let image = base.join(expand_home(line));
+            if let Err(e) = Self::validate_image(&image) {
+                log::warn!("Skipping line {} of playlist {:?}: {}", number + 1, path, e.user_friendly_message());
+                continue;
+            }
+            if listed.insert(image.clone()) {
+                images.push(image);
+            } else {
+                log::debug!("{:?} is listed more than once in playlist {:?}, keeping the first", image, path);
+            }
+        }
+
+        if images.is_empty() {
+            return Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::NoImagesFound {
+                path: path.to_path_buf(),
+            }));
+        }
+        log::info!("Read {} images from playlist {:?}", images.len(), path);
         Ok(images)
     }
 
@@ -135,10 +347,128 @@ impl ImageDiscovery {
     }
 
     pub fn get_supported_extensions() -> Vec<&'static str> {
-        vec!["jpg", "jpeg", "png", "gif", "bmp", "tiff", "webp", "avif"]
+        IMAGE_EXTENSIONS.to_vec()
+    }
+}
+
+/// Run `check` over `items` on up to `SCAN_WORKERS` threads, keeping what it
+/// returns in the order of `items`. Items are handed over through a bounded
+/// channel as they come, so a lazy `items` is never collected in full.
+fn parallel_filter_map<T: Send, U: Send>(items: impl Iterator<Item = T>, check: impl Fn(T) -> Option<U> + Sync) -> Vec<U> {
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(SCAN_WORKERS);
+    let (sender, receiver) = mpsc::sync_channel::<(usize, T)>(workers * 64);
+    let receiver = Mutex::new(receiver);
+
+    let mut kept: Vec<(usize, U)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| scope.spawn(|| {
+                let mut kept = Vec::new();
+                loop {
+                    // The lock is only held while waiting for the next item
+                    let next = receiver.lock().unwrap().recv();
+                    let Ok((index, item)) = next else { break };
+                    if let Some(result) = check(item) {
+                        kept.push((index, result));
+                    }
+                }
+                kept
+            }))
+            .collect();
+
+        for item in items.enumerate() {
+            if sender.send(item).is_err() {
+                break;
+            }
+        }
+        drop(sender);
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("discovery worker panicked"))
+            .collect()
+    });
+    kept.sort_unstable_by_key(|(index, _)| *index);
+    kept.into_iter().map(|(_, result)| result).collect()
+}
+
+fn has_image_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// `images` without files whose contents match an earlier one in path order,
+/// so a choice between copies doesn't depend on discovery order. Only files of
+/// the same size are read and compared.
+fn dedupe(path: &Path, images: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, image) in images.iter().enumerate() {
+        if let Ok(metadata) = std::fs::metadata(image) {
+            by_size.entry(metadata.len()).or_default().push(index);
+        }
+    }
+
+    let mut duplicate = vec![false; images.len()];
+    for mut same_size in by_size.into_values().filter(|same_size| same_size.len() > 1) {
+        same_size.sort_by(|&a, &b| images[a].cmp(&images[b]));
+        let mut first_with_hash: HashMap<u64, usize> = HashMap::new();
+        for index in same_size {
+            // Unreadable files are kept, they can't be shown to be copies
+            let Some(hash) = content_hash(&images[index]) else { continue };
+            match first_with_hash.get(&hash) {
+                Some(&first) => {
+                    log::debug!("{:?} has the same contents as {:?}, skipping it", images[index], images[first]);
+                    duplicate[index] = true;
+                }
+                None => {
+                    first_with_hash.insert(hash, index);
+                }
+            }
+        }
+    }
+
+    let collapsed = duplicate.iter().filter(|duplicate| **duplicate).count();
+    if collapsed > 0 {
+        log::info!("Skipped {} duplicate images in {:?}", collapsed, path);
+    }
+    images.into_iter()
+        .zip(duplicate)
+        .filter_map(|(image, duplicate)| (!duplicate).then_some(image))
+        .collect()
+}
+
+fn content_hash(path: &Path) -> Option<u64> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Some(hasher.finish()),
+            Ok(read) => hasher.write(&buffer[..read]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
     }
 }
 
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.as_encoded_bytes().starts_with(b".")
+}
+
+/// `entry` with a leading `~` replaced by the home directory
+fn expand_home(entry: &str) -> PathBuf {
+    match (entry.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(entry),
+    }
+}
+
+fn real_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,7 +489,7 @@ mod tests {
         fs::write(test_dir.join("image3.gif"), "fake gif").unwrap();
         fs::write(test_dir.join("text.txt"), "not an image").unwrap();
         
-        let images = ImageDiscovery::discover_images(test_dir).unwrap();
+        let images = ImageDiscovery::discover_images(test_dir, &DiscoveryOptions::default(), false).unwrap();
         
         assert_eq!(images.len(), 3);
         assert!(images.iter().any(|p| p.file_name().unwrap() == "image1.jpg"));
@@ -168,12 +498,197 @@ mod tests {
         assert!(!images.iter().any(|p| p.file_name().unwrap() == "text.txt"));
     }
 
+    #[test]
+    fn test_discover_images_rescans_changed_trees() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("image1.jpg"), "fake jpg").unwrap();
+
+        let images = ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), false).unwrap();
+        assert_eq!(DiscoveryCache::global().get(temp_dir.path(), &DiscoveryOptions::default()), Some(images.clone()));
+        assert_eq!(ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), false).unwrap(), images);
+
+        // A change deep in the tree is noticed
+        fs::write(nested.join("image2.jpg"), "fake jpg").unwrap();
+        assert_eq!(ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), false).unwrap().len(), 2);
+        fs::remove_file(nested.join("image1.jpg")).unwrap();
+        assert_eq!(ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), true).unwrap(), vec![nested.join("image2.jpg")]);
+    }
+
+    #[test]
+    fn test_discover_images_symlinks() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let sub = root.join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(root.join("a.png"), "fake png").unwrap();
+        fs::write(sub.join("b.png"), "fake png").unwrap();
+        // A link back up the tree, and a second way into sub
+        std::os::unix::fs::symlink(root, sub.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&sub, root.join("alias")).unwrap();
+
+        let mut images = ImageDiscovery::discover_images(root, &DiscoveryOptions::default(), true).unwrap();
+        images.sort();
+        let names: Vec<_> = images.iter().map(|image| image.file_name().unwrap().to_owned()).collect();
+        assert_eq!(names, vec!["a.png", "b.png"]);
+
+        let options = DiscoveryOptions { follow_symlinks: false, ..DiscoveryOptions::default() };
+        let images = ImageDiscovery::discover_images(root, &options, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert!(images.iter().all(|image| !image.starts_with(root.join("alias"))));
+
+        // Cached per set of options
+        assert_eq!(DiscoveryCache::global().get(root, &options).map(|images| images.len()), Some(2));
+        assert_eq!(DiscoveryCache::global().get(root, &DiscoveryOptions::default()), None);
+    }
+
+    #[test]
+    fn test_parallel_filter_map_keeps_order() {
+        let evens = parallel_filter_map(0..10_000, |n| (n % 2 == 0).then_some(n * 10));
+        assert_eq!(evens, (0..10_000).step_by(2).map(|n| n * 10).collect::<Vec<_>>());
+        assert!(parallel_filter_map(std::iter::empty::<u32>(), Some).is_empty());
+    }
+
+    #[test]
+    fn test_discover_images_deep_tree() {
+        let temp_dir = tempdir().unwrap();
+        let mut expected = Vec::new();
+        let mut level = vec![temp_dir.path().to_path_buf()];
+        for depth in 0..5 {
+            let mut next = Vec::new();
+            for dir in &level {
+                for branch in ["a", "b", "c"] {
+                    let sub = dir.join(branch);
+                    fs::create_dir(&sub).unwrap();
+                    fs::write(sub.join(format!("{}.png", depth)), "fake png").unwrap();
+                    fs::write(sub.join(format!("{}.JPG", depth)), "fake jpg").unwrap();
+                    fs::write(sub.join("notes.txt"), "not an image").unwrap();
+                    expected.push(sub.join(format!("{}.png", depth)));
+                    expected.push(sub.join(format!("{}.JPG", depth)));
+                    next.push(sub);
+                }
+            }
+            level = next;
+        }
+        expected.sort();
+
+        let images = ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), true).unwrap();
+        // Same order scan after scan
+        assert_eq!(ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), true).unwrap(), images);
+        let mut sorted = images.clone();
+        sorted.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn test_discover_images_dedupe() {
+        let temp_dir = tempdir().unwrap();
+        let dark = temp_dir.path().join("dark");
+        let light = temp_dir.path().join("light");
+        fs::create_dir(&dark).unwrap();
+        fs::create_dir(&light).unwrap();
+        fs::write(dark.join("sea.png"), "sea").unwrap();
+        fs::write(light.join("sea-copy.png"), "sea").unwrap();
+        fs::write(light.join("sky.png"), "sky").unwrap();
+        fs::write(light.join("longer.png"), "sea and sky").unwrap();
+
+        let options = DiscoveryOptions { dedupe: true, ..DiscoveryOptions::default() };
+        let mut images = ImageDiscovery::discover_images(temp_dir.path(), &options, true).unwrap();
+        images.sort();
+        // The copy that comes first by path stays
+        assert_eq!(images, vec![dark.join("sea.png"), light.join("longer.png"), light.join("sky.png")]);
+
+        assert_eq!(ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), true).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_discover_images_skip_hidden() {
+        let temp_dir = tempdir().unwrap();
+        let walls = temp_dir.path().join(".walls");
+        let cache = walls.join(".thumbnails");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(walls.join("a.png"), "fake png").unwrap();
+        fs::write(walls.join(".junk.png"), "fake png").unwrap();
+        fs::write(cache.join("a-small.png"), "fake png").unwrap();
+
+        // A hidden path itself is still searched
+        assert_eq!(ImageDiscovery::discover_images(&walls, &DiscoveryOptions::default(), true).unwrap(), vec![walls.join("a.png")]);
+
+        let options = DiscoveryOptions { skip_hidden: false, ..DiscoveryOptions::default() };
+        assert_eq!(ImageDiscovery::discover_images(&walls, &options, true).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_discover_images_max_depth() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let artist = root.join("artist");
+        let archive = artist.join("2019");
+        fs::create_dir_all(&archive).unwrap();
+        fs::write(root.join("a.png"), "fake png").unwrap();
+        fs::write(artist.join("b.png"), "fake png").unwrap();
+        fs::write(archive.join("c.png"), "fake png").unwrap();
+
+        let two_levels = DiscoveryOptions { max_depth: Some(2), ..DiscoveryOptions::default() };
+        let mut images = ImageDiscovery::discover_images(root, &two_levels, true).unwrap();
+        images.sort();
+        assert_eq!(images, vec![root.join("a.png"), artist.join("b.png")]);
+        assert_eq!(ImageDiscovery::discover_images(root, &DiscoveryOptions::default(), true).unwrap().len(), 3);
+
+        // Images only below the cutoff are no images at all
+        let top_level = DiscoveryOptions { max_depth: Some(1), ..DiscoveryOptions::default() };
+        assert_eq!(ImageDiscovery::discover_images(&artist, &top_level, true).unwrap(), vec![artist.join("b.png")]);
+        fs::remove_file(root.join("a.png")).unwrap();
+        assert!(matches!(ImageDiscovery::discover_images(root, &top_level, true),
+            Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::NoImagesFound { .. }))));
+    }
+
+    #[test]
+    fn test_discover_images_min_resolution() {
+        let temp_dir = tempdir().unwrap();
+        let png = |width: u32, height: u32| {
+            let mut bytes = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 13];
+            bytes.extend(b"IHDR");
+            bytes.extend(width.to_be_bytes());
+            bytes.extend(height.to_be_bytes());
+            bytes
+        };
+        fs::write(temp_dir.path().join("4k.png"), png(3840, 2160)).unwrap();
+        fs::write(temp_dir.path().join("tiny.png"), png(800, 600)).unwrap();
+        fs::write(temp_dir.path().join("narrow.png"), png(3840, 1000)).unwrap();
+        fs::write(temp_dir.path().join("unknown.avif"), "no header").unwrap();
+
+        let mut options = DiscoveryOptions { min_resolution: Some((1920, 1080)), ..DiscoveryOptions::default() };
+        let mut images = ImageDiscovery::discover_images(temp_dir.path(), &options, true).unwrap();
+        images.sort();
+        assert_eq!(images, vec![temp_dir.path().join("4k.png"), temp_dir.path().join("unknown.avif")]);
+
+        options.keep_unknown_resolution = false;
+        assert_eq!(ImageDiscovery::discover_images(temp_dir.path(), &options, true).unwrap(), vec![temp_dir.path().join("4k.png")]);
+
+        options.min_resolution = Some((7680, 4320));
+        assert!(matches!(ImageDiscovery::discover_images(temp_dir.path(), &options, true),
+            Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::NoImagesFound { .. }))));
+    }
+
+    #[tokio::test]
+    async fn test_discover_images_async() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("nested/image1.jpg"), "fake jpg").unwrap();
+
+        let images = ImageDiscovery::discover_images_async(temp_dir.path().to_path_buf(), DiscoveryOptions::default(), false).await.unwrap();
+        assert_eq!(images, ImageDiscovery::discover_images(temp_dir.path(), &DiscoveryOptions::default(), false).unwrap());
+        assert!(ImageDiscovery::discover_images_async(temp_dir.path().join("missing"), DiscoveryOptions::default(), true).await.is_err());
+    }
+
     #[test]
     fn test_discover_images_empty_directory() {
         let temp_dir = tempdir().unwrap();
         let test_dir = temp_dir.path();
         
-        let result = ImageDiscovery::discover_images(test_dir);
+        let result = ImageDiscovery::discover_images(test_dir, &DiscoveryOptions::default(), false);
         assert!(result.is_err());
         
         match result.unwrap_err() {
@@ -184,11 +699,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_discover_images_single_file() {
+        let temp_dir = tempdir().unwrap();
+        let image = temp_dir.path().join("only.png");
+        fs::write(&image, [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]).unwrap();
+
+        assert_eq!(ImageDiscovery::discover_images(&image, &DiscoveryOptions::default(), false).unwrap(), vec![image.clone()]);
+
+        // Files are checked like images, not just by name
+        let fake = temp_dir.path().join("fake.png");
+        fs::write(&fake, "not a png").unwrap();
+        assert!(matches!(ImageDiscovery::discover_images(&fake, &DiscoveryOptions::default(), false),
+            Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::CorruptedImage { .. }))));
+        let text = temp_dir.path().join("notes.md");
+        fs::write(&text, "hello").unwrap();
+        assert!(matches!(ImageDiscovery::discover_images(&text, &DiscoveryOptions::default(), false),
+            Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::UnsupportedFormat { .. }))));
+    }
+
+    #[test]
+    fn test_discover_images_playlist() {
+        let temp_dir = tempdir().unwrap();
+        let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        let first = temp_dir.path().join("z-first.png");
+        let second = temp_dir.path().join("a-second.png");
+        fs::write(&first, png).unwrap();
+        fs::write(&second, png).unwrap();
+        fs::write(temp_dir.path().join("fake.png"), "not a png").unwrap();
+
+        let playlist = temp_dir.path().join("evening.m3u");
+        fs::write(&playlist, format!(
+            "# evening rotation\n\n{}\n/nowhere/missing.png\nfake.png\n  a-second.png  \n{}\n",
+            first.display(), first.display(),
+        )).unwrap();
+        // In file order, with missing, broken and repeated entries skipped
+        assert_eq!(ImageDiscovery::discover_images(&playlist, &DiscoveryOptions::default(), false).unwrap(), vec![first, second]);
+
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/walls/a.png"), home.join("walls/a.png"));
+        assert_eq!(expand_home("~user/a.png"), PathBuf::from("~user/a.png"));
+
+        // Nothing usable left is an error
+        let empty = temp_dir.path().join("empty.txt");
+        fs::write(&empty, "# nothing yet\n/nowhere/missing.png\n").unwrap();
+        assert!(matches!(ImageDiscovery::discover_images(&empty, &DiscoveryOptions::default(), false),
+            Err(SwwwsError::ImageDiscovery(ImageDiscoveryError::NoImagesFound { .. }))));
+    }
+
     #[test]
     fn test_discover_images_nonexistent_directory() {
         let nonexistent_path = Path::new("/nonexistent/directory");
         
-        let result = ImageDiscovery::discover_images(nonexistent_path);
+        let result = ImageDiscovery::discover_images(nonexistent_path, &DiscoveryOptions::default(), false);
         assert!(result.is_err());
         
         match result.unwrap_err() {
@@ -206,7 +769,7 @@ mod tests {
         
         // Create a valid image file with proper JPEG header
         let jpeg_header = [0xFF, 0xD8, 0xFF, 0xE0]; // JPEG magic bytes
-        fs::write(test_dir.join("valid.jpg"), &jpeg_header).unwrap();
+        fs::write(test_dir.join("valid.jpg"), jpeg_header).unwrap();
         let valid_path = test_dir.join("valid.jpg");
         
         // Create an invalid file
@@ -249,7 +812,7 @@ mod tests {
         fs::write(test_dir.join("image2.PNG"), "fake png").unwrap();
         fs::write(test_dir.join("image3.GIF"), "fake gif").unwrap();
         
-        let images = ImageDiscovery::discover_images(test_dir).unwrap();
+        let images = ImageDiscovery::discover_images(test_dir, &DiscoveryOptions::default(), false).unwrap();
         
         assert_eq!(images.len(), 3);
         assert!(images.iter().any(|p| p.file_name().unwrap() == "image1.JPG"));
@@ -270,7 +833,7 @@ mod tests {
         fs::write(test_dir.join("root.jpg"), "fake jpg").unwrap();
         fs::write(subdir.join("sub.png"), "fake png").unwrap();
         
-        let images = ImageDiscovery::discover_images(test_dir).unwrap();
+        let images = ImageDiscovery::discover_images(test_dir, &DiscoveryOptions::default(), false).unwrap();
         
         assert_eq!(images.len(), 2);
         assert!(images.iter().any(|p| p.file_name().unwrap() == "root.jpg"));
@@ -297,7 +860,7 @@ mod tests {
         perms.set_mode(0o000);
         fs::set_permissions(&restricted_dir, perms).unwrap();
         
-        let result = ImageDiscovery::discover_images(&restricted_dir);
+        let result = ImageDiscovery::discover_images(&restricted_dir, &DiscoveryOptions::default(), false);
         assert!(result.is_err());
         
         // Restore permissions for cleanup
@@ -1,115 +1,750 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
 use std::os::unix::net::UnixStream;
 use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
 use serde::{Serialize, Deserialize};
-use anyhow::{Result, Context};
+use anyhow::{Result, Context, bail};
+use crate::problems::{ProblemCategory, ProblemReport};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum IpcCommand {
-    Next { output: Option<String> },
-    Previous { output: Option<String> },
+    Next { output: Option<String>, #[serde(default)] group: Option<String> }, // group by name or index
+    Previous { output: Option<String>, #[serde(default)] group: Option<String> },
     Pause,
     Resume,
+    PauseGroup { group: String }, // stops one group's timer, the rest carry on
+    ResumeGroup { group: String },
     TogglePause,
     Reload,
     Status,
+    ConfigureOutput { output: String, settings: OutputSettings, persist: bool },
+    Problems,
+    ClearProblems { category: Option<ProblemCategory> }, // None clears every category
+    QueueList {
+        output: Option<String>,
+        count: usize, // upcoming images
+        #[serde(default)]
+        group: Option<String>,
+        #[serde(default)]
+        recent: usize, // images shown before the current one
+    }, // no output or group: every queue, each listed once
+    SetImage { output: Option<String>, path: PathBuf }, // None shows it on every output
+    Shuffle { output: Option<String> }, // None reshuffles every queue
+    GetCurrent { output: Option<String> }, // None: every managed output
+    SetInterval { output: Option<String>, duration: Option<String> }, // None duration goes back to the config's
+    GetConfig { output: Option<String> }, // None: every connected output
+    Subscribe, // keeps the connection open and streams an IpcEvent frame for each event
+    Rescan { output: Option<String> }, // None rescans every queue
+    Shutdown, // the daemon exits once the answer is sent
+    SetBehavior { behavior: String, groups: Option<Vec<Vec<String>>> }, // None groups keeps the configured ones
+    History { output: Option<String>, limit: usize }, // None: changes on every output
+    Outputs, // every output swww reports, and configured ones it doesn't
+    Ping, // answered right away, even while the daemon is busy
+    Blacklist { output: Option<String>, path: Option<PathBuf> }, // no path: the image on screen on `output`
+    Unblacklist { path: PathBuf },
+    ListBlacklist,
+    Pin { output: Option<String> }, // None pins every managed output to the image it shows
+    Unpin { output: Option<String> }, // None unpins every output
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum IpcResponse {
     Success { message: String },
-    Error { message: String },
+    Error {
+        message: String,
+        #[serde(default)]
+        kind: ErrorKind, // older daemons send no kind; theirs read as Failed
+    },
     Status {
         outputs: Vec<OutputStatus>,
         paused: bool,
+        #[serde(default)]
+        daemon: Option<DaemonInfo>,
+    },
+    Problems { categories: Vec<ProblemReport> },
+    QueueList { queues: Vec<QueueListing> }, // in output order
+    Current { images: Vec<CurrentImage> }, // in output order
+    History { changes: Vec<HistoryEntry> }, // newest first
+    Outputs { outputs: Vec<OutputInfo> }, // in `swww query` order, then configured outputs not found
+    Blacklist { paths: Vec<String> }, // canonical, in path order
+    Reloaded { message: String, summary: ReloadSummary },
+    Pong {
+        version: String, // of swwws-daemon
+        uptime: u64, // seconds
+        #[serde(default)]
+        pid: Option<u32>, // of swwws-daemon; unset by daemons that predate it
+    },
+    Config {
+        behavior: String, // the monitor behavior the daemon is running with
+        outputs: Vec<EffectiveConfig>,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl IpcResponse {
+    pub fn error(kind: ErrorKind, message: impl Into<String>) -> Self {
+        IpcResponse::Error { message: message.into(), kind }
+    }
+}
+
+/// Why the daemon refused a command, so clients can tell a typo in an output
+/// name from a daemon that can't do what it was asked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ErrorKind {
+    /// Carrying out the command went wrong
+    #[default]
+    Failed,
+    /// The command contradicts itself or has a malformed value
+    InvalidArgument,
+    /// It names an output, group or image that doesn't exist
+    NotFound,
+    /// It doesn't apply right now, e.g. to a disabled output or a static wallpaper
+    Unavailable,
+}
+
+/// One queue and the outputs it drives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueListing {
+    pub queue: String, // the output, or the group or shared queue driving it
+    pub mode: OutputMode,
+    pub outputs: Vec<String>,
+    pub position: usize, // of the current image this cycle, from 1
+    pub size: usize,
+    pub images: Vec<QueueEntry>, // recent images, the current one, then upcoming ones in order
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub path: String,
+    pub current: bool,
+    #[serde(default)]
+    pub offset: i64, // from the current image: -1 was shown before it, 1 comes next
+}
+
+/// An output swww reports, or one the config has a section for that it doesn't
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputInfo {
+    pub name: String,
+    /// False for a configured output swww didn't report
+    pub connected: bool,
+    pub size: Option<(u32, u32)>,
+    pub scale: Option<String>, // as swww prints it
+    /// Whether a section named after it applies; without one only `[any]` does
+    pub section: bool,
+    pub group: Option<String>,
+    pub enabled: bool,
+    /// The queue driving it; None when it has none
+    pub queue: Option<String>,
+    /// For an output not found, a connected one whose name differs only in case
+    pub near_match: Option<String>,
+}
+
+/// A wallpaper change from the daemon's change log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub output: String,
+    pub image: String, // full path
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub trigger: String, // the ChangeReason: startup, timer, manual, refresh or schedule
+}
+
+/// Something that happened in the daemon, streamed to `Subscribe` connections
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcEvent {
+    WallpaperChanged {
+        output: String,
+        image: String, // full path
+        trigger: String, // the ChangeReason: startup, timer, manual, refresh or schedule
+    },
+    Paused,
+    Resumed,
+    Reloaded { success: bool, message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentImage {
+    pub output: String,
+    pub path: Option<String>, // full path; None before the first image is shown
+    pub mode: OutputMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    pub output: String,
+    pub mode: OutputMode,
+    pub toml: String, // the merged settings as a `[output]` section
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub uptime: u64, // seconds
+    pub config_path: String,
+    pub reloads_succeeded: u32,
+    pub reloads_failed: u32,
+    pub last_reload: Option<ReloadRecord>,
+    pub config_modified: bool, // config file changed on disk since it was last loaded
+    #[serde(default)]
+    pub runtime_outputs: Vec<String>, // outputs with settings from `output configure` that aren't in the file
+    #[serde(default)]
+    pub dry_run: bool, // swww commands are printed instead of run
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadRecord {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub success: bool,
+    pub message: String,
+}
+
+/// What a reload changed, for `swwws-cli reload --verbose`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReloadSummary {
+    pub changes: Vec<String>, // one line per setting, e.g. `DP-1: duration "5m" → "10m"`
+    pub rebuilt: Vec<String>, // queues built again, named as `swwws-cli queue` lists them
+    pub outputs: Vec<String>, // outputs whose settings changed
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputStatus {
     pub name: String,
-    pub current_image: Option<String>,
+    pub current_image: Option<String>, // full path
     pub queue_position: usize,
     pub queue_size: usize,
     pub timer_remaining: Option<u64>, // seconds
     pub paused: bool,
+    #[serde(default)]
+    pub static_wallpaper: bool, // duration = "never"; the timer never fires
+    #[serde(default)]
+    pub disabled: bool, // enabled = false; swwws leaves this output alone
+    #[serde(default)]
+    pub mode: OutputMode,
+    #[serde(default)]
+    pub pinned: bool, // held on current_image by `swwws-cli pin`
+}
+
+/// Which queue drives an output
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OutputMode {
+    /// Its own queue
+    #[default]
+    Independent,
+    /// The queue shared by every output
+    Synchronized,
+    /// The queue of the named group
+    Group(String),
+}
+
+impl OutputStatus {
+    /// A connected output that swwws doesn't manage
+    pub fn disabled(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            current_image: None,
+            queue_position: 0,
+            queue_size: 0,
+            timer_remaining: None,
+            paused: false,
+            static_wallpaper: false,
+            disabled: true,
+            mode: OutputMode::Independent,
+            pinned: false,
+        }
+    }
+}
+
+/// Status as `swwws-cli status --json` prints it, for scripts and status bars.
+///
+/// Kept apart from `OutputStatus` so the IPC format can change without
+/// breaking them: fields may be added here, but none are renamed or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub paused: bool,
+    pub outputs: Vec<OutputReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputReport {
+    pub name: String,
+    /// Full path of the image on screen
+    pub image: Option<String>,
+    /// Index of the current image in the queue, starting at 0
+    pub queue_position: usize,
+    pub queue_size: usize,
+    /// Seconds until the next change; None for static, pinned and disabled outputs
+    pub remaining_seconds: Option<u64>,
+    pub paused: bool,
+    #[serde(rename = "static")]
+    pub static_wallpaper: bool,
+    pub disabled: bool,
+    /// Held on `image` by `swwws-cli pin` until unpinned
+    pub pinned: bool,
+    /// "independent", "synchronized" or "group"
+    pub mode: String,
+    /// Name of the group, when `mode` is "group"
+    pub group: Option<String>,
+}
+
+impl StatusReport {
+    pub fn new(outputs: &[OutputStatus], paused: bool) -> Self {
+        let outputs = outputs.iter()
+            .map(|output| {
+                let (mode, group) = match &output.mode {
+                    OutputMode::Independent => ("independent", None),
+                    OutputMode::Synchronized => ("synchronized", None),
+                    OutputMode::Group(group) => ("group", Some(group.clone())),
+                };
+                OutputReport {
+                    name: output.name.clone(),
+                    image: output.current_image.clone(),
+                    queue_position: output.queue_position,
+                    queue_size: output.queue_size,
+                    remaining_seconds: output.timer_remaining,
+                    paused: output.paused,
+                    static_wallpaper: output.static_wallpaper,
+                    disabled: output.disabled,
+                    pinned: output.pinned,
+                    mode: mode.to_string(),
+                    group,
+                }
+            })
+            .collect();
+        Self { paused, outputs }
+    }
+}
+
+/// Name of the socket for a Wayland display, so that each session gets its own
+/// daemon. Like swww, only the last component of a `WAYLAND_DISPLAY` path is
+/// used, and `wayland-0` stands in when it is unset.
+fn socket_name(wayland_display: Option<&str>) -> String {
+    let display = wayland_display
+        .filter(|display| !display.is_empty())
+        .map(|display| display.rsplit('/').next().unwrap_or(display))
+        .unwrap_or("wayland-0");
+    format!("swwws-{}.sock", display)
+}
+
+/// Where the IPC socket lives. The daemon and the CLI compute it the same way,
+/// so they still find each other when the runtime dir can't be used.
+/// `SWWWS_SOCKET` overrides it for both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketLocation {
+    pub path: PathBuf,
+    /// Why `$XDG_RUNTIME_DIR` wasn't used, when it wasn't
+    pub fallback_reason: Option<String>,
+}
+
+impl SocketLocation {
+    /// Location for the current user and environment
+    pub fn current() -> Self {
+        if let Some(path) = std::env::var_os("SWWWS_SOCKET").filter(|path| !path.is_empty()) {
+            return Self { path: PathBuf::from(path), fallback_reason: None };
+        }
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        let wayland_display = std::env::var("WAYLAND_DISPLAY").ok();
+        Self::resolve(runtime_dir, wayland_display.as_deref(), Path::new("/tmp"), rustix::process::getuid().as_raw())
+    }
+
+    /// The socket for `wayland_display` in `runtime_dir` if that is a writable
+    /// directory, otherwise in a private `swwws-$UID` directory under `fallback_base`
+    pub fn resolve(runtime_dir: Option<PathBuf>, wayland_display: Option<&str>, fallback_base: &Path, uid: u32) -> Self {
+        let name = socket_name(wayland_display);
+        let fallback_reason = match &runtime_dir {
+            None => Some("XDG_RUNTIME_DIR is not set".to_string()),
+            Some(dir) if !dir.is_dir() => Some(format!("XDG_RUNTIME_DIR {:?} does not exist", dir)),
+            Some(dir) if rustix::fs::access(dir, rustix::fs::Access::WRITE_OK).is_err() => {
+                Some(format!("XDG_RUNTIME_DIR {:?} is not writable", dir))
+            }
+            Some(_) => None,
+        };
+
+        match (runtime_dir, fallback_reason) {
+            (Some(dir), None) => Self { path: dir.join(name), fallback_reason: None },
+            (_, fallback_reason) => Self {
+                path: fallback_base.join(format!("swwws-{}", uid)).join(name),
+                fallback_reason,
+            },
+        }
+    }
+
+    /// Create the private fallback directory if needed, and refuse any directory
+    /// where another user could replace the socket
+    pub fn prepare_dir(&self) -> Result<()> {
+        let dir = self.path.parent()
+            .with_context(|| format!("Socket path {:?} has no parent directory", self.path))?;
+
+        if self.fallback_reason.is_some() {
+            if let Err(e) = std::fs::DirBuilder::new().mode(0o700).create(dir) {
+                if e.kind() != std::io::ErrorKind::AlreadyExists {
+                    return Err(e).with_context(|| format!("Failed to create socket directory {:?}", dir));
+                }
+            }
+
+            let metadata = std::fs::symlink_metadata(dir)
+                .with_context(|| format!("Failed to inspect socket directory {:?}", dir))?;
+            if !metadata.is_dir()
+                || metadata.uid() != rustix::process::getuid().as_raw()
+                || metadata.mode() & 0o077 != 0
+            {
+                bail!("Refusing to use socket directory {:?}: it must be a directory owned by you with mode 0700", dir);
+            }
+        }
+
+        let metadata = std::fs::metadata(dir)
+            .with_context(|| format!("Failed to inspect socket directory {:?}", dir))?;
+        if metadata.mode() & 0o002 != 0 {
+            bail!("Refusing to put the IPC socket in world-writable directory {:?}", dir);
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings given by `swwws-cli output configure`, as they would be written in
+/// the output's config section. Unset fields are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputSettings {
+    pub path: Option<String>,
+    pub duration: Option<String>,
+}
+
+impl OutputSettings {
+    /// Set fields as config keys and values, in a stable order
+    pub fn entries(&self) -> Vec<(&'static str, &str)> {
+        [("path", &self.path), ("duration", &self.duration)]
+            .into_iter()
+            .filter_map(|(key, value)| value.as_deref().map(|value| (key, value)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Take over the fields set in `other`
+    pub fn update(&mut self, other: &OutputSettings) {
+        if other.path.is_some() {
+            self.path = other.path.clone();
+        }
+        if other.duration.is_some() {
+            self.duration = other.duration.clone();
+        }
+    }
+}
+
+/// Version of the IPC protocol, sent with every command and response. Bumped
+/// when the commands or responses change in a way the other side can't read.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionedCommand {
+    version: u64,
+    command: IpcCommand,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionedResponse {
+    version: u64,
+    response: IpcResponse,
+}
+
+/// What to tell the user when swwws-cli and the daemon speak different
+/// protocol versions. Version 0 is a side from before versions were sent.
+fn version_mismatch(client: u64, daemon: u64) -> String {
+    let stale = if client < daemon {
+        "swwws-cli is older than the daemon: update it, or check which swwws-cli comes first in PATH"
+    } else {
+        "the daemon is older than swwws-cli: restart swwws-daemon so the updated one runs"
+    };
+    format!("swwws-cli speaks IPC protocol version {} and the daemon version {}; {}", client, daemon, stale)
+}
+
+/// The `version` field of a JSON message, if it has one
+fn message_version(json: &serde_json::Value) -> Option<u64> {
+    json.get("version").and_then(serde_json::Value::as_u64)
+}
+
+/// The command in `json`, or what to answer instead, and whether the client sent
+/// a version. An unversioned client is older than versions; its command is still
+/// run if it reads as one.
+fn parse_command(json: &[u8]) -> (std::result::Result<IpcCommand, String>, bool) {
+    let value: serde_json::Value = match serde_json::from_slice(json) {
+        Ok(value) => value,
+        Err(e) => return (Err(format!("Malformed command: {}", e)), false),
+    };
+    match message_version(&value) {
+        Some(PROTOCOL_VERSION) => {
+            let command = serde_json::from_value::<VersionedCommand>(value)
+                .map(|versioned| versioned.command)
+                .map_err(|e| format!("Malformed command: {}", e));
+            (command, true)
+        }
+        Some(version) => (Err(version_mismatch(version, PROTOCOL_VERSION)), true),
+        None => (serde_json::from_value(value).map_err(|_| version_mismatch(0, PROTOCOL_VERSION)), false),
+    }
+}
+
+fn is_eof(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::UnexpectedEof)
+}
+
+/// Largest message either side accepts. Well above any real command or
+/// response; a bigger length prefix means a broken or hostile peer.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// How long the server waits for a client to send its command
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long an event may wait on a subscriber that stopped reading before it is dropped
+const EVENT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Write `payload` as one frame: its length as 4 little-endian bytes, then the bytes
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> Result<()> {
+    if payload.len() > MAX_FRAME_SIZE {
+        bail!("IPC message of {} bytes is over the {} byte limit", payload.len(), MAX_FRAME_SIZE);
+    }
+    writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read one frame written by `write_frame`
+pub fn read_frame(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let mut prefix = [0u8; 4];
+    reader.read_exact(&mut prefix)?;
+    read_payload(reader, prefix)
+}
+
+fn read_payload(reader: &mut impl Read, prefix: [u8; 4]) -> Result<Vec<u8>> {
+    let length = u32::from_le_bytes(prefix) as usize;
+    if length > MAX_FRAME_SIZE {
+        bail!("IPC message of {} bytes is over the {} byte limit", length, MAX_FRAME_SIZE);
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// A command as the client sent it
+#[derive(Debug, PartialEq, Eq)]
+enum Request {
+    Framed(Vec<u8>),
+    /// Bare JSON up to EOF, from a client older than framing. Gets an unframed
+    /// response. Kept for one release.
+    Legacy(Vec<u8>),
+}
+
+/// Read a command, framed or not. Bare JSON starts with `{` or `"`, and its
+/// first four bytes read as a length far over `MAX_FRAME_SIZE`, so the two
+/// can't be mistaken for each other.
+fn read_request(reader: &mut impl Read) -> Result<Request> {
+    let mut prefix = [0u8; 4];
+    reader.read_exact(&mut prefix)?;
+    if u32::from_le_bytes(prefix) as usize > MAX_FRAME_SIZE && matches!(prefix[0], b'{' | b'"') {
+        let mut json = prefix.to_vec();
+        reader.read_to_end(&mut json)?;
+        return Ok(Request::Legacy(json));
+    }
+    read_payload(reader, prefix).map(Request::Framed)
+}
+
+/// Connections that asked for events with `Subscribe`. Clones share the same
+/// connections, so the daemon can broadcast from wherever an event happens.
+#[derive(Debug, Clone, Default)]
+pub struct Subscribers {
+    streams: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+impl Subscribers {
+    fn add(&self, stream: UnixStream) -> Result<()> {
+        stream.set_write_timeout(Some(EVENT_WRITE_TIMEOUT))?;
+        self.streams.lock().unwrap().push(stream);
+        Ok(())
+    }
+
+    /// Send `event` to every subscriber. Those that hung up or stopped reading are dropped.
+    pub fn broadcast(&self, event: &IpcEvent) {
+        let mut streams = self.streams.lock().unwrap();
+        if streams.is_empty() {
+            return;
+        }
+        let json = match serde_json::to_vec(event) {
+            Ok(json) => json,
+            Err(e) => {
+                log::error!("Failed to serialize IPC event: {}", e);
+                return;
+            }
+        };
+        streams.retain_mut(|stream| match write_frame(stream, &json) {
+            Ok(()) => true,
+            Err(e) => {
+                log::debug!("Dropping IPC subscriber: {}", e);
+                false
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Events from a `Subscribe` connection, as they come
+pub struct EventStream {
+    stream: UnixStream,
+}
+
+impl Iterator for EventStream {
+    type Item = Result<IpcEvent>;
+
+    /// Blocks until the next event. Ends when the daemon closes the connection.
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = match read_frame(&mut self.stream) {
+            Ok(frame) => frame,
+            Err(e) if is_eof(&e) => return None,
+            Err(e) => return Some(Err(e.context("Failed to read event from daemon"))),
+        };
+        Some(serde_json::from_slice(&frame).with_context(|| "Failed to deserialize event"))
+    }
 }
 
 pub struct IpcClient {
     socket_path: PathBuf,
+    timeout: Option<Duration>,
 }
 
 impl IpcClient {
     pub fn new() -> Self {
-        let socket_path = dirs::runtime_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join("swwws.sock");
-        Self { socket_path }
+        Self { socket_path: SocketLocation::current().path, timeout: None }
+    }
+
+    /// Give up on sending the command or reading the answer after `timeout`,
+    /// instead of waiting as long as the daemon takes. A timed-out command
+    /// fails with an `io::ErrorKind::WouldBlock` error in its chain.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
     }
 
     pub fn send_command(&self, command: IpcCommand) -> Result<IpcResponse> {
+        self.exchange(command).map(|(response, _)| response)
+    }
+
+    /// Ask for events. The daemon's answer comes first; after a success, events
+    /// arrive on the stream until either side hangs up.
+    pub fn subscribe(&self) -> Result<(IpcResponse, EventStream)> {
+        self.exchange(IpcCommand::Subscribe).map(|(response, stream)| (response, EventStream { stream }))
+    }
+
+    fn exchange(&self, command: IpcCommand) -> Result<(IpcResponse, UnixStream)> {
         let mut stream = UnixStream::connect(&self.socket_path)
-            .with_context(|| format!("Failed to connect to swwws daemon at {:?}", self.socket_path))?;
+            .with_context(|| format!(
+                "Failed to connect to swwws daemon at {:?} (the socket follows WAYLAND_DISPLAY; set SWWWS_SOCKET to use another)",
+                self.socket_path,
+            ))?;
+        stream.set_read_timeout(self.timeout)
+            .and_then(|_| stream.set_write_timeout(self.timeout))
+            .with_context(|| "Failed to set IPC timeout")?;
 
-        let command_json = serde_json::to_string(&command)
+        let command_json = serde_json::to_string(&VersionedCommand { version: PROTOCOL_VERSION, command })
             .with_context(|| "Failed to serialize command")?;
         
-        stream.write_all(command_json.as_bytes())
+        write_frame(&mut stream, command_json.as_bytes())
             .with_context(|| "Failed to send command to daemon")?;
+        // A daemon from before framing reads up to EOF, so it gives up instead of waiting forever
         stream.shutdown(std::net::Shutdown::Write)
             .with_context(|| "Failed to shutdown write stream")?;
 
-        let mut response = String::new();
-        stream.read_to_string(&mut response)
-            .with_context(|| "Failed to read response from daemon")?;
+        let response = match read_frame(&mut stream) {
+            Err(e) if is_eof(&e) => {
+                bail!("The daemon closed the connection without answering; it may be older than swwws-cli, so try restarting swwws-daemon");
+            }
+            response => response.with_context(|| "Failed to read response from daemon")?,
+        };
 
-        let ipc_response: IpcResponse = serde_json::from_str(&response)
+        let response: serde_json::Value = serde_json::from_slice(&response)
+            .with_context(|| "Failed to deserialize response")?;
+        match message_version(&response) {
+            Some(PROTOCOL_VERSION) => {}
+            version => bail!("{}", version_mismatch(PROTOCOL_VERSION, version.unwrap_or(0))),
+        }
+        let VersionedResponse { response, .. } = serde_json::from_value(response)
             .with_context(|| "Failed to deserialize response")?;
 
-        Ok(ipc_response)
+        Ok((response, stream))
+    }
+}
+
+impl Default for IpcClient {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 pub struct IpcServer {
-    socket_path: PathBuf,
+    location: SocketLocation,
+    subscribers: Subscribers,
+    shutdown: Arc<Notify>,
 }
 
 impl IpcServer {
     pub fn new() -> Self {
-        let socket_path = dirs::runtime_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join("swwws.sock");
-        Self { socket_path }
+        Self { location: SocketLocation::current(), subscribers: Subscribers::default(), shutdown: Arc::new(Notify::new()) }
+    }
+
+    /// Register `Subscribe` connections with `subscribers`, for the daemon to broadcast into
+    pub fn with_subscribers(mut self, subscribers: Subscribers) -> Self {
+        self.subscribers = subscribers;
+        self
+    }
+
+    /// Notify `shutdown` once a `Shutdown` command has been answered with
+    /// success. The socket is removed first, so no new client connects.
+    pub fn with_shutdown(mut self, shutdown: Arc<Notify>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.location.path
     }
 
     pub fn start<F>(&self, handler: F) -> Result<()>
     where
         F: Fn(IpcCommand) -> Result<IpcResponse> + Send + Clone + 'static,
     {
-        // Remove existing socket if it exists
-        if self.socket_path.exists() {
-            std::fs::remove_file(&self.socket_path)
-                .with_context(|| "Failed to remove existing socket")?;
-        }
-
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = self.socket_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| "Failed to create socket directory")?;
-        }
-
-        let listener = std::os::unix::net::UnixListener::bind(&self.socket_path)
-            .with_context(|| format!("Failed to bind to socket {:?}", self.socket_path))?;
-
-        log::info!("IPC server listening on {:?}", self.socket_path);
+        let listener = self.bind()?;
+        log::info!("IPC server listening on {:?}", self.socket_path());
 
         for stream in listener.incoming() {
             match stream {
                 Ok(stream) => {
                     let handler = handler.clone();
+                    let subscribers = self.subscribers.clone();
+                    let shutdown = Arc::clone(&self.shutdown);
+                    let socket_path = self.socket_path().to_path_buf();
                     std::thread::spawn(move || {
-                        if let Err(e) = Self::handle_connection(stream, &handler) {
-                            log::error!("Error handling IPC connection: {}", e);
+                        match Self::handle_connection(stream, &handler, &subscribers) {
+                            Ok(true) => {
+                                if let Err(e) = std::fs::remove_file(&socket_path) {
+                                    log::warn!("Failed to remove socket {:?}: {}", socket_path, e);
+                                }
+                                shutdown.notify_one();
+                            }
+                            Ok(false) => {}
+                            Err(e) => log::error!("Error handling IPC connection: {}", e),
                         }
                     });
                 }
@@ -122,29 +757,449 @@ impl IpcServer {
         Ok(())
     }
 
+    /// Bind the socket, readable and writable by its owner only. A socket left
+    /// behind by a daemon that died is replaced; one that still answers is not.
+    fn bind(&self) -> Result<std::os::unix::net::UnixListener> {
+        self.location.prepare_dir()?;
+
+        let path = self.socket_path();
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                bail!("{:?} exists and is not a socket; move it out of the way or set SWWWS_SOCKET", path);
+            }
+            if UnixStream::connect(path).is_ok() {
+                bail!("Another swwws daemon is already running on {:?}; stop it with `swwws-cli stop` first", path);
+            }
+            log::info!("Removing stale socket {:?}", path);
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove stale socket {:?}", path))?;
+        }
+
+        let listener = std::os::unix::net::UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind to socket {:?}", path))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict permissions of socket {:?}", path))?;
+        Ok(listener)
+    }
+
+    /// Answer the command on `stream`. Returns true if it was a `Shutdown` the
+    /// handler agreed to.
     fn handle_connection<F>(
         mut stream: std::os::unix::net::UnixStream,
         handler: &F,
-    ) -> Result<()>
+        subscribers: &Subscribers,
+    ) -> Result<bool>
     where
         F: Fn(IpcCommand) -> Result<IpcResponse>,
     {
-        let mut command_json = String::new();
-        stream.read_to_string(&mut command_json)
+        // A client that connects and sends nothing doesn't hold a thread forever
+        stream.set_read_timeout(Some(READ_TIMEOUT))
+            .with_context(|| "Failed to set IPC read timeout")?;
+        let request = read_request(&mut stream)
             .with_context(|| "Failed to read command from client")?;
+        let (command, versioned) = match &request {
+            Request::Framed(json) | Request::Legacy(json) => parse_command(json),
+        };
 
-        let command: IpcCommand = serde_json::from_str(&command_json)
-            .with_context(|| "Failed to deserialize command")?;
+        // Answered in the form the client asked in
+        let encode = |response: IpcResponse| {
+            if versioned {
+                serde_json::to_string(&VersionedResponse { version: PROTOCOL_VERSION, response })
+            } else {
+                serde_json::to_string(&response)
+            }
+        };
 
-        let response = handler(command)
-            .unwrap_or_else(|e| IpcResponse::Error { message: e.to_string() });
+        let shutdown = matches!(command, Ok(IpcCommand::Shutdown));
+        let response = match command {
+            // Events are framed, so only framed clients can subscribe
+            Ok(IpcCommand::Subscribe) if matches!(request, Request::Framed(_)) => {
+                let response = IpcResponse::Success { message: "Subscribed to daemon events".to_string() };
+                write_frame(&mut stream, encode(response)?.as_bytes())
+                    .with_context(|| "Failed to send response to client")?;
+                subscribers.add(stream).with_context(|| "Failed to register IPC subscriber")?;
+                return Ok(false);
+            }
+            Ok(command) => handler(command).unwrap_or_else(|e| IpcResponse::error(ErrorKind::Failed, e.to_string())),
+            Err(message) => {
+                log::warn!("Rejected IPC command: {}", message);
+                IpcResponse::error(ErrorKind::InvalidArgument, message)
+            }
+        };
 
-        let response_json = serde_json::to_string(&response)
+        let stopping = shutdown && matches!(response, IpcResponse::Success { .. });
+        let response_json = encode(response)
             .with_context(|| "Failed to serialize response")?;
 
-        stream.write_all(response_json.as_bytes())
-            .with_context(|| "Failed to send response to client")?;
+        match request {
+            Request::Framed(_) => write_frame(&mut stream, response_json.as_bytes()),
+            Request::Legacy(_) => stream.write_all(response_json.as_bytes()).map_err(Into::into),
+        }
+        .with_context(|| "Failed to send response to client")?;
 
-        Ok(())
+        Ok(stopping)
+    }
+}
+
+impl Default for IpcServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_status_report_round_trip() {
+        let response = IpcResponse::Status {
+            outputs: vec![
+                OutputStatus {
+                    name: "DP-1".to_string(),
+                    current_image: Some("/walls/nested/long name.png".to_string()),
+                    queue_position: 3,
+                    queue_size: 40,
+                    timer_remaining: Some(125),
+                    paused: false,
+                    static_wallpaper: false,
+                    disabled: false,
+                    mode: OutputMode::Group("desk".to_string()),
+                    pinned: true,
+                },
+                OutputStatus::disabled("HDMI-A-1"),
+            ],
+            paused: false,
+            daemon: None,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        let IpcResponse::Status { outputs, paused, .. } = serde_json::from_str(&json).unwrap() else {
+            panic!("expected a status response");
+        };
+
+        let report = StatusReport::new(&outputs, paused);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["outputs"][0]["image"], "/walls/nested/long name.png");
+        assert_eq!(json["outputs"][0]["remaining_seconds"], 125);
+        assert_eq!(json["outputs"][0]["mode"], "group");
+        assert_eq!(json["outputs"][0]["group"], "desk");
+        assert_eq!(json["outputs"][0]["pinned"], true);
+        assert_eq!(json["outputs"][1]["disabled"], true);
+        assert_eq!(json["outputs"][1]["static"], false);
+        assert_eq!(serde_json::from_value::<StatusReport>(json).unwrap(), report);
+    }
+
+    /// Hands out at most `chunk` bytes per read, like a socket under load
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_frames_survive_partial_reads() {
+        let mut written = Vec::new();
+        write_frame(&mut written, br#"{"Next":{"output":null}}"#).unwrap();
+        write_frame(&mut written, b"").unwrap();
+        assert_eq!(&written[..4], &24u32.to_le_bytes());
+
+        let mut reader = Trickle { data: &written, chunk: 1 };
+        assert_eq!(read_frame(&mut reader).unwrap(), br#"{"Next":{"output":null}}"#);
+        assert_eq!(read_frame(&mut reader).unwrap(), b"");
+        // Nothing left: EOF instead of a frame
+        assert!(read_frame(&mut reader).is_err());
+
+        // Cut short in the payload
+        let mut reader = Trickle { data: &written[..10], chunk: 3 };
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_oversized_frames_are_rejected() {
+        let huge = ((MAX_FRAME_SIZE + 1) as u32).to_le_bytes();
+        let error = read_frame(&mut &huge[..]).unwrap_err();
+        assert!(error.to_string().contains("over the"), "{}", error);
+        assert!(write_frame(&mut Vec::new(), &vec![0u8; MAX_FRAME_SIZE + 1]).is_err());
+        // Not bare JSON either
+        assert!(read_request(&mut &[0xFF, 0xFF, 0xFF, 0xFF, 0][..]).is_err());
+    }
+
+    #[test]
+    fn test_legacy_clients_are_told_apart() {
+        for command in [IpcCommand::Pause, IpcCommand::Next { output: Some("DP-1".to_string()), group: None }] {
+            let json = serde_json::to_vec(&command).unwrap();
+            let mut framed = Vec::new();
+            write_frame(&mut framed, &json).unwrap();
+
+            assert_eq!(read_request(&mut Trickle { data: &framed, chunk: 2 }).unwrap(), Request::Framed(json.clone()));
+            assert_eq!(read_request(&mut Trickle { data: &json, chunk: 2 }).unwrap(), Request::Legacy(json.clone()));
+        }
+    }
+
+    #[test]
+    fn test_server_answers_both_protocols() {
+        let handler = |command: IpcCommand| Ok(IpcResponse::Success { message: format!("{:?}", command) });
+
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let serving = std::thread::spawn(move || IpcServer::handle_connection(server, &handler, &Subscribers::default()));
+        write_frame(&mut client, br#""Pause""#).unwrap();
+        let response: IpcResponse = serde_json::from_slice(&read_frame(&mut client).unwrap()).unwrap();
+        assert!(matches!(response, IpcResponse::Success { message } if message == "Pause"));
+        serving.join().unwrap().unwrap();
+
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let serving = std::thread::spawn(move || IpcServer::handle_connection(server, &handler, &Subscribers::default()));
+        client.write_all(br#""Resume""#).unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response, r#"{"Success":{"message":"Resume"}}"#);
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_shutdown_is_reported_once_answered() {
+        let answer = |command: &[u8], handler: &dyn Fn(IpcCommand) -> Result<IpcResponse>| {
+            let (mut client, server) = UnixStream::pair().unwrap();
+            write_frame(&mut client, command).unwrap();
+            let stopping = IpcServer::handle_connection(server, &handler, &Subscribers::default()).unwrap();
+            assert!(read_frame(&mut client).is_ok());
+            stopping
+        };
+        let agree = |_: IpcCommand| Ok(IpcResponse::Success { message: "Stopping".to_string() });
+        let refuse = |_: IpcCommand| Ok(IpcResponse::error(ErrorKind::Failed, "Busy"));
+
+        assert!(answer(br#""Shutdown""#, &agree));
+        assert!(!answer(br#""Shutdown""#, &refuse));
+        assert!(!answer(br#""Pause""#, &agree));
+    }
+
+    fn server_at(path: PathBuf) -> IpcServer {
+        IpcServer { location: SocketLocation { path, fallback_reason: None }, ..IpcServer::new() }
+    }
+
+    #[test]
+    fn test_running_daemon_is_not_replaced() {
+        let temp_dir = tempdir().unwrap();
+        let server = server_at(temp_dir.path().join("swwws.sock"));
+
+        let listener = server.bind().unwrap();
+        let mode = std::fs::metadata(server.socket_path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let error = server.bind().unwrap_err();
+        assert!(error.to_string().contains("already running"), "{}", error);
+        assert!(server.socket_path().exists());
+
+        // Dropping the listener leaves the socket file behind, as a crash would
+        drop(listener);
+        assert!(server.socket_path().exists());
+        let _listener = server.bind().unwrap();
+
+        let not_a_socket = server_at(temp_dir.path().join("notes.txt"));
+        std::fs::write(not_a_socket.socket_path(), "keep me").unwrap();
+        assert!(not_a_socket.bind().is_err());
+        assert_eq!(std::fs::read_to_string(not_a_socket.socket_path()).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn test_errors_without_a_kind_read_as_failed() {
+        let response: IpcResponse = serde_json::from_str(r#"{"Error":{"message":"Busy"}}"#).unwrap();
+        assert!(matches!(response, IpcResponse::Error { kind: ErrorKind::Failed, .. }));
+
+        let json = serde_json::to_value(IpcResponse::error(ErrorKind::NotFound, "Unknown output 'DP-9'")).unwrap();
+        assert_eq!(json, serde_json::json!({ "Error": { "message": "Unknown output 'DP-9'", "kind": "NotFound" } }));
+    }
+
+    #[test]
+    fn test_client_and_server_check_versions() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("swwws.sock");
+        let listener = std::os::unix::net::UnixListener::bind(&socket_path).unwrap();
+        let client = IpcClient { socket_path, timeout: None };
+        let serve_one = |listener: &std::os::unix::net::UnixListener| {
+            let (stream, _) = listener.accept().unwrap();
+            let handler = |command: IpcCommand| Ok(IpcResponse::Success { message: format!("{:?}", command) });
+            IpcServer::handle_connection(stream, &handler, &Subscribers::default()).unwrap();
+        };
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| serve_one(&listener));
+            let response = client.send_command(IpcCommand::Pause).unwrap();
+            assert!(matches!(response, IpcResponse::Success { message } if message == "Pause"));
+        });
+
+        // A daemon from before framing reads to EOF and hangs up without answering
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let (mut stream, _) = listener.accept().unwrap();
+                stream.read_to_end(&mut Vec::new()).unwrap();
+            });
+            let error = client.send_command(IpcCommand::Pause).unwrap_err();
+            assert!(error.to_string().contains("restarting swwws-daemon"), "{}", error);
+        });
+    }
+
+    #[test]
+    fn test_client_gives_up_after_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let socket_path = temp_dir.path().join("swwws.sock");
+        // Connections queue up, but nothing ever answers them
+        let _listener = std::os::unix::net::UnixListener::bind(&socket_path).unwrap();
+        let client = IpcClient { socket_path, timeout: None }.with_timeout(Duration::from_millis(100));
+
+        let started = std::time::Instant::now();
+        let error = client.send_command(IpcCommand::Ping).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let kind = error.chain().find_map(|cause| cause.downcast_ref::<std::io::Error>()).map(std::io::Error::kind);
+        assert_eq!(kind, Some(std::io::ErrorKind::WouldBlock), "{:#}", error);
+    }
+
+    #[test]
+    fn test_stale_side_is_named() {
+        let answer = |json: &str| {
+            let (mut client, server) = UnixStream::pair().unwrap();
+            write_frame(&mut client, json.as_bytes()).unwrap();
+            let handler = |command: IpcCommand| Ok(IpcResponse::Success { message: format!("{:?}", command) });
+            IpcServer::handle_connection(server, &handler, &Subscribers::default()).unwrap();
+            serde_json::from_slice::<serde_json::Value>(&read_frame(&mut client).unwrap()).unwrap()
+        };
+
+        let current = answer(r#"{"version": 1, "command": "Pause"}"#);
+        // Sent by clients from before group selectors
+        let next = answer(r#"{"version": 1, "command": {"Next": {"output": "DP-1"}}}"#);
+        assert_eq!(next["response"]["Success"]["message"], r#"Next { output: Some("DP-1"), group: None }"#);
+        assert_eq!(current["version"], PROTOCOL_VERSION);
+        assert_eq!(current["response"]["Success"]["message"], "Pause");
+
+        let newer = answer(r#"{"version": 2, "command": {"Teleport": {}}}"#);
+        let message = newer["response"]["Error"]["message"].as_str().unwrap();
+        assert!(message.contains("the daemon is older than swwws-cli"), "{}", message);
+
+        // Unversioned, and not a command this daemon knows
+        let older = answer(r#"{"Frobnicate": {"output": null}}"#);
+        let message = older["Error"]["message"].as_str().unwrap();
+        assert!(message.contains("swwws-cli is older than the daemon"), "{}", message);
+
+        assert!(version_mismatch(1, 0).contains("restart swwws-daemon"));
+    }
+
+    #[test]
+    fn test_subscribers_get_events_until_they_hang_up() {
+        let subscribers = Subscribers::default();
+        let handler = |_: IpcCommand| -> Result<IpcResponse> { panic!("Subscribe is answered by the server") };
+
+        let mut clients = Vec::new();
+        for _ in 0..2 {
+            let (mut client, server) = UnixStream::pair().unwrap();
+            write_frame(&mut client, br#""Subscribe""#).unwrap();
+            IpcServer::handle_connection(server, &handler, &subscribers).unwrap();
+            let response: IpcResponse = serde_json::from_slice(&read_frame(&mut client).unwrap()).unwrap();
+            assert!(matches!(response, IpcResponse::Success { .. }));
+            clients.push(client);
+        }
+        assert_eq!(subscribers.len(), 2);
+
+        let changed = IpcEvent::WallpaperChanged {
+            output: "DP-1".to_string(),
+            image: "/walls/a.png".to_string(),
+            trigger: "timer".to_string(),
+        };
+        subscribers.broadcast(&changed);
+        subscribers.broadcast(&IpcEvent::Paused);
+        let mut first = EventStream { stream: clients.remove(0) };
+        assert_eq!(first.next().unwrap().unwrap(), changed);
+        assert_eq!(first.next().unwrap().unwrap(), IpcEvent::Paused);
+
+        // The second one hung up
+        drop(clients);
+        subscribers.broadcast(&IpcEvent::Resumed);
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(first.next().unwrap().unwrap(), IpcEvent::Resumed);
+
+        // The last handle closes the connections
+        drop(subscribers);
+        assert!(first.next().is_none());
+    }
+
+    #[test]
+    fn test_usable_runtime_dir_is_used() {
+        let runtime = tempdir().unwrap();
+        let location = SocketLocation::resolve(Some(runtime.path().to_path_buf()), Some("wayland-1"), Path::new("/tmp"), 1000);
+        assert_eq!(location.path, runtime.path().join("swwws-wayland-1.sock"));
+        assert!(location.fallback_reason.is_none());
+    }
+
+    #[test]
+    fn test_socket_is_named_after_the_display() {
+        assert_eq!(socket_name(Some("wayland-1")), "swwws-wayland-1.sock");
+        assert_eq!(socket_name(Some("/run/user/1000/wayland-2")), "swwws-wayland-2.sock");
+        assert_eq!(socket_name(None), "swwws-wayland-0.sock");
+        assert_eq!(socket_name(Some("")), "swwws-wayland-0.sock");
+    }
+
+    #[test]
+    fn test_fallback_is_a_private_per_user_dir() {
+        let base = tempdir().unwrap();
+        let uid = rustix::process::getuid().as_raw();
+
+        let missing = SocketLocation::resolve(None, None, base.path(), uid);
+        let gone = SocketLocation::resolve(Some(base.path().join("gone")), None, base.path(), uid);
+        let expected = base.path().join(format!("swwws-{}", uid)).join("swwws-wayland-0.sock");
+        assert_eq!(missing.path, expected);
+        assert_eq!(gone.path, expected);
+        assert!(gone.fallback_reason.unwrap().contains("does not exist"));
+
+        missing.prepare_dir().unwrap();
+        let mode = std::fs::metadata(expected.parent().unwrap()).unwrap().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        // Preparing again finds the same directory acceptable
+        missing.prepare_dir().unwrap();
+
+        // A fallback dir others can enter is refused
+        std::fs::set_permissions(expected.parent().unwrap(), std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(missing.prepare_dir().is_err());
+    }
+
+    #[test]
+    fn test_world_writable_dir_is_refused() {
+        let runtime = tempdir().unwrap();
+        std::fs::set_permissions(runtime.path(), std::fs::Permissions::from_mode(0o777)).unwrap();
+        let location = SocketLocation::resolve(Some(runtime.path().to_path_buf()), None, Path::new("/tmp"), 1000);
+        assert!(location.prepare_dir().is_err());
+    }
+
+    #[test]
+    fn test_client_and_server_agree_without_runtime_dir() {
+        let names = ["XDG_RUNTIME_DIR", "WAYLAND_DISPLAY", "SWWWS_SOCKET"];
+        let previous: Vec<_> = names.iter().map(std::env::var_os).collect();
+        std::env::remove_var("XDG_RUNTIME_DIR");
+        std::env::remove_var("SWWWS_SOCKET");
+        std::env::set_var("WAYLAND_DISPLAY", "wayland-5");
+        let (client, server) = (IpcClient::new(), IpcServer::new());
+        std::env::set_var("SWWWS_SOCKET", "/somewhere/else.sock");
+        let (overridden_client, overridden_server) = (IpcClient::new(), IpcServer::new());
+        for (name, value) in names.iter().zip(previous) {
+            match value {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+
+        let uid = rustix::process::getuid().as_raw();
+        assert_eq!(client.socket_path(), server.socket_path());
+        assert_eq!(client.socket_path(), Path::new(&format!("/tmp/swwws-{}/swwws-wayland-5.sock", uid)));
+        assert_eq!(overridden_client.socket_path(), Path::new("/somewhere/else.sock"));
+        assert_eq!(overridden_server.socket_path(), overridden_client.socket_path());
     }
 }
@@ -1,34 +1,60 @@
 pub mod queue;
+pub mod collation;
+pub mod change;
+pub mod pipeline;
+pub mod hook;
 pub mod image_discovery;
+pub mod discovery_cache;
 pub mod swww;
 pub mod command_builder;
+pub mod transition;
 pub mod executor;
 pub mod duration;
+pub mod start_offset;
+pub mod schedule;
+pub mod power;
+pub mod orientation;
+pub mod resolution;
+pub mod pattern;
 pub mod ipc;
 pub mod state;
+pub mod reload;
+pub mod problems;
+pub mod shown;
+pub mod pin;
+pub mod blacklist;
 pub mod error;
 pub mod swww_client;
 
-pub use queue::{Queue, Sorting};
-pub use image_discovery::ImageDiscovery;
+pub use queue::{Queue, QueueOptions, ReplacePolicy, Sorting};
+pub use collation::Collation;
+pub use change::{ChangeReason, ChangeRequest};
+pub use pipeline::{ChangePipeline, PipelineMetrics, WallpaperBackend, WallpaperChange};
+pub use image_discovery::{DiscoveryOptions, ImageDiscovery};
+pub use discovery_cache::DiscoveryCache;
 pub use swww::SwwwIntegration;
 pub use command_builder::CommandBuilder;
-pub use executor::ProcessExecutor;
-pub use duration::parse_duration;
-pub use ipc::{IpcClient, IpcServer, IpcCommand, IpcResponse, OutputStatus};
+pub use executor::{ProcessExecutor, QueriedOutput};
+pub use duration::{parse_duration, Interval, IntervalOverrides, IntervalPicks};
+pub use start_offset::StartOffset;
+pub use schedule::{Profile, Schedule};
+pub use power::{PowerTracker, ResumeAction, SuspendDetector, SwwwRestartDetector};
+pub use orientation::Orientation;
+pub use resolution::MinResolution;
+pub use ipc::{IpcClient, IpcServer, IpcCommand, IpcEvent, IpcResponse, ErrorKind, Subscribers, EventStream, CurrentImage, EffectiveConfig, OutputMode, OutputReport, OutputStatus, QueueEntry, QueueListing, HistoryEntry, OutputInfo, StatusReport, DaemonInfo, ReloadRecord, ReloadSummary, SocketLocation, OutputSettings};
 pub use state::{DaemonState, OutputState};
+pub use reload::ReloadStats;
+pub use problems::{ProblemCategory, ProblemReport, ProblemStore};
+pub use blacklist::Blacklist;
+pub use shown::{ChangeLog, ChangeRecord, ImageRecord, ShownHistory};
+pub use pin::Pins;
 pub use error::{SwwwsError, Result, ErrorReporting};
 pub use swww_client::{SwwwClient, SwwwOutput, SwwwTransition};
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
 pub enum MonitorBehavior {
+    #[default]
     Independent,   // Each monitor has its own queue and timing
     Synchronized,  // All monitors show same image at same time
     Grouped(Vec<Vec<String>>), // Custom groups of monitors
 }
-
-impl Default for MonitorBehavior {
-    fn default() -> Self {
-        MonitorBehavior::Independent
-    }
-}
@@ -0,0 +1,61 @@
+/// Which way round an output is, from the size `swww query` reports for it.
+/// A rotated monitor reports its rotated size, so this follows the rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+}
+
+impl Orientation {
+    /// Square outputs count as landscape
+    pub fn from_size(width: u32, height: u32) -> Self {
+        if height > width {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+}
+
+/// Parse the size out of a `swww query` line such as
+/// `DP-1: 1920x1080, scale: 1, currently displaying: ...`
+pub fn parse_query_size(line: &str) -> Option<(u32, u32)> {
+    let rest = line.strip_prefix(": ").unwrap_or(line);
+    let (_, rest) = rest.split_once(':')?;
+    let size = rest.trim_start().split([',', ' ']).next()?;
+    let (width, height) = size.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Parse the scale out of a `swww query` line, as swww prints it
+pub fn parse_query_scale(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("scale:")?;
+    let scale = rest.trim_start().split([',', ' ']).next()?;
+    (!scale.is_empty()).then(|| scale.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_size() {
+        assert_eq!(parse_query_size("DP-1: 1920x1080, scale: 1, currently displaying: image: /a.png"), Some((1920, 1080)));
+        assert_eq!(parse_query_size(": HDMI-A-1: 1080x1920, scale: 1.5, currently displaying: color: 000000"), Some((1080, 1920)));
+        assert_eq!(parse_query_size("DP-1: unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_query_scale() {
+        assert_eq!(parse_query_scale(": HDMI-A-1: 1080x1920, scale: 1.5, currently displaying: color: 000000").as_deref(), Some("1.5"));
+        assert_eq!(parse_query_scale("DP-1: 1920x1080, scale: 1"), Some("1".to_string()));
+        assert_eq!(parse_query_scale("DP-1: unknown"), None);
+    }
+
+    #[test]
+    fn test_orientation_from_size() {
+        assert_eq!(Orientation::from_size(1080, 1920), Orientation::Portrait);
+        assert_eq!(Orientation::from_size(2560, 1440), Orientation::Landscape);
+        assert_eq!(Orientation::from_size(1000, 1000), Orientation::Landscape);
+    }
+}
@@ -0,0 +1,63 @@
+/// Whether an output name entry is a glob pattern rather than a literal name
+pub fn is_pattern(entry: &str) -> bool {
+    entry.contains(['*', '?'])
+}
+
+/// Match `name` against a glob `pattern`: `*` matches any run of characters,
+/// `?` exactly one. Anything else must match literally.
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and where in `name` it started matching
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("DP-*", "DP-3"));
+        assert!(matches("DP-*", "DP-"));
+        assert!(!matches("DP-*", "eDP-1"));
+        assert!(matches("*DP-?", "eDP-1"));
+        assert!(!matches("DP-?", "DP-10"));
+        assert!(matches("HDMI-*-1", "HDMI-A-1"));
+        assert!(matches("eDP-1", "eDP-1"));
+        assert!(!matches("eDP-1", "eDP-2"));
+    }
+
+    #[test]
+    fn test_is_pattern() {
+        assert!(is_pattern("DP-*"));
+        assert!(is_pattern("DP-?"));
+        assert!(!is_pattern("HDMI-A-1"));
+    }
+}
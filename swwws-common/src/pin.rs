@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::change::ChangeReason;
+
+/// Outputs held on one image with `swwws-cli pin`, kept across restarts in
+/// the state file. Their timers don't change them; naming one in a command does.
+#[derive(Debug, Default)]
+pub struct Pins {
+    pinned: Mutex<BTreeMap<String, PathBuf>>,
+}
+
+impl Pins {
+    /// Returns false if `output` was pinned to `image` already
+    pub fn pin(&self, output: &str, image: &Path) -> bool {
+        self.pinned.lock().unwrap().insert(output.to_string(), image.to_path_buf()).as_deref() != Some(image)
+    }
+
+    /// The image `output` was pinned to, if it was
+    pub fn unpin(&self, output: &str) -> Option<PathBuf> {
+        self.pinned.lock().unwrap().remove(output)
+    }
+
+    pub fn image(&self, output: &str) -> Option<PathBuf> {
+        self.pinned.lock().unwrap().get(output).cloned()
+    }
+
+    pub fn is_pinned(&self, output: &str) -> bool {
+        self.pinned.lock().unwrap().contains_key(output)
+    }
+
+    /// `outputs` without the pinned ones, for changes that aren't aimed at one output
+    pub fn unpinned(&self, outputs: &[String]) -> Vec<String> {
+        let pinned = self.pinned.lock().unwrap();
+        outputs.iter().filter(|output| !pinned.contains_key(*output)).cloned().collect()
+    }
+
+    /// Every pin, keyed by output as the state file stores them
+    pub fn pinned(&self) -> BTreeMap<String, PathBuf> {
+        self.pinned.lock().unwrap().clone()
+    }
+
+    /// Take over pins loaded from the state file
+    pub fn restore(&self, saved: &BTreeMap<String, PathBuf>) {
+        self.pinned.lock().unwrap().extend(saved.iter().map(|(output, image)| (output.clone(), image.clone())));
+    }
+
+    /// The image a change for `reason` should put on `output` instead of
+    /// `image`, or None if it should be dropped. A manual change moves the pin
+    /// along; timers and schedules leave a pinned output alone; anything that
+    /// re-applies puts the pinned image back. A pin whose file is gone is dropped.
+    pub fn redirect(&self, output: &str, image: &Path, reason: ChangeReason) -> Option<PathBuf> {
+        let mut pinned = self.pinned.lock().unwrap();
+        let Some(pin) = pinned.get_mut(output) else {
+            return Some(image.to_path_buf());
+        };
+        if !pin.exists() {
+            log::warn!("{}: pinned image {:?} no longer exists, unpinning", output, pin);
+            pinned.remove(output);
+            return Some(image.to_path_buf());
+        }
+        match reason {
+            ChangeReason::Manual => {
+                *pin = image.to_path_buf();
+                Some(image.to_path_buf())
+            }
+            ChangeReason::Timer | ChangeReason::Schedule => None,
+            ChangeReason::Startup | ChangeReason::Refresh | ChangeReason::Wake => Some(pin.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_only_manual_changes_move_a_pin() {
+        let temp_dir = tempdir().unwrap();
+        let pinned = temp_dir.path().join("pinned.png");
+        let next = temp_dir.path().join("next.png");
+        std::fs::write(&pinned, b"png").unwrap();
+        std::fs::write(&next, b"png").unwrap();
+
+        let pins = Pins::default();
+        assert!(pins.pin("DP-1", &pinned));
+        assert!(!pins.pin("DP-1", &pinned));
+        assert_eq!(pins.redirect("HDMI-A-1", &next, ChangeReason::Timer), Some(next.clone()));
+        assert_eq!(pins.redirect("DP-1", &next, ChangeReason::Timer), None);
+        assert_eq!(pins.redirect("DP-1", &next, ChangeReason::Schedule), None);
+        assert_eq!(pins.redirect("DP-1", &next, ChangeReason::Refresh), Some(pinned.clone()));
+        assert_eq!(pins.redirect("DP-1", &next, ChangeReason::Startup), Some(pinned));
+        assert_eq!(pins.redirect("DP-1", &next, ChangeReason::Manual), Some(next.clone()));
+        assert_eq!(pins.image("DP-1"), Some(next));
+
+        let outputs = ["DP-1".to_string(), "HDMI-A-1".to_string()];
+        assert_eq!(pins.unpinned(&outputs), ["HDMI-A-1"]);
+    }
+
+    #[test]
+    fn test_pins_on_deleted_images_are_dropped() {
+        let temp_dir = tempdir().unwrap();
+        let gone = temp_dir.path().join("gone.png");
+        let next = temp_dir.path().join("next.png");
+
+        let pins = Pins::default();
+        pins.restore(&BTreeMap::from([("DP-1".to_string(), gone)]));
+        assert_eq!(pins.redirect("DP-1", &next, ChangeReason::Timer), Some(next));
+        assert!(!pins.is_pinned("DP-1"));
+        assert!(pins.pinned().is_empty());
+    }
+}
@@ -0,0 +1,592 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::Notify;
+use crate::change::{ChangeReason, ChangeRequest};
+use crate::command_builder::OutputConfig;
+use crate::error::ErrorReporting;
+use crate::hook;
+use crate::ipc::{IpcEvent, Subscribers};
+use crate::executor::ProcessExecutor;
+use crate::problems::{ProblemCategory, ProblemStore};
+use crate::shown::{ChangeLog, ShownHistory};
+use crate::pin::Pins;
+use crate::error::ImageDiscoveryError;
+use crate::{Result, SwwwsError};
+
+const MAX_RETRIES: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// A wallpaper change a backend is carrying out, done once it resolves
+pub type WallpaperChange<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Something that can put an image on an output
+pub trait WallpaperBackend: Send + Sync {
+    fn set_wallpaper<'a>(&'a self, image: &'a Path, config: &'a OutputConfig, output: &'a str) -> WallpaperChange<'a>;
+}
+
+impl WallpaperBackend for ProcessExecutor {
+    fn set_wallpaper<'a>(&'a self, image: &'a Path, config: &'a OutputConfig, output: &'a str) -> WallpaperChange<'a> {
+        Box::pin(self.run_swww_command(image, config, Some(output)))
+    }
+}
+
+/// Counters describing pipeline activity since startup
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineMetrics {
+    pub completed: u64,
+    pub failed: u64,
+    /// Timer changes dropped because a newer change for the output was already waiting
+    pub skipped: u64,
+    /// Total time changes spent waiting for a free backend slot
+    pub limiter_wait: Duration,
+    /// Highest number of backend calls that ran at once
+    pub peak_concurrency: usize,
+}
+
+#[derive(Default)]
+struct MetricsCounters {
+    completed: AtomicU64,
+    failed: AtomicU64,
+    skipped: AtomicU64,
+    limiter_wait_us: AtomicU64,
+    peak_concurrency: AtomicUsize,
+}
+
+/// Counting semaphore bounding simultaneous backend calls. Unlike a tokio
+/// `Semaphore`, its limit can go down as well as up on a reload.
+struct Limiter {
+    state: Mutex<(usize, usize)>, // (active, limit)
+    freed: Notify,
+}
+
+impl Limiter {
+    async fn acquire(&self) -> usize {
+        loop {
+            // Listening before looking, so a slot freed in between isn't missed
+            let freed = self.freed.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.0 < state.1 {
+                    state.0 += 1;
+                    return state.0;
+                }
+            }
+            freed.await;
+        }
+    }
+
+    fn release(&self) {
+        self.state.lock().unwrap().0 -= 1;
+        self.freed.notify_waiters();
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.state.lock().unwrap().1 = limit.max(1);
+        self.freed.notify_waiters();
+    }
+}
+
+struct Shared {
+    backend: Arc<dyn WallpaperBackend>,
+    limiter: Limiter,
+    metrics: MetricsCounters,
+    problems: Arc<ProblemStore>,
+    shown: Arc<ShownHistory>,
+    log: Arc<ChangeLog>,
+    pins: Arc<Pins>,
+    subscribers: Subscribers,
+    /// Images whose file was gone when a change tried to set them
+    missing: Mutex<Vec<PathBuf>>,
+    pending: Mutex<usize>,
+    idle: Condvar,
+}
+
+/// Runs wallpaper changes as tasks on a tokio runtime, off the caller's thread.
+///
+/// Each output gets its own lane, a task that lives as long as the pipeline, so
+/// changes for one output are applied in the order they were submitted while
+/// different outputs proceed in parallel. At most `max_concurrent` backend calls
+/// run at once; the rest wait for a free slot.
+///
+/// A lane does not start an output's next change until the previous transition
+/// has settled. Timer changes that piled up meanwhile are skipped in favor of the
+/// newest one.
+pub struct ChangePipeline {
+    shared: Arc<Shared>,
+    runtime: Handle,
+    lanes: Mutex<HashMap<String, UnboundedSender<ChangeRequest>>>,
+}
+
+impl ChangePipeline {
+    /// A pipeline running its changes on `runtime`. Submitting works from any
+    /// thread, inside the runtime or not.
+    pub fn new(runtime: Handle, backend: Arc<dyn WallpaperBackend>, max_concurrent: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                backend,
+                limiter: Limiter {
+                    state: Mutex::new((0, max_concurrent.max(1))),
+                    freed: Notify::new(),
+                },
+                metrics: MetricsCounters::default(),
+                problems: Arc::new(ProblemStore::default()),
+                shown: Arc::new(ShownHistory::default()),
+                log: Arc::new(ChangeLog::default()),
+                pins: Arc::new(Pins::default()),
+                subscribers: Subscribers::default(),
+                missing: Mutex::new(Vec::new()),
+                pending: Mutex::new(0),
+                idle: Condvar::new(),
+            }),
+            runtime,
+            lanes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queue a change. Returns immediately; the change runs on the output's lane.
+    pub fn submit(&self, request: ChangeRequest) {
+        *self.shared.pending.lock().unwrap() += 1;
+
+        let output = request.output.clone();
+        let mut lanes = self.lanes.lock().unwrap();
+        let lane = lanes
+            .entry(output.clone())
+            .or_insert_with(|| self.spawn_lane());
+
+        if let Err(mpsc::error::SendError(request)) = lane.send(request) {
+            // The lane died (a backend call panicked); start a fresh one
+            log::warn!("Change lane for {} stopped, restarting it", output);
+            let lane = self.spawn_lane();
+            lane.send(request).unwrap();
+            lanes.insert(output, lane);
+        }
+    }
+
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        self.shared.limiter.set_limit(max_concurrent);
+    }
+
+    pub fn metrics(&self) -> PipelineMetrics {
+        let metrics = &self.shared.metrics;
+        PipelineMetrics {
+            completed: metrics.completed.load(Ordering::Relaxed),
+            failed: metrics.failed.load(Ordering::Relaxed),
+            skipped: metrics.skipped.load(Ordering::Relaxed),
+            limiter_wait: Duration::from_micros(metrics.limiter_wait_us.load(Ordering::Relaxed)),
+            peak_concurrency: metrics.peak_concurrency.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Where changes that failed for good are recorded. The daemon records its
+    /// other failures here too.
+    pub fn problems(&self) -> Arc<ProblemStore> {
+        Arc::clone(&self.shared.problems)
+    }
+
+    /// Where every applied change is recorded, except wake re-applies which
+    /// show nothing new
+    pub fn shown(&self) -> Arc<ShownHistory> {
+        Arc::clone(&self.shared.shown)
+    }
+
+    /// Which image went up on which output, and when. Wake re-applies are left
+    /// out like they are from `shown`.
+    pub fn change_log(&self) -> Arc<ChangeLog> {
+        Arc::clone(&self.shared.log)
+    }
+
+    /// Outputs held on one image. The daemon checks each change against
+    /// these before submitting it.
+    pub fn pins(&self) -> Arc<Pins> {
+        Arc::clone(&self.shared.pins)
+    }
+
+    /// Where every applied change is announced, except wake re-applies. The
+    /// daemon hands these to the IPC server and announces its other events here.
+    pub fn subscribers(&self) -> Subscribers {
+        self.shared.subscribers.clone()
+    }
+
+    /// Images that failed to apply because their file no longer exists, since the
+    /// last call. The daemon drops these from its queues.
+    pub fn take_missing_images(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.shared.missing.lock().unwrap())
+    }
+
+    /// Block until every submitted change has been applied or given up on
+    pub fn wait_idle(&self) {
+        let mut pending = self.shared.pending.lock().unwrap();
+        while *pending > 0 {
+            pending = self.shared.idle.wait(pending).unwrap();
+        }
+    }
+
+    fn spawn_lane(&self) -> UnboundedSender<ChangeRequest> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<ChangeRequest>();
+        let shared = Arc::clone(&self.shared);
+        self.runtime.spawn(async move {
+            let mut backlog = VecDeque::new();
+            loop {
+                if backlog.is_empty() {
+                    match rx.recv().await {
+                        Some(request) => backlog.push_back(request),
+                        None => break,
+                    }
+                }
+                while let Ok(request) = rx.try_recv() {
+                    backlog.push_back(request);
+                }
+                let Some(request) = backlog.pop_front() else { continue };
+
+                // A timer change with a successor already waiting would only flash by
+                if request.reason == ChangeReason::Timer && !backlog.is_empty() {
+                    log::debug!("Skipping timer change for {}: {:?}", request.output, request.image);
+                    shared.metrics.skipped.fetch_add(1, Ordering::Relaxed);
+                    Self::finish(&shared);
+                    continue;
+                }
+
+                if Self::apply(&shared, &request).await {
+                    tokio::time::sleep(request.settle).await;
+                }
+                Self::finish(&shared);
+            }
+        });
+        tx
+    }
+
+    /// Run the backend call with retries. Returns whether it succeeded.
+    async fn apply(shared: &Shared, request: &ChangeRequest) -> bool {
+        for attempt in 0..MAX_RETRIES {
+            let waited = Instant::now();
+            let active = shared.limiter.acquire().await;
+            shared.metrics.limiter_wait_us.fetch_add(waited.elapsed().as_micros() as u64, Ordering::Relaxed);
+            shared.metrics.peak_concurrency.fetch_max(active, Ordering::Relaxed);
+
+            let result = shared.backend.set_wallpaper(&request.image, &request.config, &request.output).await;
+            shared.limiter.release();
+
+            match result {
+                Ok(()) => {
+                    log::info!("Set wallpaper for {} ({}, {} transition): {:?}", request.output, request.reason,
+                        request.config.transition_type.as_deref().unwrap_or("default"), request.image);
+                    shared.metrics.completed.fetch_add(1, Ordering::Relaxed);
+                    if request.reason != ChangeReason::Wake {
+                        shared.shown.record(&request.image);
+                        shared.log.record(&request.output, &request.image, &request.reason.to_string());
+                        shared.subscribers.broadcast(&IpcEvent::WallpaperChanged {
+                            output: request.output.clone(),
+                            image: request.image.to_string_lossy().to_string(),
+                            trigger: request.reason.to_string(),
+                        });
+                    }
+                    if let Some(command) = &request.on_change {
+                        hook::spawn(hook::expand(command, &request.image, &request.output), request.output.clone());
+                    }
+                    return true;
+                }
+                Err(e) if is_missing_file(&e) => {
+                    // Retrying won't bring the file back
+                    log::warn!("Failed to set wallpaper for {}: {}", request.output, e.user_friendly_message());
+                    shared.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                    shared.problems.record(ProblemCategory::Change, Some(&request.output), format!(
+                        "Failed to set {:?}: {}", request.image, e.user_friendly_message()));
+                    shared.missing.lock().unwrap().push(request.image.clone());
+                    return false;
+                }
+                Err(e) if attempt < MAX_RETRIES - 1 => {
+                    log::warn!("Failed to set wallpaper for {} (attempt {}/{}): {}. Retrying in {}ms...",
+                        request.output, attempt + 1, MAX_RETRIES, e.user_friendly_message(),
+                        RETRY_DELAY.as_millis());
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                Err(e) => {
+                    log::error!("Failed to set wallpaper for {} after {} attempts: {}",
+                        request.output, MAX_RETRIES, e.user_friendly_message());
+                    shared.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                    shared.problems.record(ProblemCategory::Change, Some(&request.output), format!(
+                        "Failed to set {:?}: {}", request.image, e.user_friendly_message()));
+                }
+            }
+        }
+        false
+    }
+
+    fn finish(shared: &Shared) {
+        let mut pending = shared.pending.lock().unwrap();
+        *pending -= 1;
+        if *pending == 0 {
+            shared.idle.notify_all();
+        }
+    }
+}
+
+fn is_missing_file(error: &SwwwsError) -> bool {
+    matches!(
+        error,
+        SwwwsError::ImageDiscovery(ImageDiscoveryError::FileAccess { source, .. })
+            if source.kind() == std::io::ErrorKind::NotFound
+    )
+}
+
+impl std::fmt::Debug for ChangePipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangePipeline")
+            .field("metrics", &self.metrics())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+
+    /// Name of the runtime threads the tests' changes run on
+    const LANE_THREADS: &str = "change-lanes";
+
+    /// Runtime the tests' pipelines run their lanes on, as the daemon's main one
+    fn runtime() -> Handle {
+        static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+        RUNTIME.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .thread_name(LANE_THREADS)
+                .enable_all()
+                .build()
+                .unwrap()
+        }).handle().clone()
+    }
+
+    /// Records calls and how many ran at once
+    #[derive(Default)]
+    struct MockBackend {
+        running: AtomicUsize,
+        max_seen: AtomicUsize,
+        calls: Mutex<Vec<(String, PathBuf)>>,
+        /// Name of the thread each call ran on
+        threads: Mutex<Vec<Option<String>>>,
+        fail_first: AtomicUsize,
+        delay: Duration,
+    }
+
+    impl WallpaperBackend for MockBackend {
+        fn set_wallpaper<'a>(&'a self, image: &'a Path, _config: &'a OutputConfig, output: &'a str) -> WallpaperChange<'a> {
+            Box::pin(async move {
+                self.threads.lock().unwrap().push(std::thread::current().name().map(str::to_string));
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_seen.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(self.delay.max(Duration::from_millis(10))).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+
+                if image.starts_with("/gone") {
+                    return crate::image_discovery::ImageDiscovery::validate_image(image);
+                }
+                if self.fail_first.load(Ordering::SeqCst) > 0 {
+                    self.fail_first.fetch_sub(1, Ordering::SeqCst);
+                    return Err(crate::SwwwsError::Swww(crate::error::SwwwError::DaemonNotFound));
+                }
+                self.calls.lock().unwrap().push((output.to_string(), image.to_path_buf()));
+                Ok(())
+            })
+        }
+    }
+
+    fn request(output: &str, image: &str) -> ChangeRequest {
+        ChangeRequest { reason: ChangeReason::Manual, ..timer_request(output, image) }
+    }
+
+    fn timer_request(output: &str, image: &str) -> ChangeRequest {
+        ChangeRequest {
+            output: output.to_string(),
+            image: PathBuf::from(image),
+            config: OutputConfig {
+                path: None,
+                mode: None,
+                transition_type: None,
+                transition_step: None,
+                transition_angle: None,
+                transition_pos: None,
+                transition_bezier: None,
+                transition_fps: None,
+                resize: None,
+                fill_color: None,
+                filter: None,
+                invert_y: None,
+                transition_wave: None,
+            },
+            reason: ChangeReason::Timer,
+            settle: Duration::ZERO,
+            on_change: None,
+        }
+    }
+
+    #[test]
+    fn test_concurrency_never_exceeds_limit() {
+        let backend = Arc::new(MockBackend::default());
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 2);
+
+        for round in 0..3 {
+            for output in 0..8 {
+                pipeline.submit(request(&format!("OUT-{}", output), &format!("/walls/{}.png", round)));
+            }
+        }
+        pipeline.wait_idle();
+
+        assert_eq!(backend.calls.lock().unwrap().len(), 24);
+        assert!(backend.max_seen.load(Ordering::SeqCst) <= 2);
+        let metrics = pipeline.metrics();
+        assert_eq!(metrics.completed, 24);
+        assert_eq!(metrics.peak_concurrency, 2);
+        // With 8 outputs and 2 slots, most changes had to wait
+        assert!(metrics.limiter_wait > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_per_output_order_is_preserved() {
+        let backend = Arc::new(MockBackend::default());
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 1);
+
+        for i in 0..5 {
+            pipeline.submit(request("DP-1", &format!("/walls/{}.png", i)));
+            pipeline.submit(request("DP-2", &format!("/walls/{}.png", i)));
+        }
+        pipeline.wait_idle();
+
+        let calls = backend.calls.lock().unwrap();
+        for output in ["DP-1", "DP-2"] {
+            let order: Vec<&PathBuf> = calls.iter().filter(|(o, _)| o == output).map(|(_, p)| p).collect();
+            let expected: Vec<PathBuf> = (0..5).map(|i| PathBuf::from(format!("/walls/{}.png", i))).collect();
+            assert_eq!(order, expected.iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_changes_run_on_the_runtime_one_lane_per_output() {
+        // Keybind presses on three monitors: no thread, let alone a runtime, per change
+        let backend = Arc::new(MockBackend::default());
+        let pipeline = Arc::new(ChangePipeline::new(runtime(), backend.clone(), 3));
+        let presses: Vec<_> = (0..4)
+            .map(|client| std::thread::spawn({
+                let pipeline = Arc::clone(&pipeline);
+                move || for press in 0..5 {
+                    for output in ["DP-1", "DP-2", "HDMI-A-1"] {
+                        pipeline.submit(request(output, &format!("/walls/{}-{}.png", client, press)));
+                    }
+                }
+            }))
+            .collect();
+        for client in presses {
+            client.join().unwrap();
+        }
+        pipeline.wait_idle();
+
+        assert_eq!(pipeline.metrics().completed, 60);
+        assert_eq!(pipeline.lanes.lock().unwrap().len(), 3);
+        let threads = backend.threads.lock().unwrap();
+        assert!(threads.iter().all(|name| name.as_deref() == Some(LANE_THREADS)), "{:?}", threads);
+    }
+
+    #[test]
+    fn test_failed_change_is_retried() {
+        let backend = Arc::new(MockBackend::default());
+        backend.fail_first.store(1, Ordering::SeqCst);
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 4);
+
+        pipeline.submit(request("DP-1", "/walls/a.png"));
+        pipeline.wait_idle();
+
+        assert_eq!(backend.calls.lock().unwrap().len(), 1);
+        assert_eq!(pipeline.metrics().failed, 0);
+        assert_eq!(pipeline.shown().get(Path::new("/walls/a.png")).map(|record| record.shown_count), Some(1));
+        let logged: Vec<String> = pipeline.change_log().latest(None, 10).into_iter().map(|(_, change)| change.image).collect();
+        assert_eq!(logged, ["/walls/a.png"]);
+    }
+
+    #[test]
+    fn test_missing_file_is_reported_not_retried() {
+        let backend = Arc::new(MockBackend::default());
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 4);
+
+        let started = Instant::now();
+        pipeline.submit(request("DP-1", "/gone/a.png"));
+        pipeline.submit(request("DP-2", "/walls/b.png"));
+        pipeline.wait_idle();
+
+        assert!(started.elapsed() < RETRY_DELAY);
+        assert_eq!(pipeline.metrics().failed, 1);
+        assert_eq!(pipeline.take_missing_images(), vec![PathBuf::from("/gone/a.png")]);
+        assert!(pipeline.take_missing_images().is_empty());
+    }
+
+    #[test]
+    fn test_hook_runs_only_after_success() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let marker = |name: &str| temp_dir.path().join(name);
+        let hook = |name: &str| Some(format!("echo {{output}} > {}", marker(name).display()));
+
+        let backend = Arc::new(MockBackend::default());
+        backend.fail_first.store(MAX_RETRIES as usize, Ordering::SeqCst);
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 4);
+        pipeline.submit(ChangeRequest { on_change: hook("failed"), ..request("DP-1", "/walls/a.png") });
+        pipeline.wait_idle();
+        pipeline.submit(ChangeRequest { on_change: hook("applied"), ..request("DP-2", "/walls/a.png") });
+        pipeline.wait_idle();
+
+        // The hook runs on its own thread
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !marker("applied").exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(marker("applied").exists());
+        assert!(!marker("failed").exists());
+    }
+
+    #[test]
+    fn test_slow_transitions_serialize_and_skip_missed_deadlines() {
+        // Each change takes longer than the 100ms interval between deadlines
+        let backend = Arc::new(MockBackend { delay: Duration::from_millis(150), ..Default::default() });
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 4);
+
+        for tick in 0..10 {
+            pipeline.submit(timer_request("DP-1", &format!("/walls/{}.png", tick)));
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        pipeline.wait_idle();
+
+        let calls = backend.calls.lock().unwrap();
+        let metrics = pipeline.metrics();
+        assert_eq!(backend.max_seen.load(Ordering::SeqCst), 1);
+        assert!(metrics.skipped > 0);
+        assert_eq!(metrics.completed + metrics.skipped, 10);
+        assert_eq!(calls.len() as u64, metrics.completed);
+        // The newest deadline always wins
+        assert_eq!(calls.last().unwrap().1, PathBuf::from("/walls/9.png"));
+    }
+
+    #[test]
+    fn test_next_change_waits_for_transition_to_settle() {
+        let backend = Arc::new(MockBackend::default());
+        let pipeline = ChangePipeline::new(runtime(), backend.clone(), 4);
+        let settle = Duration::from_millis(200);
+
+        let started = Instant::now();
+        pipeline.submit(ChangeRequest { settle, ..request("DP-1", "/walls/a.png") });
+        pipeline.submit(ChangeRequest { settle, ..request("DP-1", "/walls/b.png") });
+        // Other outputs are not held up
+        pipeline.submit(request("DP-2", "/walls/a.png"));
+        pipeline.wait_idle();
+
+        assert!(started.elapsed() >= settle * 2);
+        assert_eq!(backend.calls.lock().unwrap().len(), 3);
+        assert_eq!(pipeline.metrics().skipped, 0);
+    }
+}
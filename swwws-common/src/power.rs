@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime};
+use serde::{Deserialize, Serialize};
+
+/// Minimum time between two wake re-applies of the same output
+pub const WAKE_DEBOUNCE: Duration = Duration::from_secs(10);
+
+/// A gap this long between two wakeups of the daemon, which come at least every
+/// few seconds, means the system was suspended
+pub const SUSPEND_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// What the timers do once the system resumes from suspend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResumeAction {
+    /// Every countdown starts over, as if the daemon had just started
+    #[default]
+    Restart,
+    /// Every output changes once right away, then counts down as usual
+    Change,
+    /// Like `Change`, but the outputs change one after another
+    Stagger,
+}
+
+/// Notices suspend/resume from the wall clock. `Instant` may or may not
+/// advance while the system sleeps, so timers can't tell on their own.
+#[derive(Debug)]
+pub struct SuspendDetector {
+    last_tick: Option<SystemTime>,
+    threshold: Duration,
+}
+
+impl SuspendDetector {
+    pub fn new(threshold: Duration) -> Self {
+        Self { last_tick: None, threshold }
+    }
+
+    /// Record a tick at `now`. Returns how long the system was away if the
+    /// wall clock jumped more than the threshold since the last tick. A clock
+    /// set back is not a resume.
+    pub fn tick(&mut self, now: SystemTime) -> Option<Duration> {
+        let last = self.last_tick.replace(now)?;
+        now.duration_since(last).ok().filter(|gap| *gap > self.threshold)
+    }
+}
+
+/// Notices swww-daemon coming back after a crash or restart, when every output
+/// is black until it is told what to show: a failed health check followed by a
+/// good one, or its socket replaced by another between two good ones.
+#[derive(Debug, Default)]
+pub struct SwwwRestartDetector {
+    failed: bool,
+    socket: Option<u64>, // inode of swww-daemon's socket at the last good check
+}
+
+impl SwwwRestartDetector {
+    /// Record a health check: whether swww answered, and the inode of its
+    /// socket where it could be found. Returns whether swww-daemon restarted
+    /// since the last good check.
+    pub fn check(&mut self, answered: bool, socket: Option<u64>) -> bool {
+        if !answered {
+            self.failed = true;
+            return false;
+        }
+        let replaced = matches!((self.socket, socket), (Some(before), Some(now)) if before != now);
+        self.socket = socket.or(self.socket);
+        std::mem::take(&mut self.failed) || replaced
+    }
+}
+
+/// Follows which outputs `swww query` reports to notice monitors coming back
+/// from sleep. Outputs in deep sleep drop out of the list and reappear on wake.
+#[derive(Debug)]
+pub struct PowerTracker {
+    present: HashSet<String>,
+    /// Outputs seen at least once, so a late hotplug is not taken for a wake
+    known: HashSet<String>,
+    last_wake: HashMap<String, Instant>,
+    debounce: Duration,
+}
+
+impl PowerTracker {
+    pub fn new(debounce: Duration) -> Self {
+        Self {
+            present: HashSet::new(),
+            known: HashSet::new(),
+            last_wake: HashMap::new(),
+            debounce,
+        }
+    }
+
+    /// Record the outputs present at `now` and return those that went from off
+    /// to on since the last call. An output that woke less than `debounce` ago
+    /// is not reported again.
+    pub fn observe(&mut self, outputs: &[String], now: Instant) -> Vec<String> {
+        let mut woke = Vec::new();
+        for output in outputs {
+            if !self.known.contains(output) || self.present.contains(output) {
+                continue;
+            }
+            let recent = self.last_wake.get(output)
+                .is_some_and(|last| now.duration_since(*last) < self.debounce);
+            if recent {
+                log::debug!("Ignoring repeated wake of {}", output);
+                continue;
+            }
+            self.last_wake.insert(output.clone(), now);
+            woke.push(output.clone());
+        }
+
+        self.present = outputs.iter().cloned().collect();
+        self.known.extend(outputs.iter().cloned());
+        woke
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outputs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_off_on_is_a_wake() {
+        let mut tracker = PowerTracker::new(WAKE_DEBOUNCE);
+        let start = Instant::now();
+
+        assert!(tracker.observe(&outputs(&["DP-1", "DP-2"]), start).is_empty());
+        assert!(tracker.observe(&outputs(&["DP-1"]), start + Duration::from_secs(1)).is_empty());
+        assert_eq!(tracker.observe(&outputs(&["DP-1", "DP-2"]), start + Duration::from_secs(2)), outputs(&["DP-2"]));
+        // Staying on is not another wake
+        assert!(tracker.observe(&outputs(&["DP-1", "DP-2"]), start + Duration::from_secs(3)).is_empty());
+    }
+
+    #[test]
+    fn test_new_output_is_not_a_wake() {
+        let mut tracker = PowerTracker::new(WAKE_DEBOUNCE);
+        let start = Instant::now();
+
+        tracker.observe(&outputs(&["DP-1"]), start);
+        assert!(tracker.observe(&outputs(&["DP-1", "HDMI-A-1"]), start + Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_wall_clock_jump_is_a_resume() {
+        let mut detector = SuspendDetector::new(SUSPEND_THRESHOLD);
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(detector.tick(at(0)), None);
+        assert_eq!(detector.tick(at(1)), None);
+        // A slow tick, e.g. waiting on a long reload, is no suspend
+        assert_eq!(detector.tick(at(12)), None);
+        assert_eq!(detector.tick(at(8 * 3600)), Some(Duration::from_secs(8 * 3600 - 12)));
+        assert_eq!(detector.tick(at(8 * 3600 + 1)), None);
+        // Nor is the clock being set back
+        assert_eq!(detector.tick(at(60)), None);
+    }
+
+    #[test]
+    fn test_swww_coming_back_is_a_restart() {
+        let mut detector = SwwwRestartDetector::default();
+        assert!(!detector.check(true, Some(7)));
+        assert!(!detector.check(true, Some(7)));
+        // Down for a while, then back on the same socket path
+        assert!(!detector.check(false, None));
+        assert!(!detector.check(false, None));
+        assert!(detector.check(true, Some(9)));
+        assert!(!detector.check(true, Some(9)));
+        // Restarted between two checks
+        assert!(detector.check(true, Some(12)));
+        // A socket that can't be found says nothing
+        assert!(!detector.check(true, None));
+        assert!(!detector.check(true, Some(12)));
+    }
+
+    #[test]
+    fn test_flicker_is_debounced() {
+        let mut tracker = PowerTracker::new(Duration::from_secs(10));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        tracker.observe(&outputs(&["DP-1"]), at(0));
+        tracker.observe(&[], at(1));
+        assert_eq!(tracker.observe(&outputs(&["DP-1"]), at(2)).len(), 1);
+        tracker.observe(&[], at(3));
+        assert!(tracker.observe(&outputs(&["DP-1"]), at(4)).is_empty());
+        tracker.observe(&[], at(13));
+        assert_eq!(tracker.observe(&outputs(&["DP-1"]), at(14)).len(), 1);
+    }
+}
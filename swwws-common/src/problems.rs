@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Records kept per category; older ones are dropped and only counted
+pub const RECORDS_PER_CATEGORY: usize = 50;
+
+/// Longer messages are cut to this many characters
+pub const MAX_MESSAGE_LEN: usize = 500;
+
+/// Kind of failure, so unrelated problems don't push each other out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ProblemCategory {
+    /// swww failed to set a wallpaper after all retries
+    Change,
+    /// An image directory could not be read or had no images
+    Discovery,
+    /// A config reload was rejected
+    Reload,
+}
+
+impl ProblemCategory {
+    pub const ALL: [ProblemCategory; 3] = [ProblemCategory::Change, ProblemCategory::Discovery, ProblemCategory::Reload];
+}
+
+impl std::fmt::Display for ProblemCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProblemCategory::Change => write!(f, "change"),
+            ProblemCategory::Discovery => write!(f, "discovery"),
+            ProblemCategory::Reload => write!(f, "reload"),
+        }
+    }
+}
+
+impl std::str::FromStr for ProblemCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ProblemCategory::ALL.into_iter()
+            .find(|category| category.to_string() == s)
+            .ok_or_else(|| format!("unknown problem category {:?}, expected change, discovery or reload", s))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemRecord {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub output: Option<String>,
+    pub message: String,
+}
+
+/// What `swwws-cli problems` shows for one category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemReport {
+    pub category: ProblemCategory,
+    /// Problems recorded since startup or the last clear
+    pub total: u64,
+    /// Of those, how many were pushed out of `recent`
+    pub dropped: u64,
+    /// Problems per output; those without an output are counted under ""
+    pub by_output: BTreeMap<String, u64>,
+    /// Newest last
+    pub recent: Vec<ProblemRecord>,
+}
+
+#[derive(Debug, Default)]
+struct Ring {
+    records: VecDeque<ProblemRecord>,
+    total: u64,
+    dropped: u64,
+    by_output: BTreeMap<String, u64>,
+}
+
+/// Bounded record of failures in a long-running daemon. Each category keeps its
+/// newest `capacity` records and counts the rest, so memory use does not grow
+/// with the number of failures.
+#[derive(Debug)]
+pub struct ProblemStore {
+    capacity: usize,
+    categories: Mutex<HashMap<ProblemCategory, Ring>>,
+}
+
+impl Default for ProblemStore {
+    fn default() -> Self {
+        Self::new(RECORDS_PER_CATEGORY)
+    }
+}
+
+impl ProblemStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            categories: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record(&self, category: ProblemCategory, output: Option<&str>, message: impl Into<String>) {
+        let mut message = message.into();
+        if let Some((cut, _)) = message.char_indices().nth(MAX_MESSAGE_LEN) {
+            message.truncate(cut);
+            message.push('…');
+        }
+
+        let mut categories = self.categories.lock().unwrap();
+        let ring = categories.entry(category).or_default();
+        ring.total += 1;
+        *ring.by_output.entry(output.unwrap_or_default().to_string()).or_default() += 1;
+        if ring.records.len() == self.capacity {
+            ring.records.pop_front();
+            ring.dropped += 1;
+        }
+        ring.records.push_back(ProblemRecord {
+            timestamp: chrono::Utc::now(),
+            output: output.map(str::to_string),
+            message,
+        });
+    }
+
+    /// Categories that have problems, in `ProblemCategory` order
+    pub fn report(&self) -> Vec<ProblemReport> {
+        let categories = self.categories.lock().unwrap();
+        let mut reports: Vec<ProblemReport> = categories.iter()
+            .filter(|(_, ring)| ring.total > 0)
+            .map(|(category, ring)| ProblemReport {
+                category: *category,
+                total: ring.total,
+                dropped: ring.dropped,
+                by_output: ring.by_output.clone(),
+                recent: ring.records.iter().cloned().collect(),
+            })
+            .collect();
+        reports.sort_by_key(|report| report.category);
+        reports
+    }
+
+    /// Forget the problems of one category, or of all of them
+    pub fn clear(&self, category: Option<ProblemCategory>) {
+        let mut categories = self.categories.lock().unwrap();
+        match category {
+            Some(category) => {
+                categories.remove(&category);
+            }
+            None => categories.clear(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_stays_bounded() {
+        let store = ProblemStore::new(RECORDS_PER_CATEGORY);
+        let long = "x".repeat(MAX_MESSAGE_LEN * 4);
+        for i in 0..100_000u64 {
+            let output = format!("DP-{}", i % 3);
+            store.record(ProblemCategory::Change, Some(&output), format!("{} {}", i, long));
+        }
+
+        let report = store.report();
+        assert_eq!(report.len(), 1);
+        let change = &report[0];
+        assert_eq!(change.total, 100_000);
+        assert_eq!(change.recent.len(), RECORDS_PER_CATEGORY);
+        assert_eq!(change.dropped, 100_000 - RECORDS_PER_CATEGORY as u64);
+        assert_eq!(change.by_output.len(), 3);
+        assert!(change.recent.iter().all(|r| r.message.chars().count() <= MAX_MESSAGE_LEN + 1));
+        // The newest records are the ones kept
+        assert!(change.recent.last().unwrap().message.starts_with("99999 "));
+    }
+
+    #[test]
+    fn test_categories_are_separate_and_clear_selectively() {
+        let store = ProblemStore::new(2);
+        for _ in 0..5 {
+            store.record(ProblemCategory::Change, Some("DP-1"), "swww failed");
+        }
+        store.record(ProblemCategory::Reload, None, "bad config");
+
+        let report = store.report();
+        assert_eq!(report.iter().map(|r| r.category).collect::<Vec<_>>(),
+            vec![ProblemCategory::Change, ProblemCategory::Reload]);
+        assert_eq!(report[1].recent.len(), 1);
+        assert_eq!(report[1].by_output.get(""), Some(&1));
+
+        store.clear(Some(ProblemCategory::Change));
+        let report = store.report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].category, ProblemCategory::Reload);
+
+        store.clear(None);
+        assert!(store.report().is_empty());
+    }
+
+    #[test]
+    fn test_parse_category() {
+        assert_eq!("discovery".parse::<ProblemCategory>(), Ok(ProblemCategory::Discovery));
+        assert!("disk".parse::<ProblemCategory>().is_err());
+    }
+}
@@ -1,13 +1,37 @@
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use serde::{Serialize, Deserialize};
+use crate::collation::Collation;
+use crate::shown::ShownHistory;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Sorting {
     Random,
     Ascending,
     Descending,
+    /// Most recently modified file first
+    NewestFirst,
+    /// Least recently modified file first
+    OldestFirst,
+    /// Images shown longest ago (or never) first, in random order among equals
+    LeastRecentlyShown,
+}
+
+impl Sorting {
+    fn by_mtime(&self) -> bool {
+        matches!(self, Sorting::NewestFirst | Sorting::OldestFirst)
+    }
+
+    /// Whether each cycle is in a new order rather than a fixed one
+    pub fn shuffles(&self) -> bool {
+        matches!(self, Sorting::Random | Sorting::LeastRecentlyShown)
+    }
 }
 
 impl std::fmt::Display for Sorting {
@@ -16,66 +40,242 @@ impl std::fmt::Display for Sorting {
             Sorting::Random => write!(f, "random"),
             Sorting::Ascending => write!(f, "ascending"),
             Sorting::Descending => write!(f, "descending"),
+            Sorting::NewestFirst => write!(f, "newest first"),
+            Sorting::OldestFirst => write!(f, "oldest first"),
+            Sorting::LeastRecentlyShown => write!(f, "least recently shown"),
+        }
+    }
+}
+
+/// Optional queue behavior beyond size and sorting
+#[derive(Debug, Clone, Default)]
+pub struct QueueOptions {
+    pub collation: Collation,
+    /// In random mode, how many of the last images shown may not come up again
+    /// among the first picks of a new cycle. `None` uses the buffer size.
+    pub no_repeat_window: Option<usize>,
+    /// Seed for random mode's shuffles, so the order can be reproduced. `None`
+    /// seeds from the OS.
+    pub seed: Option<u64>,
+    /// How many shown images `previous` can step back through. `None` keeps the
+    /// whole cycle.
+    pub history_size: Option<usize>,
+    /// When images were last shown, for `LeastRecentlyShown`. Without it every
+    /// image counts as never shown, so cycles are simply shuffled.
+    pub shown: Option<Arc<ShownHistory>>,
+}
+
+/// How `Queue::replace_pool` carries state over into the new pool
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplacePolicy {
+    /// Keep the current image as current if it is still in the new pool
+    pub keep_current: bool,
+    /// Keep history entries that are still in the new pool, so `previous` walks back through them
+    pub keep_history: bool,
+    /// Make this image current instead, if it is in the new pool. Takes precedence over `keep_current`
+    pub start_at: Option<PathBuf>,
+}
+
+impl ReplacePolicy {
+    /// Swap the pool without disturbing what is on screen (reloads, watchers)
+    pub fn warm() -> Self {
+        Self {
+            keep_current: true,
+            keep_history: true,
+            start_at: None,
+        }
+    }
+
+    /// Start over on the new pool as if the queue had just been created
+    pub fn fresh() -> Self {
+        Self::default()
+    }
+
+    /// Start over on the new pool, but resume at a known image (state restore)
+    pub fn resume_at(image: PathBuf) -> Self {
+        Self {
+            start_at: Some(image),
+            ..Self::default()
         }
     }
 }
 
+/// Order `images` in place according to the sorting mode and collation
+pub fn sort_images(images: &mut [PathBuf], sorting: &Sorting, collation: Collation) {
+    let mut slots: Vec<usize> = (0..images.len()).collect();
+    sort_slots(images, &mut slots, sorting, collation, &mut rand::thread_rng(), None);
+
+    let mut unsorted: Vec<PathBuf> = images.iter_mut().map(std::mem::take).collect();
+    for (image, slot) in images.iter_mut().zip(slots) {
+        *image = std::mem::take(&mut unsorted[slot]);
+    }
+}
+
+/// Order `slots`, indices into `paths`, like `sort_images` orders the paths
+/// themselves. Shuffles with `rng` and orders `LeastRecentlyShown` by `shown`.
+fn sort_slots<R: Rng + ?Sized>(
+    paths: &[PathBuf],
+    slots: &mut [usize],
+    sorting: &Sorting,
+    collation: Collation,
+    rng: &mut R,
+    shown: Option<&ShownHistory>,
+) {
+    match sorting {
+        Sorting::Random => {
+            slots.shuffle(rng);
+        }
+        Sorting::LeastRecentlyShown => {
+            // Shuffled first so the stable sort leaves equals in random order
+            slots.shuffle(rng);
+            if let Some(shown) = shown {
+                let last_shown: HashMap<usize, _> = slots.iter()
+                    .map(|&slot| (slot, shown.last_shown(&paths[slot])))
+                    .collect();
+                slots.sort_by_key(|slot| last_shown[slot]);
+            }
+        }
+        Sorting::Ascending => {
+            slots.sort_by(|&a, &b| collation.compare(&paths[a], &paths[b]));
+        }
+        Sorting::Descending => {
+            slots.sort_by(|&a, &b| collation.compare(&paths[b], &paths[a]));
+        }
+        Sorting::NewestFirst | Sorting::OldestFirst => {
+            let mtimes: HashMap<usize, Option<SystemTime>> = slots.iter()
+                .map(|&slot| (slot, modified(&paths[slot])))
+                .collect();
+            for (&slot, _) in mtimes.iter().filter(|(_, mtime)| mtime.is_none()) {
+                log::warn!("Can't read modification time of {:?}, sorting it last", paths[slot]);
+            }
+            slots.sort_by(|&a, &b| {
+                compare_mtime(sorting, collation, (&paths[a], mtimes[&a]), (&paths[b], mtimes[&b]))
+            });
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Order by modification time for the mtime sorting modes. Files without one go
+/// last, and equal times fall back to the collation so the order is stable.
+fn compare_mtime(
+    sorting: &Sorting,
+    collation: Collation,
+    (a, a_time): (&Path, Option<SystemTime>),
+    (b, b_time): (&Path, Option<SystemTime>),
+) -> Ordering {
+    match (a_time, b_time) {
+        (Some(a_time), Some(b_time)) => {
+            let by_time = if *sorting == Sorting::NewestFirst {
+                b_time.cmp(&a_time)
+            } else {
+                a_time.cmp(&b_time)
+            };
+            by_time.then_with(|| collation.compare(a, b))
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => collation.compare(a, b),
+    }
+}
+
+/// Reorder a reshuffled cycle so that as many images as there are in `recent`
+/// come first, none of them from `recent`. The rest keep their shuffled order.
+fn keep_back(images: &mut Vec<usize>, recent: &HashSet<usize>) {
+    let mut fresh = 0;
+    let (head, rest): (Vec<usize>, Vec<usize>) = images.drain(..).partition(|image| {
+        let take = fresh < recent.len() && !recent.contains(image);
+        fresh += usize::from(take);
+        take
+    });
+    images.extend(head);
+    images.extend(rest);
+}
+
+/// A cycling queue of images.
+///
+/// Each path is stored once, in `paths`; everything else holds indices into it.
+/// Moving images through the queue never copies a path, so a queue over a huge
+/// collection costs little more than the paths themselves, and clones of a
+/// queue share them.
 #[derive(Debug, Clone)]
 pub struct Queue {
-    buffer: VecDeque<PathBuf>,
-    current: Option<PathBuf>,
-    tail: VecDeque<PathBuf>,
+    /// Every image the queue has been given. Entries of removed images stay,
+    /// unreferenced, until the pool is replaced.
+    paths: Arc<Vec<PathBuf>>,
+    buffer: VecDeque<usize>,
+    current: Option<usize>,
+    tail: VecDeque<usize>,
+    /// Images shown this cycle that fell out of `tail`, oldest first. They
+    /// rejoin the pool when the cycle restarts.
+    played: Vec<usize>,
     size: usize,
     sorting: Sorting,
-    images: Vec<PathBuf>,
+    options: QueueOptions,
+    images: VecDeque<usize>,
+    rng: StdRng,
 }
 
 impl Queue {
     pub fn new(size: usize, sorting: Sorting, images: Vec<PathBuf>) -> Option<Self> {
+        Self::with_options(size, sorting, images, QueueOptions::default())
+    }
+
+    pub fn with_options(
+        size: usize,
+        sorting: Sorting,
+        images: Vec<PathBuf>,
+        options: QueueOptions,
+    ) -> Option<Self> {
         if images.is_empty() {
             return None;
         }
 
+        let slots: Vec<usize> = (0..images.len()).collect();
         let mut queue = Self {
+            paths: Arc::new(images),
             buffer: VecDeque::new(),
             current: None,
             tail: VecDeque::new(),
+            played: Vec::new(),
             size,
             sorting,
-            images: images.clone(),
+            rng: options.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+            options,
+            images: VecDeque::new(),
         };
+        queue.warn_if_window_clamped(slots.len());
 
-        queue.initialize(images);
+        queue.initialize(slots);
         Some(queue)
     }
 
-    fn initialize(&mut self, mut images: Vec<PathBuf>) {
-        match self.sorting {
-            Sorting::Random => {
-                let mut rng = rand::thread_rng();
-                images.shuffle(&mut rng);
-            }
-            Sorting::Ascending => {
-                images.sort();
-            }
-            Sorting::Descending => {
-                images.sort_by(|a, b| b.cmp(a));
-            }
-        }
+    fn initialize(&mut self, mut images: Vec<usize>) {
+        self.sort(&mut images);
 
-        self.images = images;
+        self.images = images.into();
         
         // Set the first image as current
-        if !self.images.is_empty() {
-            self.current = Some(self.images.remove(0));
-        }
+        self.current = self.images.pop_front();
         
         self.refill();
     }
 
+    fn path(&self, slot: usize) -> &PathBuf {
+        &self.paths[slot]
+    }
+
+    fn sort(&mut self, slots: &mut [usize]) {
+        sort_slots(&self.paths, slots, &self.sorting, self.options.collation, &mut self.rng, self.options.shown.as_deref());
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<&PathBuf> {
-        if let Some(current) = &self.current {
-            self.tail.push_back(current.clone());
+        if let Some(current) = self.current.take() {
+            self.push_history(current);
         }
 
         self.current = self.buffer.pop_front();
@@ -86,121 +286,427 @@ impl Queue {
             self.current = self.buffer.pop_front();
         }
         
-        self.current.as_ref()
+        self.current_image()
+    }
+
+    /// Advance like `next`, moving on again while the image is one of `taken`, at
+    /// most `max_skips` times. The flag is set when the image is taken all the same.
+    pub fn next_distinct(&mut self, taken: &HashSet<PathBuf>, max_skips: usize) -> Option<(PathBuf, bool)> {
+        let mut image = self.next()?.clone();
+        for _ in 0..max_skips {
+            if !taken.contains(&image) {
+                break;
+            }
+            image = self.next()?.clone();
+        }
+        let duplicate = taken.contains(&image);
+        Some((image, duplicate))
+    }
+
+    /// Up to `n` upcoming images, in the order `next` will show them, without
+    /// advancing. Stops at the end of the cycle, since a new cycle is only
+    /// sorted (or reshuffled) once it starts.
+    pub fn peek(&self, n: usize) -> Vec<&PathBuf> {
+        self.buffer.iter().chain(self.images.iter()).take(n).map(|&slot| self.path(slot)).collect()
+    }
+
+    /// Up to `n` images shown before the current one, oldest first, including
+    /// ones `previous` can no longer reach
+    pub fn recent(&self, n: usize) -> Vec<&PathBuf> {
+        let shown = self.played.len() + self.tail.len();
+        self.played.iter().chain(self.tail.iter()).skip(shown.saturating_sub(n)).map(|&slot| self.path(slot)).collect()
+    }
+
+    /// Drop `image` from the queue for good: history, upcoming images and the
+    /// rest of the pool. If it was current, the next image becomes current
+    /// without the removed one entering history. Returns whether it was in the queue.
+    pub fn remove_image(&mut self, image: &Path) -> bool {
+        let before = self.size() + self.images.len();
+        let paths = &self.paths;
+        let keep = |slot: &usize| paths[*slot] != image;
+        self.played.retain(keep);
+        self.tail.retain(keep);
+        self.buffer.retain(keep);
+        self.images.retain(keep);
+
+        if self.current.is_some_and(|slot| self.path(slot) == image) {
+            self.current = self.buffer.pop_front();
+            self.refill();
+            if self.current.is_none() {
+                self.current = self.buffer.pop_front();
+            }
+        } else {
+            self.refill();
+        }
+
+        self.size() + self.images.len() < before
     }
 
+    /// Step back to the image shown before the current one. Returns None and
+    /// stays put when there is no history left to step back through.
     pub fn previous(&mut self) -> Option<&PathBuf> {
-        if let Some(current) = &self.current {
-            self.buffer.push_front(current.clone());
+        if self.tail.is_empty() {
+            return None;
+        }
+        if let Some(current) = self.current {
+            self.buffer.push_front(current);
         }
 
         self.current = self.tail.pop_back();
-        self.current.as_ref()
+        self.current_image()
     }
 
     fn refill(&mut self) {
-        // Use remove(0) to maintain order for ascending/descending sorts
-        while self.buffer.len() < self.size && !self.images.is_empty() {
-            let image = self.images.remove(0);
-            self.buffer.push_back(image);
+        while self.buffer.len() < self.size {
+            match self.images.pop_front() {
+                Some(image) => self.buffer.push_back(image),
+                None => break,
+            }
         }
         
         // If buffer is still empty and we have no more images, but we have a tail (history),
         // restart the queue by moving all images from tail back to the pool
-        if self.buffer.is_empty() && self.images.is_empty() && !self.tail.is_empty() {
-            log::debug!("Queue exhausted, restarting cycle with {} images", self.tail.len());
+        if self.buffer.is_empty() && self.images.is_empty() && (!self.tail.is_empty() || !self.played.is_empty()) {
+            log::debug!("Queue exhausted, restarting cycle with {} images", self.played.len() + self.tail.len());
             
-            // Move all tail images back to the main pool for reprocessing
-            let mut restart_images: Vec<PathBuf> = self.tail.drain(..).collect();
+            // The images shown last, newest first, before the history is drained
+            let recent = self.recently_shown();
+            let last_shown = self.current
+                .or_else(|| self.tail.back().copied())
+                .or_else(|| self.played.last().copied());
+
+            // Move all shown images back to the main pool for reprocessing
+            let mut restart_images: Vec<usize> = self.played.drain(..).chain(self.tail.drain(..)).collect();
             
             // Re-sort according to our sorting mode
-            match self.sorting {
-                Sorting::Random => {
-                    let mut rng = rand::thread_rng();
-                    restart_images.shuffle(&mut rng);
-                }
-                Sorting::Ascending => {
-                    restart_images.sort();
-                }
-                Sorting::Descending => {
-                    restart_images.sort_by(|a, b| b.cmp(a));
-                }
+            self.sort(&mut restart_images);
+            keep_back(&mut restart_images, &recent);
+            // Whatever the window, never open the new cycle with the image still on screen
+            if self.sorting.shuffles() && restart_images.len() > 1 && restart_images.first() == last_shown.as_ref() {
+                restart_images.rotate_left(1);
             }
             
             // Put them back in images pool and refill buffer
-            self.images = restart_images;
-            // Use remove(0) to maintain order
-            while self.buffer.len() < self.size && !self.images.is_empty() {
-                let image = self.images.remove(0);
-                self.buffer.push_back(image);
+            self.images = restart_images.into();
+            while self.buffer.len() < self.size {
+                match self.images.pop_front() {
+                    Some(image) => self.buffer.push_back(image),
+                    None => break,
+                }
             }
         }
     }
 
+    /// How many images the no-repeat window covers in a pool of `total`. At most
+    /// half of it, so there are always enough images outside the window to pick.
+    fn no_repeat_window(&self, total: usize) -> usize {
+        self.options.no_repeat_window.unwrap_or(self.size).min(total / 2)
+    }
+
+    fn warn_if_window_clamped(&self, total: usize) {
+        let Some(wanted) = self.options.no_repeat_window else {
+            return;
+        };
+        if self.sorting == Sorting::Random && wanted > self.no_repeat_window(total) {
+            log::warn!(
+                "no_repeat_window of {} is too large for {} images, using {}",
+                wanted, total, self.no_repeat_window(total),
+            );
+        }
+    }
+
+    /// The last images shown, as many as the no-repeat window covers. Empty
+    /// outside random mode, where a new cycle repeats the sort order anyway.
+    fn recently_shown(&self) -> HashSet<usize> {
+        if self.sorting != Sorting::Random {
+            return HashSet::new();
+        }
+        let window = self.no_repeat_window(self.played.len() + self.tail.len() + usize::from(self.current.is_some()));
+        self.current.iter()
+            .chain(self.tail.iter().rev())
+            .chain(self.played.iter().rev())
+            .take(window)
+            .copied()
+            .collect()
+    }
+
     pub fn is_full(&self) -> bool {
         self.buffer.len() >= self.size
     }
 
     pub fn current_image(&self) -> Option<&PathBuf> {
-        self.current.as_ref()
+        self.current.map(|slot| self.path(slot))
     }
 
+    /// How many images were shown this cycle before the current one, whether or
+    /// not `previous` can still reach them
     pub fn current_position(&self) -> usize {
-        self.tail.len()
+        self.played.len() + self.tail.len()
     }
 
     pub fn size(&self) -> usize {
-        self.tail.len() + self.buffer.len() + if self.current.is_some() { 1 } else { 0 }
+        self.played.len() + self.tail.len() + self.buffer.len() + if self.current.is_some() { 1 } else { 0 }
     }
 
     pub fn get_sorting(&self) -> Sorting {
         self.sorting.clone()
     }
 
+    pub fn get_collation(&self) -> Collation {
+        self.options.collation
+    }
+
     pub fn get_all_images(&self) -> Vec<PathBuf> {
-        let mut all_images = Vec::new();
-        
-        // Add images in the order they would be processed
-        // This includes tail (history), current, and buffer (future)
-        all_images.extend(self.tail.iter().cloned());
-        if let Some(current) = &self.current {
-            all_images.push(current.clone());
-        }
-        all_images.extend(self.buffer.iter().cloned());
-        all_images.extend(self.images.iter().cloned());
-        
-        all_images
+        // In the order they would be processed: history, current, then upcoming
+        self.played.iter()
+            .chain(self.tail.iter())
+            .chain(self.current.iter())
+            .chain(self.buffer.iter())
+            .chain(self.images.iter())
+            .map(|&slot| self.path(slot).clone())
+            .collect()
     }
 
-    pub fn set_position(&mut self, position: usize) -> bool {
-        // Clear current state
-        self.current = None;
-        self.buffer.clear();
-        self.tail.clear();
-        
-        // Rebuild the queue to the specified position
-        let total_images = self.images.len();
-        if position >= total_images {
+    /// Change how many upcoming images are kept in the buffer
+    pub fn set_buffer_size(&mut self, size: usize) {
+        self.size = size;
+        while self.buffer.len() > self.size {
+            if let Some(image) = self.buffer.pop_back() {
+                self.images.push_front(image);
+            }
+        }
+        self.refill();
+    }
+
+    /// Replace the image pool in place.
+    ///
+    /// Every image of the new pool ends up exactly once in the queue (history,
+    /// current or upcoming), and images no longer in the pool are dropped. Which
+    /// current image and history survive is decided by `policy`. Upcoming images
+    /// are shuffled in random mode; in ordered modes they continue from the current
+    /// image's place in the sort order, so newly added files are reached in order.
+    ///
+    /// Returns false and leaves the queue untouched if the new pool is empty.
+    pub fn replace_pool(&mut self, new_images: Vec<PathBuf>, policy: ReplacePolicy) -> bool {
+        let mut seen = HashSet::new();
+        let pool: Vec<PathBuf> = new_images
+            .into_iter()
+            .filter(|image| seen.insert(image.clone()))
+            .collect();
+        if pool.is_empty() {
             return false;
         }
-        
-        // Move images to tail up to the position
-        for _ in 0..position {
-            if !self.images.is_empty() {
-                let image = self.images.remove(0);
-                self.tail.push_back(image);
+
+        // Where each image of the new pool sits in it
+        let slots: HashMap<&PathBuf, usize> = pool.iter().enumerate().map(|(slot, image)| (image, slot)).collect();
+        let current = policy.start_at.as_ref()
+            .and_then(|image| slots.get(image).copied())
+            .or_else(|| {
+                self.current.take()
+                    .filter(|_| policy.keep_current)
+                    .and_then(|slot| slots.get(self.path(slot)).copied())
+            });
+
+        let tail: VecDeque<usize> = if policy.keep_history {
+            self.played.iter()
+                .chain(self.tail.iter())
+                .filter_map(|&slot| slots.get(self.path(slot)).copied())
+                .filter(|&slot| Some(slot) != current)
+                .collect()
+        } else {
+            VecDeque::new()
+        };
+
+        let placed: HashSet<usize> = tail.iter().chain(current.iter()).copied().collect();
+        let mut upcoming: Vec<usize> = (0..pool.len())
+            .filter(|slot| !placed.contains(slot))
+            .collect();
+        drop(slots);
+
+        self.paths = Arc::new(pool);
+        self.sort(&mut upcoming);
+        if let Some(current) = current {
+            self.continue_after(current, &mut upcoming);
+        }
+
+        self.played.clear();
+        self.tail = tail;
+        self.trim_history();
+        self.buffer.clear();
+        self.images = upcoming.into();
+        self.current = current;
+        self.refill();
+
+        if self.current.is_none() {
+            self.current = self.buffer.pop_front();
+            self.refill();
+        }
+
+        true
+    }
+
+    /// Change how far back `previous` can go, dropping history beyond it
+    pub fn set_history_size(&mut self, history_size: Option<usize>) {
+        self.options.history_size = history_size;
+        self.trim_history();
+    }
+
+    fn push_history(&mut self, image: usize) {
+        self.tail.push_back(image);
+        self.trim_history();
+    }
+
+    /// Move history beyond `history_size` out of reach of `previous`
+    fn trim_history(&mut self) {
+        let limit = self.options.history_size.unwrap_or(usize::MAX);
+        while self.tail.len() > limit {
+            if let Some(oldest) = self.tail.pop_front() {
+                self.played.push(oldest);
             }
         }
-        
-        // Set current image
-        if !self.images.is_empty() {
-            let image = self.images.remove(0);
-            self.current = Some(image);
+    }
+
+    /// Change the no-repeat window used from the next cycle on
+    pub fn set_no_repeat_window(&mut self, window: Option<usize>) {
+        self.options.no_repeat_window = window;
+        self.warn_if_window_clamped(self.size());
+    }
+
+    /// Add images that are not in the queue yet, keeping the current image and
+    /// history. In random mode they are shuffled into the images still to come;
+    /// in ordered modes they take their place in the sort order, so one sorting
+    /// after the current image comes up this cycle and one before it after the
+    /// wrap. Returns how many were added.
+    pub fn add_images(&mut self, new_images: Vec<PathBuf>) -> usize {
+        let mut present: HashSet<usize> = self.played.iter()
+            .chain(self.tail.iter())
+            .chain(self.current.iter())
+            .chain(self.buffer.iter())
+            .chain(self.images.iter())
+            .copied()
+            .collect();
+        // Images removed earlier get their old slot back, the rest a new one
+        let known: HashMap<&Path, usize> = self.paths.iter()
+            .enumerate()
+            .map(|(slot, path)| (path.as_path(), slot))
+            .collect();
+        let mut slots = Vec::new();
+        let mut unknown = Vec::new();
+        for image in new_images {
+            match known.get(image.as_path()) {
+                Some(&slot) => slots.push(slot),
+                None => unknown.push(image),
+            }
         }
-        
+        drop(known);
+        unknown.sort();
+        unknown.dedup();
+        if !unknown.is_empty() {
+            let paths = Arc::make_mut(&mut self.paths);
+            slots.extend(paths.len()..paths.len() + unknown.len());
+            paths.extend(unknown);
+        }
+        let added: Vec<usize> = slots.into_iter().filter(|&slot| present.insert(slot)).collect();
+        let count = added.len();
+        if count == 0 {
+            return 0;
+        }
+
+        if self.sorting.shuffles() {
+            for image in added {
+                let at = self.rng.gen_range(0..=self.images.len());
+                self.images.insert(at, image);
+            }
+        } else {
+            let mut upcoming: Vec<usize> = self.buffer.drain(..)
+                .chain(self.images.drain(..))
+                .chain(added)
+                .collect();
+            self.sort(&mut upcoming);
+            if let Some(current) = self.current {
+                self.continue_after(current, &mut upcoming);
+            }
+            self.images = upcoming.into();
+        }
+
+        self.refill();
+        if self.current.is_none() {
+            self.current = self.buffer.pop_front();
+            self.refill();
+        }
+        count
+    }
+
+    /// Rotate sorted `upcoming` images so those after `current` come first, and
+    /// those before it after the wrap. Shuffled orders are left as they are.
+    fn continue_after(&self, current: usize, upcoming: &mut [usize]) {
+        if !self.sorting.shuffles() {
+            let split = upcoming
+                .iter()
+                .position(|&image| self.order(self.path(current), self.path(image)) == Ordering::Less)
+                .unwrap_or(upcoming.len());
+            upcoming.rotate_left(split);
+        }
+    }
+
+    fn order(&self, a: &Path, b: &Path) -> Ordering {
+        match self.sorting {
+            Sorting::Descending => self.options.collation.compare(b, a),
+            _ if self.sorting.by_mtime() => {
+                compare_mtime(&self.sorting, self.options.collation, (a, modified(a)), (b, modified(b)))
+            }
+            _ => self.options.collation.compare(a, b),
+        }
+    }
+
+    /// Shuffle the images still to come in this cycle again, leaving the current
+    /// image and history alone. Returns false, changing nothing, unless the
+    /// queue is in random mode.
+    pub fn reshuffle_remaining(&mut self) -> bool {
+        if self.sorting != Sorting::Random {
+            return false;
+        }
+        let mut remaining: Vec<usize> = self.buffer.drain(..).chain(self.images.drain(..)).collect();
+        remaining.shuffle(&mut self.rng);
+        self.images = remaining.into();
+        self.refill();
+        true
+    }
+
+    /// Where `image` is in this cycle's order, as `set_position` counts it
+    pub fn position_of(&self, image: &Path) -> Option<usize> {
+        self.played.iter()
+            .chain(self.tail.iter())
+            .chain(self.current.iter())
+            .chain(self.buffer.iter())
+            .chain(self.images.iter())
+            .position(|&slot| self.path(slot) == image)
+    }
+
+    /// Make the image at `position` in this cycle's order current, with the ones
+    /// before it as history. Returns false, leaving the queue as it was, when
+    /// there are not that many images.
+    pub fn set_position(&mut self, position: usize) -> bool {
+        let total_images = self.current_position() + usize::from(self.current.is_some()) + self.buffer.len() + self.images.len();
+        if position >= total_images {
+            return false;
+        }
+
+        // The cycle in order: history, current, upcoming, then the rest of the pool
+        let mut order: Vec<usize> = self.played.drain(..)
+            .chain(self.tail.drain(..))
+            .chain(self.current.take())
+            .chain(self.buffer.drain(..))
+            .chain(self.images.drain(..))
+            .collect();
+        self.images = order.split_off(position + 1).into();
+        self.current = order.pop();
+        self.tail = order.into();
+        self.trim_history();
+
         // Refill buffer
         self.refill();
-        
+
         true
     }
 }
@@ -261,6 +767,214 @@ mod tests {
         assert_eq!(seen_images.len(), 3);
     }
     
+    #[test]
+    fn test_queue_unicode_collation_across_cycles() {
+        let images = vec![
+            PathBuf::from("/test/zebra.png"),
+            PathBuf::from("/test/Ähre.png"),
+            PathBuf::from("/test/apfel.png"),
+        ];
+
+        let options = QueueOptions { collation: Collation::Unicode, ..Default::default() };
+        let mut queue = Queue::with_options(1, Sorting::Ascending, images, options).unwrap();
+
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/Ähre.png")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/apfel.png")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/zebra.png")));
+
+        // The cycle restart must re-sort with the same collation
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/Ähre.png")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/apfel.png")));
+    }
+
+    #[test]
+    fn test_queue_natural_collation_across_cycles() {
+        let images: Vec<PathBuf> = [10, 2, 1, 120, 11]
+            .iter()
+            .map(|n| PathBuf::from(format!("/test/frame_{}.png", n)))
+            .collect();
+
+        let options = QueueOptions { collation: Collation::Natural, ..Default::default() };
+        let mut queue = Queue::with_options(2, Sorting::Ascending, images, options).unwrap();
+        let frame = |n: u32| PathBuf::from(format!("/test/frame_{}.png", n));
+
+        assert_eq!(queue.current_image(), Some(&frame(1)));
+        let order: Vec<PathBuf> = (0..6).map(|_| queue.next().unwrap().clone()).collect();
+        // The cycle restart re-sorts in the same natural order
+        assert_eq!(order, [2, 10, 11, 120, 1, 2].map(frame).to_vec());
+    }
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| PathBuf::from(format!("/test/{}", n))).collect()
+    }
+
+    fn assert_each_once(queue: &Queue, pool: &[PathBuf]) {
+        let mut all = queue.get_all_images();
+        all.sort();
+        let mut expected = pool.to_vec();
+        expected.sort();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn test_replace_pool_warm_keeps_current_and_history() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"])).unwrap();
+        queue.next();
+        queue.next();
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+
+        // 2.jpg is gone, 5.jpg is new
+        let pool = paths(&["1.jpg", "3.jpg", "4.jpg", "5.jpg"]);
+        assert!(queue.replace_pool(pool.clone(), ReplacePolicy::warm()));
+
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_each_once(&queue, &pool);
+
+        // History survived the swap, minus the removed image
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/4.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/5.jpg")));
+    }
+
+    #[test]
+    fn test_replace_pool_removed_current_advances_in_order() {
+        let mut queue = Queue::new(1, Sorting::Ascending, paths(&["a.jpg", "c.jpg", "e.jpg"])).unwrap();
+        queue.next();
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/c.jpg")));
+
+        let pool = paths(&["a.jpg", "b.jpg", "d.jpg", "e.jpg"]);
+        assert!(queue.replace_pool(pool.clone(), ReplacePolicy::warm()));
+
+        // c.jpg vanished, so the queue picks up with the first upcoming image
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/b.jpg")));
+        assert_each_once(&queue, &pool);
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/d.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/e.jpg")));
+    }
+
+    #[test]
+    fn test_replace_pool_ordered_new_images_follow_current() {
+        let mut queue = Queue::new(1, Sorting::Descending, paths(&["1.jpg", "3.jpg", "5.jpg"])).unwrap();
+        queue.next();
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+
+        let pool = paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg", "6.jpg"]);
+        assert!(queue.replace_pool(pool.clone(), ReplacePolicy { keep_current: true, ..Default::default() }));
+
+        // Without history, the images before 3.jpg in the order come after the wrap
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_each_once(&queue, &pool);
+        let order: Vec<PathBuf> = (0..5).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(order, paths(&["2.jpg", "1.jpg", "6.jpg", "5.jpg", "4.jpg"]));
+    }
+
+    #[test]
+    fn test_replace_pool_fresh_and_resume_at() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg"])).unwrap();
+        queue.next();
+
+        let pool = paths(&["1.jpg", "2.jpg", "3.jpg"]);
+        assert!(queue.replace_pool(pool.clone(), ReplacePolicy::fresh()));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_eq!(queue.current_position(), 0);
+
+        assert!(queue.replace_pool(pool.clone(), ReplacePolicy::resume_at(PathBuf::from("/test/3.jpg"))));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_each_once(&queue, &pool);
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/1.jpg")));
+
+        // A start image outside the pool falls back to the policy's other rules
+        assert!(queue.replace_pool(pool, ReplacePolicy::resume_at(PathBuf::from("/test/gone.jpg"))));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/1.jpg")));
+    }
+
+    #[test]
+    fn test_replace_pool_random_excludes_current_and_history() {
+        let mut queue = Queue::new(3, Sorting::Random, paths(&["a.jpg", "b.jpg", "c.jpg", "d.jpg"])).unwrap();
+        queue.next();
+        let current = queue.current_image().unwrap().clone();
+
+        let mut pool = paths(&["a.jpg", "b.jpg", "c.jpg", "d.jpg", "e.jpg", "e.jpg"]);
+        assert!(queue.replace_pool(pool.clone(), ReplacePolicy::warm()));
+        pool.pop();
+
+        assert_eq!(queue.current_image(), Some(&current));
+        assert_eq!(queue.current_position(), 1);
+        assert_each_once(&queue, &pool);
+    }
+
+    #[test]
+    fn test_replace_pool_empty_is_rejected() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg"])).unwrap();
+        assert!(!queue.replace_pool(Vec::new(), ReplacePolicy::warm()));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_each_once(&queue, &paths(&["1.jpg", "2.jpg"]));
+    }
+
+    #[test]
+    fn test_set_buffer_size_keeps_order() {
+        let mut queue = Queue::new(3, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg"])).unwrap();
+        queue.set_buffer_size(1);
+        assert!(queue.is_full());
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/2.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/4.jpg")));
+    }
+
+    /// Files in a temp dir with mtimes `age` seconds in the past
+    fn aged_files(dir: &Path, files: &[(&str, u64)]) -> Vec<PathBuf> {
+        let now = SystemTime::now();
+        files.iter().map(|(name, age)| {
+            let path = dir.join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(*age)).unwrap();
+            path
+        }).collect()
+    }
+
+    #[test]
+    fn test_queue_mtime_order() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let images = aged_files(dir, &[("old.jpg", 300), ("new.jpg", 10), ("mid.jpg", 100), ("tie.jpg", 100)]);
+        let missing = dir.join("missing.jpg");
+        let mut pool = images.clone();
+        pool.push(missing.clone());
+
+        let mut queue = Queue::new(2, Sorting::NewestFirst, pool.clone()).unwrap();
+        assert_eq!(queue.current_image(), Some(&dir.join("new.jpg")));
+        assert_eq!(queue.next(), Some(&dir.join("mid.jpg")));
+        assert_eq!(queue.next(), Some(&dir.join("tie.jpg")));
+        assert_eq!(queue.next(), Some(&dir.join("old.jpg")));
+        // Unreadable metadata sorts last
+        assert_eq!(queue.next(), Some(&missing));
+        assert_eq!(queue.next(), Some(&dir.join("new.jpg")));
+
+        let mut queue = Queue::new(2, Sorting::OldestFirst, pool).unwrap();
+        let order: Vec<PathBuf> = std::iter::once(queue.current_image().unwrap().clone())
+            .chain((0..4).map(|_| queue.next().unwrap().clone()))
+            .collect();
+        assert_eq!(order, vec![dir.join("old.jpg"), dir.join("mid.jpg"), dir.join("tie.jpg"), dir.join("new.jpg"), missing]);
+    }
+
+    #[test]
+    fn test_replace_pool_newest_first_continues_after_current() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let images = aged_files(dir, &[("a.jpg", 300), ("b.jpg", 200), ("c.jpg", 100)]);
+        let mut queue = Queue::new(1, Sorting::NewestFirst, images.clone()).unwrap();
+        queue.next();
+        assert_eq!(queue.current_image(), Some(&dir.join("b.jpg")));
+
+        // A file newer than everything arrives; it comes around after the wrap
+        let mut pool = images;
+        pool.extend(aged_files(dir, &[("d.jpg", 0)]));
+        assert!(queue.replace_pool(pool, ReplacePolicy { keep_current: true, ..Default::default() }));
+        let order: Vec<PathBuf> = (0..3).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(order, vec![dir.join("a.jpg"), dir.join("d.jpg"), dir.join("c.jpg")]);
+    }
+
     #[test] 
     fn test_queue_never_exhausted() {
         let images = vec![
@@ -277,4 +991,471 @@ mod tests {
             assert_eq!(queue.next(), Some(&PathBuf::from("/test/single.jpg")));
         }
     }
+
+    #[test]
+    fn test_next_distinct_skips_taken_images() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg"])).unwrap();
+        let taken: HashSet<PathBuf> = paths(&["2.jpg"]).into_iter().collect();
+
+        assert_eq!(queue.next_distinct(&taken, 5), Some((PathBuf::from("/test/3.jpg"), false)));
+        assert_eq!(queue.next_distinct(&HashSet::new(), 5), Some((PathBuf::from("/test/1.jpg"), false)));
+    }
+
+    #[test]
+    fn test_next_distinct_with_two_images_degrades_gracefully() {
+        // Two outputs sharing a pool of two images: each can only keep its own
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg"])).unwrap();
+        let other_shows_2: HashSet<PathBuf> = paths(&["2.jpg"]).into_iter().collect();
+        for _ in 0..4 {
+            assert_eq!(queue.next_distinct(&other_shows_2, 5), Some((PathBuf::from("/test/1.jpg"), false)));
+        }
+
+        // Every image is taken: give up after the retries and accept a duplicate
+        let all_taken: HashSet<PathBuf> = paths(&["1.jpg", "2.jpg"]).into_iter().collect();
+        let (image, duplicate) = queue.next_distinct(&all_taken, 5).unwrap();
+        assert!(duplicate);
+        assert!(all_taken.contains(&image));
+    }
+
+    #[test]
+    fn test_set_position_keeps_every_image() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"])).unwrap();
+        assert!(queue.set_position(2));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.current_position(), 2);
+        assert_eq!(queue.size(), 4);
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/2.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/4.jpg")));
+
+        assert!(!queue.set_position(4));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/4.jpg")));
+    }
+
+    #[test]
+    fn test_offset_queues_keep_their_gap() {
+        let images = paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg"]);
+        let mut first = Queue::new(2, Sorting::Ascending, images.clone()).unwrap();
+        let mut second = Queue::new(2, Sorting::Ascending, images.clone()).unwrap();
+        assert!(second.set_position(1));
+
+        let index = |queue: &Queue| images.iter().position(|image| Some(image) == queue.current_image()).unwrap();
+        for _ in 0..4 {
+            assert_eq!((index(&first) + 1) % images.len(), index(&second));
+            first.next();
+            second.next();
+        }
+    }
+
+    /// Smallest distance between two showings of the same image
+    fn closest_repeat(shown: &[PathBuf]) -> usize {
+        let mut last_seen: HashMap<&PathBuf, usize> = HashMap::new();
+        let mut closest = usize::MAX;
+        for (i, image) in shown.iter().enumerate() {
+            if let Some(previous) = last_seen.insert(image, i) {
+                closest = closest.min(i - previous);
+            }
+        }
+        closest
+    }
+
+    fn random_run(queue: &mut Queue, count: usize) -> Vec<PathBuf> {
+        std::iter::once(queue.current_image().unwrap().clone())
+            .chain((0..count).map(|_| queue.next().unwrap().clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_no_repeat_window_spans_cycle_restarts() {
+        let names: Vec<String> = (0..20).map(|i| format!("{}.jpg", i)).collect();
+        let images = paths(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let options = QueueOptions { no_repeat_window: Some(8), ..Default::default() };
+        let mut queue = Queue::with_options(2, Sorting::Random, images, options).unwrap();
+
+        assert!(closest_repeat(&random_run(&mut queue, 20 * 50)) > 8);
+    }
+
+    #[test]
+    fn test_no_repeat_window_defaults_to_buffer_size_and_clamps() {
+        let names: Vec<String> = (0..10).map(|i| format!("{}.jpg", i)).collect();
+        let images = paths(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut queue = Queue::new(4, Sorting::Random, images).unwrap();
+        assert!(closest_repeat(&random_run(&mut queue, 10 * 50)) > 4);
+
+        // Larger than the pool allows: clamped to half of it
+        let options = QueueOptions { no_repeat_window: Some(10), ..Default::default() };
+        let mut queue = Queue::with_options(1, Sorting::Random, paths(&["a.jpg", "b.jpg", "c.jpg"]), options).unwrap();
+        assert!(closest_repeat(&random_run(&mut queue, 3 * 50)) > 1);
+
+        queue.set_no_repeat_window(Some(0));
+        assert_eq!(random_run(&mut queue, 30).len(), 31);
+    }
+
+    #[test]
+    fn test_no_repeat_window_leaves_ordered_cycles_alone() {
+        let options = QueueOptions { no_repeat_window: Some(2), ..Default::default() };
+        let mut queue = Queue::with_options(1, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"]), options).unwrap();
+        assert_eq!(random_run(&mut queue, 5), paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg", "1.jpg", "2.jpg"]));
+    }
+
+    #[test]
+    fn test_peek_does_not_disturb_next() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"])).unwrap();
+
+        // Past the buffer into the pool, and no further than the end of the cycle
+        assert_eq!(queue.peek(2), vec![&PathBuf::from("/test/2.jpg"), &PathBuf::from("/test/3.jpg")]);
+        assert_eq!(queue.peek(10).len(), 3);
+        assert!(queue.peek(0).is_empty());
+
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/2.jpg")));
+        assert_eq!(queue.peek(1), vec![&PathBuf::from("/test/3.jpg")]);
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/4.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/4.jpg")));
+    }
+
+    #[test]
+    fn test_peek_matches_random_order() {
+        let mut queue = Queue::new(3, Sorting::Random, paths(&["a.jpg", "b.jpg", "c.jpg", "d.jpg", "e.jpg"])).unwrap();
+        let upcoming: Vec<PathBuf> = queue.peek(4).into_iter().cloned().collect();
+        let shown: Vec<PathBuf> = (0..4).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(upcoming, shown);
+    }
+
+    #[test]
+    fn test_remove_current_image_advances() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"])).unwrap();
+        queue.next();
+        assert!(queue.remove_image(Path::new("/test/2.jpg")));
+
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.current_position(), 1);
+        assert_eq!(queue.size(), 3);
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/4.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/1.jpg")));
+        assert!(!queue.get_all_images().contains(&PathBuf::from("/test/2.jpg")));
+    }
+
+    #[test]
+    fn test_remove_buffered_and_unknown_images() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"])).unwrap();
+        assert!(queue.remove_image(Path::new("/test/2.jpg")));
+        assert!(!queue.remove_image(Path::new("/test/2.jpg")));
+        assert!(!queue.remove_image(Path::new("/test/9.jpg")));
+
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_eq!(queue.get_all_images(), paths(&["1.jpg", "3.jpg", "4.jpg"]));
+        // The buffer was topped up from the pool
+        assert!(queue.is_full());
+        let order: Vec<PathBuf> = (0..4).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(order, paths(&["3.jpg", "4.jpg", "1.jpg", "3.jpg"]));
+    }
+
+    #[test]
+    fn test_remove_last_remaining_image() {
+        let mut queue = Queue::new(1, Sorting::Ascending, paths(&["1.jpg", "2.jpg"])).unwrap();
+        assert!(queue.remove_image(Path::new("/test/1.jpg")));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/2.jpg")));
+        assert!(queue.remove_image(Path::new("/test/2.jpg")));
+
+        assert_eq!(queue.current_image(), None);
+        assert_eq!(queue.size(), 0);
+        assert_eq!(queue.next(), None);
+        assert_eq!(queue.previous(), None);
+    }
+
+    #[test]
+    fn test_add_images_ordered_keeps_position() {
+        let mut queue = Queue::new(1, Sorting::Ascending, paths(&["1.jpg", "3.jpg", "5.jpg"])).unwrap();
+        queue.next();
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+
+        // 4 comes up next in order, 2 once the order wraps; known paths are skipped
+        assert_eq!(queue.add_images(paths(&["4.jpg", "2.jpg", "5.jpg", "1.jpg", "4.jpg"])), 2);
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.current_position(), 1);
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/1.jpg")));
+        let order: Vec<PathBuf> = (0..5).map(|_| queue.next().unwrap().clone()).collect();
+        // 2 still belongs to this cycle, 1 was already shown in it
+        assert_eq!(order, paths(&["3.jpg", "4.jpg", "5.jpg", "2.jpg", "1.jpg"]));
+    }
+
+    #[test]
+    fn test_add_images_descending() {
+        let mut queue = Queue::new(2, Sorting::Descending, paths(&["2.jpg", "4.jpg", "6.jpg"])).unwrap();
+        assert_eq!(queue.add_images(paths(&["5.jpg", "7.jpg", "1.jpg"])), 3);
+        let order: Vec<PathBuf> = (0..6).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(order, paths(&["5.jpg", "4.jpg", "2.jpg", "1.jpg", "7.jpg", "6.jpg"]));
+    }
+
+    #[test]
+    fn test_add_images_during_cycle_restart() {
+        // Last image of the cycle is current and the next cycle has been set up
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg"])).unwrap();
+        queue.next();
+        queue.next();
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+
+        assert_eq!(queue.add_images(paths(&["0.jpg", "4.jpg"])), 2);
+        assert_eq!(queue.get_all_images().len(), 5);
+        // 4 follows 3 in order, then the new cycle starts with 0
+        let order: Vec<PathBuf> = (0..4).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(order, paths(&["4.jpg", "0.jpg", "1.jpg", "2.jpg"]));
+    }
+
+    #[test]
+    fn test_add_images_random_shows_every_image_once_per_cycle() {
+        let mut queue = Queue::new(2, Sorting::Random, paths(&["a.jpg", "b.jpg", "c.jpg"])).unwrap();
+        queue.next();
+        let shown: Vec<PathBuf> = queue.get_all_images().into_iter().take(queue.current_position() + 1).collect();
+
+        assert_eq!(queue.add_images(paths(&["d.jpg", "e.jpg", "a.jpg"])), 2);
+        assert_eq!(queue.current_position() + 1, shown.len());
+        assert_eq!(queue.get_all_images().len(), 5);
+
+        // The rest of the cycle is everything not shown yet, new images included
+        let mut rest: Vec<PathBuf> = (0..5 - shown.len()).map(|_| queue.next().unwrap().clone()).collect();
+        rest.extend(shown);
+        rest.sort();
+        assert_eq!(rest, paths(&["a.jpg", "b.jpg", "c.jpg", "d.jpg", "e.jpg"]));
+    }
+
+    #[test]
+    fn test_seeded_queues_repeat_their_order() {
+        let images = paths(&["a.jpg", "b.jpg", "c.jpg", "d.jpg", "e.jpg"]);
+        let options = QueueOptions { seed: Some(7), ..Default::default() };
+        let mut first = Queue::with_options(2, Sorting::Random, images.clone(), options.clone()).unwrap();
+        let mut second = Queue::with_options(2, Sorting::Random, images, options).unwrap();
+        assert_eq!(random_run(&mut first, 30), random_run(&mut second, 30));
+    }
+
+    #[test]
+    fn test_no_back_to_back_repeat_across_cycles() {
+        for count in 2..6 {
+            let names: Vec<String> = (0..count).map(|i| format!("{}.jpg", i)).collect();
+            let images = paths(&names.iter().map(String::as_str).collect::<Vec<_>>());
+            for seed in 0..20 {
+                // Without a window, only the boundary check stands between cycles
+                let options = QueueOptions { no_repeat_window: Some(0), seed: Some(seed), ..Default::default() };
+                let mut queue = Queue::with_options(1, Sorting::Random, images.clone(), options).unwrap();
+                let shown = random_run(&mut queue, 200);
+                assert!(shown.windows(2).all(|pair| pair[0] != pair[1]), "{} images, seed {}: {:?}", count, seed, shown);
+
+                // Also when the image on screen was taken out of the cycle's order
+                let mut queue = Queue::with_options(3, Sorting::Random, images.clone(), QueueOptions { seed: Some(seed), ..Default::default() }).unwrap();
+                queue.set_position(count - 1);
+                let shown = random_run(&mut queue, 200);
+                assert!(shown.windows(2).all(|pair| pair[0] != pair[1]), "{} images, seed {}: {:?}", count, seed, shown);
+            }
+        }
+    }
+
+    #[test]
+    fn test_previous_stops_at_history_size() {
+        let options = QueueOptions { history_size: Some(2), ..Default::default() };
+        let images = paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg"]);
+        let mut queue = Queue::with_options(1, Sorting::Ascending, images, options).unwrap();
+        for _ in 0..3 {
+            queue.next();
+        }
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/4.jpg")));
+        assert_eq!(queue.current_position(), 3);
+
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/2.jpg")));
+        // 1.jpg is out of reach; the queue stays where it is
+        assert_eq!(queue.previous(), None);
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/2.jpg")));
+        assert_eq!(queue.current_position(), 1);
+        assert_eq!(queue.size(), 5);
+
+        let order: Vec<PathBuf> = (0..5).map(|_| queue.next().unwrap().clone()).collect();
+        assert_eq!(order, paths(&["3.jpg", "4.jpg", "5.jpg", "1.jpg", "2.jpg"]));
+    }
+
+    #[test]
+    fn test_bounded_history_keeps_every_image_in_rotation() {
+        let names: Vec<String> = (0..50).map(|i| format!("{:02}.jpg", i)).collect();
+        let images = paths(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let options = QueueOptions { history_size: Some(3), ..Default::default() };
+        let mut queue = Queue::with_options(2, Sorting::Ascending, images.clone(), options).unwrap();
+
+        // Images pushed out of the history still come around again
+        let shown = random_run(&mut queue, 149);
+        assert_eq!(shown[..50], images[..]);
+        assert!(images.iter().all(|image| shown.iter().filter(|s| *s == image).count() >= 2));
+        assert_eq!(queue.get_all_images().len(), 50);
+
+        // Shrinking the history moves the rest out of reach
+        queue.set_history_size(Some(1));
+        assert!(queue.previous().is_some());
+        assert!(queue.previous().is_none());
+    }
+
+    #[test]
+    fn test_previous_at_start_keeps_current() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg"])).unwrap();
+        assert_eq!(queue.previous(), None);
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/1.jpg")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("/test/2.jpg")));
+    }
+
+    #[test]
+    fn test_position_of_jumps_with_set_position() {
+        let mut queue = Queue::new(1, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg"])).unwrap();
+        queue.next();
+        assert_eq!(queue.position_of(Path::new("/test/1.jpg")), Some(0));
+        assert_eq!(queue.position_of(Path::new("/test/2.jpg")), Some(1));
+        assert_eq!(queue.position_of(Path::new("/test/9.jpg")), None);
+
+        let position = queue.position_of(Path::new("/test/3.jpg")).unwrap();
+        assert!(queue.set_position(position));
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/3.jpg")));
+        assert_eq!(queue.previous(), Some(&PathBuf::from("/test/2.jpg")));
+    }
+
+    #[test]
+    fn test_reshuffle_remaining_keeps_current_and_history() {
+        let names: Vec<String> = (0..30).map(|i| format!("{:02}.jpg", i)).collect();
+        let images = paths(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let options = QueueOptions { seed: Some(3), ..Default::default() };
+        let mut queue = Queue::with_options(5, Sorting::Random, images, options).unwrap();
+        queue.next();
+        queue.next();
+        let current = queue.current_image().cloned();
+        let history: Vec<PathBuf> = queue.get_all_images()[..2].to_vec();
+        let mut upcoming: Vec<PathBuf> = queue.peek(usize::MAX).into_iter().cloned().collect();
+
+        assert!(queue.reshuffle_remaining());
+        assert_eq!(queue.current_image().cloned(), current);
+        assert_eq!(queue.current_position(), 2);
+        assert_eq!(queue.get_all_images()[..2], history[..]);
+        let mut reshuffled: Vec<PathBuf> = queue.peek(usize::MAX).into_iter().cloned().collect();
+        assert_ne!(reshuffled, upcoming);
+        assert!(queue.is_full());
+        reshuffled.sort();
+        upcoming.sort();
+        assert_eq!(reshuffled, upcoming);
+    }
+
+    #[test]
+    fn test_reshuffle_remaining_refuses_ordered_queues() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg"])).unwrap();
+        assert!(!queue.reshuffle_remaining());
+        assert_eq!(queue.peek(2), vec![&PathBuf::from("/test/2.jpg"), &PathBuf::from("/test/3.jpg")]);
+    }
+
+    #[test]
+    fn test_recent_lists_shown_images_oldest_first() {
+        let mut queue = Queue::new(2, Sorting::Ascending, paths(&["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg"])).unwrap();
+        assert!(queue.recent(3).is_empty());
+        for _ in 0..3 {
+            queue.next();
+        }
+        assert_eq!(queue.current_image(), Some(&PathBuf::from("/test/4.jpg")));
+        assert_eq!(queue.recent(2), vec![&PathBuf::from("/test/2.jpg"), &PathBuf::from("/test/3.jpg")]);
+        assert_eq!(queue.recent(10).len(), 3);
+        assert!(queue.recent(0).is_empty());
+    }
+
+    #[test]
+    fn test_least_recently_shown_orders_each_cycle() {
+        use chrono::TimeZone;
+        let shown = Arc::new(ShownHistory::default());
+        let day = |d: u32| chrono::Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap();
+        shown.record_at(Path::new("/test/a.jpg"), day(3));
+        shown.record_at(Path::new("/test/b.jpg"), day(1));
+        shown.record_at(Path::new("/test/c.jpg"), day(2));
+
+        let options = QueueOptions { shown: Some(Arc::clone(&shown)), seed: Some(1), ..Default::default() };
+        let mut queue = Queue::with_options(1, Sorting::LeastRecentlyShown, paths(&["a.jpg", "b.jpg", "c.jpg", "d.jpg"]), options).unwrap();
+        // Never shown first, then oldest showing first. Each image is recorded
+        // as it goes up, as the change pipeline does, so later cycles come round
+        // in the same order.
+        let mut order = vec![queue.current_image().unwrap().clone()];
+        shown.record_at(&order[0], day(10));
+        for d in 11..18 {
+            let image = queue.next().unwrap().clone();
+            shown.record_at(&image, day(d));
+            order.push(image);
+        }
+        assert_eq!(order, paths(&["d.jpg", "b.jpg", "c.jpg", "a.jpg", "d.jpg", "b.jpg", "c.jpg", "a.jpg"]));
+    }
+
+    #[test]
+    fn test_least_recently_shown_breaks_ties_randomly() {
+        let names: Vec<String> = (0..10).map(|i| format!("{}.jpg", i)).collect();
+        let images = paths(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let orders: HashSet<Vec<PathBuf>> = (0..5)
+            .map(|seed| {
+                let options = QueueOptions { shown: Some(Arc::default()), seed: Some(seed), ..Default::default() };
+                let mut queue = Queue::with_options(2, Sorting::LeastRecentlyShown, images.clone(), options).unwrap();
+                random_run(&mut queue, 9)
+            })
+            .collect();
+        assert!(orders.len() > 1);
+    }
+
+    /// Counts the bytes allocated on the current thread, so a test can measure
+    /// its own allocations while others run alongside it
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size()));
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocated_by(f: impl FnOnce()) -> usize {
+        let before = ALLOCATED.with(|allocated| allocated.get());
+        f();
+        ALLOCATED.with(|allocated| allocated.get()) - before
+    }
+
+    /// Bytes allocated by three full cycles over `count` images named with
+    /// `name_length` characters, and by cloning the queue afterwards
+    fn cycle_allocations(count: usize, name_length: usize) -> (usize, usize) {
+        let images: Vec<PathBuf> = (0..count)
+            .map(|i| PathBuf::from(format!("/walls/{:0>width$}.png", i, width = name_length)))
+            .collect();
+        let options = QueueOptions { seed: Some(7), ..Default::default() };
+        let mut queue = Queue::with_options(10, Sorting::Random, images, options).unwrap();
+
+        let cycled = allocated_by(|| {
+            for _ in 0..3 * count {
+                queue.next();
+            }
+        });
+        let cloned = allocated_by(|| drop(queue.clone()));
+        (cycled, cloned)
+    }
+
+    #[test]
+    fn test_cycling_never_copies_paths() {
+        let count = 20_000;
+        let short = cycle_allocations(count, 8);
+        let long = cycle_allocations(count, 200);
+
+        // What the queue allocates depends on how many images there are, not on
+        // how long their paths are
+        assert_eq!(short, long);
+        let path_bytes = count * 200;
+        assert!(long.0 < path_bytes, "cycling allocated {} bytes", long.0);
+        assert!(long.1 < path_bytes / 4, "cloning allocated {} bytes", long.1);
+    }
 }
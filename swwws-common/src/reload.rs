@@ -0,0 +1,135 @@
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+use crate::ipc::{DaemonInfo, ReloadRecord};
+
+/// Modification time of `path`, if it can be read
+pub fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Bookkeeping behind the daemon section of the Status response
+#[derive(Debug)]
+pub struct ReloadStats {
+    started: Instant,
+    succeeded: u32,
+    failed: u32,
+    last: Option<ReloadRecord>,
+    loaded_mtime: Option<SystemTime>,
+}
+
+impl ReloadStats {
+    /// `loaded_mtime` is the config file's mtime from just before it was first loaded
+    pub fn new(loaded_mtime: Option<SystemTime>) -> Self {
+        Self {
+            started: Instant::now(),
+            succeeded: 0,
+            failed: 0,
+            last: None,
+            loaded_mtime,
+        }
+    }
+
+    /// Record the outcome of a reload. `mtime` is the config file's mtime from
+    /// before it was read, so edits made during the reload still count as newer
+    pub fn record(&mut self, success: bool, message: impl Into<String>, mtime: Option<SystemTime>) {
+        if success {
+            self.succeeded += 1;
+            self.loaded_mtime = mtime;
+        } else {
+            self.failed += 1;
+        }
+
+        self.last = Some(ReloadRecord {
+            timestamp: chrono::Utc::now(),
+            success,
+            message: message.into(),
+        });
+    }
+
+    /// Count the file as loaded as of `mtime`, after the daemon wrote to it itself
+    pub fn mark_loaded(&mut self, mtime: Option<SystemTime>) {
+        self.loaded_mtime = mtime;
+    }
+
+    /// Whether the file on disk is newer than the last successfully loaded one
+    pub fn config_modified(&self, config_path: &Path) -> bool {
+        match (file_mtime(config_path), self.loaded_mtime) {
+            (Some(on_disk), Some(loaded)) => on_disk > loaded,
+            _ => false,
+        }
+    }
+
+    pub fn info(&self, config_path: &Path) -> DaemonInfo {
+        DaemonInfo {
+            uptime: self.started.elapsed().as_secs(),
+            config_path: config_path.to_string_lossy().to_string(),
+            reloads_succeeded: self.succeeded,
+            reloads_failed: self.failed,
+            last_reload: self.last.clone(),
+            config_modified: self.config_modified(config_path),
+            runtime_outputs: Vec::new(),
+            dry_run: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn touch(path: &Path, mtime: SystemTime) {
+        fs::File::options().write(true).open(path).unwrap().set_modified(mtime).unwrap();
+    }
+
+    #[test]
+    fn test_reload_counts_and_last_result() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, "[any]\n").unwrap();
+
+        let mut stats = ReloadStats::new(file_mtime(&config_path));
+        stats.record(true, "Configuration reloaded successfully", file_mtime(&config_path));
+        stats.record(false, "Failed to reload configuration: bad toml", file_mtime(&config_path));
+
+        let info = stats.info(&config_path);
+        assert_eq!(info.reloads_succeeded, 1);
+        assert_eq!(info.reloads_failed, 1);
+        let last = info.last_reload.unwrap();
+        assert!(!last.success);
+        assert_eq!(last.message, "Failed to reload configuration: bad toml");
+    }
+
+    #[test]
+    fn test_config_modified_since_last_reload() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, "[any]\n").unwrap();
+        let loaded = SystemTime::now() - Duration::from_secs(60);
+        touch(&config_path, loaded);
+
+        let mut stats = ReloadStats::new(file_mtime(&config_path));
+        assert!(!stats.config_modified(&config_path));
+
+        touch(&config_path, loaded + Duration::from_secs(10));
+        assert!(stats.config_modified(&config_path));
+
+        // A failed reload does not count as having loaded the new file
+        stats.record(false, "failed", file_mtime(&config_path));
+        assert!(stats.info(&config_path).config_modified);
+
+        stats.record(true, "ok", file_mtime(&config_path));
+        assert!(!stats.info(&config_path).config_modified);
+    }
+
+    #[test]
+    fn test_missing_config_is_not_flagged() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("missing.toml");
+
+        let stats = ReloadStats::new(Some(SystemTime::now()));
+        assert!(!stats.config_modified(&config_path));
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// Smallest image an output accepts, from `min_resolution`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum MinResolution {
+    /// The output's own size, as `swww query` reports it
+    Auto,
+    Fixed { width: u32, height: u32 },
+}
+
+impl MinResolution {
+    /// Width and height an image needs on an output of `output_size`. None when
+    /// there is nothing to compare against: `auto` on an output of unknown size.
+    pub fn resolve(&self, output_size: Option<(u32, u32)>) -> Option<(u32, u32)> {
+        match self {
+            MinResolution::Auto => output_size,
+            MinResolution::Fixed { width, height } => Some((*width, *height)),
+        }
+    }
+}
+
+impl FromStr for MinResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(MinResolution::Auto);
+        }
+        let size = s.split_once(['x', 'X'])
+            .and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)));
+        match size {
+            Some((width, height)) if width > 0 && height > 0 => Ok(MinResolution::Fixed { width, height }),
+            _ => Err(format!("expected \"auto\" or a size like \"1920x1080\", got {:?}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for MinResolution {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MinResolution> for String {
+    fn from(resolution: MinResolution) -> Self {
+        resolution.to_string()
+    }
+}
+
+impl fmt::Display for MinResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MinResolution::Auto => write!(f, "auto"),
+            MinResolution::Fixed { width, height } => write!(f, "{}x{}", width, height),
+        }
+    }
+}
+
+/// Width and height of the image at `path`, read from its header without
+/// decoding it. None for formats this doesn't understand (TIFF, AVIF) and for
+/// files that are unreadable or cut short.
+pub fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let mut file = BufReader::new(File::open(path).ok()?);
+    let mut header = [0u8; 30];
+    let read = read_up_to(&mut file, &mut header)?;
+    let header = &header[..read];
+
+    let u16_le = |at: usize| Some(u16::from_le_bytes(header.get(at..at + 2)?.try_into().ok()?) as u32);
+    let u24_le = |at: usize| Some(u16_le(at)? | (*header.get(at + 2)? as u32) << 16);
+    let u32_be = |at: usize| Some(u32::from_be_bytes(header.get(at..at + 4)?.try_into().ok()?));
+    let i32_le = |at: usize| Some(i32::from_le_bytes(header.get(at..at + 4)?.try_into().ok()?));
+
+    match header {
+        [0x89, b'P', b'N', b'G', ..] => Some((u32_be(16)?, u32_be(20)?)),
+        [b'G', b'I', b'F', b'8', ..] => Some((u16_le(6)?, u16_le(8)?)),
+        // Height is negative for top-down bitmaps
+        [b'B', b'M', ..] => Some((i32_le(18)?.unsigned_abs(), i32_le(22)?.unsigned_abs())),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => match header.get(12..16)? {
+            b"VP8 " => Some((u16_le(26)? & 0x3fff, u16_le(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(header.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => Some((u24_le(24)? + 1, u24_le(27)? + 1)),
+            _ => None,
+        },
+        // Segments before the frame can be any length, so keep reading past the header
+        [0xFF, 0xD8, ..] => jpeg_dimensions(&mut header[2..].chain(file)),
+        _ => None,
+    }
+}
+
+/// Size from the first start-of-frame segment of a JPEG positioned after SOI
+fn jpeg_dimensions(file: &mut impl Read) -> Option<(u32, u32)> {
+    let mut byte = [0u8; 1];
+    loop {
+        // Markers may be padded with any number of 0xFF bytes
+        file.read_exact(&mut byte).ok()?;
+        if byte[0] != 0xFF {
+            return None;
+        }
+        let mut marker = 0xFF;
+        while marker == 0xFF {
+            file.read_exact(&mut byte).ok()?;
+            marker = byte[0];
+        }
+
+        match marker {
+            // Standalone markers have no length
+            0x01 | 0xD0..=0xD7 => continue,
+            // Start of scan or end of image before any frame
+            0xD9 | 0xDA => return None,
+            _ => {}
+        }
+        let mut length = [0u8; 2];
+        file.read_exact(&mut length).ok()?;
+        let length = u16::from_be_bytes(length) as u64;
+
+        // SOF0 to SOF15, except DHT, JPG and DAC which share the range
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let mut frame = [0u8; 5];
+            file.read_exact(&mut frame).ok()?;
+            let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+            let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+            return Some((width, height));
+        }
+        std::io::copy(&mut file.by_ref().take(length.checked_sub(2)?), &mut std::io::sink()).ok()?;
+    }
+}
+
+/// Fill as much of `buffer` as the file has. None if reading fails.
+fn read_up_to(file: &mut impl Read, buffer: &mut [u8]) -> Option<usize> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match file.read(&mut buffer[read..]).ok()? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Some(read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn probe(bytes: &[u8]) -> Option<(u32, u32)> {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("image");
+        std::fs::write(&path, bytes).unwrap();
+        image_dimensions(&path)
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 13];
+        png.extend(b"IHDR");
+        png.extend(3840u32.to_be_bytes());
+        png.extend(2160u32.to_be_bytes());
+        assert_eq!(probe(&png), Some((3840, 2160)));
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(800u16.to_le_bytes());
+        gif.extend(600u16.to_le_bytes());
+        assert_eq!(probe(&gif), Some((800, 600)));
+
+        let mut bmp = vec![b'B', b'M'];
+        bmp.extend([0u8; 16]);
+        bmp.extend(1024i32.to_le_bytes());
+        bmp.extend((-768i32).to_le_bytes());
+        assert_eq!(probe(&bmp), Some((1024, 768)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X".to_vec();
+        webp.extend([0u8; 8]);
+        webp.extend([0x7F, 0x07, 0x00, 0x37, 0x04, 0x00]); // 1920 - 1, 1080 - 1
+        assert_eq!(probe(&webp), Some((1920, 1080)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8L\0\0\0\0\x2F".to_vec();
+        webp.extend((639u32 | 479 << 14).to_le_bytes());
+        assert_eq!(probe(&webp), Some((640, 480)));
+
+        // An APP0 segment to skip, padding, then a baseline frame
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x06, b'J', b'F', b'I', b'F', 0xFF, 0xFF, 0xC0, 0x00, 0x11, 0x08];
+        jpeg.extend(1080u16.to_be_bytes());
+        jpeg.extend(1920u16.to_be_bytes());
+        assert_eq!(probe(&jpeg), Some((1920, 1080)));
+
+        assert_eq!(probe(&[0xFF, 0xD8, 0xFF, 0xD9]), None);
+        assert_eq!(probe(&[0x89, b'P', b'N', b'G']), None);
+        assert_eq!(probe(b"II*\0 tiff"), None);
+    }
+
+    #[test]
+    fn test_parse_min_resolution() {
+        assert_eq!("1920x1080".parse(), Ok(MinResolution::Fixed { width: 1920, height: 1080 }));
+        assert_eq!("AUTO".parse(), Ok(MinResolution::Auto));
+        assert!("1920".parse::<MinResolution>().is_err());
+        assert!("0x1080".parse::<MinResolution>().is_err());
+        assert_eq!(MinResolution::Fixed { width: 1920, height: 1080 }.to_string(), "1920x1080");
+
+        assert_eq!(MinResolution::Auto.resolve(Some((2560, 1440))), Some((2560, 1440)));
+        assert_eq!(MinResolution::Auto.resolve(None), None);
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Profile name used outside every window, and for outputs without a schedule
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Time-of-day image directories for one output.
+///
+/// ```toml
+/// ["DP-1".schedule]
+/// fallback = "/walls/night"
+/// windows = [{ from = "07:00", to = "19:00", path = "/walls/day" }]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub windows: Vec<ScheduleWindow>,
+    /// Directory used outside all windows; the output's `path` when unset
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScheduleWindow {
+    #[serde(deserialize_with = "deserialize_time", serialize_with = "serialize_time")]
+    pub from: NaiveTime,
+    #[serde(deserialize_with = "deserialize_time", serialize_with = "serialize_time")]
+    pub to: NaiveTime,
+    pub path: String,
+    /// Shown in logs and saved with the queue; defaults to "HH:MM-HH:MM"
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// The image directory in effect at some time of day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub path: Option<String>,
+}
+
+impl ScheduleWindow {
+    /// Whether `time` falls in `[from, to)`. Windows with `to` before `from` wrap past midnight.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.to {
+            self.from <= time && time < self.to
+        } else {
+            time >= self.from || time < self.to
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            format!("{}-{}", self.from.format("%H:%M"), self.to.format("%H:%M"))
+        })
+    }
+}
+
+impl Schedule {
+    /// Profile at `time`. The first matching window wins; outside all windows the
+    /// fallback is used, or `default_path` if there is none.
+    pub fn profile_at(&self, time: NaiveTime, default_path: Option<&str>) -> Profile {
+        match self.windows.iter().find(|window| window.contains(time)) {
+            Some(window) => Profile {
+                name: window.name(),
+                path: Some(window.path.clone()),
+            },
+            None => Profile {
+                name: DEFAULT_PROFILE.to_string(),
+                path: self.fallback.clone().or_else(|| default_path.map(str::to_string)),
+            },
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for window in &self.windows {
+            if window.from == window.to {
+                return Err(format!("window {} is empty", window.name()));
+            }
+            if window.path.is_empty() {
+                return Err(format!("window {} has no path", window.name()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn deserialize_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(&s, "%H:%M:%S"))
+        .map_err(|_| serde::de::Error::custom(format!("invalid time {:?}, expected HH:MM", s)))
+}
+
+fn serialize_time<S>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&time.format("%H:%M").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn window(from: NaiveTime, to: NaiveTime, path: &str) -> ScheduleWindow {
+        ScheduleWindow { from, to, path: path.to_string(), name: None }
+    }
+
+    #[test]
+    fn test_profile_at_day_and_night() {
+        let schedule = Schedule {
+            windows: vec![window(at(7, 0), at(19, 0), "/walls/day")],
+            fallback: Some("/walls/night".to_string()),
+        };
+
+        let day = schedule.profile_at(at(12, 0), Some("/walls"));
+        assert_eq!(day.name, "07:00-19:00");
+        assert_eq!(day.path.as_deref(), Some("/walls/day"));
+
+        // `to` is exclusive
+        let night = schedule.profile_at(at(19, 0), Some("/walls"));
+        assert_eq!(night.name, DEFAULT_PROFILE);
+        assert_eq!(night.path.as_deref(), Some("/walls/night"));
+    }
+
+    #[test]
+    fn test_window_wrapping_midnight_and_default_path() {
+        let schedule = Schedule {
+            windows: vec![window(at(22, 0), at(6, 0), "/walls/late")],
+            fallback: None,
+        };
+
+        assert_eq!(schedule.profile_at(at(23, 30), None).path.as_deref(), Some("/walls/late"));
+        assert_eq!(schedule.profile_at(at(2, 0), None).path.as_deref(), Some("/walls/late"));
+        assert_eq!(schedule.profile_at(at(12, 0), Some("/walls")).path.as_deref(), Some("/walls"));
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        let schedule: Schedule = toml::from_str(
+            "windows = [{ from = \"07:00\", to = \"19:00\", path = \"/walls/day\", name = \"day\" }]\n",
+        ).unwrap();
+        assert_eq!(schedule.windows[0].from, at(7, 0));
+        assert_eq!(schedule.windows[0].name(), "day");
+        assert!(schedule.validate().is_ok());
+
+        assert!(toml::from_str::<Schedule>("windows = [{ from = \"7am\", to = \"19:00\", path = \"/x\" }]\n").is_err());
+
+        let empty = Schedule { windows: vec![window(at(7, 0), at(7, 0), "/x")], fallback: None };
+        assert!(empty.validate().is_err());
+    }
+}
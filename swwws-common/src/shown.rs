@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// How often an image was put on an output, and when it last was
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageRecord {
+    pub shown_count: u64,
+    pub last_shown: DateTime<Utc>,
+}
+
+/// Display history of every image, kept across restarts in the state file. The
+/// change pipeline records each change it applies, and `LeastRecentlyShown`
+/// queues order their cycles by it.
+#[derive(Debug, Default)]
+pub struct ShownHistory {
+    records: Mutex<HashMap<PathBuf, ImageRecord>>,
+}
+
+impl ShownHistory {
+    pub fn record(&self, image: &Path) {
+        self.record_at(image, Utc::now());
+    }
+
+    pub fn record_at(&self, image: &Path, at: DateTime<Utc>) {
+        let mut records = self.records.lock().unwrap();
+        let record = records.entry(image.to_path_buf()).or_insert(ImageRecord { shown_count: 0, last_shown: at });
+        record.shown_count += 1;
+        record.last_shown = record.last_shown.max(at);
+    }
+
+    /// None for an image never shown
+    pub fn last_shown(&self, image: &Path) -> Option<DateTime<Utc>> {
+        self.records.lock().unwrap().get(image).map(|record| record.last_shown)
+    }
+
+    pub fn get(&self, image: &Path) -> Option<ImageRecord> {
+        self.records.lock().unwrap().get(image).cloned()
+    }
+
+    /// Every record, keyed by path as the state file stores them
+    pub fn records(&self) -> HashMap<String, ImageRecord> {
+        self.records.lock().unwrap().iter()
+            .map(|(image, record)| (image.to_string_lossy().to_string(), record.clone()))
+            .collect()
+    }
+
+    /// Take over records loaded from the state file
+    pub fn restore(&self, saved: &HashMap<String, ImageRecord>) {
+        let mut records = self.records.lock().unwrap();
+        for (image, record) in saved {
+            records.insert(PathBuf::from(image), record.clone());
+        }
+    }
+}
+
+/// One wallpaper change the pipeline applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeRecord {
+    pub image: String, // full path
+    pub at: DateTime<Utc>,
+    pub trigger: String, // the ChangeReason
+}
+
+/// The latest changes on each output, kept across restarts in the state file.
+/// Each output keeps at most `change_log_size` of them; older ones are dropped.
+#[derive(Debug)]
+pub struct ChangeLog {
+    changes: Mutex<HashMap<String, VecDeque<ChangeRecord>>>, // oldest first
+    limit: Mutex<usize>,
+}
+
+impl Default for ChangeLog {
+    fn default() -> Self {
+        Self { changes: Mutex::default(), limit: Mutex::new(100) }
+    }
+}
+
+impl ChangeLog {
+    pub fn record(&self, output: &str, image: &Path, trigger: &str) {
+        self.record_at(output, image, trigger, Utc::now());
+    }
+
+    pub fn record_at(&self, output: &str, image: &Path, trigger: &str, at: DateTime<Utc>) {
+        let limit = *self.limit.lock().unwrap();
+        let mut changes = self.changes.lock().unwrap();
+        let log = changes.entry(output.to_string()).or_default();
+        log.push_back(ChangeRecord { image: image.to_string_lossy().to_string(), at, trigger: trigger.to_string() });
+        while log.len() > limit {
+            log.pop_front();
+        }
+    }
+
+    /// Keep at most `limit` changes per output from now on, dropping the oldest
+    pub fn set_limit(&self, limit: usize) {
+        *self.limit.lock().unwrap() = limit;
+        for log in self.changes.lock().unwrap().values_mut() {
+            while log.len() > limit {
+                log.pop_front();
+            }
+        }
+    }
+
+    /// Up to `limit` changes, newest first, on `output` or on every output
+    pub fn latest(&self, output: Option<&str>, limit: usize) -> Vec<(String, ChangeRecord)> {
+        let changes = self.changes.lock().unwrap();
+        let mut latest: Vec<(String, ChangeRecord)> = changes.iter()
+            .filter(|(name, _)| output.is_none_or(|output| output == name.as_str()))
+            .flat_map(|(name, log)| log.iter().map(move |change| (name.clone(), change.clone())))
+            .collect();
+        latest.sort_by(|(a_output, a), (b_output, b)| b.at.cmp(&a.at).then_with(|| a_output.cmp(b_output)));
+        latest.truncate(limit);
+        latest
+    }
+
+    pub fn has_output(&self, output: &str) -> bool {
+        self.changes.lock().unwrap().contains_key(output)
+    }
+
+    /// Every output's changes, oldest first, as the state file stores them
+    pub fn changes(&self) -> HashMap<String, Vec<ChangeRecord>> {
+        self.changes.lock().unwrap().iter()
+            .map(|(output, log)| (output.clone(), log.iter().cloned().collect()))
+            .collect()
+    }
+
+    /// Take over changes loaded from the state file
+    pub fn restore(&self, saved: &HashMap<String, Vec<ChangeRecord>>) {
+        let limit = *self.limit.lock().unwrap();
+        let mut changes = self.changes.lock().unwrap();
+        for (output, log) in saved {
+            let skip = log.len().saturating_sub(limit);
+            changes.insert(output.clone(), log.iter().skip(skip).cloned().collect());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_record_counts_and_keeps_latest() {
+        let history = ShownHistory::default();
+        let early = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        history.record_at(Path::new("/walls/a.png"), late);
+        history.record_at(Path::new("/walls/a.png"), early);
+
+        assert_eq!(history.get(Path::new("/walls/a.png")), Some(ImageRecord { shown_count: 2, last_shown: late }));
+        assert_eq!(history.last_shown(Path::new("/walls/b.png")), None);
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        let history = ShownHistory::default();
+        history.record(Path::new("/walls/a.png"));
+
+        let restored = ShownHistory::default();
+        restored.restore(&history.records());
+        assert_eq!(restored.get(Path::new("/walls/a.png")), history.get(Path::new("/walls/a.png")));
+    }
+
+    #[test]
+    fn test_change_log_is_capped_and_newest_first() {
+        let log = ChangeLog::default();
+        log.set_limit(2);
+        let at = |h: u32| Utc.with_ymd_and_hms(2024, 1, 1, h, 0, 0).unwrap();
+        log.record_at("DP-1", Path::new("/walls/a.png"), "startup", at(1));
+        log.record_at("HDMI-A-1", Path::new("/walls/b.png"), "startup", at(2));
+        log.record_at("DP-1", Path::new("/walls/c.png"), "timer", at(3));
+        log.record_at("DP-1", Path::new("/walls/d.png"), "manual", at(4));
+
+        let images = |changes: Vec<(String, ChangeRecord)>| -> Vec<String> {
+            changes.into_iter().map(|(output, change)| format!("{} {}", output, change.image)).collect()
+        };
+        assert_eq!(images(log.latest(None, 10)), ["DP-1 /walls/d.png", "DP-1 /walls/c.png", "HDMI-A-1 /walls/b.png"]);
+        assert_eq!(images(log.latest(Some("DP-1"), 1)), ["DP-1 /walls/d.png"]);
+
+        let restored = ChangeLog::default();
+        restored.restore(&log.changes());
+        assert_eq!(restored.latest(None, 10), log.latest(None, 10));
+        restored.set_limit(1);
+        assert_eq!(images(restored.latest(None, 10)), ["DP-1 /walls/d.png", "HDMI-A-1 /walls/b.png"]);
+    }
+}
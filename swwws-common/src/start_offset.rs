@@ -0,0 +1,134 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::duration::parse_duration;
+
+/// How long an output waits, on top of its normal duration, before its first change.
+/// Spreads out outputs that would otherwise all change at the same moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum StartOffset {
+    /// A random fraction of the output's duration
+    Random,
+    Fixed(Duration),
+}
+
+impl StartOffset {
+    /// Delay for `output`'s first deadline. With a seed, the random delay depends only
+    /// on the seed and the output name, so restarts produce the same spread.
+    pub fn resolve(&self, output: &str, period: Duration, seed: Option<u64>) -> Duration {
+        match self {
+            StartOffset::Fixed(offset) => *offset,
+            StartOffset::Random => {
+                let mut rng = match seed {
+                    Some(seed) => {
+                        let mut hasher = DefaultHasher::new();
+                        output.hash(&mut hasher);
+                        StdRng::seed_from_u64(seed ^ hasher.finish())
+                    }
+                    None => StdRng::from_entropy(),
+                };
+                period.mul_f64(rng.gen::<f64>())
+            }
+        }
+    }
+}
+
+impl FromStr for StartOffset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("random") {
+            return Ok(StartOffset::Random);
+        }
+        parse_duration(s)
+            .map(StartOffset::Fixed)
+            .map_err(|_| format!("expected \"random\" or a duration, got {:?}", s))
+    }
+}
+
+impl TryFrom<String> for StartOffset {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<StartOffset> for String {
+    fn from(offset: StartOffset) -> Self {
+        offset.to_string()
+    }
+}
+
+impl fmt::Display for StartOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartOffset::Random => write!(f, "random"),
+            StartOffset::Fixed(offset) => write!(f, "{}", humantime::format_duration(*offset)),
+        }
+    }
+}
+
+/// Time left until a timer started at `started` reaches `period`. `started` may lie
+/// in the future when a start offset pushed the first deadline back.
+pub fn remaining(started: Instant, period: Duration) -> Duration {
+    (started + period).saturating_duration_since(Instant::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_offset() {
+        assert_eq!("random".parse::<StartOffset>(), Ok(StartOffset::Random));
+        assert_eq!("90s".parse::<StartOffset>(), Ok(StartOffset::Fixed(Duration::from_secs(90))));
+        assert!("sometime".parse::<StartOffset>().is_err());
+    }
+
+    #[test]
+    fn test_seeded_offsets_differ_per_output_and_repeat() {
+        let period = Duration::from_secs(300);
+        let outputs = ["DP-1", "DP-2", "HDMI-A-1", "eDP-1"];
+
+        let offsets: Vec<Duration> = outputs
+            .iter()
+            .map(|output| StartOffset::Random.resolve(output, period, Some(42)))
+            .collect();
+
+        assert!(offsets.iter().all(|offset| *offset < period));
+        for (i, a) in offsets.iter().enumerate() {
+            for b in &offsets[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+
+        // Same seed, same spread
+        let again: Vec<Duration> = outputs
+            .iter()
+            .map(|output| StartOffset::Random.resolve(output, period, Some(42)))
+            .collect();
+        assert_eq!(offsets, again);
+    }
+
+    #[test]
+    fn test_offset_shifts_only_the_first_deadline() {
+        let period = Duration::from_secs(300);
+        let offset = StartOffset::Fixed(Duration::from_secs(120)).resolve("DP-1", period, None);
+        let now = Instant::now();
+
+        // First deadline lands offset + period from startup
+        let first = remaining(now + offset, period);
+        assert!(first > Duration::from_secs(419) && first <= Duration::from_secs(420));
+
+        // After a change the timer restarts at now, so later periods are unchanged
+        let next = remaining(Instant::now(), period);
+        assert!(next > Duration::from_secs(299) && next <= period);
+    }
+}
@@ -1,9 +1,15 @@
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::os::unix::fs::DirBuilderExt;
 use anyhow::{Result, Context};
+use crate::blacklist::Blacklist;
 use crate::queue::Sorting;
+use crate::shown::{ChangeRecord, ImageRecord};
+
+/// Environment variable that overrides where the state file goes
+pub const STATE_FILE_ENV: &str = "SWWWS_STATE_FILE";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OutputState {
@@ -13,6 +19,12 @@ pub struct OutputState {
     pub sorting: Sorting,
     pub images: Vec<String>,
     pub last_updated: chrono::DateTime<chrono::Utc>,
+    /// Schedule profile the queue was built from; None in state files from before schedules
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// `offset` the queue was started with; 0 in state files from before offsets
+    #[serde(default)]
+    pub offset: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +32,24 @@ pub struct DaemonState {
     pub outputs: HashMap<String, OutputState>,
     pub global_paused: bool,
     pub last_save: chrono::DateTime<chrono::Utc>,
+    /// Display history per image path; empty in state files from before it was kept
+    #[serde(default)]
+    pub images: HashMap<String, ImageRecord>,
+    /// Latest wallpaper changes per output, oldest first
+    #[serde(default)]
+    pub changes: HashMap<String, Vec<ChangeRecord>>,
+    /// Images left out of every queue, from `swwws-cli blacklist`
+    #[serde(default)]
+    pub blacklist: Blacklist,
+    /// Image each pinned output is held on, from `swwws-cli pin`
+    #[serde(default)]
+    pub pinned: BTreeMap<String, PathBuf>,
+}
+
+impl Default for DaemonState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DaemonState {
@@ -28,13 +58,17 @@ impl DaemonState {
             outputs: HashMap::new(),
             global_paused: false,
             last_save: chrono::Utc::now(),
+            images: HashMap::new(),
+            changes: HashMap::new(),
+            blacklist: Blacklist::default(),
+            pinned: BTreeMap::new(),
         }
     }
 
     pub fn save(&self, state_file: &Path) -> Result<()> {
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = state_file.parent() {
-            fs::create_dir_all(parent)
+        // Create parent directory if it doesn't exist, readable only by us
+        if let Some(parent) = state_file.parent().filter(|parent| !parent.exists()) {
+            fs::DirBuilder::new().recursive(true).mode(0o700).create(parent)
                 .with_context(|| format!("Failed to create state directory: {:?}", parent))?;
         }
 
@@ -64,11 +98,29 @@ impl DaemonState {
         Ok(state)
     }
 
-    pub fn get_state_file() -> PathBuf {
-        dirs::state_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join("swwws")
-            .join("state.json")
+    /// Where the state is kept: `$SWWWS_STATE_FILE` if set, then `configured`
+    /// (`state_file` in the config), then `swwws/state.json` in the state dir
+    pub fn get_state_file(configured: Option<&Path>) -> PathBuf {
+        let from_env = std::env::var_os(STATE_FILE_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        Self::resolve_state_file(from_env, configured, dirs::state_dir(), rustix::process::getuid().as_raw())
+    }
+
+    /// Without a state dir the file goes in a per-user directory under /tmp, so
+    /// users on the same machine don't overwrite each other's state
+    pub fn resolve_state_file(
+        from_env: Option<PathBuf>,
+        configured: Option<&Path>,
+        state_dir: Option<PathBuf>,
+        uid: u32,
+    ) -> PathBuf {
+        from_env
+            .or_else(|| configured.map(Path::to_path_buf))
+            .unwrap_or_else(|| match state_dir {
+                Some(dir) => dir.join("swwws").join("state.json"),
+                None => PathBuf::from(format!("/tmp/swwws-{}", uid)).join("state.json"),
+            })
     }
 
     pub fn update_output_state(
@@ -87,12 +139,26 @@ impl DaemonState {
             sorting,
             images: images.iter().map(|p| p.to_string_lossy().to_string()).collect(),
             last_updated: chrono::Utc::now(),
+            profile: self.outputs.get(output_name).and_then(|s| s.profile.clone()),
+            offset: self.outputs.get(output_name).map_or(0, |s| s.offset),
         };
         
         self.outputs.insert(output_name.to_string(), output_state);
         self.last_save = chrono::Utc::now();
     }
 
+    pub fn set_output_profile(&mut self, output_name: &str, profile: &str) {
+        if let Some(output_state) = self.outputs.get_mut(output_name) {
+            output_state.profile = Some(profile.to_string());
+        }
+    }
+
+    pub fn set_output_offset(&mut self, output_name: &str, offset: usize) {
+        if let Some(output_state) = self.outputs.get_mut(output_name) {
+            output_state.offset = offset;
+        }
+    }
+
     pub fn get_output_state(&self, output_name: &str) -> Option<&OutputState> {
         self.outputs.get(output_name)
     }
@@ -156,6 +222,19 @@ mod tests {
         assert_eq!(output_state.images.len(), 2);
     }
 
+    #[test]
+    fn test_image_history_is_optional() {
+        let temp_dir = tempdir().unwrap();
+        let state_file = temp_dir.path().join("state.json");
+        std::fs::write(&state_file, r#"{"outputs": {}, "global_paused": false, "last_save": "2024-01-01T00:00:00Z"}"#).unwrap();
+        assert!(DaemonState::load(&state_file).unwrap().images.is_empty());
+
+        let mut state = DaemonState::new();
+        state.images.insert("/test/a.jpg".to_string(), ImageRecord { shown_count: 3, last_shown: chrono::Utc::now() });
+        state.save(&state_file).unwrap();
+        assert_eq!(DaemonState::load(&state_file).unwrap().images["/test/a.jpg"].shown_count, 3);
+    }
+
     #[test]
     fn test_stale_state_cleanup() {
         let mut state = DaemonState::new();
@@ -181,4 +260,54 @@ mod tests {
         // Should be cleaned up
         assert!(state.get_output_state("recent-output").is_none());
     }
+
+    #[test]
+    fn test_output_profile_is_kept_across_updates() {
+        let mut state = DaemonState::new();
+        let images = [PathBuf::from("/walls/day/a.jpg")];
+        state.update_output_state("DP-1", Some(&images[0]), 0, 10, Sorting::Random, &images);
+        assert_eq!(state.get_output_state("DP-1").unwrap().profile, None);
+
+        state.set_output_profile("DP-1", "day");
+        state.update_output_state("DP-1", Some(&images[0]), 1, 10, Sorting::Random, &images);
+        assert_eq!(state.get_output_state("DP-1").unwrap().profile.as_deref(), Some("day"));
+
+        state.set_output_offset("DP-1", 2);
+        state.update_output_state("DP-1", Some(&images[0]), 2, 10, Sorting::Random, &images);
+        assert_eq!(state.get_output_state("DP-1").unwrap().offset, 2);
+
+        // State files written before profiles existed still load
+        let json = r#"{"outputs":{"DP-1":{"current_image":null,"queue_position":0,"queue_size":10,
+            "sorting":"Random","images":[],"last_updated":"2025-01-01T00:00:00Z"}},
+            "global_paused":false,"last_save":"2025-01-01T00:00:00Z"}"#;
+        let legacy: DaemonState = serde_json::from_str(json).unwrap();
+        assert_eq!(legacy.get_output_state("DP-1").unwrap().profile, None);
+        assert_eq!(legacy.get_output_state("DP-1").unwrap().offset, 0);
+    }
+
+    #[test]
+    fn test_state_file_location() {
+        let configured = Path::new("/data/swwws.json");
+        let env = Some(PathBuf::from("/env/state.json"));
+        let state_dir = Some(PathBuf::from("/home/me/.local/state"));
+
+        assert_eq!(DaemonState::resolve_state_file(env, Some(configured), state_dir.clone(), 1000), Path::new("/env/state.json"));
+        assert_eq!(DaemonState::resolve_state_file(None, Some(configured), state_dir.clone(), 1000), configured);
+        assert_eq!(DaemonState::resolve_state_file(None, None, state_dir, 1000), Path::new("/home/me/.local/state/swwws/state.json"));
+        assert_eq!(DaemonState::resolve_state_file(None, None, None, 1000), Path::new("/tmp/swwws-1000/state.json"));
+    }
+
+    #[test]
+    fn test_save_creates_private_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        let state_file = temp_dir.path().join("a").join("b").join("state.json");
+        DaemonState::new().save(&state_file).unwrap();
+
+        for dir in [state_file.parent().unwrap(), &temp_dir.path().join("a")] {
+            assert_eq!(fs::metadata(dir).unwrap().permissions().mode() & 0o777, 0o700, "{:?}", dir);
+        }
+        assert!(DaemonState::load(&state_file).is_ok());
+    }
 }
@@ -51,15 +51,22 @@ impl SwwwClient {
             }
         }
         
+        // The path is only a guess without a runtime dir; say so instead of just failing
+        let hint = if env::var_os("XDG_RUNTIME_DIR").is_none() {
+            " (XDG_RUNTIME_DIR is not set, so this is only a guess at where swww-daemon put its socket)"
+        } else {
+            ""
+        };
         Err(anyhow!(
-            "Failed to connect to swww daemon at {:?}: {:?}",
+            "Failed to connect to swww daemon at {:?}: {:?}{}",
             socket_path,
-            last_error.unwrap()
+            last_error.unwrap(),
+            hint
         ))
     }
     
     /// Get the socket path for swww daemon
-    fn get_socket_path() -> PathBuf {
+    pub fn get_socket_path() -> PathBuf {
         let mut runtime = env::var("XDG_RUNTIME_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| {
@@ -182,7 +189,7 @@ impl SwwwClient {
             let iov = IoSliceMut::new(&mut buf);
             match net::recvmsg(&self.socket, &mut [iov], &mut control, RecvFlags::WAITALL) {
                 Ok(_) => break,
-                Err(e) if matches!(e, rustix::io::Errno::WOULDBLOCK | rustix::io::Errno::INTR) => {
+                Err(rustix::io::Errno::WOULDBLOCK | rustix::io::Errno::INTR) => {
                     thread::sleep(Duration::from_millis(1));
                 }
                 Err(e) => return Err(anyhow!("Failed to receive response: {}", e)),
@@ -0,0 +1,112 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::command_builder::OutputConfig;
+
+/// `transition_type` value that picks a type from `transition_types` on every change
+pub const RANDOM_TRANSITION: &str = "random";
+
+/// Transition types swww understands
+pub const TRANSITION_TYPES: &[&str] = &[
+    "none", "simple", "fade", "left", "right", "top", "bottom",
+    "wipe", "wave", "grow", "center", "any", "outer",
+];
+
+/// Types drawn from when `transition_type = "random"` and no list is configured
+pub const DEFAULT_RANDOM_TYPES: &[&str] = &[
+    "fade", "left", "right", "top", "bottom", "wipe", "wave", "grow", "center", "outer",
+];
+
+pub fn is_known_type(name: &str) -> bool {
+    TRANSITION_TYPES.contains(&name)
+}
+
+/// Replace a `"random"` transition type in `config` with one drawn from `types`,
+/// along with a random angle or position when the drawn type uses one
+pub fn resolve_random(config: &mut OutputConfig, types: &[String]) {
+    resolve_random_with(config, types, &mut rand::thread_rng());
+}
+
+pub fn resolve_random_with(config: &mut OutputConfig, types: &[String], rng: &mut impl Rng) {
+    if config.transition_type.as_deref() != Some(RANDOM_TRANSITION) {
+        return;
+    }
+
+    let chosen = match types.choose(rng) {
+        Some(chosen) => chosen.clone(),
+        None => DEFAULT_RANDOM_TYPES.choose(rng).unwrap().to_string(),
+    };
+
+    match chosen.as_str() {
+        "wipe" | "wave" => config.transition_angle = Some(rng.gen_range(0.0..360.0f32).round()),
+        "grow" | "outer" => {
+            config.transition_pos = Some(format!("{:.2},{:.2}", rng.gen::<f32>(), rng.gen::<f32>()));
+        }
+        _ => {}
+    }
+    config.transition_type = Some(chosen);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn config(transition_type: &str) -> OutputConfig {
+        OutputConfig {
+            path: None,
+            mode: None,
+            transition_type: Some(transition_type.to_string()),
+            transition_step: None,
+            transition_angle: Some(90.0),
+            transition_pos: Some("center".to_string()),
+            transition_bezier: None,
+            transition_fps: None,
+            resize: None,
+            fill_color: None,
+            filter: None,
+            invert_y: None,
+            transition_wave: None,
+        }
+    }
+
+    #[test]
+    fn test_random_type_comes_from_list() {
+        let types = vec!["grow".to_string(), "wave".to_string()];
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut seen = Vec::new();
+
+        for _ in 0..20 {
+            let mut config = config(RANDOM_TRANSITION);
+            resolve_random_with(&mut config, &types, &mut rng);
+            let chosen = config.transition_type.clone().unwrap();
+            assert!(types.contains(&chosen));
+
+            // Parameters the chosen type uses are randomized too
+            match chosen.as_str() {
+                "grow" => assert_ne!(config.transition_pos.as_deref(), Some("center")),
+                "wave" => assert!((0.0..360.0).contains(&config.transition_angle.unwrap())),
+                _ => unreachable!(),
+            }
+            seen.push(chosen);
+        }
+
+        assert!(seen.iter().any(|t| t == "grow") && seen.iter().any(|t| t == "wave"));
+    }
+
+    #[test]
+    fn test_fixed_type_is_left_alone() {
+        let mut config = config("fade");
+        resolve_random_with(&mut config, &["wipe".to_string()], &mut StdRng::seed_from_u64(1));
+        assert_eq!(config.transition_type.as_deref(), Some("fade"));
+        assert_eq!(config.transition_pos.as_deref(), Some("center"));
+    }
+
+    #[test]
+    fn test_empty_list_uses_defaults() {
+        let mut config = config(RANDOM_TRANSITION);
+        resolve_random_with(&mut config, &[], &mut StdRng::seed_from_u64(3));
+        let chosen = config.transition_type.unwrap();
+        assert!(DEFAULT_RANDOM_TYPES.contains(&chosen.as_str()));
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use swwws_common::{DiscoveryOptions, ErrorReporting, ImageDiscovery, MonitorBehavior};
+use swwws_common::pattern;
+use crate::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Result of `check_config`, grouped by config section in the order they were checked
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub sections: Vec<(String, Vec<Finding>)>,
+}
+
+impl CheckReport {
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|(_, findings)| findings)
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    fn section(&mut self, name: &str) -> &mut Vec<Finding> {
+        let index = match self.sections.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                self.sections.push((name.to_string(), Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+        &mut self.sections[index].1
+    }
+
+    fn push(&mut self, section: &str, severity: Severity, message: impl Into<String>) {
+        self.section(section).push(Finding { severity, message: message.into() });
+    }
+}
+
+/// Load and validate the config at `path`, then check what the daemon would
+/// find at startup: image directories and monitor group wiring
+pub fn check_config(path: &Path) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    let config = match Config::load_from(path) {
+        Ok(config) => config,
+        Err(e) => {
+            report.push("file", Severity::Error, e.user_friendly_message());
+            return report;
+        }
+    };
+    report.push("file", Severity::Info, format!("Parsed and validated {:?}", path));
+    for warning in config.timing_warnings().into_iter().chain(config.transition_warnings()) {
+        report.push("file", Severity::Warning, warning);
+    }
+
+    // Sections other than outputs are walked with the settings an output
+    // without a section of its own would get
+    let options_for = |section: &str| config.get_output_config(section).discovery_options(None);
+    let mut discovered: HashMap<(PathBuf, DiscoveryOptions), Finding> = HashMap::new();
+    let mut check_path = |report: &mut CheckReport, section: &str, image_path: &str| {
+        let image_path = PathBuf::from(image_path);
+        let options = options_for(section);
+        let finding = discovered.entry((image_path.clone(), options.clone())).or_insert_with(|| {
+            match ImageDiscovery::discover_images(&image_path, &options, false) {
+                Ok(images) => Finding {
+                    severity: Severity::Info,
+                    message: format!("{} images in {:?}", images.len(), image_path),
+                },
+                Err(e) => Finding {
+                    severity: Severity::Error,
+                    message: e.user_friendly_message(),
+                },
+            }
+        });
+        report.section(section).push(finding.clone());
+    };
+
+    match &config.any.path {
+        Some(image_path) => check_path(&mut report, "any", image_path),
+        None => report.push(
+            "any",
+            Severity::Warning,
+            "No path set; outputs without a section of their own will be skipped",
+        ),
+    }
+
+    let mut output_names: Vec<&String> = config.outputs.keys().collect();
+    output_names.sort();
+    for name in &output_names {
+        let merged = config.get_output_config(name);
+        match &merged.path {
+            Some(image_path) => check_path(&mut report, name, image_path),
+            None => report.push(name, Severity::Warning, "No path set here or in [any]; this output will be skipped"),
+        }
+        check_offset(&merged, name, &mut report);
+    }
+
+    // Schedule directories, reported under the section that defines them
+    let scheduled = std::iter::once(("any", &config.any))
+        .chain(output_names.iter().map(|name| (name.as_str(), &config.outputs[*name])));
+    for (section, output) in scheduled {
+        if let Some(schedule) = &output.schedule {
+            let paths = schedule.windows.iter().map(|w| &w.path).chain(&schedule.fallback);
+            for image_path in paths {
+                check_path(&mut report, section, image_path);
+            }
+        }
+    }
+
+    for (name, group) in &config.groups {
+        if let Some(image_path) = &group.settings.path {
+            check_path(&mut report, &format!("groups.{}", name), image_path);
+        }
+    }
+
+    // Orientation paths, reported under the section that sets them
+    let oriented = std::iter::once(("any".to_string(), &config.any))
+        .chain(output_names.iter().map(|name| (name.to_string(), &config.outputs[*name])))
+        .chain(config.groups.iter().map(|(name, group)| (format!("groups.{}", name), &group.settings)));
+    for (section, output) in oriented {
+        for image_path in output.portrait_path.iter().chain(&output.landscape_path) {
+            check_path(&mut report, &section, image_path);
+        }
+    }
+
+    check_monitor_groups(&config, &output_names, &mut report);
+    report
+}
+
+/// An offset only means something in a fixed order, and within the image count
+fn check_offset(output: &crate::OutputConfig, section: &str, report: &mut CheckReport) {
+    if output.offset == 0 {
+        return;
+    }
+    if output.sorting.shuffles() {
+        report.push(section, Severity::Warning, format!(
+            "offset = {} has no effect with {:?} sorting; use an ordered sorting like Ascending", output.offset, output.sorting));
+    }
+    let count = output.path.as_ref()
+        .and_then(|path| ImageDiscovery::discover_images(Path::new(path), &output.discovery_options(None), false).ok())
+        .map(|images| images.len());
+    if let Some(count) = count.filter(|count| output.offset >= *count) {
+        report.push(section, Severity::Warning, format!(
+            "offset = {} is not less than the {} images in the path; it wraps around to {}",
+            output.offset, count, output.offset % count));
+    }
+}
+
+fn check_monitor_groups(config: &Config, output_names: &[&String], report: &mut CheckReport) {
+    const SECTION: &str = "monitor_groups";
+    let grouped = matches!(config.monitor_behavior, MonitorBehavior::Grouped(_));
+
+    let defined = !config.groups.is_empty() || config.monitor_groups.is_some();
+    match (defined, grouped) {
+        (false, true) => {
+            report.push(SECTION, Severity::Warning,
+                "monitor_behavior is \"Grouped\" but neither monitor_groups nor [groups] is set; falling back to Independent");
+            return;
+        }
+        (false, false) => return,
+        (true, false) => {
+            report.push(SECTION, Severity::Warning,
+                "monitor groups are set but monitor_behavior is not \"Grouped\"; groups are ignored");
+            return;
+        }
+        (true, true) => {}
+    }
+
+    let groups = config.named_groups();
+    for (name, group) in &groups {
+        // The daemon uses the group's own path, or else that of the first output in the group that has one
+        let own_path = config.groups.get(name).and_then(|g| g.settings.path.clone());
+        let mut group_path: Option<(String, String)> = own_path.map(|path| (format!("[groups.{}]", name), path));
+        // Glob members only name outputs once they are connected
+        for output in group.iter().filter(|output| !pattern::is_pattern(output)) {
+            let Some(own) = config.outputs.get(output.as_str()).and_then(|o| o.path.clone()) else {
+                if group_path.is_none() {
+                    group_path = config.get_output_config(output).path.map(|path| (output.clone(), path));
+                }
+                continue;
+            };
+            match &group_path {
+                None => group_path = Some((output.clone(), own)),
+                Some((first, path)) if own != *path => {
+                    report.push(SECTION, Severity::Warning, format!(
+                        "Group {}: [{}] sets its own path, but the group uses the path of {}", name, output, first));
+                }
+                _ => {}
+            }
+        }
+
+        match group_path {
+            Some(_) => report.push(SECTION, Severity::Info, format!("Group {}: {}", name, group.join(", "))),
+            None => report.push(SECTION, Severity::Error, format!(
+                "Group {} ({}) has no output with a wallpaper path, and [any] sets none", name, group.join(", "))),
+        }
+    }
+
+    for name in output_names {
+        if !groups.iter().flat_map(|(_, outputs)| outputs).any(|entry| pattern::matches(entry, name)) {
+            report.push(SECTION, Severity::Warning,
+                format!("[{}] is not part of any group; it will rotate independently", name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_config(dir: &Path, content: &str) -> PathBuf {
+        let path = dir.join("config.toml");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_check_valid_config() {
+        let temp_dir = tempdir().unwrap();
+        let walls = temp_dir.path().join("walls");
+        fs::create_dir(&walls).unwrap();
+        fs::write(walls.join("a.png"), b"png").unwrap();
+
+        let path = write_config(temp_dir.path(), &format!("[any]\npath = {:?}\n", walls));
+        let report = check_config(&path);
+
+        assert!(!report.has_errors());
+        assert_eq!(report.count(Severity::Warning), 0);
+        assert!(report.sections.iter().any(|(name, _)| name == "any"));
+    }
+
+    #[test]
+    fn test_check_missing_and_empty_directories() {
+        let temp_dir = tempdir().unwrap();
+        let empty = temp_dir.path().join("empty");
+        fs::create_dir(&empty).unwrap();
+
+        let path = write_config(temp_dir.path(), &format!(
+            "[any]\npath = {:?}\n\n[\"DP-1\"]\npath = \"/nonexistent/walls\"\n",
+            empty
+        ));
+        let report = check_config(&path);
+
+        assert!(report.has_errors());
+        assert_eq!(report.count(Severity::Error), 2);
+    }
+
+    #[test]
+    fn test_check_invalid_toml_is_a_single_error() {
+        let temp_dir = tempdir().unwrap();
+        let path = write_config(temp_dir.path(), "[global]\nduration = \"invalid\"\n");
+        let report = check_config(&path);
+
+        assert_eq!(report.sections.len(), 1);
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn test_check_monitor_groups_consistency() {
+        let temp_dir = tempdir().unwrap();
+        let walls = temp_dir.path().join("walls");
+        fs::create_dir(&walls).unwrap();
+        fs::write(walls.join("a.jpg"), b"jpg").unwrap();
+
+        let path = write_config(temp_dir.path(), &format!(
+            "monitor_groups = [[\"DP-1\", \"DP-2\"], [\"HDMI-A-1\"]]\n\n[global]\nmonitor_behavior = \"Grouped\"\n\n\
+             [\"DP-1\"]\npath = {walls:?}\n\n[\"DP-2\"]\npath = \"/elsewhere\"\n\n[\"eDP-1\"]\npath = {walls:?}\n",
+        ));
+        let report = check_config(&path);
+
+        let (_, findings) = report.sections.iter().find(|(name, _)| name == "monitor_groups").unwrap();
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+
+        // HDMI-A-1 has no section and [any] has no path
+        assert!(findings.iter().any(|f| f.severity == Severity::Error && f.message.contains("HDMI-A-1")));
+        assert!(messages.iter().any(|m| m.contains("[DP-2] sets its own path")));
+        assert!(messages.iter().any(|m| m.contains("[eDP-1] is not part of any group")));
+    }
+
+    #[test]
+    fn test_check_named_group_path() {
+        let temp_dir = tempdir().unwrap();
+        let walls = temp_dir.path().join("walls");
+        fs::create_dir(&walls).unwrap();
+        fs::write(walls.join("a.jpg"), b"jpg").unwrap();
+
+        let path = write_config(temp_dir.path(), &format!(
+            "[global]\nmonitor_behavior = \"Grouped\"\n\n[groups.desk]\noutputs = [\"DP-1\", \"DP-2\"]\npath = {walls:?}\n\n\
+             [\"DP-2\"]\npath = \"/elsewhere\"\n",
+        ));
+        let report = check_config(&path);
+
+        assert!(report.sections.iter().any(|(name, _)| name == "groups.desk"));
+        let (_, findings) = report.sections.iter().find(|(name, _)| name == "monitor_groups").unwrap();
+        assert!(findings.iter().any(|f| f.severity == Severity::Info && f.message == "Group desk: DP-1, DP-2"));
+        assert!(findings.iter().any(|f| f.message.contains("[DP-2] sets its own path, but the group uses the path of [groups.desk]")));
+    }
+
+    #[test]
+    fn test_check_offset() {
+        let temp_dir = tempdir().unwrap();
+        let walls = temp_dir.path().join("walls");
+        fs::create_dir(&walls).unwrap();
+        fs::write(walls.join("a.png"), b"png").unwrap();
+        fs::write(walls.join("b.png"), b"png").unwrap();
+
+        let path = write_config(temp_dir.path(), &format!(
+            "[any]\npath = {walls:?}\n\n[\"DP-1\"]\noffset = 1\nsorting = \"Ascending\"\n\n\
+             [\"DP-2\"]\noffset = 3\nsorting = \"Ascending\"\n\n\
+             [\"DP-3\"]\noffset = 1\nsorting = \"Random\"\n",
+        ));
+        let report = check_config(&path);
+
+        let warnings = |section: &str| report.sections.iter()
+            .find(|(name, _)| name == section).unwrap().1.iter()
+            .filter(|f| f.severity == Severity::Warning)
+            .map(|f| f.message.clone())
+            .collect::<Vec<_>>();
+        assert!(warnings("DP-1").is_empty());
+        assert_eq!(warnings("DP-2"), vec!["offset = 3 is not less than the 2 images in the path; it wraps around to 1"]);
+        assert!(warnings("DP-3")[0].contains("no effect with Random sorting"));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeSet;
+use swwws_common::{MonitorBehavior, Result};
+use toml::{Table, Value};
+use crate::{monitor_behavior_name, Config};
+
+/// Output settings swwws reads each time it changes a wallpaper, so a new value
+/// applies from the next change on. The rest shape the queues, which have to be
+/// built again for them.
+const LIVE_OUTPUT_KEYS: &[&str] = &[
+    "duration", "transition_type", "transition_step", "transition_angle", "transition_pos",
+    "transition_bezier", "transition_duration", "transition_fps", "transition_wave",
+    "transition_types", "resize", "fill_color", "filter", "invert_y", "on_change",
+    "reapply_on_wake", "manual_transition",
+];
+
+/// `[global]` settings that are only read when the daemon starts
+const RESTART_GLOBAL_KEYS: &[&str] = &["swww_binary", "state_file", "log_file", "log_level", "log_max_size", "log_keep", "dry_run", "startup_timeout"];
+
+/// `[global]` settings that shape the queues
+const QUEUE_GLOBAL_KEYS: &[&str] = &["random_seed"];
+
+/// How the config a reload loaded differs from the one the daemon ran with
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// One line per changed setting, e.g. `DP-1: duration "5m" → "10m"`
+    pub changes: Vec<String>,
+    /// Outputs whose merged settings changed, by name
+    pub outputs: BTreeSet<String>,
+    /// Whether a change only takes effect once the queues are built again
+    pub rebuild: bool,
+    /// Outputs whose own queue settings changed, so their queues are the ones
+    /// to build again
+    pub requeue: BTreeSet<String>,
+    /// Whether the change reaches every queue: the monitor behavior, or a
+    /// queue setting only `[global]` has
+    pub rebuild_all: bool,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Compare `old` and `new` as `outputs` end up with them once every section is
+/// merged in, then the monitor behavior and the settings only `[global]` has.
+pub fn diff(old: &Config, new: &Config, outputs: &[String]) -> Result<ConfigDiff> {
+    let mut diff = ConfigDiff::default();
+
+    let mut names: Vec<&String> = outputs.iter().collect();
+    names.sort();
+    for output in names {
+        let before = Table::try_from(old.get_output_config(output))?;
+        let after = Table::try_from(new.get_output_config(output))?;
+        for (key, from, to) in changed(&before, &after) {
+            diff.changes.push(format!("{}: {} {} → {}", output, key, from, to));
+            diff.outputs.insert(output.clone());
+            if !LIVE_OUTPUT_KEYS.contains(&key.as_str()) {
+                diff.requeue.insert(output.clone());
+                diff.rebuild = true;
+            }
+        }
+    }
+
+    let (before, after) = (old.get_effective_monitor_behavior(), new.get_effective_monitor_behavior());
+    if before != after {
+        diff.changes.push(format!("monitor_behavior: {} → {}", describe(&before), describe(&after)));
+        diff.outputs.extend(outputs.iter().cloned());
+        diff.rebuild = true;
+        diff.rebuild_all = true;
+    }
+
+    // Settings `[global]` passes on to the outputs were compared above
+    let output_keys = Table::try_from(new.get_output_config(""))?;
+    let before = Table::try_from(&old.global)?;
+    let after = Table::try_from(&new.global)?;
+    for (key, from, to) in changed(&before, &after) {
+        if output_keys.contains_key(&key) {
+            continue;
+        }
+        let restart = if RESTART_GLOBAL_KEYS.contains(&key.as_str()) { " (takes effect after a restart)" } else { "" };
+        diff.changes.push(format!("global: {} {} → {}{}", key, from, to, restart));
+        if QUEUE_GLOBAL_KEYS.contains(&key.as_str()) {
+            diff.rebuild = true;
+            diff.rebuild_all = true;
+        }
+    }
+    Ok(diff)
+}
+
+/// Keys whose value differs, with both values as TOML. Keys missing on one
+/// side were unset there.
+fn changed(before: &Table, after: &Table) -> Vec<(String, String, String)> {
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .map(|key| (key.clone(), render(before.get(key)), render(after.get(key))))
+        .collect()
+}
+
+fn render(value: Option<&Value>) -> String {
+    value.map_or_else(|| "unset".to_string(), Value::to_string)
+}
+
+fn describe(behavior: &MonitorBehavior) -> String {
+    match behavior {
+        MonitorBehavior::Grouped(groups) => {
+            let groups: Vec<String> = groups.iter().map(|group| group.join("+")).collect();
+            format!("{} ({})", monitor_behavior_name(behavior), groups.join(", "))
+        }
+        _ => monitor_behavior_name(behavior).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        Config::from_toml_str(toml).unwrap()
+    }
+
+    fn outputs() -> Vec<String> {
+        vec!["HDMI-A-1".to_string(), "DP-1".to_string()]
+    }
+
+    #[test]
+    fn test_identical_configs_have_no_changes() {
+        let toml = "[any]\npath = \"/walls\"\nduration = \"5m\"\n";
+        assert!(diff(&config(toml), &config(toml), &outputs()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_live_settings_need_no_rebuild() {
+        let old = config("[any]\npath = \"/walls\"\nduration = \"5m\"\n");
+        let new = config("[any]\npath = \"/walls\"\nduration = \"5m\"\n\n[DP-1]\npath = \"/walls\"\nduration = \"10m\"\ntransition_type = \"fade\"\n");
+        let diff = diff(&old, &new, &outputs()).unwrap();
+        assert_eq!(diff.outputs, BTreeSet::from(["DP-1".to_string()]));
+        assert_eq!(diff.changes.len(), 2, "{:?}", diff.changes);
+        assert!(diff.changes.iter().all(|change| change.starts_with("DP-1: ")), "{:?}", diff.changes);
+        assert!(diff.changes.iter().any(|change| change.starts_with("DP-1: transition_type")), "{:?}", diff.changes);
+        assert!(!diff.rebuild);
+        assert!(diff.requeue.is_empty());
+    }
+
+    #[test]
+    fn test_paths_sorting_and_groups_need_a_rebuild() {
+        let old = config("[any]\npath = \"/walls\"\n");
+        let diff_of = |new: &str| diff(&old, &config(new), &outputs()).unwrap();
+
+        let moved = diff_of("[any]\npath = \"/other\"\n");
+        assert_eq!(moved.changes, [r#"DP-1: path "/walls" → "/other""#, r#"HDMI-A-1: path "/walls" → "/other""#]);
+        assert!(moved.rebuild);
+        assert!(!moved.rebuild_all);
+        assert_eq!(moved.requeue, BTreeSet::from(["DP-1".to_string(), "HDMI-A-1".to_string()]));
+        let one = diff_of("[any]\npath = \"/walls\"\n\n[DP-1]\npath = \"/other\"\n");
+        assert_eq!(one.requeue, BTreeSet::from(["DP-1".to_string()]));
+        assert!(diff_of("[global]\nsorting = \"Descending\"\n\n[any]\npath = \"/walls\"\n").rebuild);
+
+        let grouped = diff_of("monitor_behavior = \"Grouped\"\nmonitor_groups = [[\"DP-1\", \"HDMI-A-1\"]]\n\n[any]\npath = \"/walls\"\n");
+        assert!(grouped.rebuild_all);
+        assert!(diff_of("[global]\nrandom_seed = 7\n\n[any]\npath = \"/walls\"\n").rebuild_all);
+        assert!(grouped.changes.iter().any(|change| change.starts_with("monitor_behavior: ")), "{:?}", grouped.changes);
+
+        let restart = diff_of("[global]\nswww_binary = \"/opt/swww\"\n\n[any]\npath = \"/walls\"\n");
+        assert_eq!(restart.changes, [r#"global: swww_binary unset → "/opt/swww" (takes effect after a restart)"#]);
+        assert!(!restart.rebuild);
+        assert!(restart.outputs.is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::Path;
+use swwws_common::{OutputSettings, SwwwsError, error::ConfigError, Result};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// Write `settings` into `output`'s section of the config `content`, adding the
+/// section at the end if there is none. Everything else, comments and layout
+/// included, stays as it was.
+pub fn set_output_section(content: &str, output: &str, settings: &OutputSettings) -> Result<String> {
+    let mut document: DocumentMut = content.parse()
+        .map_err(|e: toml_edit::TomlError| SwwwsError::Config(ConfigError::TomlParse {
+            message: e.to_string(),
+        }))?;
+
+    if !document.contains_key(output) {
+        let mut section = Table::new();
+        if !content.trim().is_empty() {
+            section.decor_mut().set_prefix("\n");
+        }
+        document.insert(output, Item::Table(section));
+    }
+
+    let section = document[output].as_table_mut()
+        .ok_or_else(|| SwwwsError::Config(ConfigError::InvalidValue {
+            field: output.to_string(),
+            value: "not a [section]".to_string(),
+        }))?;
+    for (key, value) in settings.entries() {
+        set_value(section, key, value);
+    }
+
+    Ok(document.to_string())
+}
+
+/// Set `key`, keeping the comment and spacing around a value that was already there
+fn set_value(section: &mut Table, key: &str, value: &str) {
+    match section.get_mut(key).and_then(Item::as_value_mut) {
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = Value::from(value);
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            section.insert(key, toml_edit::value(value));
+        }
+    }
+}
+
+/// Write `settings` into `output`'s section of the config file at `config_path`
+pub fn persist_output_section(config_path: &Path, output: &str, settings: &OutputSettings) -> Result<()> {
+    let content = crate::Config::read_file(config_path)?;
+    let updated = set_output_section(&content, output, settings)?;
+    std::fs::write(config_path, updated)
+        .map_err(|e| SwwwsError::Config(ConfigError::FileWrite {
+            path: config_path.to_path_buf(),
+            source: e,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    const COMMENTED: &str = r#"# My wallpapers
+# (hand-written, please keep these notes)
+
+[global]
+duration = "5m"   # default pace
+sorting = "Random"
+
+[any]
+path = "/walls/any"   # everything else
+
+# The big one on the left
+["DP-1"]
+path = "/walls/wide"  # ultrawide crops
+duration = "10m"      # slower here
+"#;
+
+    fn settings(path: Option<&str>, duration: Option<&str>) -> OutputSettings {
+        OutputSettings {
+            path: path.map(str::to_string),
+            duration: duration.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_new_section_is_appended_and_comments_kept() {
+        let updated = set_output_section(COMMENTED, "DP-4", &settings(Some("/walls/new"), Some("2m"))).unwrap();
+
+        assert!(updated.starts_with(COMMENTED), "existing text changed:\n{}", updated);
+        assert!(updated.ends_with("\n[DP-4]\npath = \"/walls/new\"\nduration = \"2m\"\n"), "got:\n{}", updated);
+
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.outputs["DP-4"].path.as_deref(), Some("/walls/new"));
+        assert_eq!(config.outputs["DP-4"].duration, Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_existing_section_is_updated_in_place() {
+        let updated = set_output_section(COMMENTED, "DP-1", &settings(None, Some("1h"))).unwrap();
+
+        assert_eq!(updated, COMMENTED.replace("duration = \"10m\"      # slower here", "duration = \"1h\"      # slower here"));
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.outputs["DP-1"].path.as_deref(), Some("/walls/wide"));
+        assert_eq!(config.outputs["DP-1"].duration, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_missing_key_is_added_to_existing_section() {
+        let content = "[any]\npath = \"/walls/any\"\n\n# note\n[\"HDMI-A-1\"]\nsorting = \"Ascending\" # sorted\n\n[global]\nduration = \"5m\"\n";
+        let updated = set_output_section(content, "HDMI-A-1", &settings(Some("/walls/tv"), None)).unwrap();
+
+        assert!(updated.contains("# note\n[\"HDMI-A-1\"]\nsorting = \"Ascending\" # sorted\npath = \"/walls/tv\"\n"), "got:\n{}", updated);
+        assert!(updated.ends_with("\n[global]\nduration = \"5m\"\n"), "got:\n{}", updated);
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.outputs["HDMI-A-1"].path.as_deref(), Some("/walls/tv"));
+    }
+
+    #[test]
+    fn test_empty_file_and_bad_file() {
+        let updated = set_output_section("", "DP-4", &settings(Some("/walls/new"), None)).unwrap();
+        assert_eq!(updated, "[DP-4]\npath = \"/walls/new\"\n");
+
+        assert!(set_output_section("[any\npath = 1", "DP-4", &settings(Some("/w"), None)).is_err());
+        assert!(set_output_section("DP-4 = 3\n", "DP-4", &settings(Some("/w"), None)).is_err());
+    }
+
+    #[test]
+    fn test_overrides_load_like_a_section() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, COMMENTED).unwrap();
+
+        let mut overrides = std::collections::BTreeMap::new();
+        overrides.insert("DP-1".to_string(), settings(None, Some("30s")));
+        overrides.insert("DP-4".to_string(), settings(Some("/walls/new"), None));
+        let config = Config::load_with_overrides(&path, &overrides).unwrap();
+        assert_eq!(config.get_output_config("DP-1").duration, Duration::from_secs(30));
+        assert_eq!(config.get_output_config("DP-1").path.as_deref(), Some("/walls/wide"));
+        assert_eq!(config.get_output_config("DP-4").path.as_deref(), Some("/walls/new"));
+        // The file itself is untouched
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), COMMENTED);
+
+        overrides.insert("DP-4".to_string(), settings(None, Some("soon")));
+        assert!(Config::load_with_overrides(&path, &overrides).is_err());
+    }
+
+    #[test]
+    fn test_persist_output_section() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, COMMENTED).unwrap();
+
+        persist_output_section(&path, "DP-4", &settings(Some("/walls/new"), None)).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with(COMMENTED));
+        assert!(Config::load_from(&path).unwrap().outputs.contains_key("DP-4"));
+    }
+}
@@ -0,0 +1,93 @@
+/// Expand a leading `~` and `$VAR` / `${VAR}` references the way a shell would.
+///
+/// Returns the name of the first variable that is not set, rather than
+/// silently substituting an empty string.
+pub fn expand_path(raw: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    if rest == "~" || rest.starts_with("~/") {
+        let home = dirs::home_dir().ok_or_else(|| "HOME".to_string())?;
+        out.push_str(&home.to_string_lossy());
+        rest = &rest[1..];
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) if is_name(&braced[..end]) => (&braced[..end], end + 2),
+                _ => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            if is_name(&after[..end]) { (&after[..end], end) } else { ("", 0) }
+        };
+
+        if name.is_empty() {
+            // Not a variable reference, keep the `$` literally
+            out.push('$');
+            rest = after;
+            continue;
+        }
+
+        let value = std::env::var(name).map_err(|_| name.to_string())?;
+        out.push_str(&value);
+        rest = &after[consumed..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
+        assert_eq!(expand_path("~/Pictures/walls").unwrap(), format!("{}/Pictures/walls", home));
+        assert_eq!(expand_path("~").unwrap(), home);
+        // Only a leading tilde is special
+        assert_eq!(expand_path("/walls/~old").unwrap(), "/walls/~old");
+        assert_eq!(expand_path("~user/walls").unwrap(), "~user/walls");
+    }
+
+    #[test]
+    fn test_expand_home_variable() {
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(expand_path("$HOME/walls").unwrap(), format!("{}/walls", home));
+        assert_eq!(expand_path("${HOME}/walls").unwrap(), format!("{}/walls", home));
+    }
+
+    #[test]
+    fn test_expand_variables_in_the_middle() {
+        std::env::set_var("SWWWS_TEST_THEME", "dark");
+        assert_eq!(expand_path("/walls/$SWWWS_TEST_THEME/4k").unwrap(), "/walls/dark/4k");
+        assert_eq!(expand_path("/walls/${SWWWS_TEST_THEME}_4k").unwrap(), "/walls/dark_4k");
+    }
+
+    #[test]
+    fn test_expand_undefined_variable_is_an_error() {
+        assert_eq!(expand_path("$SWWWS_TEST_UNSET_VAR/walls"), Err("SWWWS_TEST_UNSET_VAR".to_string()));
+        assert_eq!(expand_path("/walls/${SWWWS_TEST_UNSET_VAR}"), Err("SWWWS_TEST_UNSET_VAR".to_string()));
+    }
+
+    #[test]
+    fn test_expand_leaves_non_variables_alone() {
+        assert_eq!(expand_path("/walls/$5 deals").unwrap(), "/walls/$5 deals");
+        assert_eq!(expand_path("/walls/cost$").unwrap(), "/walls/cost$");
+        assert_eq!(expand_path("/walls/${unclosed").unwrap(), "/walls/${unclosed");
+    }
+}
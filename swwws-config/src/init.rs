@@ -0,0 +1,67 @@
+/// Commented starter config for `swwws-cli init`, with a section stub for each
+/// of `outputs`. Every setting is commented out, so the file loads as-is and
+/// only does something once a `path` is filled in.
+pub fn starter_config(outputs: &[String]) -> String {
+    let mut config = String::from(
+        r#"# swwws configuration
+# See CONFIGURATION.md for every option.
+
+# How outputs share wallpapers: "Independent", "Synchronized" or "Grouped"
+# monitor_behavior = "Independent"
+
+# Defaults for every output
+[global]
+# duration = "3m"              # How long each image stays up
+# sorting = "Random"           # "Random", "Ascending", "Descending", "NewestFirst", "OldestFirst" or "LeastRecentlyShown"
+# transition_type = "center"   # Any swww transition, or "random"
+# transition_duration = "500ms"
+# transition_fps = 30
+# resize = "crop"              # "crop", "fit", "stretch" or "no"
+
+# Used by any output without a section of its own
+[any]
+# path = "~/Pictures/Wallpapers"
+"#,
+    );
+
+    if !outputs.is_empty() {
+        config.push_str("\n# Outputs found when this file was created\n");
+    }
+    for output in outputs {
+        config.push_str(&format!(
+            "\n[{:?}]\n# path = \"~/Pictures/Wallpapers\"\n# duration = \"3m\"\n",
+            output,
+        ));
+    }
+
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_starter_config_loads() {
+        let outputs = vec!["DP-1".to_string(), "HDMI-A-1".to_string()];
+        let content = starter_config(&outputs);
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, &content).unwrap();
+        let config = Config::load_from(&path).expect("starter config should load");
+
+        let mut sections: Vec<&String> = config.outputs.keys().collect();
+        sections.sort();
+        assert_eq!(sections, vec!["DP-1", "HDMI-A-1"]);
+        assert!(config.any.path.is_none());
+    }
+
+    #[test]
+    fn test_starter_config_without_outputs() {
+        let config: Config = toml::from_str(&starter_config(&[])).unwrap();
+        assert!(config.outputs.is_empty());
+    }
+}
@@ -1,18 +1,180 @@
-use serde::{Deserialize, Serialize, Deserializer};
-use std::path::PathBuf;
-#[cfg(test)]
-use std::path::Path;
+use serde::{Deserialize, Serialize, Deserializer, Serializer};
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
 use std::time::Duration;
-use swwws_common::{Sorting, MonitorBehavior, SwwwsError, error::ConfigError, Result};
+use swwws_common::{Sorting, Collation, ChangeReason, DiscoveryOptions, ImageDiscovery, MinResolution, MonitorBehavior, StartOffset, ResumeAction, Schedule, Profile, Interval, Orientation, OutputSettings, SwwwsError, error::ConfigError, Result};
+use swwws_common::command_builder::OutputConfig as CommandConfig;
+use swwws_common::schedule::DEFAULT_PROFILE;
+use swwws_common::transition;
+use swwws_common::pattern;
+use chrono::NaiveTime;
+
+pub mod check;
+pub mod diff;
+pub mod edit;
+pub mod expand;
+pub mod init;
+pub mod render;
+
+/// A length as written: whole seconds, fractional seconds, or a humantime string
+enum DurationSetting {
+    Seconds(i64),
+    Fractional(f64),
+    Text(String),
+}
+
+impl DurationSetting {
+    fn parse<E: serde::de::Error>(self) -> std::result::Result<Duration, E> {
+        match self {
+            DurationSetting::Seconds(seconds) => u64::try_from(seconds)
+                .map(Duration::from_secs)
+                .map_err(|_| E::custom(format!("invalid duration {}: must not be negative", seconds))),
+            DurationSetting::Fractional(seconds) => Duration::try_from_secs_f64(seconds)
+                .map_err(|_| E::custom(format!("invalid duration {}: must be a non-negative number of seconds", seconds))),
+            DurationSetting::Text(duration_str) => swwws_common::duration::parse_duration(&duration_str)
+                .map_err(E::custom),
+        }
+    }
+
+    /// `"never"` or `"0"` as a string, the values that keep a wallpaper static
+    fn is_never(&self) -> bool {
+        matches!(self, DurationSetting::Text(duration_str) if matches!(duration_str.trim(), "never" | "0"))
+    }
+}
+
+impl<'de> Deserialize<'de> for DurationSetting {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl serde::de::Visitor<'_> for DurationVisitor {
+            type Value = DurationSetting;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a number of seconds or a duration string like \"5m\"")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, seconds: i64) -> std::result::Result<Self::Value, E> {
+                Ok(DurationSetting::Seconds(seconds))
+            }
 
-// Custom deserialization for Duration from human-readable strings
+            fn visit_u64<E: serde::de::Error>(self, seconds: u64) -> std::result::Result<Self::Value, E> {
+                i64::try_from(seconds)
+                    .map(DurationSetting::Seconds)
+                    .map_err(|_| E::custom(format!("invalid duration {}: too long", seconds)))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, seconds: f64) -> std::result::Result<Self::Value, E> {
+                Ok(DurationSetting::Fractional(seconds))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, duration_str: &str) -> std::result::Result<Self::Value, E> {
+                Ok(DurationSetting::Text(duration_str.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+// Custom deserialization for Duration from seconds or human-readable strings
 fn deserialize_duration<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let duration_str = String::deserialize(deserializer)?;
-    swwws_common::duration::parse_duration(&duration_str)
-        .map_err(serde::de::Error::custom)
+    DurationSetting::deserialize(deserializer)?.parse()
+}
+
+fn serialize_duration<S>(duration: &Duration, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&humantime::format_duration(*duration).to_string())
+}
+
+/// `duration` as written: a length, or a `{ min = "3m", max = "10m" }` table
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IntervalSetting {
+    Fixed(DurationSetting),
+    Range { min: DurationSetting, max: DurationSetting },
+}
+
+impl IntervalSetting {
+    fn parse<E: serde::de::Error>(self) -> std::result::Result<Interval, E> {
+        match self {
+            IntervalSetting::Fixed(duration) => duration.parse().map(Interval::Fixed),
+            IntervalSetting::Range { min, max } => Ok(Interval::Random { min: min.parse()?, max: max.parse()? }),
+        }
+    }
+}
+
+fn deserialize_interval<'de, D>(deserializer: D) -> std::result::Result<Interval, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    IntervalSetting::deserialize(deserializer)?.parse()
+}
+
+/// `Interval` back in the form `deserialize_rotation` reads
+fn serialize_interval<S>(interval: &Interval, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let format = |duration: &Duration| humantime::format_duration(*duration).to_string();
+    match interval {
+        Interval::Fixed(duration) if duration.is_zero() => serializer.serialize_str("never"),
+        Interval::Fixed(duration) => serializer.serialize_str(&format(duration)),
+        Interval::Random { min, max } => {
+            use serde::ser::SerializeStruct;
+            let mut range = serializer.serialize_struct("Interval", 2)?;
+            range.serialize_field("min", &format(min))?;
+            range.serialize_field("max", &format(max))?;
+            range.end()
+        }
+    }
+}
+
+/// Like `deserialize_interval`, but also takes `"never"` or `"0"` for a wallpaper
+/// that never rotates, stored as a zero duration
+fn deserialize_rotation<'de, D>(deserializer: D) -> std::result::Result<Interval, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match IntervalSetting::deserialize(deserializer)? {
+        IntervalSetting::Fixed(duration) if duration.is_never() => Ok(Interval::Fixed(Duration::ZERO)),
+        setting => setting.parse(),
+    }
+}
+
+/// `monitor_behavior` is written by name. The groups themselves come from
+/// `monitor_groups` and `[groups]`, so `"Grouped"` starts out with none.
+fn deserialize_monitor_behavior<'de, D>(deserializer: D) -> std::result::Result<MonitorBehavior, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "Independent" => Ok(MonitorBehavior::Independent),
+        "Synchronized" => Ok(MonitorBehavior::Synchronized),
+        "Grouped" => Ok(MonitorBehavior::Grouped(vec![])),
+        other => Err(serde::de::Error::custom(format!(
+            "unknown monitor_behavior {:?}, expected \"Independent\", \"Synchronized\" or \"Grouped\"", other
+        ))),
+    }
+}
+
+fn monitor_behavior_name(behavior: &MonitorBehavior) -> &'static str {
+    match behavior {
+        MonitorBehavior::Independent => "Independent",
+        MonitorBehavior::Synchronized => "Synchronized",
+        MonitorBehavior::Grouped(_) => "Grouped",
+    }
+}
+
+fn serialize_monitor_behavior<S>(behavior: &MonitorBehavior, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(monitor_behavior_name(behavior))
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -21,20 +183,36 @@ pub struct Config {
     pub global: GlobalConfig,
     #[serde(default)]
     pub any: OutputConfig,
-    #[serde(default = "default_monitor_behavior")]
+    #[serde(default = "default_monitor_behavior", deserialize_with = "deserialize_monitor_behavior", serialize_with = "serialize_monitor_behavior")]
     pub monitor_behavior: MonitorBehavior,
     #[serde(default)]
     pub monitor_groups: Option<Vec<Vec<String>>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, GroupConfig>,
     #[serde(flatten)]
     pub outputs: std::collections::HashMap<String, OutputConfig>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GlobalConfig {
-    #[serde(default = "default_duration", deserialize_with = "deserialize_duration")]
-    pub duration: Duration,
+    #[serde(default = "default_duration", deserialize_with = "deserialize_interval", serialize_with = "serialize_interval")]
+    pub duration: Interval,
     #[serde(default = "default_queue_size")]
     pub queue_size: usize,
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+    #[serde(default = "default_follow_symlinks")]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub min_resolution: Option<MinResolution>,
+    #[serde(default = "default_keep_unknown_resolution")]
+    pub keep_unknown_resolution: bool,
+    #[serde(default = "default_skip_hidden")]
+    pub skip_hidden: bool,
+    #[serde(default = "default_dedupe")]
+    pub dedupe: bool,
     #[serde(default = "default_sorting")]
     pub sorting: Sorting,
     #[serde(default = "default_transition_type")]
@@ -47,8 +225,10 @@ pub struct GlobalConfig {
     pub transition_pos: String,
     #[serde(default = "default_transition_bezier")]
     pub transition_bezier: String,
-    #[serde(default = "default_transition_duration", deserialize_with = "deserialize_duration")]
+    #[serde(default = "default_transition_duration", deserialize_with = "deserialize_duration", serialize_with = "serialize_duration")]
     pub transition_duration: Duration,
+    #[serde(default = "default_transition_fps")]
+    pub transition_fps: u32,
     #[serde(default = "default_resize")]
     pub resize: String,
     #[serde(default = "default_fill_color")]
@@ -59,15 +239,85 @@ pub struct GlobalConfig {
     pub invert_y: bool,
     #[serde(default = "default_transition_wave")]
     pub transition_wave: String,
+    #[serde(default)]
+    pub transition_types: Option<Vec<String>>,
+    #[serde(default = "default_allow_unknown_transitions")]
+    pub allow_unknown_transitions: bool,
+    #[serde(default = "default_collation")]
+    pub collation: Collation,
+    #[serde(default)]
+    pub no_repeat_window: Option<usize>,
+    #[serde(default)]
+    pub start_offset: Option<StartOffset>,
+    #[serde(default = "default_reapply_on_wake")]
+    pub reapply_on_wake: bool,
+    #[serde(default)]
+    pub on_change: Option<String>,
+    #[serde(default = "default_on_change_once")]
+    pub on_change_once: bool,
+    #[serde(default = "default_distinct_across_outputs")]
+    pub distinct_across_outputs: bool,
+    #[serde(default = "default_max_concurrent_changes")]
+    pub max_concurrent_changes: usize,
+    #[serde(default = "default_watch_paths")]
+    pub watch_paths: bool,
+    #[serde(default = "default_clear_on_exit")]
+    pub clear_on_exit: bool,
+    #[serde(default = "default_on_resume")]
+    pub on_resume: ResumeAction,
+    #[serde(default = "default_change_log_size")]
+    pub change_log_size: usize,
+    #[serde(default)]
+    pub random_seed: Option<u64>,
+    #[serde(default = "default_strict")]
+    pub strict: bool,
+    #[serde(default)]
+    pub swww_binary: Option<String>,
+    #[serde(default)]
+    pub state_file: Option<String>,
+    #[serde(default)]
+    pub viewer: Option<String>,
+    #[serde(default)]
+    pub log_file: Option<String>,
+    #[serde(default)]
+    pub log_level: Option<String>,
+    #[serde(default = "default_log_max_size")]
+    pub log_max_size: u64,
+    #[serde(default = "default_log_keep")]
+    pub log_keep: usize,
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    #[serde(default = "default_startup_timeout", deserialize_with = "deserialize_duration", serialize_with = "serialize_duration")]
+    pub startup_timeout: Duration, // how long startup waits for swww-daemon and its outputs
+    #[serde(default)]
+    pub manual_transition: Option<TransitionOverride>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct OutputConfig {
     pub path: Option<String>,
-    #[serde(default = "default_duration", deserialize_with = "deserialize_duration")]
-    pub duration: Duration,
+    #[serde(default)]
+    pub portrait_path: Option<String>,
+    #[serde(default)]
+    pub landscape_path: Option<String>,
+    #[serde(default = "default_duration", deserialize_with = "deserialize_rotation", serialize_with = "serialize_interval")]
+    pub duration: Interval,
     #[serde(default = "default_queue_size")]
     pub queue_size: usize,
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+    #[serde(default = "default_follow_symlinks")]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub min_resolution: Option<MinResolution>,
+    #[serde(default = "default_keep_unknown_resolution")]
+    pub keep_unknown_resolution: bool,
+    #[serde(default = "default_skip_hidden")]
+    pub skip_hidden: bool,
+    #[serde(default = "default_dedupe")]
+    pub dedupe: bool,
     #[serde(default = "default_sorting")]
     pub sorting: Sorting,
     #[serde(default = "default_transition_type")]
@@ -80,8 +330,10 @@ pub struct OutputConfig {
     pub transition_pos: String,
     #[serde(default = "default_transition_bezier")]
     pub transition_bezier: String,
-    #[serde(default = "default_transition_duration", deserialize_with = "deserialize_duration")]
+    #[serde(default = "default_transition_duration", deserialize_with = "deserialize_duration", serialize_with = "serialize_duration")]
     pub transition_duration: Duration,
+    #[serde(default = "default_transition_fps")]
+    pub transition_fps: u32,
     #[serde(default = "default_resize")]
     pub resize: String,
     #[serde(default = "default_fill_color")]
@@ -92,11 +344,191 @@ pub struct OutputConfig {
     pub invert_y: bool,
     #[serde(default = "default_transition_wave")]
     pub transition_wave: String,
+    #[serde(default)]
+    pub transition_types: Option<Vec<String>>,
+    #[serde(default = "default_collation")]
+    pub collation: Collation,
+    #[serde(default)]
+    pub no_repeat_window: Option<usize>,
+    #[serde(default)]
+    pub start_offset: Option<StartOffset>,
+    #[serde(default = "default_reapply_on_wake")]
+    pub reapply_on_wake: bool,
+    #[serde(default = "default_offset")]
+    pub offset: usize,
+    #[serde(default)]
+    pub on_change: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    #[serde(default)]
+    pub manual_transition: Option<TransitionOverride>,
+}
+
+/// A `[groups.NAME]` section: outputs sharing one queue, with settings that take
+/// precedence over those of the member outputs
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GroupConfig {
+    pub outputs: Vec<String>,
+    #[serde(flatten)]
+    pub settings: OutputConfig,
+}
+
+/// Transition settings that replace the normal ones for manual changes
+/// (`swwws-cli next`/`previous`). Unset fields fall through to the next layer.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TransitionOverride {
+    pub transition_type: Option<String>,
+    pub transition_step: Option<u32>,
+    pub transition_angle: Option<f32>,
+    pub transition_pos: Option<String>,
+    pub transition_bezier: Option<String>,
+    pub transition_fps: Option<u32>,
+    pub transition_wave: Option<String>,
+}
+
+impl TransitionOverride {
+    /// Fill the fields unset here from `below`
+    fn or(&self, below: &TransitionOverride) -> TransitionOverride {
+        TransitionOverride {
+            transition_type: self.transition_type.clone().or_else(|| below.transition_type.clone()),
+            transition_step: self.transition_step.or(below.transition_step),
+            transition_angle: self.transition_angle.or(below.transition_angle),
+            transition_pos: self.transition_pos.clone().or_else(|| below.transition_pos.clone()),
+            transition_bezier: self.transition_bezier.clone().or_else(|| below.transition_bezier.clone()),
+            transition_fps: self.transition_fps.or(below.transition_fps),
+            transition_wave: self.transition_wave.clone().or_else(|| below.transition_wave.clone()),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.transition_step == Some(0) {
+            return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                field: "manual_transition.transition_step".to_string(),
+                value: "0".to_string(),
+            }));
+        }
+
+        if let Some(angle) = self.transition_angle {
+            if !(0.0..=360.0).contains(&angle) {
+                return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                    field: "manual_transition.transition_angle".to_string(),
+                    value: angle.to_string(),
+                }));
+            }
+        }
+
+        if let Some(fps) = self.transition_fps {
+            if fps == 0 || fps > 255 {
+                return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                    field: "manual_transition.transition_fps".to_string(),
+                    value: fps.to_string(),
+                }));
+            }
+        }
+
+        validate_transition_strings(
+            "manual_transition.",
+            self.transition_pos.as_deref(),
+            self.transition_bezier.as_deref(),
+            self.transition_wave.as_deref(),
+        )
+    }
+}
+
+/// `transition_types` must name real swww transitions
+fn validate_transition_types(types: &Option<Vec<String>>) -> Result<()> {
+    let Some(types) = types else { return Ok(()) };
+
+    if types.is_empty() {
+        return Err(SwwwsError::Config(ConfigError::InvalidValue {
+            field: "transition_types".to_string(),
+            value: "[]".to_string(),
+        }));
+    }
+
+    if let Some(unknown) = types.iter().find(|t| !transition::is_known_type(t)) {
+        return Err(SwwwsError::Config(ConfigError::InvalidValue {
+            field: "transition_types".to_string(),
+            value: unknown.clone(),
+        }));
+    }
+
+    Ok(())
+}
+
+/// Named positions swww accepts for `transition_pos`
+const TRANSITION_POSITIONS: &[&str] = &[
+    "center", "top", "left", "right", "bottom", "top-left", "top-right", "bottom-left", "bottom-right",
+];
+
+/// `value` rejected for `field`, with the format that would have been accepted
+fn invalid_format(field: &str, value: &str, expected: &str) -> SwwwsError {
+    SwwwsError::Config(ConfigError::InvalidValue {
+        field: field.to_string(),
+        value: format!("{:?} (expected {})", value, expected),
+    })
+}
+
+/// Whether `value` is exactly `count` comma-separated numbers
+fn is_number_list(value: &str, count: usize) -> bool {
+    let parts: Vec<&str> = value.split(',').collect();
+    parts.len() == count && parts.iter().all(|part| part.trim().parse::<f32>().is_ok_and(f32::is_finite))
+}
+
+/// Check the free-form transition settings swww would otherwise reject at
+/// change time. `prefix` goes before each field name in errors.
+fn validate_transition_strings(prefix: &str, pos: Option<&str>, bezier: Option<&str>, wave: Option<&str>) -> Result<()> {
+    if let Some(pos) = pos.filter(|pos| !TRANSITION_POSITIONS.contains(pos) && !is_number_list(pos, 2)) {
+        return Err(invalid_format(&format!("{}transition_pos", prefix), pos, &format!(
+            "one of {} or an x,y pair like \"0.5,0.5\"", TRANSITION_POSITIONS.join(", "))));
+    }
+    if let Some(bezier) = bezier.filter(|bezier| !is_number_list(bezier, 4)) {
+        return Err(invalid_format(&format!("{}transition_bezier", prefix), bezier,
+            "four comma-separated numbers like \"0.25,0.1,0.25,1\""));
+    }
+    if let Some(wave) = wave.filter(|wave| !is_number_list(wave, 2)) {
+        return Err(invalid_format(&format!("{}transition_wave", prefix), wave,
+            "two comma-separated numbers like \"20,20\""));
+    }
+    Ok(())
+}
+
+/// Deepest `max_depth` accepted. Wallpaper trees are never this deep, so a
+/// larger value is almost certainly a typo.
+const MAX_DISCOVERY_DEPTH: usize = 64;
+
+/// `max_depth` counts directory levels; 0 means unlimited
+fn validate_max_depth(max_depth: Option<usize>) -> Result<()> {
+    match max_depth {
+        Some(depth) if depth > MAX_DISCOVERY_DEPTH => Err(invalid_format(
+            "max_depth", &depth.to_string(), &format!("0 for unlimited, or at most {}", MAX_DISCOVERY_DEPTH))),
+        _ => Ok(()),
+    }
+}
+
+fn validate_fill_color(fill_color: &str) -> Result<()> {
+    if fill_color.len() != 6 || !fill_color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid_format("fill_color", fill_color, "a 6-digit hex color like \"000000\""));
+    }
+    Ok(())
+}
+
+/// Stack two optional override blocks field by field, `top` winning
+fn layer_overrides(
+    top: &Option<TransitionOverride>,
+    bottom: &Option<TransitionOverride>,
+) -> Option<TransitionOverride> {
+    match (top, bottom) {
+        (Some(top), Some(bottom)) => Some(top.or(bottom)),
+        (top, bottom) => top.clone().or_else(|| bottom.clone()),
+    }
 }
 
 // Default values
-fn default_duration() -> Duration {
-    Duration::from_secs(300) // 5 minutes
+fn default_duration() -> Interval {
+    Interval::Fixed(Duration::from_secs(300)) // 5 minutes
 }
 
 fn default_queue_size() -> usize {
@@ -131,6 +563,10 @@ fn default_transition_duration() -> Duration {
     Duration::from_millis(500)
 }
 
+fn default_transition_fps() -> u32 {
+    30
+}
+
 fn default_resize() -> String {
     "crop".to_string()
 }
@@ -151,6 +587,94 @@ fn default_transition_wave() -> String {
     "20,20".to_string()
 }
 
+fn default_collation() -> Collation {
+    Collation::Byte
+}
+
+fn default_max_concurrent_changes() -> usize {
+    4
+}
+
+fn default_strict() -> bool {
+    false
+}
+
+fn default_reapply_on_wake() -> bool {
+    false
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_on_change_once() -> bool {
+    false
+}
+
+fn default_distinct_across_outputs() -> bool {
+    false
+}
+
+fn default_offset() -> usize {
+    0
+}
+
+fn default_allow_unknown_transitions() -> bool {
+    false
+}
+
+fn default_history_size() -> usize {
+    100
+}
+
+fn default_watch_paths() -> bool {
+    true
+}
+
+fn default_clear_on_exit() -> bool {
+    false
+}
+
+fn default_follow_symlinks() -> bool {
+    true
+}
+
+fn default_keep_unknown_resolution() -> bool {
+    true
+}
+
+fn default_skip_hidden() -> bool {
+    true
+}
+
+fn default_dedupe() -> bool {
+    false
+}
+
+fn default_change_log_size() -> usize {
+    100
+}
+
+fn default_on_resume() -> ResumeAction {
+    ResumeAction::default()
+}
+
+fn default_log_max_size() -> u64 {
+    10
+}
+
+fn default_log_keep() -> usize {
+    3
+}
+
+fn default_dry_run() -> bool {
+    false
+}
+
+fn default_startup_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
 fn default_monitor_behavior() -> MonitorBehavior {
     MonitorBehavior::Independent
 }
@@ -160,6 +684,13 @@ impl Default for GlobalConfig {
         Self {
             duration: default_duration(),
             queue_size: default_queue_size(),
+            history_size: default_history_size(),
+            follow_symlinks: default_follow_symlinks(),
+            max_depth: None,
+            min_resolution: None,
+            keep_unknown_resolution: default_keep_unknown_resolution(),
+            skip_hidden: default_skip_hidden(),
+            dedupe: default_dedupe(),
             sorting: default_sorting(),
             transition_type: default_transition_type(),
             transition_step: default_transition_step(),
@@ -167,11 +698,38 @@ impl Default for GlobalConfig {
             transition_pos: default_transition_pos(),
             transition_bezier: default_transition_bezier(),
             transition_duration: default_transition_duration(),
+            transition_fps: default_transition_fps(),
             resize: default_resize(),
             fill_color: default_fill_color(),
             filter: default_filter(),
             invert_y: default_invert_y(),
             transition_wave: default_transition_wave(),
+            transition_types: None,
+            allow_unknown_transitions: default_allow_unknown_transitions(),
+            collation: default_collation(),
+            no_repeat_window: None,
+            start_offset: None,
+            reapply_on_wake: default_reapply_on_wake(),
+            on_change: None,
+            on_change_once: default_on_change_once(),
+            distinct_across_outputs: default_distinct_across_outputs(),
+            max_concurrent_changes: default_max_concurrent_changes(),
+            watch_paths: default_watch_paths(),
+            clear_on_exit: default_clear_on_exit(),
+            on_resume: default_on_resume(),
+            change_log_size: default_change_log_size(),
+            random_seed: None,
+            strict: default_strict(),
+            swww_binary: None,
+            state_file: None,
+            viewer: None,
+            log_file: None,
+            log_level: None,
+            log_max_size: default_log_max_size(),
+            log_keep: default_log_keep(),
+            dry_run: default_dry_run(),
+            startup_timeout: default_startup_timeout(),
+            manual_transition: None,
         }
     }
 }
@@ -183,6 +741,7 @@ impl Default for Config {
             any: OutputConfig::default(),
             monitor_behavior: default_monitor_behavior(),
             monitor_groups: None,
+            groups: BTreeMap::new(),
             outputs: std::collections::HashMap::new(),
         }
     }
@@ -192,8 +751,17 @@ impl Default for OutputConfig {
     fn default() -> Self {
         Self {
             path: None,
+            portrait_path: None,
+            landscape_path: None,
             duration: default_duration(),
             queue_size: default_queue_size(),
+            history_size: default_history_size(),
+            follow_symlinks: default_follow_symlinks(),
+            max_depth: None,
+            min_resolution: None,
+            keep_unknown_resolution: default_keep_unknown_resolution(),
+            skip_hidden: default_skip_hidden(),
+            dedupe: default_dedupe(),
             sorting: default_sorting(),
             transition_type: default_transition_type(),
             transition_step: default_transition_step(),
@@ -201,36 +769,65 @@ impl Default for OutputConfig {
             transition_pos: default_transition_pos(),
             transition_bezier: default_transition_bezier(),
             transition_duration: default_transition_duration(),
+            transition_fps: default_transition_fps(),
             resize: default_resize(),
             fill_color: default_fill_color(),
             filter: default_filter(),
             invert_y: default_invert_y(),
             transition_wave: default_transition_wave(),
+            transition_types: None,
+            collation: default_collation(),
+            no_repeat_window: None,
+            start_offset: None,
+            reapply_on_wake: default_reapply_on_wake(),
+            offset: default_offset(),
+            on_change: None,
+            enabled: default_enabled(),
+            schedule: None,
+            manual_transition: None,
         }
     }
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        let config_path = Self::config_path()?;
-        
+        Self::load_from(&Self::config_path()?)
+    }
+
+    pub fn load_from(config_path: &Path) -> Result<Self> {
+        let content = Self::read_file(config_path)?;
+        log::debug!("Loading configuration from: {:?}", config_path);
+        Self::from_toml_str(&content)
+    }
+
+    /// Like `load_from`, with `overrides` applied as if each output's settings
+    /// were written in its section of the file
+    pub fn load_with_overrides(config_path: &Path, overrides: &BTreeMap<String, OutputSettings>) -> Result<Self> {
+        let mut content = Self::read_file(config_path)?;
+        for (output, settings) in overrides {
+            content = edit::set_output_section(&content, output, settings)?;
+        }
+        Self::from_toml_str(&content)
+    }
+
+    pub(crate) fn read_file(config_path: &Path) -> Result<String> {
         if !config_path.exists() {
             return Err(SwwwsError::Config(ConfigError::FileRead {
-                path: config_path,
+                path: config_path.to_path_buf(),
                 source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
             }));
         }
         
-        let content = std::fs::read_to_string(&config_path)
+        std::fs::read_to_string(config_path)
             .map_err(|e| SwwwsError::Config(ConfigError::FileRead {
-                path: config_path.clone(),
+                path: config_path.to_path_buf(),
                 source: e,
-            }))?;
-        
-        // Parse configuration content
-        log::debug!("Loading configuration from: {:?}", config_path);
-        
-        let mut config: Config = toml::from_str(&content)
+            }))
+    }
+
+    /// Parse, expand and validate config file content
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)
             .map_err(|e| SwwwsError::Config(ConfigError::TomlParse {
                 message: e.to_string(),
             }))?;
@@ -247,6 +844,9 @@ impl Config {
         }
         
         log::debug!("Monitor behavior: {:?}", config.get_effective_monitor_behavior());
+
+        // Resolve ~ and $VARS before validation so errors show the real path
+        config.expand_paths()?;
         
         // Validate the configuration
         config.validate()?;
@@ -254,14 +854,76 @@ impl Config {
         Ok(config)
     }
     
-    fn config_path() -> Result<PathBuf> {
+    /// Config file to use when none is given explicitly: `$SWWWS_CONFIG` if set,
+    /// otherwise `$XDG_CONFIG_HOME/swwws/config.toml`
+    pub fn config_path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os("SWWWS_CONFIG").filter(|p| !p.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+
         let config_dir = dirs::config_dir()
-            .ok_or_else(|| SwwwsError::Config(ConfigError::NoConfigDir))?
+            .ok_or(SwwwsError::Config(ConfigError::NoConfigDir))?
             .join("swwws");
         
         Ok(config_dir.join("config.toml"))
     }
     
+    /// Expand `~` and environment variables in every section's `path`, including
+    /// the paths of its schedule, and in `swww_binary`, `state_file`, `viewer`
+    /// and `log_file`
+    pub fn expand_paths(&mut self) -> Result<()> {
+        let global = &mut self.global;
+        for (field, path) in [
+            ("swww_binary", &mut global.swww_binary),
+            ("state_file", &mut global.state_file),
+            ("viewer", &mut global.viewer),
+            ("log_file", &mut global.log_file),
+        ] {
+            if let Some(path) = path {
+                *path = expand::expand_path(path)
+                    .map_err(|variable| SwwwsError::Config(ConfigError::UndefinedVariable {
+                        field: format!("global.{}", field),
+                        variable,
+                    }))?;
+            }
+        }
+
+        let sections = std::iter::once(("any".to_string(), &mut self.any))
+            .chain(self.outputs.iter_mut().map(|(name, output)| (name.clone(), output)))
+            .chain(self.groups.iter_mut().map(|(name, group)| (format!("groups.{}", name), &mut group.settings)));
+
+        for (section, output) in sections {
+            let expand_field = |field: &str, path: &mut String| {
+                *path = expand::expand_path(path)
+                    .map_err(|variable| SwwwsError::Config(ConfigError::UndefinedVariable {
+                        field: format!("{}.{}", section, field),
+                        variable,
+                    }))?;
+                Ok::<(), SwwwsError>(())
+            };
+
+            if let Some(path) = &mut output.path {
+                expand_field("path", path)?;
+            }
+            if let Some(path) = &mut output.portrait_path {
+                expand_field("portrait_path", path)?;
+            }
+            if let Some(path) = &mut output.landscape_path {
+                expand_field("landscape_path", path)?;
+            }
+            if let Some(schedule) = &mut output.schedule {
+                if let Some(fallback) = &mut schedule.fallback {
+                    expand_field("schedule.fallback", fallback)?;
+                }
+                for window in &mut schedule.windows {
+                    expand_field("schedule.windows.path", &mut window.path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn get_output_config(&self, output_name: &str) -> OutputConfig {
         let mut config = self.any.clone();
         
@@ -272,20 +934,84 @@ impl Config {
         if let Some(output_config) = self.outputs.get(output_name) {
             config.merge_from_output(output_config);
         }
+
+        // Settings of a [groups.NAME] section win over the member's own
+        if matches!(self.monitor_behavior, MonitorBehavior::Grouped(_)) {
+            if let Some(group) = self.groups.values().find(|group| group.outputs.iter().any(|o| pattern::matches(o, output_name))) {
+                let mut group_config = group.settings.clone();
+                group_config.merge(&config);
+                config = group_config;
+            }
+        }
         
         config
     }
+
+    /// Every monitor group with its name: `[groups.NAME]` sections in name order,
+    /// then the lists of `monitor_groups` as `group_0`, `group_1`, ...
+    /// Members are as written, glob patterns included.
+    pub fn named_groups(&self) -> Vec<(String, Vec<String>)> {
+        let named = self.groups.iter()
+            .map(|(name, group)| (name.clone(), group.outputs.clone()));
+        let anonymous = self.monitor_groups.iter().flatten().enumerate()
+            .map(|(index, outputs)| (format!("group_{}", index), outputs.clone()));
+        named.chain(anonymous).collect()
+    }
     
+    /// Groups with glob members expanded against `live` outputs. Literal members
+    /// are kept as written. Fails if an output ends up in more than one group.
+    pub fn resolve_groups(&self, live: &[String]) -> Result<Vec<(String, Vec<String>)>> {
+        let mut owner: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+        let mut resolved = Vec::new();
+
+        for (name, entries) in self.named_groups() {
+            let mut outputs: Vec<String> = Vec::new();
+            for entry in &entries {
+                let matched: Vec<String> = if pattern::is_pattern(entry) {
+                    live.iter().filter(|output| pattern::matches(entry, output)).cloned().collect()
+                } else {
+                    vec![entry.clone()]
+                };
+                for output in matched {
+                    if outputs.contains(&output) {
+                        continue;
+                    }
+                    if let Some(other) = owner.insert(output.clone(), name.clone()) {
+                        return Err(SwwwsError::Config(ConfigError::Validation {
+                            message: format!("Output '{}' matches both group '{}' and group '{}'", output, other, name),
+                        }));
+                    }
+                    outputs.push(output);
+                }
+            }
+            resolved.push((name, outputs));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Switch to `behavior` at runtime. `groups` replace every configured group,
+    /// `[groups.NAME]` sections included; without them the configured groups stay.
+    pub fn set_monitor_behavior(&mut self, behavior: MonitorBehavior, groups: Option<Vec<Vec<String>>>) -> Result<()> {
+        self.monitor_behavior = behavior;
+        if let Some(groups) = groups {
+            self.monitor_groups = Some(groups);
+            self.groups.clear();
+        }
+        self.validate_monitor_behavior()
+    }
+
     pub fn get_effective_monitor_behavior(&self) -> MonitorBehavior {
-        match (&self.monitor_behavior, &self.monitor_groups) {
-            (MonitorBehavior::Grouped(_), Some(groups)) => {
-                MonitorBehavior::Grouped(groups.clone())
+        let groups = self.named_groups();
+        match &self.monitor_behavior {
+            MonitorBehavior::Grouped(_) if !groups.is_empty() => {
+                MonitorBehavior::Grouped(groups.into_iter().map(|(_, outputs)| outputs).collect())
             }
-            (MonitorBehavior::Grouped(_), None) => {
-                log::warn!("Monitor behavior set to 'Grouped' but no monitor_groups defined, falling back to Independent");
+            MonitorBehavior::Grouped(_) => {
+                log::warn!("Monitor behavior set to 'Grouped' but no monitor_groups or [groups] defined, falling back to Independent");
                 MonitorBehavior::Independent
             }
-            (behavior, _) => behavior.clone()
+            behavior => behavior.clone()
         }
     }
     
@@ -306,62 +1032,173 @@ impl Config {
         
         // Validate monitor behavior and groups
         self.validate_monitor_behavior()?;
+
+        let unknown_types = self.unknown_transition_types();
+        match unknown_types.first() {
+            Some((field, value)) if !self.global.allow_unknown_transitions => {
+                return Err(invalid_format(field, value, &format!(
+                    "one of {} or \"{}\"; set allow_unknown_transitions = true under [global] to pass other types to swww",
+                    transition::TRANSITION_TYPES.join(", "), transition::RANDOM_TRANSITION)));
+            }
+            _ => {
+                for warning in self.transition_warnings() {
+                    log::warn!("{}", warning);
+                }
+            }
+        }
+
+        for warning in self.timing_warnings() {
+            if self.global.strict {
+                return Err(SwwwsError::Config(ConfigError::Validation { message: warning }));
+            }
+            log::warn!("{}", warning);
+        }
         
         Ok(())
     }
+
+    /// `transition_type` values that are neither swww types nor "random", as
+    /// (field, value) in the order the sections are written
+    fn unknown_transition_types(&self) -> Vec<(String, String)> {
+        let mut names: Vec<&String> = self.outputs.keys().collect();
+        names.sort();
+        let sections = std::iter::once(("any".to_string(), &self.any))
+            .chain(names.into_iter().map(|name| (name.clone(), &self.outputs[name])))
+            .chain(self.groups.iter().map(|(name, group)| (format!("groups.{}", name), &group.settings)))
+            .map(|(section, output)| (section, Some(output.transition_type.as_str()), &output.manual_transition));
+
+        std::iter::once(("global".to_string(), Some(self.global.transition_type.as_str()), &self.global.manual_transition))
+            .chain(sections)
+            .flat_map(|(section, transition_type, manual)| {
+                let manual_type = manual.as_ref().and_then(|manual| manual.transition_type.as_deref());
+                [
+                    transition_type.map(|value| (format!("{}.transition_type", section), value)),
+                    manual_type.map(|value| (format!("{}.manual_transition.transition_type", section), value)),
+                ]
+            })
+            .flatten()
+            .filter(|(_, value)| *value != transition::RANDOM_TRANSITION && !transition::is_known_type(value))
+            .map(|(field, value)| (field, value.to_string()))
+            .collect()
+    }
+
+    /// Unknown transition types let through by allow_unknown_transitions
+    pub fn transition_warnings(&self) -> Vec<String> {
+        if !self.global.allow_unknown_transitions {
+            return Vec::new();
+        }
+        self.unknown_transition_types()
+            .into_iter()
+            .map(|(field, value)| format!("{} {:?} is not a known swww transition; passing it to swww as is", field, value))
+            .collect()
+    }
+
+    /// Sections whose duration leaves less than one transition of still time
+    /// between changes. Errors under `strict`, warnings otherwise.
+    pub fn timing_warnings(&self) -> Vec<String> {
+        let mut any = self.any.clone();
+        any.merge_from_global(&self.global);
+        let mut sections: Vec<(&str, OutputConfig)> = vec![("any", any)];
+        let mut names: Vec<&String> = self.outputs.keys().collect();
+        names.sort();
+        sections.extend(names.into_iter().map(|name| (name.as_str(), self.get_output_config(name))));
+
+        sections
+            .into_iter()
+            .filter(|(_, config)| !config.is_static() && config.duration.min() < config.transition_duration * 2)
+            .map(|(section, config)| format!(
+                "[{}] duration {:?} is less than twice transition_duration {:?}; changes will wait for each transition to finish",
+                section, config.duration, config.transition_duration,
+            ))
+            .collect()
+    }
     
     fn validate_monitor_behavior(&self) -> Result<()> {
-        if let Some(groups) = &self.monitor_groups {
-            // Check that groups is not empty
-            if groups.is_empty() {
+        // Check that groups is not empty
+        if self.monitor_groups.as_ref().is_some_and(|groups| groups.is_empty()) {
+            return Err(SwwwsError::Config(ConfigError::Validation {
+                message: "monitor_groups cannot be empty".to_string(),
+            }));
+        }
+
+        for (name, group) in &self.groups {
+            group.settings.validate()
+                .map_err(|e| SwwwsError::Config(ConfigError::Validation {
+                    message: format!("Group '{}': {}", name, e),
+                }))?;
+        }
+
+        let groups = self.named_groups();
+
+        // Check that no group is empty
+        for (name, outputs) in &groups {
+            if outputs.is_empty() {
+                return Err(SwwwsError::Config(ConfigError::Validation {
+                    message: format!("Group '{}' is empty", name),
+                }));
+            }
+        }
+
+        // Check for duplicate outputs across groups
+        let mut all_outputs = std::collections::HashSet::new();
+        for output in groups.iter().flat_map(|(_, outputs)| outputs) {
+            if !all_outputs.insert(output.clone()) {
                 return Err(SwwwsError::Config(ConfigError::Validation {
-                    message: "monitor_groups cannot be empty".to_string(),
+                    message: format!("Output '{}' appears in multiple groups", output),
                 }));
             }
-            
-            // Check that no group is empty
-            for (i, group) in groups.iter().enumerate() {
-                if group.is_empty() {
+        }
+
+        // A literal name caught by another group's pattern would be in both.
+        // Overlap between two patterns is only known once outputs are connected,
+        // and is rejected by resolve_groups.
+        for (name, outputs) in &groups {
+            for output in outputs.iter().filter(|output| !pattern::is_pattern(output)) {
+                let other = groups.iter()
+                    .filter(|(other, _)| other != name)
+                    .find(|(_, entries)| entries.iter().any(|entry| pattern::is_pattern(entry) && pattern::matches(entry, output)));
+                if let Some((other, _)) = other {
                     return Err(SwwwsError::Config(ConfigError::Validation {
-                        message: format!("Group {} is empty", i),
+                        message: format!("Output '{}' in group '{}' also matches a pattern of group '{}'", output, name, other),
                     }));
                 }
             }
-            
-            // Check for duplicate outputs across groups
-            let mut all_outputs = std::collections::HashSet::new();
-            for (_i, group) in groups.iter().enumerate() {
-                for output in group {
-                    if !all_outputs.insert(output.clone()) {
-                        return Err(SwwwsError::Config(ConfigError::Validation {
-                            message: format!("Output '{}' appears in multiple groups", output),
-                        }));
-                    }
-                }
-            }
-            
+        }
+
+        match (groups.is_empty(), matches!(self.monitor_behavior, MonitorBehavior::Grouped(_))) {
             // If monitor_behavior is not Grouped but groups are defined, warn
-            if !matches!(self.monitor_behavior, MonitorBehavior::Grouped(_)) {
-                log::warn!("monitor_groups defined but monitor_behavior is not 'Grouped'");
-            }
-        } else if matches!(self.monitor_behavior, MonitorBehavior::Grouped(_)) {
+            (false, false) => log::warn!("monitor groups defined but monitor_behavior is not 'Grouped'"),
             // monitor_behavior is Grouped but no groups defined - this will fall back to Independent
-            log::warn!("monitor_behavior is 'Grouped' but no monitor_groups defined");
+            (true, true) => log::warn!("monitor_behavior is 'Grouped' but no monitor_groups or [groups] defined"),
+            _ => {}
         }
         
         Ok(())
     }
 }
 
-impl GlobalConfig {
-    fn validate(&self) -> Result<()> {
-        // Validate duration
-        if self.duration < Duration::from_secs(1) {
+/// A duration must be at least a second; a random range must also have `min <= max`
+fn validate_interval(interval: Interval) -> Result<()> {
+    if interval.min() < Duration::from_secs(1) {
+        return Err(SwwwsError::Config(ConfigError::InvalidValue {
+            field: "duration".to_string(),
+            value: format!("{:?}", interval.min()),
+        }));
+    }
+    if let Interval::Random { min, max } = interval {
+        if min > max {
             return Err(SwwwsError::Config(ConfigError::InvalidValue {
                 field: "duration".to_string(),
-                value: format!("{:?}", self.duration),
+                value: format!("min {:?} is greater than max {:?}", min, max),
             }));
         }
+    }
+    Ok(())
+}
+
+impl GlobalConfig {
+    fn validate(&self) -> Result<()> {
+        validate_interval(self.duration)?;
         
         // Validate queue size
         if self.queue_size == 0 {
@@ -394,15 +1231,149 @@ impl GlobalConfig {
                 value: format!("{:?}", self.transition_duration),
             }));
         }
+
+        // Validate transition fps (swww takes a u8)
+        if self.transition_fps == 0 || self.transition_fps > 255 {
+            return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                field: "transition_fps".to_string(),
+                value: self.transition_fps.to_string(),
+            }));
+        }
+
+        validate_transition_strings(
+            "",
+            Some(&self.transition_pos),
+            Some(&self.transition_bezier),
+            Some(&self.transition_wave),
+        )?;
+        validate_fill_color(&self.fill_color)?;
+
+        if let Some(manual) = &self.manual_transition {
+            manual.validate()?;
+        }
+
+        validate_transition_types(&self.transition_types)?;
+        validate_max_depth(self.max_depth)?;
+
+        if self.max_concurrent_changes == 0 {
+            return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                field: "max_concurrent_changes".to_string(),
+                value: self.max_concurrent_changes.to_string(),
+            }));
+        }
+        if let Some(level) = self.log_level.as_deref().filter(|level| level.parse::<log::LevelFilter>().is_err()) {
+            return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                field: "log_level".to_string(),
+                value: level.to_string(),
+            }));
+        }
+        if self.log_max_size == 0 {
+            return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                field: "log_max_size".to_string(),
+                value: self.log_max_size.to_string(),
+            }));
+        }
         
         Ok(())
     }
 }
 
 impl OutputConfig {
+    /// Settings for the swww command builder, with the manual transition
+    /// override applied when the change was requested by the user. A "random"
+    /// transition type is resolved here, so every call may pick a different one.
+    /// Wake re-applies never animate.
+    pub fn command_config(&self, reason: ChangeReason) -> CommandConfig {
+        let manual = match (reason, &self.manual_transition) {
+            (ChangeReason::Manual, Some(manual)) => manual.clone(),
+            _ => TransitionOverride::default(),
+        };
+
+        let mut command = CommandConfig {
+            path: self.path.as_ref().map(PathBuf::from),
+            mode: None,
+            transition_type: Some(manual.transition_type.unwrap_or_else(|| self.transition_type.clone())),
+            transition_step: Some(manual.transition_step.unwrap_or(self.transition_step) as u8),
+            transition_angle: Some(manual.transition_angle.unwrap_or(self.transition_angle)),
+            transition_pos: Some(manual.transition_pos.unwrap_or_else(|| self.transition_pos.clone())),
+            transition_bezier: Some(manual.transition_bezier.unwrap_or_else(|| self.transition_bezier.clone())),
+            transition_fps: Some(manual.transition_fps.unwrap_or(self.transition_fps) as u8),
+            resize: Some(self.resize.clone()),
+            fill_color: Some(self.fill_color.clone()),
+            filter: Some(self.filter.clone()),
+            invert_y: Some(self.invert_y),
+            transition_wave: Some(manual.transition_wave.unwrap_or_else(|| self.transition_wave.clone())),
+        };
+        transition::resolve_random(&mut command, self.transition_types.as_deref().unwrap_or_default());
+        if reason == ChangeReason::Wake {
+            command.transition_type = Some("none".to_string());
+        }
+        command
+    }
+
+    /// Schedule profile at `time`. Without a schedule this is always `path`.
+    pub fn profile_at(&self, time: NaiveTime) -> Profile {
+        match &self.schedule {
+            Some(schedule) => schedule.profile_at(time, self.path.as_deref()),
+            None => Profile {
+                name: DEFAULT_PROFILE.to_string(),
+                path: self.path.clone(),
+            },
+        }
+    }
+
+    /// This config with `path` replaced by `portrait_path` or `landscape_path`
+    /// when one is set for `orientation`
+    pub fn oriented(mut self, orientation: Option<Orientation>) -> Self {
+        let oriented_path = match orientation {
+            Some(Orientation::Portrait) => self.portrait_path.clone(),
+            Some(Orientation::Landscape) => self.landscape_path.clone(),
+            None => None,
+        };
+        if oriented_path.is_some() {
+            self.path = oriented_path;
+        }
+        self
+    }
+
+    /// Whether this output keeps one wallpaper instead of rotating: `duration = "never"`
+    /// or `"0"`, or a `path` that points at a single image file rather than a playlist
+    pub fn is_static(&self) -> bool {
+        self.duration == Duration::ZERO
+            || self.path.as_deref().is_some_and(|path| {
+                Path::new(path).is_file() && !ImageDiscovery::is_playlist(Path::new(path))
+            })
+    }
+
+    /// How image discovery walks this output's wallpaper directory. A
+    /// `min_resolution` of "auto" goes by `output_size`, and keeps every image
+    /// while that is unknown.
+    pub fn discovery_options(&self, output_size: Option<(u32, u32)>) -> DiscoveryOptions {
+        DiscoveryOptions {
+            follow_symlinks: self.follow_symlinks,
+            max_depth: self.max_depth.filter(|depth| *depth > 0),
+            min_resolution: self.min_resolution.and_then(|min| min.resolve(output_size)),
+            keep_unknown_resolution: self.keep_unknown_resolution,
+            skip_hidden: self.skip_hidden,
+            dedupe: self.dedupe,
+        }
+    }
+
+    /// Schedule profile for the current local time
+    pub fn active_profile(&self) -> Profile {
+        self.profile_at(chrono::Local::now().time())
+    }
+
     pub fn merge(&mut self, other: &OutputConfig) {
+        // Orientation paths only come along with the path they override
         if self.path.is_none() {
             self.path = other.path.clone();
+            if self.portrait_path.is_none() {
+                self.portrait_path = other.portrait_path.clone();
+            }
+            if self.landscape_path.is_none() {
+                self.landscape_path = other.landscape_path.clone();
+            }
         }
         if self.duration == default_duration() {
             self.duration = other.duration;
@@ -410,6 +1381,27 @@ impl OutputConfig {
         if self.queue_size == default_queue_size() {
             self.queue_size = other.queue_size;
         }
+        if self.history_size == default_history_size() {
+            self.history_size = other.history_size;
+        }
+        if self.follow_symlinks == default_follow_symlinks() {
+            self.follow_symlinks = other.follow_symlinks;
+        }
+        if self.max_depth.is_none() {
+            self.max_depth = other.max_depth;
+        }
+        if self.min_resolution.is_none() {
+            self.min_resolution = other.min_resolution;
+        }
+        if self.keep_unknown_resolution == default_keep_unknown_resolution() {
+            self.keep_unknown_resolution = other.keep_unknown_resolution;
+        }
+        if self.skip_hidden == default_skip_hidden() {
+            self.skip_hidden = other.skip_hidden;
+        }
+        if self.dedupe == default_dedupe() {
+            self.dedupe = other.dedupe;
+        }
         if self.sorting == default_sorting() {
             self.sorting = other.sorting.clone();
         }
@@ -431,6 +1423,9 @@ impl OutputConfig {
         if self.transition_duration == default_transition_duration() {
             self.transition_duration = other.transition_duration;
         }
+        if self.transition_fps == default_transition_fps() {
+            self.transition_fps = other.transition_fps;
+        }
         if self.resize == default_resize() {
             self.resize = other.resize.clone();
         }
@@ -446,6 +1441,34 @@ impl OutputConfig {
         if self.transition_wave == default_transition_wave() {
             self.transition_wave = other.transition_wave.clone();
         }
+        if self.transition_types.is_none() {
+            self.transition_types = other.transition_types.clone();
+        }
+        if self.collation == default_collation() {
+            self.collation = other.collation;
+        }
+        if self.no_repeat_window.is_none() {
+            self.no_repeat_window = other.no_repeat_window;
+        }
+        if self.start_offset.is_none() {
+            self.start_offset = other.start_offset;
+        }
+        if self.reapply_on_wake == default_reapply_on_wake() {
+            self.reapply_on_wake = other.reapply_on_wake;
+        }
+        if self.offset == default_offset() {
+            self.offset = other.offset;
+        }
+        if self.on_change.is_none() {
+            self.on_change = other.on_change.clone();
+        }
+        if self.enabled == default_enabled() {
+            self.enabled = other.enabled;
+        }
+        if self.schedule.is_none() {
+            self.schedule = other.schedule.clone();
+        }
+        self.manual_transition = layer_overrides(&self.manual_transition, &other.manual_transition);
     }
     
     pub fn merge_from_global(&mut self, global: &GlobalConfig) {
@@ -455,6 +1478,27 @@ impl OutputConfig {
         if self.queue_size == default_queue_size() {
             self.queue_size = global.queue_size;
         }
+        if self.history_size == default_history_size() {
+            self.history_size = global.history_size;
+        }
+        if self.follow_symlinks == default_follow_symlinks() {
+            self.follow_symlinks = global.follow_symlinks;
+        }
+        if self.max_depth.is_none() {
+            self.max_depth = global.max_depth;
+        }
+        if self.min_resolution.is_none() {
+            self.min_resolution = global.min_resolution;
+        }
+        if self.keep_unknown_resolution == default_keep_unknown_resolution() {
+            self.keep_unknown_resolution = global.keep_unknown_resolution;
+        }
+        if self.skip_hidden == default_skip_hidden() {
+            self.skip_hidden = global.skip_hidden;
+        }
+        if self.dedupe == default_dedupe() {
+            self.dedupe = global.dedupe;
+        }
         if self.sorting == default_sorting() {
             self.sorting = global.sorting.clone();
         }
@@ -476,6 +1520,9 @@ impl OutputConfig {
         if self.transition_duration == default_transition_duration() {
             self.transition_duration = global.transition_duration;
         }
+        if self.transition_fps == default_transition_fps() {
+            self.transition_fps = global.transition_fps;
+        }
         if self.resize == default_resize() {
             self.resize = global.resize.clone();
         }
@@ -491,12 +1538,42 @@ impl OutputConfig {
         if self.transition_wave == default_transition_wave() {
             self.transition_wave = global.transition_wave.clone();
         }
+        if self.transition_types.is_none() {
+            self.transition_types = global.transition_types.clone();
+        }
+        if self.collation == default_collation() {
+            self.collation = global.collation;
+        }
+        if self.no_repeat_window.is_none() {
+            self.no_repeat_window = global.no_repeat_window;
+        }
+        if self.start_offset.is_none() {
+            self.start_offset = global.start_offset;
+        }
+        if self.reapply_on_wake == default_reapply_on_wake() {
+            self.reapply_on_wake = global.reapply_on_wake;
+        }
+        if self.on_change.is_none() {
+            self.on_change = global.on_change.clone();
+        }
+        self.manual_transition = layer_overrides(&self.manual_transition, &global.manual_transition);
     }
     
     pub fn merge_from_output(&mut self, other: &OutputConfig) {
         // Always override with output-specific values
         self.duration = other.duration;
         self.queue_size = other.queue_size;
+        self.history_size = other.history_size;
+        self.follow_symlinks = other.follow_symlinks;
+        if other.max_depth.is_some() {
+            self.max_depth = other.max_depth;
+        }
+        if other.min_resolution.is_some() {
+            self.min_resolution = other.min_resolution;
+        }
+        self.keep_unknown_resolution = other.keep_unknown_resolution;
+        self.skip_hidden = other.skip_hidden;
+        self.dedupe = other.dedupe;
         self.sorting = other.sorting.clone();
         self.transition_type = other.transition_type.clone();
         self.transition_step = other.transition_step;
@@ -504,24 +1581,51 @@ impl OutputConfig {
         self.transition_pos = other.transition_pos.clone();
         self.transition_bezier = other.transition_bezier.clone();
         self.transition_duration = other.transition_duration;
+        self.transition_fps = other.transition_fps;
         self.resize = other.resize.clone();
         self.fill_color = other.fill_color.clone();
         self.filter = other.filter.clone();
         self.invert_y = other.invert_y;
         self.transition_wave = other.transition_wave.clone();
+        if other.transition_types.is_some() {
+            self.transition_types = other.transition_types.clone();
+        }
+        self.collation = other.collation;
+        if other.no_repeat_window.is_some() {
+            self.no_repeat_window = other.no_repeat_window;
+        }
+        if other.start_offset.is_some() {
+            self.start_offset = other.start_offset;
+        }
+        self.reapply_on_wake = other.reapply_on_wake;
+        self.offset = other.offset;
+        if other.on_change.is_some() {
+            self.on_change = other.on_change.clone();
+        }
+        self.enabled = other.enabled;
+        if other.schedule.is_some() {
+            self.schedule = other.schedule.clone();
+        }
+        self.manual_transition = layer_overrides(&other.manual_transition, &self.manual_transition);
         // Always override path if it's set
         if other.path.is_some() {
             self.path = other.path.clone();
+            // A section's own path also replaces orientation paths set further up
+            self.portrait_path = None;
+            self.landscape_path = None;
+        }
+        if other.portrait_path.is_some() {
+            self.portrait_path = other.portrait_path.clone();
+        }
+        if other.landscape_path.is_some() {
+            self.landscape_path = other.landscape_path.clone();
         }
     }
     
     fn validate(&self) -> Result<()> {
-        // Validate duration
-        if self.duration < Duration::from_secs(1) {
-            return Err(SwwwsError::Config(ConfigError::InvalidValue {
-                field: "duration".to_string(),
-                value: format!("{:?}", self.duration),
-            }));
+        // Zero is a static wallpaper
+        if self.duration != Duration::ZERO {
+            validate_interval(self.duration)?;
         }
         
         // Validate queue size
@@ -555,7 +1659,37 @@ impl OutputConfig {
                 value: format!("{:?}", self.transition_duration),
             }));
         }
-        
+
+        // Validate transition fps (swww takes a u8)
+        if self.transition_fps == 0 || self.transition_fps > 255 {
+            return Err(SwwwsError::Config(ConfigError::InvalidValue {
+                field: "transition_fps".to_string(),
+                value: self.transition_fps.to_string(),
+            }));
+        }
+
+        validate_transition_strings(
+            "",
+            Some(&self.transition_pos),
+            Some(&self.transition_bezier),
+            Some(&self.transition_wave),
+        )?;
+        validate_fill_color(&self.fill_color)?;
+
+        if let Some(manual) = &self.manual_transition {
+            manual.validate()?;
+        }
+
+        validate_transition_types(&self.transition_types)?;
+        validate_max_depth(self.max_depth)?;
+
+        if let Some(schedule) = &self.schedule {
+            schedule.validate().map_err(|message| SwwwsError::Config(ConfigError::InvalidValue {
+                field: "schedule".to_string(),
+                value: message,
+            }))?;
+        }
+
         Ok(())
     }
 }
@@ -564,8 +1698,17 @@ impl Clone for OutputConfig {
     fn clone(&self) -> Self {
         Self {
             path: self.path.clone(),
+            portrait_path: self.portrait_path.clone(),
+            landscape_path: self.landscape_path.clone(),
             duration: self.duration,
             queue_size: self.queue_size,
+            history_size: self.history_size,
+            follow_symlinks: self.follow_symlinks,
+            max_depth: self.max_depth,
+            min_resolution: self.min_resolution,
+            keep_unknown_resolution: self.keep_unknown_resolution,
+            skip_hidden: self.skip_hidden,
+            dedupe: self.dedupe,
             sorting: self.sorting.clone(),
             transition_type: self.transition_type.clone(),
             transition_step: self.transition_step,
@@ -573,11 +1716,22 @@ impl Clone for OutputConfig {
             transition_pos: self.transition_pos.clone(),
             transition_bezier: self.transition_bezier.clone(),
             transition_duration: self.transition_duration,
+            transition_fps: self.transition_fps,
             resize: self.resize.clone(),
             fill_color: self.fill_color.clone(),
             filter: self.filter.clone(),
             invert_y: self.invert_y,
             transition_wave: self.transition_wave.clone(),
+            transition_types: self.transition_types.clone(),
+            collation: self.collation,
+            no_repeat_window: self.no_repeat_window,
+            start_offset: self.start_offset,
+            reapply_on_wake: self.reapply_on_wake,
+            offset: self.offset,
+            on_change: self.on_change.clone(),
+            enabled: self.enabled,
+            schedule: self.schedule.clone(),
+            manual_transition: self.manual_transition.clone(),
         }
     }
 }
@@ -587,6 +1741,13 @@ impl Clone for GlobalConfig {
         Self {
             duration: self.duration,
             queue_size: self.queue_size,
+            history_size: self.history_size,
+            follow_symlinks: self.follow_symlinks,
+            max_depth: self.max_depth,
+            min_resolution: self.min_resolution,
+            keep_unknown_resolution: self.keep_unknown_resolution,
+            skip_hidden: self.skip_hidden,
+            dedupe: self.dedupe,
             sorting: self.sorting.clone(),
             transition_type: self.transition_type.clone(),
             transition_step: self.transition_step,
@@ -594,11 +1755,38 @@ impl Clone for GlobalConfig {
             transition_pos: self.transition_pos.clone(),
             transition_bezier: self.transition_bezier.clone(),
             transition_duration: self.transition_duration,
+            transition_fps: self.transition_fps,
             resize: self.resize.clone(),
             fill_color: self.fill_color.clone(),
             filter: self.filter.clone(),
             invert_y: self.invert_y,
             transition_wave: self.transition_wave.clone(),
+            transition_types: self.transition_types.clone(),
+            allow_unknown_transitions: self.allow_unknown_transitions,
+            collation: self.collation,
+            no_repeat_window: self.no_repeat_window,
+            start_offset: self.start_offset,
+            reapply_on_wake: self.reapply_on_wake,
+            on_change: self.on_change.clone(),
+            on_change_once: self.on_change_once,
+            distinct_across_outputs: self.distinct_across_outputs,
+            max_concurrent_changes: self.max_concurrent_changes,
+            watch_paths: self.watch_paths,
+            clear_on_exit: self.clear_on_exit,
+            on_resume: self.on_resume,
+            change_log_size: self.change_log_size,
+            random_seed: self.random_seed,
+            strict: self.strict,
+            swww_binary: self.swww_binary.clone(),
+            state_file: self.state_file.clone(),
+            viewer: self.viewer.clone(),
+            log_file: self.log_file.clone(),
+            log_level: self.log_level.clone(),
+            log_max_size: self.log_max_size,
+            log_keep: self.log_keep,
+            dry_run: self.dry_run,
+            startup_timeout: self.startup_timeout,
+            manual_transition: self.manual_transition.clone(),
         }
     }
 }
@@ -624,6 +1812,56 @@ mod tests {
         assert_eq!(config.global.transition_duration, Duration::from_millis(500));
     }
 
+    #[test]
+    fn test_duration_numbers_and_strings_mix() {
+        let toml_str = r#"
+            [global]
+            duration = 300
+            transition_duration = 0.75
+
+            ["DP-1"]
+            duration = "10m"
+            transition_duration = 2
+
+            ["DP-2"]
+            duration = 90.5
+            transition_duration = "250ms"
+
+            ["DP-3"]
+            duration = { min = 60, max = "5m" }
+
+            ["DP-4"]
+            duration = 0
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.global.duration, Duration::from_secs(300));
+        assert_eq!(config.global.transition_duration, Duration::from_millis(750));
+        assert_eq!(config.get_output_config("DP-1").duration, Duration::from_secs(600));
+        assert_eq!(config.get_output_config("DP-1").transition_duration, Duration::from_secs(2));
+        assert_eq!(config.get_output_config("DP-2").duration, Duration::from_millis(90_500));
+        assert_eq!(config.get_output_config("DP-2").transition_duration, Duration::from_millis(250));
+        assert_eq!(config.get_output_config("DP-3").duration, Interval::Random {
+            min: Duration::from_secs(60),
+            max: Duration::from_secs(300),
+        });
+        assert!(config.get_output_config("DP-4").is_static());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_bad_numeric_durations() {
+        let parse_error = |toml_str: &str| toml::from_str::<Config>(toml_str).unwrap_err().to_string();
+        assert!(parse_error("[global]\nduration = -5\n").contains("invalid duration -5"));
+        assert!(parse_error("[global]\ntransition_duration = -0.5\n").contains("invalid duration -0.5"));
+        assert!(parse_error("[global]\ntransition_duration = true\n").contains("a number of seconds or a duration string"));
+
+        // Numbers that parse but don't validate show the length they came to
+        let config: Config = toml::from_str("[global]\nduration = 0.5\n").unwrap();
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("duration: 500ms"), "{}", message);
+    }
+
     #[test]
     fn test_config_validation() {
         let mut config = Config::default();
@@ -632,19 +1870,170 @@ mod tests {
         assert!(config.validate().is_ok());
         
         // Test invalid duration
-        config.global.duration = Duration::from_secs(0);
+        config.global.duration = Duration::from_secs(0).into();
         assert!(config.validate().is_err());
         
         // Reset and test invalid queue size
-        config.global.duration = Duration::from_secs(300);
+        config.global.duration = Duration::from_secs(300).into();
         config.global.queue_size = 0;
         assert!(config.validate().is_err());
+
+        // Reset and test a zero concurrency limit
+        config.global.queue_size = 10;
+        config.global.max_concurrent_changes = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_static_duration() {
+        let toml_str = r#"
+            [any]
+            path = "/test/path"
+
+            ["DP-1"]
+            duration = "never"
+
+            ["DP-2"]
+            duration = "0"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.validate().expect("static outputs should validate");
+        assert!(config.get_output_config("DP-1").is_static());
+        assert!(config.get_output_config("DP-2").is_static());
+        assert!(!config.get_output_config("HDMI-A-1").is_static());
+        assert!(config.timing_warnings().is_empty());
+
+        // Still no sub-second rotation
+        let config: Config = toml::from_str("[\"DP-1\"]\nduration = \"500ms\"\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_enabled() {
+        let toml_str = r#"
+            [any]
+            path = "/walls/any"
+            enabled = false
+
+            ["DP-1"]
+            path = "/walls/dp1"
+
+            ["HDMI-A-1"]
+            enabled = false
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        // A section of its own turns an output back on unless it says otherwise
+        assert!(config.get_output_config("DP-1").enabled);
+        assert!(!config.get_output_config("HDMI-A-1").enabled);
+        assert!(!config.get_output_config("DP-2").enabled);
+        assert!(Config::default().get_output_config("DP-2").enabled);
+    }
+
+    #[test]
+    fn test_single_file_path_is_static() {
+        let temp_dir = tempdir().unwrap();
+        let image = temp_dir.path().join("only.png");
+        std::fs::write(&image, "png").unwrap();
+
+        let mut config = Config::default();
+        config.any.path = Some(temp_dir.path().to_string_lossy().to_string());
+        config.outputs.insert("DP-1".to_string(), OutputConfig {
+            path: Some(image.to_string_lossy().to_string()),
+            ..Default::default()
+        });
+
+        assert!(config.get_output_config("DP-1").is_static());
+        assert!(!config.get_output_config("HDMI-A-1").is_static());
+        // The duration still has to be valid on its own
+        config.validate().unwrap();
+
+        // A playlist is a file too, but it rotates
+        let playlist = temp_dir.path().join("list.txt");
+        std::fs::write(&playlist, image.to_string_lossy().as_bytes()).unwrap();
+        config.outputs.get_mut("DP-1").unwrap().path = Some(playlist.to_string_lossy().to_string());
+        assert!(!config.get_output_config("DP-1").is_static());
+    }
+
+    #[test]
+    fn test_orientation_paths() {
+        let toml_str = r#"
+            [any]
+            path = "/walls/any"
+            portrait_path = "/walls/tall"
+
+            ["DP-1"]
+            landscape_path = "/walls/wide"
+
+            ["DP-2"]
+            path = "/walls/dp2"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let path = |output: &str, orientation| config.get_output_config(output).oriented(orientation).path;
+        assert_eq!(path("HDMI-A-1", Some(Orientation::Portrait)).as_deref(), Some("/walls/tall"));
+        assert_eq!(path("HDMI-A-1", Some(Orientation::Landscape)).as_deref(), Some("/walls/any"));
+        assert_eq!(path("HDMI-A-1", None).as_deref(), Some("/walls/any"));
+        assert_eq!(path("DP-1", Some(Orientation::Landscape)).as_deref(), Some("/walls/wide"));
+        assert_eq!(path("DP-1", Some(Orientation::Portrait)).as_deref(), Some("/walls/tall"));
+        // An output's own path beats an inherited portrait_path
+        assert_eq!(path("DP-2", Some(Orientation::Portrait)).as_deref(), Some("/walls/dp2"));
+    }
+
+    #[test]
+    fn test_random_duration_range() {
+        let toml_str = r#"
+            [global]
+            duration = { min = "3m", max = "10m" }
+
+            ["DP-1"]
+            duration = "1m"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.validate().expect("range should validate");
+        let range = Interval::Random { min: Duration::from_secs(180), max: Duration::from_secs(600) };
+        assert_eq!(config.get_output_config("HDMI-A-1").duration, range);
+        assert_eq!(config.get_output_config("DP-1").duration, Duration::from_secs(60));
+
+        // min must not exceed max, and both must be at least a second
+        let config: Config = toml::from_str("[global]\nduration = { min = \"10m\", max = \"3m\" }\n").unwrap();
+        assert!(config.validate().is_err());
+        let config: Config = toml::from_str("[\"DP-1\"]\nduration = { min = \"500ms\", max = \"3m\" }\n").unwrap();
+        assert!(config.validate().is_err());
+        assert!(toml::from_str::<Config>("[global]\nduration = { min = \"3m\", max = \"soon\" }\n").is_err());
+    }
+
+    #[test]
+    fn test_duration_shorter_than_transitions() {
+        let toml_str = r#"
+            ["DP-1"]
+            path = "/test/path"
+            duration = "5s"
+            transition_duration = "3s"
+
+            ["DP-2"]
+            path = "/test/path"
+            duration = "10s"
+            transition_duration = "3s"
+        "#;
+
+        let mut config: Config = toml::from_str(toml_str).unwrap();
+        let warnings = config.timing_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("[DP-1]"));
+
+        // Only a warning unless strict
+        assert!(config.validate().is_ok());
+        config.global.strict = true;
+        assert!(config.validate().is_err());
     }
 
     #[test]
     fn test_output_config_merge() {
         let global = GlobalConfig {
-            duration: Duration::from_secs(300),
+            duration: Duration::from_secs(300).into(),
             queue_size: 10,
             sorting: Sorting::Random,
             transition_type: "wipe".to_string(),
@@ -653,11 +2042,12 @@ mod tests {
             transition_pos: "center".to_string(),
             transition_bezier: "0.25,0.1,0.25,1".to_string(),
             transition_duration: Duration::from_millis(500),
+            ..Default::default()
         };
         
         let mut output = OutputConfig {
             path: Some("/test/path".to_string()),
-            duration: Duration::from_secs(600), // Override global
+            duration: Duration::from_secs(600).into(), // Override global
             queue_size: 5, // Override global
             sorting: Sorting::Ascending, // Override global
             transition_type: "fade".to_string(), // Override global
@@ -666,6 +2056,8 @@ mod tests {
             transition_pos: "top-left".to_string(), // Override global
             transition_bezier: "0.5,0.5,0.5,0.5".to_string(), // Override global
             transition_duration: Duration::from_millis(1000), // Override global
+            transition_fps: 144, // Override global
+            ..Default::default()
         };
         
         output.merge_from_global(&global);
@@ -680,12 +2072,13 @@ mod tests {
         assert_eq!(output.transition_pos, "top-left");
         assert_eq!(output.transition_bezier, "0.5,0.5,0.5,0.5");
         assert_eq!(output.transition_duration, Duration::from_millis(1000));
+        assert_eq!(output.transition_fps, 144);
     }
 
     #[test]
     fn test_output_config_merge_defaults() {
         let global = GlobalConfig {
-            duration: Duration::from_secs(300),
+            duration: Duration::from_secs(300).into(),
             queue_size: 10,
             sorting: Sorting::Random,
             transition_type: "wipe".to_string(),
@@ -694,6 +2087,7 @@ mod tests {
             transition_pos: "center".to_string(),
             transition_bezier: "0.25,0.1,0.25,1".to_string(),
             transition_duration: Duration::from_millis(500),
+            ..Default::default()
         };
         
         let mut output = OutputConfig::default();
@@ -709,6 +2103,433 @@ mod tests {
         assert_eq!(output.transition_pos, "center");
         assert_eq!(output.transition_bezier, "0.25,0.1,0.25,1");
         assert_eq!(output.transition_duration, Duration::from_millis(500));
+        assert_eq!(output.transition_fps, 30);
+    }
+
+    #[test]
+    fn test_transition_fps_validation() {
+        let mut config = Config::default();
+        assert_eq!(config.global.transition_fps, 30);
+
+        config.global.transition_fps = 0;
+        assert!(config.validate().is_err());
+
+        config.global.transition_fps = 256;
+        assert!(config.validate().is_err());
+
+        config.global.transition_fps = 144;
+        assert!(config.validate().is_ok());
+
+        let toml_str = r#"
+            [global]
+            transition_fps = 144
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            transition_fps = 60
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.get_output_config("DP-1").transition_fps, 144);
+        assert_eq!(config.get_output_config("HDMI-A-1").transition_fps, 60);
+    }
+
+    #[test]
+    fn test_transition_value_validation() {
+        let error = |toml_str: &str| {
+            let config: Config = toml::from_str(toml_str).unwrap();
+            config.validate().unwrap_err().to_string()
+        };
+
+        let message = error("[global]\ntransition_type = \"wip\"\n");
+        assert!(message.contains("global.transition_type: \"wip\" (expected one of none,"), "{}", message);
+        let message = error("[\"DP-1\"]\nfill_color = \"black\"\n");
+        assert!(message.contains("Output 'DP-1'") && message.contains("fill_color: \"black\" (expected a 6-digit hex color"), "{}", message);
+        let message = error("[global]\ntransition_bezier = \"0.25,0.1,0.25\"\n");
+        assert!(message.contains("transition_bezier: \"0.25,0.1,0.25\" (expected four comma-separated numbers"), "{}", message);
+        let message = error("[global]\ntransition_wave = \"20x20\"\n");
+        assert!(message.contains("transition_wave: \"20x20\""), "{}", message);
+        let message = error("[global]\ntransition_pos = \"middle\"\n");
+        assert!(message.contains("transition_pos: \"middle\" (expected one of center,"), "{}", message);
+        let message = error("[global.manual_transition]\ntransition_pos = \"0.5\"\n");
+        assert!(message.contains("manual_transition.transition_pos"), "{}", message);
+        let message = error("[any.manual_transition]\ntransition_type = \"wobble\"\n");
+        assert!(message.contains("any.manual_transition.transition_type"), "{}", message);
+
+        let valid: Config = toml::from_str(r#"
+            [global]
+            transition_type = "random"
+            transition_pos = "0.25, 0.75"
+            transition_bezier = "0.54,0,0.34,0.99"
+            fill_color = "1a1A1a"
+
+            ["DP-1"]
+            transition_type = "grow"
+            transition_pos = "bottom-left"
+            transition_wave = "30,15.5"
+        "#).unwrap();
+        assert!(valid.validate().is_ok());
+    }
+
+    #[test]
+    fn test_unknown_transition_type_escape_hatch() {
+        let config: Config = toml::from_str(r#"
+            [global]
+            allow_unknown_transitions = true
+
+            ["DP-1"]
+            transition_type = "swirl"
+        "#).unwrap();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.transition_warnings(), vec![
+            "DP-1.transition_type \"swirl\" is not a known swww transition; passing it to swww as is".to_string(),
+        ]);
+        assert!(Config::default().transition_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_start_offset_config() {
+        let toml_str = r#"
+            [global]
+            start_offset = "random"
+            random_seed = 7
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            start_offset = "2m"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.global.random_seed, Some(7));
+        assert_eq!(config.get_output_config("DP-1").start_offset, Some(StartOffset::Random));
+        assert_eq!(
+            config.get_output_config("HDMI-A-1").start_offset,
+            Some(StartOffset::Fixed(Duration::from_secs(120)))
+        );
+
+        assert!(toml::from_str::<Config>("[global]\nstart_offset = \"later\"\n").is_err());
+    }
+
+    #[test]
+    fn test_collation_config() {
+        let toml_str = r#"
+            [global]
+            collation = "unicode"
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            collation = "natural-unicode"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.get_output_config("DP-1").collation, Collation::Unicode);
+        assert_eq!(config.get_output_config("HDMI-A-1").collation, Collation::NaturalUnicode);
+        assert_eq!(Config::default().get_output_config("DP-1").collation, Collation::Byte);
+    }
+
+    #[test]
+    fn test_history_size_config() {
+        let toml_str = r#"
+            [global]
+            history_size = 20
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            history_size = 500
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.get_output_config("DP-1").history_size, 20);
+        assert_eq!(config.get_output_config("HDMI-A-1").history_size, 500);
+        assert_eq!(Config::default().get_output_config("DP-1").history_size, 100);
+    }
+
+    #[test]
+    fn test_watch_paths_config() {
+        assert!(Config::default().global.watch_paths);
+        let config: Config = toml::from_str("[global]\nwatch_paths = false\n").unwrap();
+        assert!(!config.global.watch_paths);
+    }
+
+    #[test]
+    fn test_change_log_size_config() {
+        assert_eq!(Config::default().global.change_log_size, 100);
+        let config: Config = toml::from_str("[global]\nchange_log_size = 20\n").unwrap();
+        assert_eq!(config.global.change_log_size, 20);
+    }
+
+    #[test]
+    fn test_log_file_config() {
+        let global = &Config::default().global;
+        assert_eq!((global.log_file.as_deref(), global.log_max_size, global.log_keep), (None, 10, 3));
+        let config = Config::from_toml_str("[global]\nlog_file = \"/var/log/swwws.log\"\nlog_level = \"debug\"\nlog_max_size = 2\n\n[any]\npath = \"/walls\"\n").unwrap();
+        assert_eq!(config.global.log_file.as_deref(), Some("/var/log/swwws.log"));
+        assert_eq!(config.global.log_level.as_deref(), Some("debug"));
+        assert_eq!(config.global.log_max_size, 2);
+        assert!(Config::from_toml_str("[global]\nlog_level = \"loud\"\n\n[any]\npath = \"/walls\"\n").is_err());
+        assert!(Config::from_toml_str("[global]\nlog_max_size = 0\n\n[any]\npath = \"/walls\"\n").is_err());
+    }
+
+    #[test]
+    fn test_startup_timeout_config() {
+        assert_eq!(Config::default().global.startup_timeout, Duration::from_secs(30));
+        let config: Config = toml::from_str("[global]\nstartup_timeout = \"2m\"\n").unwrap();
+        assert_eq!(config.global.startup_timeout, Duration::from_secs(120));
+        let config: Config = toml::from_str("[global]\nstartup_timeout = 0\n").unwrap();
+        assert_eq!(config.global.startup_timeout, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_dry_run_config() {
+        assert!(!Config::default().global.dry_run);
+        let config: Config = toml::from_str("[global]\ndry_run = true\n").unwrap();
+        assert!(config.global.dry_run);
+    }
+
+    #[test]
+    fn test_clear_on_exit_config() {
+        assert!(!Config::default().global.clear_on_exit);
+        let config: Config = toml::from_str("[global]\nclear_on_exit = true\n").unwrap();
+        assert!(config.global.clear_on_exit);
+    }
+
+    #[test]
+    fn test_on_resume_config() {
+        assert_eq!(Config::default().global.on_resume, ResumeAction::Restart);
+        let config: Config = toml::from_str("[global]\non_resume = \"stagger\"\n").unwrap();
+        assert_eq!(config.global.on_resume, ResumeAction::Stagger);
+        assert!(toml::from_str::<Config>("[global]\non_resume = \"later\"\n").is_err());
+    }
+
+    #[test]
+    fn test_follow_symlinks_config() {
+        let toml_str = r#"
+            [global]
+            follow_symlinks = false
+
+            [any]
+            path = "/test/path"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(!config.get_output_config("DP-1").discovery_options(None).follow_symlinks);
+        assert!(Config::default().get_output_config("DP-1").discovery_options(None).follow_symlinks);
+
+        let config: Config = toml::from_str("[\"HDMI-A-1\"]\npath = \"/test/path\"\nfollow_symlinks = false\n").unwrap();
+        assert!(!config.get_output_config("HDMI-A-1").follow_symlinks);
+        assert!(config.get_output_config("DP-1").follow_symlinks);
+    }
+
+    #[test]
+    fn test_min_resolution_config() {
+        let toml_str = r#"
+            [global]
+            min_resolution = "1920x1080"
+
+            ["DP-1"]
+            path = "/test/path"
+            min_resolution = "auto"
+            keep_unknown_resolution = false
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let options = config.get_output_config("HDMI-A-1").discovery_options(Some((3840, 2160)));
+        assert_eq!(options.min_resolution, Some((1920, 1080)));
+        assert!(options.keep_unknown_resolution);
+
+        let dp1 = config.get_output_config("DP-1");
+        assert_eq!(dp1.min_resolution, Some(MinResolution::Auto));
+        assert_eq!(dp1.discovery_options(Some((3840, 2160))).min_resolution, Some((3840, 2160)));
+        // Nothing to go by until swww reports the size
+        assert_eq!(dp1.discovery_options(None).min_resolution, None);
+        assert!(!dp1.discovery_options(None).keep_unknown_resolution);
+
+        assert!(toml::from_str::<Config>("[global]\nmin_resolution = \"big\"\n").is_err());
+    }
+
+    #[test]
+    fn test_skip_hidden_config() {
+        assert!(Config::default().get_output_config("DP-1").discovery_options(None).skip_hidden);
+        let config: Config = toml::from_str("[\"DP-1\"]\npath = \"/test/path\"\nskip_hidden = false\n").unwrap();
+        assert!(!config.get_output_config("DP-1").discovery_options(None).skip_hidden);
+        assert!(config.get_output_config("HDMI-A-1").discovery_options(None).skip_hidden);
+    }
+
+    #[test]
+    fn test_dedupe_config() {
+        assert!(!Config::default().get_output_config("DP-1").discovery_options(None).dedupe);
+        let config: Config = toml::from_str("[global]\ndedupe = true\n").unwrap();
+        assert!(config.get_output_config("DP-1").discovery_options(None).dedupe);
+    }
+
+    #[test]
+    fn test_max_depth_config() {
+        let toml_str = r#"
+            [global]
+            max_depth = 2
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            max_depth = 0
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.get_output_config("DP-1").discovery_options(None).max_depth, Some(2));
+        // 0 is unlimited
+        assert_eq!(config.get_output_config("HDMI-A-1").discovery_options(None).max_depth, None);
+        assert_eq!(Config::default().get_output_config("DP-1").discovery_options(None).max_depth, None);
+
+        assert!(toml::from_str::<Config>("[global]\nmax_depth = -1\n").is_err());
+        let config: Config = toml::from_str("[global]\nmax_depth = 1000\n").unwrap();
+        assert!(matches!(config.validate(),
+            Err(SwwwsError::Config(ConfigError::InvalidValue { ref field, .. })) if field == "max_depth"));
+        let config: Config = toml::from_str("[\"DP-1\"]\nmax_depth = 1000\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_no_repeat_window_config() {
+        let toml_str = r#"
+            [global]
+            no_repeat_window = 50
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            no_repeat_window = 0
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.get_output_config("DP-1").no_repeat_window, Some(50));
+        assert_eq!(config.get_output_config("HDMI-A-1").no_repeat_window, Some(0));
+        assert_eq!(Config::default().get_output_config("DP-1").no_repeat_window, None);
+    }
+
+    #[test]
+    fn test_on_change_config() {
+        let toml_str = r#"
+            [global]
+            on_change = "wal -i {image}"
+            on_change_once = true
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+            on_change = "notify-send {output}"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.global.on_change_once);
+        assert_eq!(config.get_output_config("DP-1").on_change.as_deref(), Some("wal -i {image}"));
+        assert_eq!(config.get_output_config("HDMI-A-1").on_change.as_deref(), Some("notify-send {output}"));
+        assert_eq!(Config::default().get_output_config("DP-1").on_change, None);
+    }
+
+    #[test]
+    fn test_manual_transition_resolution_order() {
+        let toml_str = r#"
+            [global]
+            transition_type = "grow"
+
+            [global.manual_transition]
+            transition_type = "simple"
+            transition_step = 255
+            transition_fps = 120
+
+            ["HDMI-A-1"]
+            path = "/test/path"
+
+            ["HDMI-A-1".manual_transition]
+            transition_step = 200
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.validate().unwrap();
+
+        // Per-output manual wins, then global manual, then the normal settings
+        let hdmi = config.get_output_config("HDMI-A-1").command_config(ChangeReason::Manual);
+        assert_eq!(hdmi.transition_step, Some(200));
+        assert_eq!(hdmi.transition_type.as_deref(), Some("simple"));
+        assert_eq!(hdmi.transition_fps, Some(120));
+        assert_eq!(hdmi.transition_pos.as_deref(), Some("center"));
+
+        let dp = config.get_output_config("DP-1").command_config(ChangeReason::Manual);
+        assert_eq!(dp.transition_step, Some(255));
+
+        let timer = config.get_output_config("DP-1").command_config(ChangeReason::Timer);
+        assert_eq!(timer.transition_type.as_deref(), Some("grow"));
+        assert_eq!(timer.transition_step, Some(90));
+    }
+
+    #[test]
+    fn test_manual_transition_changes_argv_only_for_manual_changes() {
+        let toml_str = r#"
+            [any]
+            path = "/test/path"
+            transition_type = "fade"
+            transition_step = 10
+
+            [any.manual_transition]
+            transition_type = "none"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let output_config = config.get_output_config("DP-1");
+        let builder = swwws_common::CommandBuilder::new(PathBuf::from("swww"));
+        let argv = |reason| {
+            builder
+                .build_img_command(Path::new("/test/a.png"), &output_config.command_config(reason), Some("DP-1"))
+                .get_args()
+                .map(|a| a.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let manual = argv(ChangeReason::Manual);
+        let timer = argv(ChangeReason::Timer);
+        assert_ne!(manual, timer);
+        assert!(manual.windows(2).any(|w| w == ["--transition-type", "none"]));
+        assert!(timer.windows(2).any(|w| w == ["--transition-type", "fade"]));
+        assert_eq!(argv(ChangeReason::Startup), timer);
+        assert_eq!(argv(ChangeReason::Refresh), timer);
+        assert!(argv(ChangeReason::Wake).windows(2).any(|w| w == ["--transition-type", "none"]));
+        // Fields the override leaves unset keep their normal values
+        assert!(manual.windows(2).any(|w| w == ["--transition-step", "10"]));
+    }
+
+    #[test]
+    fn test_manual_transition_validation() {
+        let toml_str = r#"
+            [global.manual_transition]
+            transition_fps = 0
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(SwwwsError::Config(ConfigError::InvalidValue { ref field, .. })) if field == "manual_transition.transition_fps"
+        ));
+    }
+
+    #[test]
+    fn test_random_transition_type() {
+        let toml_str = r#"
+            [global]
+            transition_type = "random"
+            transition_types = ["grow", "outer"]
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.validate().is_ok());
+        for _ in 0..10 {
+            let command = config.get_output_config("DP-1").command_config(ChangeReason::Timer);
+            let chosen = command.transition_type.unwrap();
+            assert!(chosen == "grow" || chosen == "outer");
+        }
+
+        let config: Config = toml::from_str("[global]\ntransition_types = [\"wipe\", \"sparkle\"]\n").unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(SwwwsError::Config(ConfigError::InvalidValue { ref value, .. })) if value == "sparkle"
+        ));
     }
 
     #[test]
@@ -736,8 +2557,7 @@ mod tests {
         
         fs::write(&config_path, config_content).unwrap();
         
-        // Mock the config_path function to return our test file
-        let config = Config::load_from_path(&config_path).unwrap();
+        let config = Config::load_from(&config_path).unwrap();
         
         assert_eq!(config.global.duration, Duration::from_secs(180));
         assert_eq!(config.global.queue_size, 5);
@@ -754,7 +2574,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let config_path = temp_dir.path().join("nonexistent.toml");
         
-        let result = Config::load_from_path(&config_path);
+        let result = Config::load_from(&config_path);
         assert!(result.is_err());
         
         match result.unwrap_err() {
@@ -775,7 +2595,7 @@ mod tests {
         
         fs::write(&config_path, invalid_content).unwrap();
         
-        let result = Config::load_from_path(&config_path);
+        let result = Config::load_from(&config_path);
         assert!(result.is_err());
         
         match result.unwrap_err() {
@@ -783,31 +2603,102 @@ mod tests {
             _ => panic!("Expected ConfigError::TomlParse"),
         }
     }
-}
 
-impl Config {
-    // Helper function for testing
-    #[cfg(test)]
-    fn load_from_path(path: &Path) -> Result<Self> {
-        if !path.exists() {
-            return Err(SwwwsError::Config(ConfigError::FileRead {
-                path: path.to_path_buf(),
-                source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
-            }));
+    #[test]
+    fn test_config_load_expands_paths() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, r#"
+            [any]
+            path = "~/Pictures/walls"
+
+            ["DP-1"]
+            path = "$HOME/other"
+
+            [global]
+            swww_binary = "~/.local/bin/swww"
+            state_file = "$HOME/state/swwws.json"
+        "#).unwrap();
+
+        let home = dirs::home_dir().unwrap();
+        let config = Config::load_from(&config_path).unwrap();
+        assert_eq!(config.global.swww_binary, Some(home.join(".local/bin/swww").to_string_lossy().to_string()));
+        assert_eq!(config.global.state_file, Some(home.join("state/swwws.json").to_string_lossy().to_string()));
+        assert_eq!(config.get_output_config("HDMI-A-1").path, Some(home.join("Pictures/walls").to_string_lossy().to_string()));
+        assert_eq!(config.get_output_config("DP-1").path, Some(home.join("other").to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn test_config_load_schedule() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, r#"
+            [any]
+            path = "/walls"
+
+            [any.schedule]
+            windows = [{ from = "07:00", to = "19:00", path = "~/walls/day", name = "day" }]
+
+            ["DP-1"]
+            path = "/walls/dp"
+
+            ["DP-1".schedule]
+            fallback = "/walls/night"
+            windows = [{ from = "20:00", to = "23:00", path = "/walls/evening" }]
+        "#).unwrap();
+
+        let config = Config::load_from(&config_path).unwrap();
+        let home = dirs::home_dir().unwrap();
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let night = NaiveTime::from_hms_opt(3, 0, 0).unwrap();
+
+        let hdmi = config.get_output_config("HDMI-A-1");
+        let day = hdmi.profile_at(noon);
+        assert_eq!(day.name, "day");
+        assert_eq!(day.path, Some(home.join("walls/day").to_string_lossy().to_string()));
+        assert_eq!(hdmi.profile_at(night), Profile { name: DEFAULT_PROFILE.to_string(), path: Some("/walls".to_string()) });
+
+        // An output's own schedule replaces the one in [any]
+        let dp = config.get_output_config("DP-1");
+        assert_eq!(dp.profile_at(noon).path.as_deref(), Some("/walls/night"));
+        assert_eq!(dp.profile_at(NaiveTime::from_hms_opt(21, 0, 0).unwrap()).name, "20:00-23:00");
+
+        fs::write(&config_path, r#"
+            ["DP-1".schedule]
+            windows = [{ from = "07:00", to = "07:00", path = "/walls/day" }]
+        "#).unwrap();
+        assert!(Config::load_from(&config_path).is_err());
+    }
+
+    #[test]
+    fn test_config_load_undefined_path_variable() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, r#"
+            ["DP-1"]
+            path = "${SWWWS_TEST_NOT_SET}/walls"
+        "#).unwrap();
+
+        match Config::load_from(&config_path).unwrap_err() {
+            SwwwsError::Config(ConfigError::UndefinedVariable { field, variable }) => {
+                assert_eq!(field, "DP-1.path");
+                assert_eq!(variable, "SWWWS_TEST_NOT_SET");
+            }
+            e => panic!("Expected ConfigError::UndefinedVariable, got {:?}", e),
         }
-        
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| SwwwsError::Config(ConfigError::FileRead {
-                path: path.to_path_buf(),
-                source: e,
-            }))?;
-        
-        let config: Config = toml::from_str(&content)
-            .map_err(|e| SwwwsError::Config(ConfigError::TomlParse {
-                message: e.to_string(),
-            }))?;
-        
-        config.validate()?;
-        Ok(config)
+    }
+
+    #[test]
+    fn test_config_path_env_override() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("profile.toml");
+
+        std::env::set_var("SWWWS_CONFIG", &config_path);
+        assert_eq!(Config::config_path().unwrap(), config_path);
+
+        // An empty value falls back to the default location
+        std::env::set_var("SWWWS_CONFIG", "");
+        assert!(Config::config_path().unwrap().ends_with("swwws/config.toml"));
+        std::env::remove_var("SWWWS_CONFIG");
     }
 }
@@ -68,6 +68,22 @@ mod tests {
         assert_eq!(groups[1], vec!["DP-3"]);
     }
 
+    #[test]
+    fn test_top_level_grouped_monitor_behavior() {
+        let config_content = r#"
+            monitor_behavior = "Grouped"
+            monitor_groups = [["DP-1", "DP-2"]]
+
+            [any]
+            path = "/test/path"
+        "#;
+
+        let config: Config = toml::from_str(config_content).expect("Config should parse");
+        assert_eq!(config.monitor_behavior, MonitorBehavior::Grouped(vec![]));
+        assert_eq!(config.get_effective_monitor_behavior(),
+            MonitorBehavior::Grouped(vec![vec!["DP-1".to_string(), "DP-2".to_string()]]));
+    }
+
     #[test]
     fn test_invalid_monitor_behavior_fails() {
         let config_content = r#"
@@ -80,4 +96,117 @@ mod tests {
         let result: std::result::Result<Config, _> = toml::from_str(config_content);
         assert!(result.is_err(), "Invalid monitor behavior should fail to parse");
     }
+
+    #[test]
+    fn test_named_groups_settings() {
+        let config_content = r#"
+            monitor_groups = [["eDP-1"]]
+
+            [groups.desk]
+            outputs = ["HDMI-A-1", "DP-2"]
+            path = "/walls/desk"
+            duration = "10m"
+            transition_type = "wipe"
+
+            [any]
+            path = "/test/path"
+
+            ["DP-2"]
+            path = "/walls/dp2"
+            transition_fps = 60
+        "#;
+
+        let mut config: Config = toml::from_str(config_content).expect("Config should parse");
+        config.monitor_behavior = MonitorBehavior::Grouped(vec![]);
+        config.validate().expect("Config should validate");
+
+        // Named groups come first, the old array syntax follows as anonymous groups
+        assert_eq!(config.named_groups(), vec![
+            ("desk".to_string(), vec!["HDMI-A-1".to_string(), "DP-2".to_string()]),
+            ("group_0".to_string(), vec!["eDP-1".to_string()]),
+        ]);
+        assert_eq!(config.get_effective_monitor_behavior(), MonitorBehavior::Grouped(vec![
+            vec!["HDMI-A-1".to_string(), "DP-2".to_string()],
+            vec!["eDP-1".to_string()],
+        ]));
+
+        // Group settings win; what the group leaves unset comes from the member
+        let member = config.get_output_config("DP-2");
+        assert_eq!(member.path.as_deref(), Some("/walls/desk"));
+        assert_eq!(member.duration, std::time::Duration::from_secs(600));
+        assert_eq!(member.transition_type, "wipe");
+        assert_eq!(member.transition_fps, 60);
+        assert_eq!(config.get_output_config("eDP-1").path.as_deref(), Some("/test/path"));
+
+        // Groups only apply in grouped mode
+        config.monitor_behavior = MonitorBehavior::Independent;
+        assert_eq!(config.get_output_config("DP-2").path.as_deref(), Some("/walls/dp2"));
+    }
+
+    #[test]
+    fn test_output_in_named_and_anonymous_group_fails() {
+        let config_content = r#"
+            monitor_groups = [["DP-2"]]
+
+            [groups.desk]
+            outputs = ["HDMI-A-1", "DP-2"]
+        "#;
+
+        let config: Config = toml::from_str(config_content).expect("Config should parse");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_glob_group_members() {
+        let config_content = r#"
+            monitor_groups = [["eDP-1"], ["DP-*"]]
+        "#;
+
+        let config: Config = toml::from_str(config_content).expect("Config should parse");
+        config.validate().expect("Config should validate");
+
+        let live: Vec<String> = ["eDP-1", "DP-4", "DP-5", "HDMI-A-1"].iter().map(|s| s.to_string()).collect();
+        let groups = config.resolve_groups(&live).unwrap();
+        assert_eq!(groups[0].1, vec!["eDP-1"]);
+        assert_eq!(groups[1].1, vec!["DP-4", "DP-5"]);
+    }
+
+    #[test]
+    fn test_overlapping_glob_groups() {
+        // A literal caught by another group's pattern is rejected at load
+        let config: Config = toml::from_str(r#"monitor_groups = [["DP-3"], ["DP-*"]]"#).unwrap();
+        assert!(config.validate().is_err());
+
+        // Two patterns only collide once an output matches both
+        let config: Config = toml::from_str(r#"monitor_groups = [["DP-*"], ["*-4"]]"#).unwrap();
+        config.validate().expect("Config should validate");
+        assert!(config.resolve_groups(&["DP-3".to_string(), "HDMI-A-4".to_string()]).is_ok());
+        let err = config.resolve_groups(&["DP-4".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("DP-4"));
+    }
+
+    #[test]
+    fn test_set_monitor_behavior() {
+        let config_content = r#"
+            monitor_behavior = "Independent"
+
+            [groups.desk]
+            outputs = ["HDMI-A-1", "DP-2"]
+            duration = "10m"
+        "#;
+
+        let mut config: Config = toml::from_str(config_content).expect("Config should parse");
+        config.set_monitor_behavior(MonitorBehavior::Grouped(vec![]), None).unwrap();
+        assert_eq!(config.get_effective_monitor_behavior(), MonitorBehavior::Grouped(vec![vec!["HDMI-A-1".to_string(), "DP-2".to_string()]]));
+
+        // Groups given at runtime replace the configured ones, settings and all
+        let groups = vec![vec!["DP-2".to_string()], vec!["HDMI-A-1".to_string()]];
+        config.set_monitor_behavior(MonitorBehavior::Grouped(vec![]), Some(groups.clone())).unwrap();
+        assert_eq!(config.get_effective_monitor_behavior(), MonitorBehavior::Grouped(groups));
+        assert!(config.groups.is_empty());
+
+        assert!(config.set_monitor_behavior(MonitorBehavior::Grouped(vec![]), Some(vec![vec![]])).is_err());
+        let overlapping = vec![vec!["DP-2".to_string()], vec!["DP-2".to_string()]];
+        assert!(config.set_monitor_behavior(MonitorBehavior::Grouped(vec![]), Some(overlapping)).is_err());
+    }
 }
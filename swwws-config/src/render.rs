@@ -0,0 +1,236 @@
+use serde::Serialize;
+use swwws_common::Result;
+use toml::{Table, Value};
+use crate::{default_monitor_behavior, monitor_behavior_name, Config, GlobalConfig, OutputConfig};
+
+impl Config {
+    /// The config as TOML that loads back into the same config. Top-level keys come
+    /// first, then `[global]`, `[any]`, `[groups.NAME]` and the output sections by name.
+    /// Without `include_defaults` only values that differ from their default are
+    /// written, and `[global]` and `[any]` are left out when nothing is set in them.
+    /// Output sections are always written, since having one changes what `[any]` applies to.
+    pub fn to_toml_string(&self, include_defaults: bool) -> Result<String> {
+        let mut top = Table::new();
+        if include_defaults || self.monitor_behavior != default_monitor_behavior() {
+            let behavior = monitor_behavior_name(&self.monitor_behavior);
+            top.insert("monitor_behavior".to_string(), Value::String(behavior.to_string()));
+        }
+        if let Some(monitor_groups) = &self.monitor_groups {
+            top.insert("monitor_groups".to_string(), Value::try_from(monitor_groups)?);
+        }
+
+        let mut sections = vec![toml::to_string(&top)?];
+
+        let global = section(&self.global, &GlobalConfig::default(), include_defaults)?;
+        if include_defaults || !global.is_empty() {
+            sections.push(wrap(&["global"], global)?);
+        }
+        let any = section(&self.any, &OutputConfig::default(), include_defaults)?;
+        if include_defaults || !any.is_empty() {
+            sections.push(wrap(&["any"], any)?);
+        }
+
+        for (name, group) in &self.groups {
+            let mut table = Table::new();
+            table.insert("outputs".to_string(), Value::try_from(&group.outputs)?);
+            table.extend(section(&group.settings, &OutputConfig::default(), include_defaults)?);
+            sections.push(wrap(&["groups", name], table)?);
+        }
+
+        let mut names: Vec<&String> = self.outputs.keys().collect();
+        names.sort();
+        for name in names {
+            let output = section(&self.outputs[name], &OutputConfig::default(), include_defaults)?;
+            sections.push(wrap(&[name], output)?);
+        }
+
+        let sections: Vec<&str> = sections.iter().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        let mut content = sections.join("\n\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        Ok(content)
+    }
+}
+
+impl OutputConfig {
+    /// This config as a `[name]` section with every field written, e.g. to show
+    /// what an output ends up with once `[global]`, `[any]` and its group are merged in
+    pub fn to_toml_section(&self, name: &str) -> Result<String> {
+        wrap(&[name], section(self, &OutputConfig::default(), true)?)
+    }
+}
+
+/// The fields of a section, without those equal to `default` unless `include_defaults`
+fn section<T: Serialize>(value: &T, default: &T, include_defaults: bool) -> Result<Table> {
+    let mut table = Table::try_from(value)?;
+    if !include_defaults {
+        let default = Table::try_from(default)?;
+        table.retain(|key, value| default.get(key) != Some(value));
+    }
+    Ok(table)
+}
+
+/// Render `table` under the section header made of `path`
+fn wrap(path: &[&str], table: Table) -> Result<String> {
+    let mut wrapped = table;
+    for key in path.iter().rev() {
+        let mut outer = Table::new();
+        outer.insert(key.to_string(), Value::Table(wrapped));
+        wrapped = outer;
+    }
+    Ok(toml::to_string(&wrapped)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &[&str] = &[
+        "",
+        r#"
+            [any]
+            path = "/walls"
+        "#,
+        r#"
+            monitor_behavior = "Synchronized"
+
+            [global]
+            duration = "90s"
+            sorting = "NewestFirst"
+            transition_type = "random"
+            transition_types = ["wipe", "grow"]
+            transition_duration = "1s 500ms"
+            max_concurrent_changes = 2
+            random_seed = 7
+
+            [any]
+            path = "/walls/shared"
+            duration = { min = "3m", max = "10m" }
+            collation = "natural"
+        "#,
+        r#"
+            monitor_behavior = "Grouped"
+            monitor_groups = [["DP-1", "DP-2"], ["HDMI-*"]]
+
+            [any]
+            path = "/walls/any"
+
+            ["DP-1"]
+            path = "/walls/dp1"
+        "#,
+        r#"
+            monitor_behavior = "Grouped"
+
+            [groups.desk]
+            outputs = ["DP-1", "DP-2"]
+            path = "/walls/wide"
+            duration = "10m"
+            transition_type = "fade"
+
+            [groups.side]
+            outputs = ["eDP-1"]
+        "#,
+        r#"
+            [global]
+            start_offset = "random"
+            reapply_on_wake = true
+
+            [global.manual_transition]
+            transition_type = "simple"
+            transition_fps = 60
+
+            [any]
+            path = "/walls/any"
+            portrait_path = "/walls/tall"
+            schedule = { fallback = "/walls/night", windows = [{ from = "07:00", to = "19:00", path = "/walls/day", name = "day" }] }
+
+            ["DP-3"]
+            path = "/walls/dp3"
+            duration = "never"
+            resize = "fit"
+            fill_color = "ff0000"
+            transition_angle = 45.5
+            invert_y = true
+            start_offset = "30s"
+
+            ["HDMI-A-1"]
+            enabled = false
+
+            ["eDP-1"]
+        "#,
+    ];
+
+    fn assert_round_trip(content: &str) {
+        let parsed = Config::from_toml_str(content).unwrap();
+        let full = parsed.to_toml_string(true).unwrap();
+
+        for include_defaults in [false, true] {
+            let written = parsed.to_toml_string(include_defaults).unwrap();
+            let reparsed = Config::from_toml_str(&written)
+                .unwrap_or_else(|e| panic!("written config does not load: {}\n{}", e, written));
+
+            assert_eq!(reparsed.to_toml_string(true).unwrap(), full, "from:\n{}", written);
+            assert_eq!(reparsed.to_toml_string(include_defaults).unwrap(), written);
+            assert_eq!(reparsed.get_effective_monitor_behavior(), parsed.get_effective_monitor_behavior());
+            let names = parsed.outputs.keys().map(String::as_str).chain(["DP-1", "DP-2", "unlisted"]);
+            for name in names {
+                assert_eq!(
+                    format!("{:?}", reparsed.get_output_config(name)),
+                    format!("{:?}", parsed.get_output_config(name)),
+                    "{} differs after a round trip through:\n{}", name, written,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_corpus_round_trips() {
+        for content in CORPUS {
+            assert_round_trip(content);
+        }
+    }
+
+    #[test]
+    fn test_defaults_are_omitted() {
+        assert_eq!(Config::default().to_toml_string(false).unwrap(), "");
+
+        let config = Config::from_toml_str(
+            "[\"DP-2\"]\nsorting = \"Random\"\n\n[any]\npath = \"/walls\"\nqueue_size = 10\n\n[\"DP-1\"]\nduration = \"2m\"\n",
+        ).unwrap();
+        assert_eq!(
+            config.to_toml_string(false).unwrap(),
+            "[any]\npath = \"/walls\"\n\n[DP-1]\nduration = \"2m\"\n\n[DP-2]\n",
+        );
+
+        let full = config.to_toml_string(true).unwrap();
+        assert!(full.starts_with("monitor_behavior = \"Independent\"\n\n[global]\nduration = \"5m\"\n"), "got:\n{}", full);
+        assert!(full.contains("transition_duration = \"500ms\""));
+    }
+
+    #[test]
+    fn test_merged_section_loads_back() {
+        let config = Config::from_toml_str(r#"
+            monitor_behavior = "Grouped"
+
+            [global]
+            duration = "90s"
+
+            [any]
+            path = "/walls/any"
+
+            [groups.desk]
+            outputs = ["DP-*"]
+            transition_type = "fade"
+
+            ["DP-1"]
+            sorting = "Random"
+        "#).unwrap();
+        let merged = config.get_output_config("DP-1");
+        let section = merged.to_toml_section("DP-1").unwrap();
+        assert!(section.starts_with("[DP-1]\npath = \"/walls/any\"\n"), "got:\n{}", section);
+
+        let reparsed = Config::from_toml_str(&section).unwrap();
+        assert_eq!(format!("{:?}", reparsed.get_output_config("DP-1")), format!("{:?}", merged));
+    }
+}
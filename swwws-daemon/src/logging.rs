@@ -0,0 +1,168 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// The daemon's logger: env_logger on stderr as always, and a log file next
+/// to it once the config says where. The file comes later because the config
+/// is loaded after logging starts.
+struct DaemonLogger {
+    stderr: env_logger::Logger,
+    file: Arc<Mutex<Option<LogFile>>>,
+}
+
+/// Opens the log file of the logger `init` installed
+pub struct LogFiles {
+    file: Arc<Mutex<Option<LogFile>>>,
+    stderr_level: LevelFilter,
+}
+
+/// Log to stderr at `level`, or as `RUST_LOG` says without it
+pub fn init(level: Option<LevelFilter>) -> LogFiles {
+    let mut builder = env_logger::Builder::new();
+    match level {
+        Some(level) => builder.filter_level(level),
+        None => builder.parse_env(env_logger::Env::default()),
+    };
+    let stderr = builder.build();
+    let stderr_level = stderr.filter();
+    let file = Arc::new(Mutex::new(None));
+    let logger = DaemonLogger { stderr, file: Arc::clone(&file) };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(stderr_level);
+    }
+    LogFiles { file, stderr_level }
+}
+
+impl LogFiles {
+    /// Also log to `path` at `level`, moving it aside once it would grow past
+    /// `max_size` bytes and keeping `keep` files moved aside
+    pub fn open(&self, path: &Path, level: LevelFilter, max_size: u64, keep: usize) -> io::Result<()> {
+        let file = LogFile::open(path, level, max_size, keep)?;
+        *self.file.lock().unwrap() = Some(file);
+        log::set_max_level(self.stderr_level.max(level));
+        Ok(())
+    }
+}
+
+impl Log for DaemonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.enabled(metadata)
+            || self.file.lock().unwrap().as_ref().is_some_and(|file| metadata.level() <= file.level)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if let Some(file) = self.file.lock().unwrap().as_mut().filter(|file| record.level() <= file.level) {
+            let line = format!(
+                "[{} {:<5} {}] {}\n",
+                humantime::format_rfc3339_seconds(SystemTime::now()),
+                record.level(),
+                record.target(),
+                record.args(),
+            );
+            // Nowhere left to report a failed write
+            let _ = file.write(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// A log file that is moved aside to `<path>.1` once it is full, `<path>.1`
+/// to `<path>.2` and so on, dropping the oldest
+struct LogFile {
+    path: PathBuf,
+    level: LevelFilter,
+    max_size: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path, level: LevelFilter, max_size: u64, keep: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), level, max_size, keep, file, size })
+    }
+
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for i in (1..self.keep).rev() {
+            // Fewer files than `keep` so far is fine
+            let _ = std::fs::rename(self.aside(i), self.aside(i + 1));
+        }
+        if self.keep > 0 {
+            std::fs::rename(&self.path, self.aside(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Where the file goes when it is moved aside the `n`th time
+    fn aside(&self, n: usize) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_full_log_files_are_moved_aside() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("logs").join("daemon.log");
+        let mut log = LogFile::open(&path, LevelFilter::Info, 20, 2).unwrap();
+        for line in ["first line\n", "second line\n", "third line\n", "fourth line\n"] {
+            log.write(line.as_bytes()).unwrap();
+        }
+
+        let read = |name: &str| std::fs::read_to_string(temp_dir.path().join("logs").join(name)).ok();
+        assert_eq!(read("daemon.log").as_deref(), Some("fourth line\n"));
+        assert_eq!(read("daemon.log.1").as_deref(), Some("third line\n"));
+        assert_eq!(read("daemon.log.2").as_deref(), Some("second line\n"));
+        assert_eq!(read("daemon.log.3"), None);
+    }
+
+    #[test]
+    fn test_log_file_keeps_growing_until_full() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("daemon.log");
+        std::fs::write(&path, "from before\n").unwrap();
+        let mut log = LogFile::open(&path, LevelFilter::Info, 1024, 0).unwrap();
+        log.write(b"appended\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "from before\nappended\n");
+
+        let mut log = LogFile::open(&path, LevelFilter::Info, 25, 0).unwrap();
+        log.write(b"no room left\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "no room left\n");
+        assert!(!temp_dir.path().join("daemon.log.1").exists());
+    }
+}
@@ -1,15 +1,66 @@
 use anyhow::Result;
-use swwws_config::Config;
+use clap::Parser;
+use swwws_config::diff::{self as config_diff, ConfigDiff};
+use swwws_config::{edit, Config};
 use swwws_common::{
-    ImageDiscovery, Queue, CommandBuilder, ProcessExecutor, IpcServer, IpcCommand, IpcResponse, OutputStatus, 
-    DaemonState as PersistentState, ErrorReporting, MonitorBehavior,
+    DiscoveryCache, DiscoveryOptions, ImageDiscovery, Queue, CommandBuilder, ProcessExecutor, IpcClient, IpcServer, IpcCommand, IpcEvent, IpcResponse, ErrorKind, CurrentImage, EffectiveConfig, OutputMode, OutputStatus, QueueEntry, QueueListing, HistoryEntry, OutputInfo, ReloadSummary,
+    DaemonState as PersistentState, ErrorReporting, MonitorBehavior, ReloadStats, ChangeReason,
+    Blacklist, ChangePipeline, ChangeRequest, Profile, PowerTracker, ProblemCategory, ProblemStore, ResumeAction, ShownHistory, SuspendDetector, SwwwClient, SwwwRestartDetector, IntervalOverrides, IntervalPicks, Interval, SocketLocation, OutputSettings, QueriedOutput, parse_duration,
 };
-use swwws_common::queue::Sorting;
-use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use swwws_common::pattern;
+use swwws_common::power::{SUSPEND_THRESHOLD, WAKE_DEBOUNCE};
+use swwws_common::schedule::DEFAULT_PROFILE;
+use swwws_common::reload::file_mtime;
+use swwws_common::start_offset::remaining;
+use swwws_common::queue::{sort_images, QueueOptions, ReplacePolicy, Sorting};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime};
 use std::sync::{Arc, Mutex};
-use tokio::time::interval;
-use std::path::PathBuf;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::Notify;
+use tokio::time::{interval_at, MissedTickBehavior};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use notify::Notifier;
+use registry::OutputRegistry;
+use scan::Scans;
+use watcher::{DirectoryWatcher, WATCH_DEBOUNCE};
+
+mod logging;
+mod notify;
+mod registry;
+mod scan;
+mod watcher;
+
+#[derive(Parser)]
+#[command(name = "swwws-daemon")]
+#[command(about = "swwws (swww slideshow daemon)")]
+#[command(version = "0.1.0")]
+struct Args {
+    /// Config file to use instead of ~/.config/swwws/config.toml (also settable via SWWWS_CONFIG)
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Stop a daemon already running on the socket and take its place
+    #[arg(long)]
+    replace: bool,
+
+    /// Also log to this file, ahead of log_file in the config
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Log level for stderr and the log file, ahead of RUST_LOG and log_level in the config
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<log::LevelFilter>,
+
+    /// Print the swww commands wallpaper changes would run instead of running them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Move every queue one image on, set the wallpapers, save the state and exit
+    #[arg(long)]
+    oneshot: bool,
+}
 
 #[derive(Debug, Clone)]
 struct MonitorGroup {
@@ -17,6 +68,7 @@ struct MonitorGroup {
     outputs: Vec<String>,
     queue: Queue,
     timer: Instant,
+    profile: String, // Schedule profile the queue was built from
 }
 
 #[derive(Debug)]
@@ -26,17 +78,118 @@ struct DaemonState {
     groups: Vec<MonitorGroup>,  // For grouped behavior
     shared_queue: Option<Queue>, // For synchronized behavior
     shared_timer: Option<Instant>, // For synchronized behavior
+    profiles: HashMap<String, String>, // Schedule profile of each individual queue
+    offsets: HashMap<String, usize>, // Offset each individual queue was started with
+    shared_profile: Option<String>, // For synchronized behavior
     paused: bool,
+    paused_groups: HashSet<String>, // Groups paused on their own with `pause --group`
+    frozen: HashMap<String, (Instant, Duration)>, // Time left on each paused timer, by the start it had then
     persistent_state: PersistentState,
+    config_path: PathBuf, // Re-read on reload
+    state_file: PathBuf, // Resolved at startup; state_file changes need a restart
+    reloads: ReloadStats,
+    changes: Arc<ChangePipeline>, // Every wallpaper change goes through here
+    swww: Arc<ProcessExecutor>, // For output queries; swww_binary is only read at startup
+    outputs: OutputRegistry, // Live outputs and the groups they belong to
+    intervals: IntervalPicks, // Target duration of each running timer
+    interval_overrides: IntervalOverrides, // From `swwws-cli interval`, ahead of the config's duration
+    overrides: BTreeMap<String, OutputSettings>, // From `swwws-cli output configure`, not in the file
+    behavior: Option<(MonitorBehavior, Option<Vec<Vec<String>>>)>, // From `swwws-cli behavior`, until a reload
+    live_config: Option<Arc<Config>>, // As last loaded, reloaded or configured
+    runtime: tokio::runtime::Handle, // Lets the IPC threads run image scans
+    notifier: Arc<Notifier>, // systemd's NOTIFY_SOCKET, when run as a Type=notify service
+}
+
+/// `IntervalPicks` key of the synchronized timer
+const SHARED_TIMER: &str = "synchronized outputs";
+
+/// How many images an output skips with distinct_across_outputs before it
+/// settles for one another output is showing
+const DISTINCT_MAX_SKIPS: usize = 5;
+
+/// Time between two outputs changing after a resume with on_resume = "stagger"
+const RESUME_STAGGER: Duration = Duration::from_secs(3);
+
+/// How often the daemon looks for new outputs, changed wallpaper directories
+/// and schedule windows
+const HOUSEKEEPING_PERIOD: Duration = Duration::from_secs(5);
+
+/// How often the state is saved while the daemon runs
+const SAVE_PERIOD: Duration = Duration::from_secs(30);
+
+/// How long a daemon already running gets to answer, and then to stop with `--replace`
+const INSTANCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the daemon checks that swww still answers
+const HEALTH_CHECK_PERIOD: Duration = Duration::from_secs(30);
+
+/// How long after a failed health check swww is asked again
+const HEALTH_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// First pause between two looks for swww at startup, doubled after each one
+/// up to `STARTUP_MAX_BACKOFF`
+const STARTUP_BACKOFF: Duration = Duration::from_millis(250);
+const STARTUP_MAX_BACKOFF: Duration = Duration::from_secs(4);
+
+/// What woke the main loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Task {
+    /// A wallpaper timer fell due, or an IPC command may have moved one
+    Timers,
+    Housekeeping,
+    Save,
+    HealthCheck,
+    /// The second look at swww after a failed health check
+    HealthRetry,
+    /// The once-a-second look for monitors back from sleep, for reapply_on_wake
+    WakeCheck,
+}
+
+/// What a running timer changes the wallpaper of
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TimerOwner {
+    Output(String),
+    Shared,
+    /// By index into `DaemonState::groups`
+    Group(usize),
+}
+
+/// Ticks every `period`, the first one a period from now. Ticks missed while
+/// suspended are not caught up in a burst.
+fn every(period: Duration) -> tokio::time::Interval {
+    let mut ticks = interval_at(tokio::time::Instant::now() + period, period);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    ticks
+}
+
+/// Whether outputs sharing a queue keep one wallpaper, going by the first of them
+fn shared_is_static(config: &Config, outputs: &[String]) -> bool {
+    outputs.first().is_some_and(|output| config.get_output_config(output).is_static())
+}
+
+/// `IntervalPicks` key of a group's timer, kept apart from output names
+fn group_timer(name: &str) -> String {
+    format!("group {}", name)
 }
 
 impl DaemonState {
-    fn new() -> Result<Self> {
-        let persistent_state = PersistentState::load(&PersistentState::get_state_file())
+    fn new(
+        config_path: PathBuf,
+        state_file: PathBuf,
+        loaded_mtime: Option<SystemTime>,
+        changes: Arc<ChangePipeline>,
+        swww: Arc<ProcessExecutor>,
+        outputs: OutputRegistry,
+    ) -> Result<Self> {
+        let persistent_state = PersistentState::load(&state_file)
             .unwrap_or_else(|e| {
                 log::warn!("Failed to load state, starting fresh: {}", e);
                 PersistentState::new()
             });
+        changes.shown().restore(&persistent_state.images);
+        changes.change_log().restore(&persistent_state.changes);
+        changes.pins().restore(&persistent_state.pinned);
+        DiscoveryCache::global().load(&DiscoveryCache::file_for(&state_file));
 
         Ok(Self {
             queues: HashMap::new(),
@@ -44,11 +197,68 @@ impl DaemonState {
             groups: Vec::new(),
             shared_queue: None,
             shared_timer: None,
+            profiles: HashMap::new(),
+            offsets: HashMap::new(),
+            shared_profile: None,
             paused: persistent_state.is_paused(),
+            paused_groups: HashSet::new(),
+            frozen: HashMap::new(),
             persistent_state,
+            config_path,
+            state_file,
+            reloads: ReloadStats::new(loaded_mtime),
+            changes,
+            swww,
+            outputs,
+            intervals: IntervalPicks::default(),
+            interval_overrides: IntervalOverrides::default(),
+            overrides: BTreeMap::new(),
+            behavior: None,
+            live_config: None,
+            runtime: tokio::runtime::Handle::current(),
+            notifier: Arc::new(Notifier::default()),
         })
     }
 
+    /// Images currently shown on outputs other than `output_name`
+    fn images_shown_elsewhere(&self, output_name: &str) -> HashSet<PathBuf> {
+        let individual = self.queues.iter()
+            .filter(|(output, _)| output.as_str() != output_name)
+            .map(|(_, queue)| queue);
+        let shared = self.groups.iter().map(|group| &group.queue).chain(self.shared_queue.as_ref());
+        individual.chain(shared)
+            .filter_map(|queue| queue.current_image().cloned())
+            .collect()
+    }
+
+    /// Move an individually driven output to its next image. With
+    /// distinct_across_outputs, images other outputs are showing are skipped.
+    fn advance_output(&mut self, output_name: &str, config: &Config) -> Option<PathBuf> {
+        if !config.global.distinct_across_outputs {
+            return self.queues.get_mut(output_name)?.next().cloned();
+        }
+
+        let taken = self.images_shown_elsewhere(output_name);
+        let (image, duplicate) = self.queues.get_mut(output_name)?.next_distinct(&taken, DISTINCT_MAX_SKIPS)?;
+        if duplicate {
+            log::info!("{}: no image free of other outputs after {} tries, showing {:?} anyway",
+                output_name, DISTINCT_MAX_SKIPS + 1, image);
+        }
+        Some(image)
+    }
+
+    /// Monitor behavior the daemon is running with, which may lag behind the
+    /// config file until the next reload
+    fn current_behavior(&self) -> MonitorBehavior {
+        if self.shared_queue.is_some() {
+            MonitorBehavior::Synchronized
+        } else if !self.groups.is_empty() {
+            MonitorBehavior::Grouped(self.groups.iter().map(|group| group.outputs.clone()).collect())
+        } else {
+            MonitorBehavior::Independent
+        }
+    }
+
     fn save_state(&mut self) -> Result<()> {
         // Sync queue state to persistent storage
         for (output_name, queue) in &self.queues {
@@ -61,24 +271,44 @@ impl DaemonState {
                     queue.get_sorting(),
                     &queue.get_all_images(),
                 );
+                if let Some(profile) = self.profiles.get(output_name) {
+                    self.persistent_state.set_output_profile(output_name, profile);
+                }
+                if let Some(offset) = self.offsets.get(output_name) {
+                    self.persistent_state.set_output_offset(output_name, *offset);
+                }
             }
         }
 
         self.persistent_state.set_paused(self.paused);
+        self.persistent_state.images = self.changes.shown().records();
+        self.persistent_state.changes = self.changes.change_log().changes();
+        self.persistent_state.pinned = self.changes.pins().pinned();
 
         // Save to file
-        let state_file = PersistentState::get_state_file();
-        self.persistent_state.save(&state_file)
+        self.persistent_state.save(&self.state_file)
             .map_err(|e| {
                 log::error!("Failed to save state: {}", e);
                 e
             })?;
 
+        if let Err(e) = DiscoveryCache::global().save(&DiscoveryCache::file_for(&self.state_file)) {
+            log::warn!("Failed to save discovery cache: {}", e);
+        }
+
         log::debug!("State saved successfully");
         Ok(())
     }
 
-    fn restore_queue_from_state(&mut self, output_name: &str, discovered_images: Vec<PathBuf>) -> bool {
+    fn restore_queue_from_state(
+        &mut self,
+        output_name: &str,
+        discovered_images: Vec<PathBuf>,
+        options: QueueOptions,
+        started: Instant,
+        profile: &str,
+        offset: usize,
+    ) -> bool {
         // Don't restore individual queues if we're in synchronized mode
         if self.shared_queue.is_some() {
             log::info!("Skipping queue restoration for {} - synchronized mode active", output_name);
@@ -88,50 +318,50 @@ impl DaemonState {
         if let Some(saved_state) = self.persistent_state.get_output_state(output_name) {
             log::info!("Attempting to restore queue for {} from saved state", output_name);
 
-            // Compare against saved image list
-            let discovered_strings: Vec<String> = discovered_images
-                .iter()
-                .map(|p| p.to_string_lossy().to_string())
-                .collect();
+            // A queue saved under another schedule profile holds the wrong directory
+            let saved_profile = saved_state.profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+            if saved_profile != profile {
+                log::info!("Saved queue for {} belongs to profile '{}', now '{}', starting fresh",
+                    output_name, saved_profile, profile);
+                return false;
+            }
 
-            match saved_state.sorting {
-                Sorting::Random => {
-                    // Random mode: restore current position if image still exists
-                    if let Some(current_image) = &saved_state.current_image {
-                        if discovered_strings.contains(current_image) {
-                            if let Some(mut queue) = Queue::new(
-                                saved_state.queue_size,
-                                saved_state.sorting.clone(),
-                                discovered_images,
-                            ) {
-                                if let Some(position) = discovered_strings.iter().position(|s| s == current_image) {
-                                    if queue.set_position(position) {
-                                        self.queues.insert(output_name.to_string(), queue);
-                                        self.timers.insert(output_name.to_string(), Instant::now());
-                                        log::info!("Restored queue for {} with current image at position {}", 
-                                            output_name, position);
-                                        return true;
-                                    }
-                                }
-                            }
-                        }
-                    }
+            // Other outputs keep their place, so a new offset only applies from the start
+            if saved_state.offset != offset {
+                log::info!("Saved queue for {} was started with offset {}, now {}, starting fresh",
+                    output_name, saved_state.offset, offset);
+                return false;
+            }
+
+            let can_restore = match saved_state.sorting {
+                // Shuffled modes: any saved current image that still exists can be resumed
+                Sorting::Random | Sorting::LeastRecentlyShown => true,
+                Sorting::Ascending | Sorting::Descending | Sorting::NewestFirst | Sorting::OldestFirst => {
+                    // Ordered mode: restore if image list unchanged. Both lists are put in
+                    // sort order first, since discovery order is filesystem-dependent
+                    let mut discovered_sorted = discovered_images.clone();
+                    sort_images(&mut discovered_sorted, &saved_state.sorting, options.collation);
+                    let mut saved_sorted: Vec<PathBuf> = saved_state.images.iter().map(PathBuf::from).collect();
+                    sort_images(&mut saved_sorted, &saved_state.sorting, options.collation);
+                    discovered_sorted == saved_sorted
                 }
-                Sorting::Ascending | Sorting::Descending => {
-                    // Ordered mode: restore if image list unchanged
-                    if discovered_strings == saved_state.images {
-                        if let Some(mut queue) = Queue::new(
-                            saved_state.queue_size,
-                            saved_state.sorting.clone(),
-                            discovered_images,
-                        ) {
-                            if queue.set_position(saved_state.queue_position) {
-                                self.queues.insert(output_name.to_string(), queue);
-                                self.timers.insert(output_name.to_string(), Instant::now());
-                                log::info!("Restored queue for {} with current image at position {}", 
-                                    output_name, saved_state.queue_position);
-                                return true;
-                            }
+            };
+
+            if let (true, Some(current_image)) = (can_restore, &saved_state.current_image) {
+                let current_image = PathBuf::from(current_image);
+                if discovered_images.contains(&current_image) {
+                    if let Some(mut queue) = Queue::with_options(
+                        saved_state.queue_size,
+                        saved_state.sorting.clone(),
+                        discovered_images.clone(),
+                        options,
+                    ) {
+                        if queue.replace_pool(discovered_images, ReplacePolicy::resume_at(current_image.clone())) {
+                            self.queues.insert(output_name.to_string(), queue);
+                            self.timers.insert(output_name.to_string(), started);
+                            self.offsets.insert(output_name.to_string(), offset);
+                            log::info!("Restored queue for {} with current image {:?}", output_name, current_image);
+                            return true;
                         }
                     }
                 }
@@ -142,7 +372,6 @@ impl DaemonState {
         false
     }
     
-    #[allow(dead_code)]
     fn get_group_for_output(&self, output_name: &str) -> Option<&MonitorGroup> {
         self.groups.iter().find(|group| group.outputs.contains(&output_name.to_string()))
     }
@@ -152,6 +381,78 @@ impl DaemonState {
         self.groups.iter_mut().find(|group| group.outputs.contains(&output_name.to_string()))
     }
     
+    /// Image currently shown on `output_name`, from whichever queue drives it
+    fn current_image_for(&self, output_name: &str) -> Option<&PathBuf> {
+        if let Some(group) = self.get_group_for_output(output_name) {
+            return group.queue.current_image();
+        }
+        self.queues.get(output_name)
+            .or(self.shared_queue.as_ref())
+            .and_then(|queue| queue.current_image())
+    }
+
+    /// Image on screen on `output_name`: its pin, or its queue's current image
+    fn shown_image(&self, output_name: &str) -> Option<PathBuf> {
+        self.changes.pins().image(output_name).or_else(|| self.current_image_for(output_name).cloned())
+    }
+
+    /// Label of every queue driving a live output, each once, as `queue_for` gives them
+    fn queue_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (name, _) in self.outputs.live().iter().filter_map(|output_name| self.queue_for(output_name)) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The queue driving `output_name` and a label for it: the group's name, the
+    /// shared queue in synchronized mode, or the output itself
+    fn queue_for(&self, output_name: &str) -> Option<(String, &Queue)> {
+        if let Some(group) = self.get_group_for_output(output_name) {
+            return Some((format!("group '{}'", group.name), &group.queue));
+        }
+        if let Some(queue) = self.queues.get(output_name) {
+            return Some((output_name.to_string(), queue));
+        }
+        self.shared_queue.as_ref().map(|queue| ("all outputs (sync)".to_string(), queue))
+    }
+
+    /// Which queue drives `output_name`, and the queue itself
+    fn mode_of(&self, output_name: &str) -> Option<(OutputMode, &Queue)> {
+        if let Some(group) = self.get_group_for_output(output_name) {
+            Some((OutputMode::Group(group.name.clone()), &group.queue))
+        } else if let Some(queue) = self.queues.get(output_name) {
+            Some((OutputMode::Independent, queue))
+        } else {
+            Some((OutputMode::Synchronized, self.shared_queue.as_ref()?))
+        }
+    }
+
+    /// Images `scans` found under `path`, blacklisted ones left out
+    fn scanned_images(&self, scans: &Scans, path: &Path, options: &DiscoveryOptions) -> Result<Vec<PathBuf>, String> {
+        scans.images(path, options).map(|images| self.persistent_state.blacklist.filter(images))
+    }
+
+    /// Index in `groups` of the group called `selector`, or at that index
+    fn resolve_group(&self, selector: &str) -> Result<usize, (ErrorKind, String)> {
+        if self.groups.is_empty() {
+            return Err((ErrorKind::Unavailable, "Outputs are not grouped; use --output or set monitor_behavior = \"Grouped\"".to_string()));
+        }
+        self.groups.iter().position(|group| group.name == selector)
+            .or_else(|| selector.parse().ok().filter(|index| *index < self.groups.len()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.groups.iter().map(|group| group.name.as_str()).collect();
+                (ErrorKind::NotFound, format!("Unknown group '{}'; groups are {}", selector, names.join(", ")))
+            })
+    }
+
+    /// Whether the timer of group `name` is stopped, by itself or with everything
+    fn group_paused(&self, name: &str) -> bool {
+        self.paused || self.paused_groups.contains(name)
+    }
+
     #[allow(dead_code)]
     fn find_outputs_in_same_group(&self, output_name: &str) -> Vec<String> {
         if let Some(group) = self.get_group_for_output(output_name) {
@@ -162,96 +463,452 @@ impl DaemonState {
     }
 }
 
-async fn initialize_output_queue(
+/// The queues driving `output`, the outputs of `group`, or every live output,
+/// each queue once however many outputs share it
+fn list_queue(state: &DaemonState, output: Option<&str>, group: Option<&str>, count: usize, recent: usize) -> IpcResponse {
+    let outputs = match (output, group) {
+        (Some(_), Some(_)) => return IpcResponse::error(ErrorKind::InvalidArgument, "Name an output or a group, not both"),
+        (Some(output), None) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => vec![output_name],
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        (None, Some(group)) => match state.resolve_group(group) {
+            Ok(index) => state.groups[index].outputs.iter()
+                .filter(|output_name| state.outputs.live().contains(output_name))
+                .cloned()
+                .collect(),
+            Err((kind, message)) => return IpcResponse::error(kind, message),
+        },
+        (None, None) => state.outputs.live().to_vec(),
+    };
+    if outputs.is_empty() {
+        return IpcResponse::error(ErrorKind::Unavailable, "No outputs are being managed");
+    }
+
+    let mut queues: Vec<QueueListing> = Vec::new();
+    for output_name in outputs {
+        let (Some((queue_name, _)), Some((mode, queue))) = (state.queue_for(&output_name), state.mode_of(&output_name)) else {
+            continue;
+        };
+        if mode != OutputMode::Independent {
+            if let Some(listed) = queues.iter_mut().find(|listed| listed.mode == mode) {
+                listed.outputs.push(output_name);
+                continue;
+            }
+        }
+
+        let entry = |path: &PathBuf, offset: i64| QueueEntry { path: path.to_string_lossy().to_string(), current: offset == 0, offset };
+        let shown = queue.recent(recent);
+        let before = shown.len() as i64;
+        let images = shown.into_iter().enumerate().map(|(i, image)| entry(image, i as i64 - before))
+            .chain(queue.current_image().map(|image| entry(image, 0)))
+            .chain(queue.peek(count).into_iter().enumerate().map(|(i, image)| entry(image, i as i64 + 1)))
+            .collect();
+        queues.push(QueueListing {
+            queue: queue_name,
+            mode,
+            outputs: vec![output_name],
+            position: queue.current_position() + 1,
+            size: queue.size(),
+            images,
+        });
+    }
+    if queues.is_empty() {
+        return IpcResponse::error(ErrorKind::Unavailable, "No queue for the outputs asked for");
+    }
+    IpcResponse::QueueList { queues }
+}
+
+/// Full path of the image each output shows, with the queue driving it. Without
+/// an output, every live output is listed.
+fn current_images(state: &DaemonState, output: Option<&str>) -> IpcResponse {
+    let outputs = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => vec![output_name],
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => state.outputs.live().to_vec(),
+    };
+
+    let images = outputs.into_iter()
+        .filter_map(|output_name| {
+            let (mode, queue) = state.mode_of(&output_name)?;
+            let path = state.changes.pins().image(&output_name).or_else(|| queue.current_image().cloned())
+                .map(|image| std::path::absolute(&image).unwrap_or(image).to_string_lossy().to_string());
+            Some(CurrentImage { output: output_name, path, mode })
+        })
+        .collect();
+    IpcResponse::Current { images }
+}
+
+/// What the daemon made of each output: its size, the config section and group
+/// it matched and its queue. Sections naming no connected output come last.
+fn list_outputs(state: &DaemonState, config: &Config) -> IpcResponse {
+    let connected = state.outputs.connected().iter().map(|name| OutputInfo {
+        name: name.clone(),
+        connected: true,
+        size: state.outputs.size(name),
+        scale: state.outputs.scale(name).map(str::to_string),
+        section: state.outputs.has_section(name),
+        group: state.outputs.group_of(name).map(str::to_string),
+        enabled: state.outputs.is_live(name),
+        queue: state.outputs.is_live(name).then(|| state.queue_for(name).map(|(queue, _)| queue)).flatten(),
+        near_match: None,
+    });
+    let missing = state.outputs.unmatched_sections().into_iter().map(|name| OutputInfo {
+        connected: false,
+        size: None,
+        scale: None,
+        section: true,
+        group: None,
+        enabled: config.get_output_config(&name).enabled,
+        queue: None,
+        near_match: state.outputs.connected().iter().find(|output| output.eq_ignore_ascii_case(&name)).cloned(),
+        name,
+    });
+    IpcResponse::Outputs { outputs: connected.chain(missing).collect() }
+}
+
+/// The latest wallpaper changes, newest first, on `output` or on every output.
+/// Outputs that are unplugged now still have their changes listed.
+fn change_history(state: &DaemonState, output: Option<&str>, limit: usize) -> IpcResponse {
+    let log = state.changes.change_log();
+    let output_name = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) => Some(output_name),
+            None if log.has_output(output) => Some(output.to_string()),
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => None,
+    };
+
+    let changes = log.latest(output_name.as_deref(), limit).into_iter()
+        .map(|(output, change)| HistoryEntry { output, image: change.image, timestamp: change.at, trigger: change.trigger })
+        .collect();
+    IpcResponse::History { changes }
+}
+
+/// The settings `output`, or every connected output, runs with: its section
+/// merged with `[global]`, `[any]` and its group, the path for its orientation,
+/// and an interval set with `swwws-cli interval`. `config` is the one the
+/// daemon runs with, so edits not reloaded yet don't show.
+fn effective_config(state: &DaemonState, config: &Config, output: Option<&str>) -> IpcResponse {
+    let outputs = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) => vec![output_name],
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => state.outputs.connected().to_vec(),
+    };
+
+    let mut effective = Vec::new();
+    for output_name in outputs {
+        let mut output_config = oriented_config(config, &state.outputs, &output_name);
+        output_config.duration = state.interval_overrides.interval(&output_name, output_config.duration);
+        let toml = match output_config.to_toml_section(&output_name) {
+            Ok(toml) => toml,
+            Err(e) => return IpcResponse::error(ErrorKind::Failed, format!("Can't show the config of {}: {}", output_name, e)),
+        };
+        let mode = state.mode_of(&output_name).map_or(OutputMode::Independent, |(mode, _)| mode);
+        effective.push(EffectiveConfig { output: output_name, mode, toml });
+    }
+
+    let behavior = match state.current_behavior() {
+        MonitorBehavior::Independent => "Independent",
+        MonitorBehavior::Synchronized => "Synchronized",
+        MonitorBehavior::Grouped(_) => "Grouped",
+    };
+    IpcResponse::Config { behavior: behavior.to_string(), outputs: effective }
+}
+
+/// Show `image` right away on `output`, or on every output, and restart the timer.
+/// A group or the shared queue shows it on all of its outputs. Queues that hold
+/// the image continue from it; for any other file they are left as they were.
+fn set_image(state: &mut DaemonState, config: &Config, output: Option<&str>, image: &Path) -> IpcResponse {
+    if let Err(e) = ImageDiscovery::validate_image(image) {
+        return IpcResponse::error(ErrorKind::InvalidArgument, format!("Can't show {:?}: {}", image, e.user_friendly_message()));
+    }
+
+    let targets = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => vec![output_name],
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => state.outputs.live().to_vec(),
+    };
+
+    let changes = Arc::clone(&state.changes);
+    let mut shown_on = Vec::new();
+    let mut outside = false;
+    let mut shared_done = false;
+    for output_name in &targets {
+        if let Some(group) = state.groups.iter_mut().find(|group| group.outputs.contains(output_name)) {
+            if shown_on.contains(&group.outputs[0]) {
+                continue;
+            }
+            outside |= !jump_to(&mut group.queue, image);
+            request_shared_change(&changes, config, &group.outputs, image, ChangeReason::Manual);
+            group.timer = Instant::now();
+            shown_on.extend(group.outputs.iter().cloned());
+        } else if let Some(queue) = state.queues.get_mut(output_name) {
+            outside |= !jump_to(queue, image);
+            request_change(&changes, config, output_name, image, ChangeReason::Manual);
+            state.timers.insert(output_name.clone(), Instant::now());
+            shown_on.push(output_name.clone());
+        } else if let Some(shared_queue) = &mut state.shared_queue {
+            if shared_done {
+                continue;
+            }
+            shared_done = true;
+            outside |= !jump_to(shared_queue, image);
+            request_shared_change(&changes, config, state.outputs.live(), image, ChangeReason::Manual);
+            state.shared_timer = Some(Instant::now());
+            shown_on.extend(state.outputs.live().iter().cloned());
+        }
+    }
+
+    if shown_on.is_empty() {
+        return IpcResponse::error(ErrorKind::Unavailable, "No queue to show the image on");
+    }
+    let name = file_name(image);
+    let note = if outside { " (not in the queue, which stays where it was)" } else { "" };
+    IpcResponse::Success { message: format!("Showing {} on {}{}", name, shown_on.join(", "), note) }
+}
+
+/// Rotate `output`, or every output, every `duration` until the config changes
+/// its interval. Outputs sharing a queue all take it, since their timer is
+/// shared. Without a duration, they go back to the configured interval.
+fn set_interval(state: &mut DaemonState, config: &Config, output: Option<&str>, duration: Option<&str>) -> IpcResponse {
+    let interval = match duration.map(parse_duration) {
+        Some(Ok(duration)) if duration.is_zero() => {
+            return IpcResponse::error(ErrorKind::InvalidArgument, "The interval can't be zero; use duration = \"never\" in the config for a static wallpaper");
+        }
+        Some(Ok(duration)) => Some(Interval::Fixed(duration)),
+        Some(Err(e)) => return IpcResponse::error(ErrorKind::InvalidArgument, e.to_string()),
+        None => None,
+    };
+
+    let targets = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => match state.get_group_for_output(&output_name) {
+                Some(group) => group.outputs.clone(),
+                None if state.shared_queue.is_some() => state.outputs.live().to_vec(),
+                None => vec![output_name],
+            },
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => state.outputs.live().to_vec(),
+    };
+
+    let Some(interval) = interval else {
+        let reset: Vec<String> = targets.into_iter().filter(|output_name| state.interval_overrides.clear(output_name)).collect();
+        if reset.is_empty() {
+            return IpcResponse::Success { message: "No interval was set, the config's applies".to_string() };
+        }
+        return IpcResponse::Success { message: format!("Back to the configured interval on {}", reset.join(", ")) };
+    };
+
+    let (static_outputs, targets): (Vec<String>, Vec<String>) = targets.into_iter()
+        .partition(|output_name| config.get_output_config(output_name).is_static());
+    if targets.is_empty() {
+        return IpcResponse::error(ErrorKind::Unavailable, format!("{} keeps a static wallpaper", static_outputs.join(", ")));
+    }
+    for output_name in &targets {
+        state.interval_overrides.set(output_name, interval, config.get_output_config(output_name).duration);
+    }
+    let every = duration.unwrap_or_default();
+    log::info!("Changing wallpaper every {} on {}", every, targets.join(", "));
+    let note = if static_outputs.is_empty() { String::new() } else { format!(" ({} stays static)", static_outputs.join(", ")) };
+    IpcResponse::Success { message: format!("Changing wallpaper every {} on {}{}", every, targets.join(", "), note) }
+}
+
+/// Forget intervals set at runtime for outputs whose configured interval `config` changed
+fn drop_replaced_intervals(state: &mut DaemonState, config: &Config) {
+    for output_name in state.interval_overrides.retain_configured(|output| config.get_output_config(output).duration) {
+        log::info!("The config changed the interval of {}, dropping the one set at runtime", output_name);
+    }
+}
+
+/// Reshuffle the images still to come in the queue driving `output`, or in every
+/// queue. Only random queues can be reshuffled.
+fn shuffle_queues(state: &mut DaemonState, output: Option<&str>) -> IpcResponse {
+    let output_name = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => Some(output_name),
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => None,
+    };
+
+    let group_of = output_name.as_ref().and_then(|name| state.get_group_for_output(name).map(|group| group.name.clone()));
+    let mut queues: Vec<(String, &mut Queue)> = Vec::new();
+    for group in &mut state.groups {
+        if output_name.is_none() || group_of.as_ref() == Some(&group.name) {
+            queues.push((format!("group '{}'", group.name), &mut group.queue));
+        }
+    }
+    if group_of.is_none() {
+        for (name, queue) in &mut state.queues {
+            if output_name.is_none() || output_name.as_ref() == Some(name) {
+                queues.push((name.clone(), queue));
+            }
+        }
+        if let Some(shared_queue) = &mut state.shared_queue {
+            if output_name.is_none() || queues.is_empty() {
+                queues.push(("all outputs (sync)".to_string(), shared_queue));
+            }
+        }
+    }
+
+    let mut shuffled = Vec::new();
+    let mut ordered = Vec::new();
+    for (name, queue) in queues {
+        if queue.reshuffle_remaining() {
+            shuffled.push(name);
+        } else {
+            ordered.push(format!("{} ({})", name, queue.get_sorting()));
+        }
+    }
+
+    match (shuffled.is_empty(), ordered.is_empty()) {
+        (true, true) => IpcResponse::error(ErrorKind::Unavailable, "No queue to reshuffle"),
+        (true, false) => IpcResponse::error(ErrorKind::Unavailable, format!("Only random queues can be reshuffled, not {}", ordered.join(", "))),
+        (false, true) => IpcResponse::Success { message: format!("Reshuffled {}", shuffled.join(", ")) },
+        (false, false) => IpcResponse::Success {
+            message: format!("Reshuffled {}; left {} in order", shuffled.join(", "), ordered.join(", ")),
+        },
+    }
+}
+
+/// Continue `queue` from `image` if it holds it. Returns false for an outside file.
+fn jump_to(queue: &mut Queue, image: &Path) -> bool {
+    queue.position_of(image).is_some_and(|position| queue.set_position(position))
+}
+
+/// Start an individual queue `offset` images into its cycle, so outputs on the
+/// same ordered folder stay that many images apart. An offset past the end wraps.
+fn apply_offset(queue: &mut Queue, output_name: &str, offset: usize) {
+    if offset == 0 {
+        return;
+    }
+    let size = queue.size();
+    if offset >= size {
+        log::warn!("offset {} for {} is not less than its {} images, using {}", offset, output_name, size, offset % size);
+    }
+    queue.set_position(offset % size);
+}
+
+fn initialize_output_queue(
     state: &mut DaemonState,
     output_name: &str,
     config: &Config,
+    scans: &Scans,
 ) {
-    let output_config = config.get_output_config(output_name);
+    // Set initial wallpaper if queue wasn't restored from state
+    if build_output_queue(state, output_name, config, scans) {
+        if let Some(current_image) = state.queues[output_name].current_image() {
+            request_change(&state.changes, config, output_name, current_image, ChangeReason::Startup);
+        }
+    }
+}
+
+/// Restore the queue of `output_name` from the state file, or start a new one.
+/// Returns true for a new queue, whose image is not on screen yet.
+fn build_output_queue(
+    state: &mut DaemonState,
+    output_name: &str,
+    config: &Config,
+    scans: &Scans,
+) -> bool {
+    let output_config = oriented_config(config, &state.outputs, output_name);
+    let profile = output_config.active_profile();
     
     // Get image path from config, skip output if none specified
-    let image_path = match &output_config.path {
+    let image_path = match &profile.path {
         Some(path_str) => PathBuf::from(path_str),
         None => {
             log::warn!("No wallpaper path configured for output '{}'", output_name);
             log::warn!("  Add a path to [any] section or create [outputs.\"{}\"] section in config", output_name);
-            return;
+            return false;
         }
     };
 
     // Discover images
-    let discovered_images = match ImageDiscovery::discover_images(&image_path) {
+    let discovered_images = match state.scanned_images(scans, &image_path, &discovery_options(&output_config, &state.outputs, &[output_name])) {
         Ok(images) => images,
         Err(e) => {
-            log::error!("Failed to discover images for {}: {}", output_name, e.user_friendly_message());
-            return;
+            log::error!("Failed to discover images for {}: {}", output_name, e);
+            state.changes.problems().record(ProblemCategory::Discovery, Some(output_name), format!(
+                "Failed to discover images in {:?}: {}", image_path, e));
+            return false;
         }
     };
 
     // Try to restore queue from state
-    if !state.restore_queue_from_state(output_name, discovered_images.clone()) {
-        // Create new queue if restoration failed
-        if let Some(queue) = Queue::new(
-            output_config.queue_size,
-            output_config.sorting,
-            discovered_images,
-        ) {
-            state.queues.insert(output_name.to_string(), queue);
-            state.timers.insert(output_name.to_string(), Instant::now());
-            
-            // Set initial wallpaper if queue wasn't restored from state
-            if let Some(current_image) = state.queues[output_name].current_image() {
-                let command_builder = CommandBuilder::new(PathBuf::from("swww"));
-                let executor = ProcessExecutor::new(command_builder);
-                
-                // Convert config to common format
-                let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-                if let Err(e) = executor.execute_swww_command(
-                    current_image,
-                    &common_config,
-                    Some(output_name),
-                ).await {
-                    log::error!("Failed to set initial wallpaper for {}: {}", 
-                        output_name, e.user_friendly_message());
-                } else {
-                    log::info!("Set initial wallpaper for {}: {:?}", output_name, current_image);
-                }
-            }
-        }
+    let started = initial_timer(&output_config, output_name, config.global.random_seed);
+    state.profiles.insert(output_name.to_string(), profile.name.clone());
+    if state.restore_queue_from_state(output_name, discovered_images.clone(), queue_options(&output_config, state.changes.shown()), started, &profile.name, output_config.offset) {
+        return false;
     }
+    // Create new queue if restoration failed
+    let Some(mut queue) = Queue::with_options(
+        output_config.queue_size,
+        output_config.sorting.clone(),
+        discovered_images,
+        queue_options(&output_config, state.changes.shown()),
+    ) else {
+        return false;
+    };
+    apply_offset(&mut queue, output_name, output_config.offset);
+    state.queues.insert(output_name.to_string(), queue);
+    state.timers.insert(output_name.to_string(), started);
+    state.offsets.insert(output_name.to_string(), output_config.offset);
+    true
 }
 
+/// Rebuild every queue for `config`. New queues start from the image in
+/// `start_at` for their output where they have it, and outputs already showing
+/// their queue's image are not set again. Outputs in `blank` were just
+/// connected, so they are set even when a saved queue is restored for them.
 fn reinitialize_daemon_state_sync(
     state: &mut DaemonState,
     config: &Config,
-    swww_outputs: &[String],
+    scans: &Scans,
+    start_at: &HashMap<String, PathBuf>,
+    blank: &[String],
 ) -> Result<(), anyhow::Error> {
     log::info!("Reinitializing daemon state (sync) due to configuration change...");
     
-    // Clear existing state
-    state.queues.clear();
+    // Clear existing state. Individual queues are kept aside so they can be
+    // warm-swapped onto the new image pool instead of starting over
+    let mut previous_queues = std::mem::take(&mut state.queues);
     state.timers.clear();
     state.groups.clear();
     state.shared_queue = None;
     state.shared_timer = None;
+    state.profiles.clear();
+    state.shared_profile = None;
+    state.outputs.clear_groups();
     // Keep paused state
     
     // Reinitialize monitor behavior
-    initialize_monitor_behavior(state, config, swww_outputs)?;
+    initialize_monitor_behavior(state, config, scans)?;
+    let swww_outputs = state.outputs.live().to_vec();
     
     // Reinitialize queues based on new behavior (using sync approaches)
     let behavior = config.get_effective_monitor_behavior();
@@ -260,69 +917,29 @@ fn reinitialize_daemon_state_sync(
     match behavior {
         MonitorBehavior::Independent => {
             log::info!("Reinitializing individual queues for Independent mode (sync)");
-            for output_name in swww_outputs {
-                initialize_output_queue_sync(state, output_name, config);
+            for output_name in &swww_outputs {
+                let previous = previous_queues.remove(output_name);
+                let blank = blank.contains(output_name);
+                initialize_output_queue_sync(state, output_name, config, previous, scans, start_at.get(output_name).map(PathBuf::as_path), blank);
             }
         }
         MonitorBehavior::Synchronized => {
             log::info!("Reinitializing for Synchronized mode (sync)");
-            if let Some(shared_queue) = &state.shared_queue {
-                if let Some(current_image) = shared_queue.current_image() {
-                    for output_name in swww_outputs {
-                        let output_config = config.get_output_config(output_name);
-                        let common_config = swwws_common::command_builder::OutputConfig {
-                            path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-                            mode: None,
-                            transition_type: Some(output_config.transition_type.clone()),
-                            transition_step: Some(output_config.transition_step as u8),
-                            transition_angle: Some(output_config.transition_angle),
-                            transition_pos: Some(output_config.transition_pos.clone()),
-                            transition_bezier: Some(output_config.transition_bezier.clone()),
-                            transition_fps: None,
-                            resize: Some(output_config.resize.clone()),
-                            fill_color: Some(output_config.fill_color.clone()),
-                            filter: Some(output_config.filter.clone()),
-                            invert_y: Some(output_config.invert_y),
-                            transition_wave: Some(output_config.transition_wave.clone()),
-                        };
-                        
-                        set_wallpaper_sync(output_name, current_image, &common_config);
-                    }
-                }
+            if let Some(shared_queue) = &mut state.shared_queue {
+                start_shared_queue(shared_queue, &state.changes, config, &swww_outputs, start_at);
             }
         }
         MonitorBehavior::Grouped(_) => {
             log::info!("Reinitializing for Grouped mode (sync)");
-            for group in &state.groups {
-                if let Some(current_image) = group.queue.current_image() {
-                    for output_name in &group.outputs {
-                        let output_config = config.get_output_config(output_name);
-                        let common_config = swwws_common::command_builder::OutputConfig {
-                            path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-                            mode: None,
-                            transition_type: Some(output_config.transition_type.clone()),
-                            transition_step: Some(output_config.transition_step as u8),
-                            transition_angle: Some(output_config.transition_angle),
-                            transition_pos: Some(output_config.transition_pos.clone()),
-                            transition_bezier: Some(output_config.transition_bezier.clone()),
-                            transition_fps: None,
-                            resize: Some(output_config.resize.clone()),
-                            fill_color: Some(output_config.fill_color.clone()),
-                            filter: Some(output_config.filter.clone()),
-                            invert_y: Some(output_config.invert_y),
-                            transition_wave: Some(output_config.transition_wave.clone()),
-                        };
-                        
-                        set_wallpaper_sync(output_name, current_image, &common_config);
-                    }
-                }
+            for group in &mut state.groups {
+                start_shared_queue(&mut group.queue, &state.changes, config, &group.outputs, start_at);
             }
             
             // Initialize independent queues for outputs not in any group
-            for output_name in swww_outputs {
-                if !state.groups.iter().any(|g| g.outputs.contains(output_name)) {
-                    initialize_output_queue_sync(state, output_name, config);
-                }
+            for output_name in state.outputs.ungrouped() {
+                let previous = previous_queues.remove(&output_name);
+                let blank = blank.contains(&output_name);
+                initialize_output_queue_sync(state, &output_name, config, previous, scans, start_at.get(&output_name).map(PathBuf::as_path), blank);
             }
         }
     }
@@ -333,15 +950,44 @@ fn reinitialize_daemon_state_sync(
     Ok(())
 }
 
+/// Rebuild the queues of `outputs`, which each have their own, leaving every
+/// other queue and timer alone. See `reinitialize_daemon_state_sync` for
+/// `start_at`.
+fn requeue_outputs(state: &mut DaemonState, config: &Config, scans: &Scans, outputs: &[String], start_at: &HashMap<String, PathBuf>) {
+    for output_name in outputs {
+        let previous = state.queues.remove(output_name);
+        state.timers.remove(output_name);
+        initialize_output_queue_sync(state, output_name, config, previous, scans, start_at.get(output_name).map(PathBuf::as_path), false);
+    }
+}
+
+/// Shared queues keep the position they had; see `reinitialize_daemon_state_sync`
+fn start_shared_queue(queue: &mut Queue, changes: &ChangePipeline, config: &Config, outputs: &[String], start_at: &HashMap<String, PathBuf>) {
+    if let Some(image) = outputs.iter().find_map(|output| start_at.get(output)) {
+        jump_to(queue, image);
+    }
+    let Some(current_image) = queue.current_image() else { return };
+    let stale: Vec<String> = outputs.iter()
+        .filter(|output| start_at.get(*output) != Some(current_image))
+        .cloned()
+        .collect();
+    request_shared_change(changes, config, &stale, current_image, ChangeReason::Refresh);
+}
+
 fn initialize_output_queue_sync(
     state: &mut DaemonState,
     output_name: &str,
     config: &Config,
+    previous: Option<Queue>,
+    scans: &Scans,
+    start_at: Option<&Path>,
+    blank: bool,
 ) {
-    let output_config = config.get_output_config(output_name);
+    let output_config = oriented_config(config, &state.outputs, output_name);
+    let profile = output_config.active_profile();
     
     // Get image path from config, skip output if none specified
-    let image_path = match &output_config.path {
+    let image_path = match &profile.path {
         Some(path_str) => PathBuf::from(path_str),
         None => {
             log::warn!("No image path configured for output {}, skipping", output_name);
@@ -350,81 +996,81 @@ fn initialize_output_queue_sync(
     };
 
     // Discover images
-    let discovered_images = match ImageDiscovery::discover_images(&image_path) {
+    let discovered_images = match state.scanned_images(scans, &image_path, &discovery_options(&output_config, &state.outputs, &[output_name])) {
         Ok(images) => images,
         Err(e) => {
-            log::error!("Failed to discover images for {}: {}", output_name, e.user_friendly_message());
+            log::error!("Failed to discover images for {}: {}", output_name, e);
+            state.changes.problems().record(ProblemCategory::Discovery, Some(output_name), format!(
+                "Failed to discover images in {:?}: {}", image_path, e));
             return;
         }
     };
 
+    // Keep a live queue going on the new pool if its ordering is unchanged
+    if let Some(mut queue) = previous {
+        let shown = queue.current_image().cloned();
+        if queue.get_sorting() == output_config.sorting
+            && queue.get_collation() == output_config.collation
+            && queue.replace_pool(discovered_images.clone(), ReplacePolicy::warm())
+        {
+            queue.set_buffer_size(output_config.queue_size);
+            queue.set_no_repeat_window(output_config.no_repeat_window);
+            queue.set_history_size(Some(output_config.history_size));
+            let current_image = queue.current_image().cloned();
+            state.queues.insert(output_name.to_string(), queue);
+            state.profiles.insert(output_name.to_string(), profile.name.clone());
+            state.timers.insert(output_name.to_string(), Instant::now());
+
+            // Only touch the wallpaper if the displayed image left the pool
+            if let Some(current_image) = current_image {
+                if shown.as_ref() != Some(&current_image) {
+                    request_change(&state.changes, config, output_name, &current_image, ChangeReason::Refresh);
+                }
+            }
+            log::info!("Kept queue for {} across reinitialization", output_name);
+            return;
+        }
+    }
+
     // Try to restore queue from state or create new one
-    if !state.restore_queue_from_state(output_name, discovered_images.clone()) {
-        if let Some(queue) = Queue::new(
+    let started = initial_timer(&output_config, output_name, config.global.random_seed);
+    state.profiles.insert(output_name.to_string(), profile.name.clone());
+    let restored = state.restore_queue_from_state(output_name, discovered_images.clone(), queue_options(&output_config, state.changes.shown()), started, &profile.name, output_config.offset);
+    if !restored {
+        if let Some(mut queue) = Queue::with_options(
             output_config.queue_size,
-            output_config.sorting,
+            output_config.sorting.clone(),
             discovered_images,
+            queue_options(&output_config, state.changes.shown()),
         ) {
+            apply_offset(&mut queue, output_name, output_config.offset);
             state.queues.insert(output_name.to_string(), queue);
-            state.timers.insert(output_name.to_string(), Instant::now());
-            
-            // Set initial wallpaper if queue wasn't restored from state
-            if let Some(current_image) = state.queues[output_name].current_image() {
-                let common_config = swwws_common::command_builder::OutputConfig {
-                    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-                    mode: None,
-                    transition_type: Some(output_config.transition_type.clone()),
-                    transition_step: Some(output_config.transition_step as u8),
-                    transition_angle: Some(output_config.transition_angle),
-                    transition_pos: Some(output_config.transition_pos.clone()),
-                    transition_bezier: Some(output_config.transition_bezier.clone()),
-                    transition_fps: None,
-                    resize: Some(output_config.resize.clone()),
-                    fill_color: Some(output_config.fill_color.clone()),
-                    filter: Some(output_config.filter.clone()),
-                    invert_y: Some(output_config.invert_y),
-                    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-                
-                set_wallpaper_sync(output_name, current_image, &common_config);
-            }
+            state.timers.insert(output_name.to_string(), started);
+            state.offsets.insert(output_name.to_string(), output_config.offset);
         }
     }
-}
-
-fn set_wallpaper_sync(
-    output_name: &str,
-    image_path: &PathBuf,
-    common_config: &swwws_common::command_builder::OutputConfig,
-) {
-    let command_builder = CommandBuilder::new(PathBuf::from("swww"));
-    let executor = ProcessExecutor::new(command_builder);
-    let output_name_clone = output_name.to_string();
-    let image_path_clone = image_path.clone();
-    let common_config_clone = common_config.clone();
 
-    std::thread::spawn(move || {
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            if let Err(e) = executor.execute_swww_command(
-                &image_path_clone,
-                &common_config_clone,
-                Some(&output_name_clone),
-            ).await {
-                log::error!("Failed to set wallpaper for {}: {}", 
-                    output_name_clone, e.user_friendly_message());
-            } else {
-                log::info!("Set wallpaper for {}: {:?}", output_name_clone, image_path_clone);
-            }
-        });
-    });
+    // A restored queue is taken to be on screen already, unless the output
+    // was just connected; a new one is set
+    let Some(queue) = state.queues.get_mut(output_name) else { return };
+    if let Some(image) = start_at {
+        jump_to(queue, image);
+    }
+    let on_screen = start_at.map(Path::to_path_buf)
+        .or_else(|| if restored && !blank { queue.current_image().cloned() } else { None });
+    if let Some(current_image) = queue.current_image() {
+        if on_screen.as_ref() != Some(current_image) {
+            request_change(&state.changes, config, output_name, current_image, ChangeReason::Refresh);
+        }
+    }
 }
 
 fn initialize_monitor_behavior(
     state: &mut DaemonState,
     config: &Config,
-    swww_outputs: &[String],
+    scans: &Scans,
 ) -> Result<(), anyhow::Error> {
+    let swww_outputs = state.outputs.live().to_vec();
     match config.get_effective_monitor_behavior() {
         MonitorBehavior::Independent => {
             log::info!("Using independent monitor behavior - each output manages its own queue");
@@ -435,69 +1081,65 @@ fn initialize_monitor_behavior(
             // Create a shared queue using the first available path
             let first_output = swww_outputs.first()
                 .ok_or_else(|| anyhow::anyhow!("No display outputs available for synchronized mode"))?;
-            let output_config = config.get_output_config(first_output);
-            let image_path = output_config.path.as_ref()
+            let output_config = oriented_config(config, &state.outputs, first_output);
+            let profile = output_config.active_profile();
+            let image_path = profile.path.as_ref()
                 .ok_or_else(|| {
                     anyhow::anyhow!("No wallpaper path configured for synchronized mode. Add 'path = \"/path/to/wallpapers\"' to [any] section in config")
                 })?;
             
-            let discovered_images = ImageDiscovery::discover_images(&PathBuf::from(image_path))
-                .map_err(|e| anyhow::anyhow!("Failed to discover images for synchronized mode: {}", e.user_friendly_message()))?;
+            let discovered_images = state.scanned_images(scans, Path::new(image_path), &discovery_options(&output_config, &state.outputs, &swww_outputs))
+                .map_err(|e| anyhow::anyhow!("Failed to discover images for synchronized mode: {}", e))?;
             
-            if let Some(shared_queue) = Queue::new(
+            if let Some(shared_queue) = Queue::with_options(
                 output_config.queue_size,
-                output_config.sorting,
+                output_config.sorting.clone(),
                 discovered_images,
+                queue_options(&output_config, state.changes.shown()),
             ) {
                 state.shared_queue = Some(shared_queue);
                 state.shared_timer = Some(Instant::now());
+                state.shared_profile = Some(profile.name);
                 log::info!("Created shared queue for synchronized mode with {} images", 
                     state.shared_queue.as_ref().unwrap().size());
             }
         }
-        MonitorBehavior::Grouped(groups) => {
+        MonitorBehavior::Grouped(_) => {
+            // Glob members are matched against the outputs connected right now
+            let groups = config.resolve_groups(&swww_outputs)
+                .map_err(|e| anyhow::anyhow!("Invalid monitor groups: {}", e.user_friendly_message()))?;
             log::info!("Using grouped monitor behavior with {} groups", groups.len());
             
-            for (group_idx, group_outputs) in groups.iter().enumerate() {
-                let group_name = format!("group_{}", group_idx);
+            for (group_name, group_outputs) in groups {
                 log::info!("Initializing group '{}' with outputs: {:?}", group_name, group_outputs);
                 
-                // Find the first output in this group that has a path configured
-                let mut group_path = None;
-                let mut group_config = None;
-                
-                for output in group_outputs {
-                    if swww_outputs.contains(output) {
-                        let output_config = config.get_output_config(output);
-                        if output_config.path.is_some() {
-                            group_path = output_config.path.clone();
-                            group_config = Some(output_config);
-                            break;
-                        }
-                    }
-                }
+                let outputs: Vec<String> = group_outputs.iter()
+                    .filter(|output| state.outputs.is_live(output))
+                    .map(|s| s.to_string())
+                    .collect();
                 
-                if let (Some(path), Some(config_data)) = (group_path, group_config) {
-                    let discovered_images = ImageDiscovery::discover_images(&PathBuf::from(&path))
-                        .map_err(|e| anyhow::anyhow!("Failed to discover images for group '{}': {}", group_name, e.user_friendly_message()))?;
+                if let Some((config_data, profile)) = group_profile(config, &state.outputs, &outputs) {
+                    let path = profile.path.as_deref().unwrap_or_default();
+                    let discovered_images = state.scanned_images(scans, Path::new(path), &discovery_options(&config_data, &state.outputs, &outputs))
+                        .map_err(|e| anyhow::anyhow!("Failed to discover images for group '{}': {}", group_name, e))?;
                     
-                    if let Some(queue) = Queue::new(
+                    if let Some(queue) = Queue::with_options(
                         config_data.queue_size,
-                        config_data.sorting,
+                        config_data.sorting.clone(),
                         discovered_images,
+                        queue_options(&config_data, state.changes.shown()),
                     ) {
                         let monitor_group = MonitorGroup {
                             name: group_name.clone(),
-                            outputs: group_outputs.iter()
-                                .filter(|output| swww_outputs.contains(output))
-                                .map(|s| s.to_string())
-                                .collect(),
+                            outputs,
                             queue,
-                            timer: Instant::now(),
+                            timer: initial_timer(&config_data, &group_name, config.global.random_seed),
+                            profile: profile.name,
                         };
                         
                         log::info!("Created group '{}' with {} outputs and {} images", 
                             group_name, monitor_group.outputs.len(), monitor_group.queue.size());
+                        state.outputs.assign_group(&group_name, &monitor_group.outputs);
                         state.groups.push(monitor_group);
                     }
                 } else {
@@ -510,108 +1152,961 @@ fn initialize_monitor_behavior(
     Ok(())
 }
 
-async fn change_wallpaper(
-    output_name: &str,
-    image_path: &std::path::Path,
+/// Wallpaper paths of the live outputs and how each is scanned, which cover every
+/// queue that can be built from `config`: groups and synchronized mode use
+/// their outputs' paths
+fn wallpaper_paths(config: &Config, outputs: &OutputRegistry) -> Vec<(PathBuf, DiscoveryOptions)> {
+    outputs.live().iter()
+        .filter_map(|output| {
+            let output_config = oriented_config(config, outputs, output);
+            let path = output_config.active_profile().path?;
+            Some((PathBuf::from(path), discovery_options(&output_config, outputs, &[output])))
+        })
+        .collect()
+}
+
+/// How to scan for a queue shown on `members`. An "auto" `min_resolution` goes
+/// by the largest of them, so no member is given an image smaller than itself.
+fn discovery_options(output_config: &swwws_config::OutputConfig, outputs: &OutputRegistry, members: &[impl AsRef<str>]) -> DiscoveryOptions {
+    output_config.discovery_options(outputs.largest_size(members))
+}
+
+/// Settings of `output_name` with `path` swapped for its portrait or landscape
+/// path, going by the size swww last reported for it
+fn oriented_config(config: &Config, outputs: &OutputRegistry, output_name: &str) -> swwws_config::OutputConfig {
+    config.get_output_config(output_name).oriented(outputs.orientation(output_name))
+}
+
+/// Settings and schedule profile of a group: those of its first output that has
+/// a wallpaper path right now, which include the group's own settings
+fn group_profile(config: &Config, registry: &OutputRegistry, outputs: &[String]) -> Option<(swwws_config::OutputConfig, Profile)> {
+    outputs.iter().find_map(|output| {
+        let output_config = oriented_config(config, registry, output);
+        let profile = output_config.active_profile();
+        profile.path.is_some().then_some((output_config, profile))
+    })
+}
+
+/// Rebuild `queue` from the directory of a newly active schedule profile, as
+/// found in `scans`. Returns false, leaving the queue as it was, if that
+/// yields no images.
+fn switch_profile(queue: &mut Queue, owner: &str, profile: &Profile, options: &DiscoveryOptions, scans: &Scans, blacklist: &Blacklist, problems: &ProblemStore) -> bool {
+    let Some(path) = &profile.path else {
+        log::warn!("Schedule profile '{}' for {} has no path, keeping the current queue", profile.name, owner);
+        return false;
+    };
+
+    match scans.images(Path::new(path), options) {
+        Ok(images) => {
+            log::info!("Switching {} to schedule profile '{}' ({})", owner, profile.name, path);
+            queue.replace_pool(blacklist.filter(images), ReplacePolicy::fresh())
+        }
+        Err(e) => {
+            log::error!("Failed to discover images for schedule profile '{}' of {}: {}", profile.name, owner, e);
+            problems.record(ProblemCategory::Discovery, None, format!(
+                "Failed to discover images for schedule profile '{}' of {}: {}", profile.name, owner, e));
+            false
+        }
+    }
+}
+
+/// Directories of the schedule profiles that became active since the queues
+/// were built, with the options `apply_schedules` is going to scan them with
+fn schedule_paths(state: &DaemonState, config: &Config) -> Vec<(PathBuf, DiscoveryOptions)> {
+    let swww_outputs = state.outputs.live();
+    let mut paths = Vec::new();
+    for output_name in state.queues.keys() {
+        let output_config = oriented_config(config, &state.outputs, output_name);
+        let profile = output_config.active_profile();
+        if state.profiles.get(output_name) != Some(&profile.name) {
+            paths.extend(profile.path.map(|path| (PathBuf::from(path), discovery_options(&output_config, &state.outputs, &[output_name]))));
+        }
+    }
+    if let (Some(_), Some(first_output)) = (&state.shared_queue, swww_outputs.first()) {
+        let output_config = oriented_config(config, &state.outputs, first_output);
+        let profile = output_config.active_profile();
+        if state.shared_profile.as_ref() != Some(&profile.name) {
+            paths.extend(profile.path.map(|path| (PathBuf::from(path), discovery_options(&output_config, &state.outputs, swww_outputs))));
+        }
+    }
+    for group in &state.groups {
+        let Some((group_config, profile)) = group_profile(config, &state.outputs, &group.outputs) else { continue };
+        if group.profile != profile.name {
+            paths.extend(profile.path.map(|path| (PathBuf::from(path), discovery_options(&group_config, &state.outputs, &group.outputs))));
+        }
+    }
+    paths
+}
+
+/// Scan the directories of schedule profiles that became active, with the
+/// state lock held only to work out which. Paused outputs switch once resumed.
+async fn scan_for_schedules(state: &Mutex<DaemonState>, startup_config: &Config) -> Scans {
+    let paths = {
+        let state = tokio::task::block_in_place(|| state.lock().unwrap());
+        if state.paused {
+            return Scans::default();
+        }
+        schedule_paths(&state, state.live_config.as_deref().unwrap_or(startup_config))
+    };
+    Scans::run(paths).await
+}
+
+/// Swap in a fresh queue wherever the active schedule window changed since the
+/// queue was built, showing its first image through the normal change path.
+/// The new profiles' images come from `scans`.
+fn apply_schedules(state: &mut DaemonState, config: &Config, scans: &Scans) {
+    let swww_outputs = state.outputs.live().to_vec();
+    let outputs: Vec<String> = state.queues.keys().cloned().collect();
+    for output_name in outputs {
+        let output_config = oriented_config(config, &state.outputs, &output_name);
+        let profile = output_config.active_profile();
+        if state.profiles.get(&output_name) == Some(&profile.name) {
+            continue;
+        }
+        if let Some(queue) = state.queues.get_mut(&output_name) {
+            if switch_profile(queue, &output_name, &profile, &discovery_options(&output_config, &state.outputs, &[&output_name]), scans, &state.persistent_state.blacklist, &state.changes.problems()) {
+                let offset = output_config.offset;
+                apply_offset(queue, &output_name, offset);
+                state.offsets.insert(output_name.clone(), offset);
+                if let Some(image) = queue.current_image() {
+                    request_change(&state.changes, config, &output_name, image, ChangeReason::Schedule);
+                }
+                state.timers.insert(output_name.clone(), Instant::now());
+            }
+        }
+        // Recorded even when the switch failed, so it is not retried every tick
+        state.profiles.insert(output_name, profile.name);
+    }
+
+    if let (Some(queue), Some(first_output)) = (&mut state.shared_queue, swww_outputs.first()) {
+        let output_config = oriented_config(config, &state.outputs, first_output);
+        let profile = output_config.active_profile();
+        if state.shared_profile.as_ref() != Some(&profile.name) {
+            let options = discovery_options(&output_config, &state.outputs, &swww_outputs);
+            if switch_profile(queue, "synchronized outputs", &profile, &options, scans, &state.persistent_state.blacklist, &state.changes.problems()) {
+                if let Some(image) = queue.current_image() {
+                    request_shared_change(&state.changes, config, &swww_outputs, image, ChangeReason::Schedule);
+                }
+                state.shared_timer = Some(Instant::now());
+            }
+            state.shared_profile = Some(profile.name);
+        }
+    }
+
+    for group in &mut state.groups {
+        let Some((group_config, profile)) = group_profile(config, &state.outputs, &group.outputs) else { continue };
+        if group.profile == profile.name {
+            continue;
+        }
+        let options = discovery_options(&group_config, &state.outputs, &group.outputs);
+        if switch_profile(&mut group.queue, &group.name, &profile, &options, scans, &state.persistent_state.blacklist, &state.changes.problems()) {
+            if let Some(image) = group.queue.current_image() {
+                request_shared_change(&state.changes, config, &group.outputs, image, ChangeReason::Schedule);
+            }
+            group.timer = Instant::now();
+        }
+        group.profile = profile.name;
+    }
+}
+
+/// Timer start for a freshly initialized output, pushed into the future by its
+/// start_offset so the first change comes that much later
+fn initial_timer(output_config: &swwws_config::OutputConfig, output_name: &str, seed: Option<u64>) -> Instant {
+    let offset = output_config.start_offset
+        .filter(|_| !output_config.is_static())
+        .map(|offset| offset.resolve(output_name, output_config.duration.min(), seed))
+        .unwrap_or_default();
+    if !offset.is_zero() {
+        log::info!("First change for {} delayed by {:?}", output_name, offset);
+    }
+    Instant::now() + offset
+}
+
+/// When each running timer falls due. Static, pinned and paused outputs and
+/// groups have no deadline; which timers run follows the monitor behavior.
+fn timer_deadlines(state: &DaemonState, config: &Config) -> Vec<(TimerOwner, Instant)> {
+    if state.paused {
+        return Vec::new();
+    }
+    let interval = |output: &str| state.interval_overrides.interval(output, config.get_output_config(output).duration);
+    let behavior = config.get_effective_monitor_behavior();
+    let mut deadlines = Vec::new();
+
+    // Single outputs: all of them when independent, the ungrouped ones when grouped
+    if !matches!(behavior, MonitorBehavior::Synchronized) {
+        for (output_name, timer) in &state.timers {
+            let grouped = matches!(behavior, MonitorBehavior::Grouped(_)) && state.outputs.group_of(output_name).is_some();
+            // Static and pinned outputs keep their wallpaper until asked over IPC
+            if grouped || config.get_output_config(output_name).is_static() || state.changes.pins().is_pinned(output_name) {
+                continue;
+            }
+            let target = state.intervals.target(output_name, *timer, interval(output_name));
+            deadlines.push((TimerOwner::Output(output_name.clone()), *timer + target));
+        }
+    }
+
+    match behavior {
+        MonitorBehavior::Independent => {}
+        MonitorBehavior::Synchronized => {
+            let outputs = state.outputs.live();
+            if let Some(timer) = state.shared_timer.filter(|_| !shared_is_static(config, outputs)) {
+                let target = outputs.first()
+                    .map(|output| state.intervals.target(SHARED_TIMER, timer, interval(output)))
+                    .unwrap_or(Duration::from_secs(300));
+                deadlines.push((TimerOwner::Shared, timer + target));
+            }
+        }
+        MonitorBehavior::Grouped(_) => {
+            for (index, group) in state.groups.iter().enumerate() {
+                if state.paused_groups.contains(&group.name) || shared_is_static(config, &group.outputs) {
+                    continue;
+                }
+                let target = group.outputs.first()
+                    .map(|first_output| state.intervals.target(&group_timer(&group.name), group.timer, interval(first_output)))
+                    .unwrap_or(Duration::from_secs(300));
+                deadlines.push((TimerOwner::Group(index), group.timer + target));
+            }
+        }
+    }
+    deadlines
+}
+
+/// The earliest timer deadline, if any timer is running
+fn next_deadline(state: &DaemonState, config: &Config) -> Option<Instant> {
+    timer_deadlines(state, config).into_iter().map(|(_, deadline)| deadline).min()
+}
+
+/// Move every timer due by `now` on to its next image and start it over
+fn run_due_timers(state: &mut DaemonState, config: &Config, now: Instant) {
+    let changes = Arc::clone(&state.changes);
+    for (owner, deadline) in timer_deadlines(state, config) {
+        if deadline > now {
+            continue;
+        }
+        match owner {
+            TimerOwner::Output(output_name) => {
+                if let Some(next_image) = state.advance_output(&output_name, config) {
+                    request_change(&changes, config, &output_name, &next_image, ChangeReason::Timer);
+                }
+                state.timers.insert(output_name, now);
+            }
+            TimerOwner::Shared => {
+                let outputs = state.outputs.live().to_vec();
+                if let Some(next_image) = state.shared_queue.as_mut().and_then(|queue| queue.next().cloned()) {
+                    log::info!("Synchronized mode: Setting same image on all outputs: {:?}", next_image);
+                    request_shared_change(&changes, config, &outputs, &next_image, ChangeReason::Timer);
+                }
+                state.shared_timer = Some(now);
+            }
+            TimerOwner::Group(index) => {
+                let group = &mut state.groups[index];
+                if let Some(next_image) = group.queue.next() {
+                    log::info!("Group '{}': Setting image on grouped outputs: {:?}", group.name, next_image);
+                    request_shared_change(&changes, config, &group.outputs, next_image, ChangeReason::Timer);
+                }
+                group.timer = now;
+            }
+        }
+    }
+}
+
+/// A timer with its `IntervalPicks` key and interval
+type TimerSlot<'a> = (String, Interval, &'a mut Instant);
+
+/// Every timer: groups, then the synchronized queue, then single outputs, in
+/// the order they change. The picks come along, as the timers borrow the state.
+fn timer_slots<'a>(state: &'a mut DaemonState, config: &Config) -> (&'a IntervalPicks, Vec<TimerSlot<'a>>) {
+    let DaemonState { timers, shared_timer, groups, outputs, intervals, interval_overrides, .. } = state;
+    let interval = |output: &str| interval_overrides.interval(output, config.get_output_config(output).duration);
+    let mut slots: Vec<(String, Interval, &mut Instant)> = Vec::new();
+    for group in groups.iter_mut() {
+        if let Some(first) = group.outputs.first() {
+            slots.push((group_timer(&group.name), interval(first), &mut group.timer));
+        }
+    }
+    if let (Some(timer), Some(first)) = (shared_timer.as_mut(), outputs.live().first()) {
+        slots.push((SHARED_TIMER.to_string(), interval(first), timer));
+    }
+    let mut singles: Vec<(&String, &mut Instant)> = timers.iter_mut().collect();
+    singles.sort_by_key(|(output, _)| outputs.live().iter().position(|live| live == *output));
+    for (output, timer) in singles {
+        slots.push((output.clone(), interval(output), timer));
+    }
+    (intervals, slots)
+}
+
+/// Stop the countdown of the timers `which` picks by key, keeping the time
+/// each had left. Timers frozen already keep what they had.
+fn freeze_timers(state: &mut DaemonState, config: &Config, now: Instant, which: impl Fn(&str) -> bool) {
+    let mut frozen = std::mem::take(&mut state.frozen);
+    let (intervals, slots) = timer_slots(state, config);
+    for (key, interval, timer) in slots {
+        if which(&key) && !frozen.contains_key(&key) {
+            let left = (*timer + intervals.target(&key, *timer, interval)).saturating_duration_since(now);
+            frozen.insert(key, (*timer, left));
+        }
+    }
+    state.frozen = frozen;
+}
+
+/// Start the timers `which` picks again with the time they had left when
+/// they were frozen. A timer restarted since, or started while paused, gets
+/// its whole interval from `now`.
+fn thaw_timers(state: &mut DaemonState, config: &Config, now: Instant, which: impl Fn(&str) -> bool) {
+    let mut frozen = std::mem::take(&mut state.frozen);
+    let (intervals, slots) = timer_slots(state, config);
+    for (key, interval, timer) in slots {
+        if !which(&key) {
+            continue;
+        }
+        *timer = match frozen.remove(&key) {
+            Some((started, left)) if *timer == started => {
+                let target = intervals.target(&key, started, interval);
+                let restarted = (now + left).checked_sub(target).unwrap_or(now);
+                intervals.carry_over(&key, started, restarted);
+                restarted
+            }
+            _ => now,
+        };
+    }
+    frozen.retain(|key, _| !which(key));
+    state.frozen = frozen;
+}
+
+/// Time left on a timer of `key` started at `started`, standing still while
+/// it is paused
+fn time_left(state: &DaemonState, key: &str, started: Instant, target: Duration, paused: bool) -> Duration {
+    match state.frozen.get(key) {
+        Some((frozen_start, left)) if paused && *frozen_start == started => *left,
+        _ if paused => target,
+        _ => remaining(started, target),
+    }
+}
+
+/// Set every timer by on_resume after the system woke from suspend, instead
+/// of letting them all fire at once, or hours late where `Instant` stood still
+fn resume_timers(state: &mut DaemonState, config: &Config, now: Instant) {
+    let action = config.global.on_resume;
+    log::info!("Resetting timers after resume ({:?})", action);
+
+    let (intervals, slots) = timer_slots(state, config);
+    for (i, (key, interval, timer)) in slots.into_iter().enumerate() {
+        *timer = match action {
+            ResumeAction::Restart => now,
+            ResumeAction::Change => intervals.start_due_at(&key, now, interval),
+            ResumeAction::Stagger => intervals.start_due_at(&key, now + RESUME_STAGGER * i as u32, interval),
+        };
+    }
+}
+
+/// Queue behavior derived from a merged output config
+fn queue_options(output_config: &swwws_config::OutputConfig, shown: Arc<ShownHistory>) -> QueueOptions {
+    QueueOptions {
+        collation: output_config.collation,
+        no_repeat_window: output_config.no_repeat_window,
+        seed: None,
+        history_size: Some(output_config.history_size),
+        shown: Some(shown),
+    }
+}
+
+/// Hand a wallpaper change to the pipeline, using the output's merged config
+fn request_change(
+    changes: &ChangePipeline,
     config: &Config,
-    executor: &ProcessExecutor,
+    output_name: &str,
+    image_path: &Path,
+    reason: ChangeReason,
 ) {
+    submit_change(changes, config, output_name, image_path, reason, true);
+}
+
+/// Hand the same change for every output sharing a queue to the pipeline. With
+/// on_change_once, only the first output's change runs the on_change hook.
+fn request_shared_change(
+    changes: &ChangePipeline,
+    config: &Config,
+    outputs: &[String],
+    image_path: &Path,
+    reason: ChangeReason,
+) {
+    for (i, output_name) in outputs.iter().enumerate() {
+        let run_hook = i == 0 || !config.global.on_change_once;
+        submit_change(changes, config, output_name, image_path, reason, run_hook);
+    }
+}
+
+fn submit_change(
+    changes: &ChangePipeline,
+    config: &Config,
+    output_name: &str,
+    image_path: &Path,
+    reason: ChangeReason,
+    run_hook: bool,
+) {
+    // Pinned outputs keep their image unless this change was asked for
+    let Some(image_path) = changes.pins().redirect(output_name, image_path, reason) else {
+        return;
+    };
     let output_config = config.get_output_config(output_name);
-    
-    // Convert config to common format
-    let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
+    // The image is the same after a wake, so there is nothing new to hook into
+    let run_hook = run_hook && reason != ChangeReason::Wake;
+    changes.submit(ChangeRequest {
+        output: output_name.to_string(),
+        image: image_path,
+        config: output_config.command_config(reason),
+        reason,
+        // Wake re-applies skip the transition, so there is nothing to wait for
+        settle: if reason == ChangeReason::Wake { Duration::ZERO } else { output_config.transition_duration },
+        on_change: output_config.on_change.filter(|_| run_hook),
+    });
+}
 
-    // Execute swww command with retry logic
-    const MAX_RETRIES: u32 = 3;
-    const RETRY_DELAY: Duration = Duration::from_millis(500);
-    
-    for attempt in 0..MAX_RETRIES {
-        match executor.execute_swww_command(
-            image_path,
-            &common_config,
-            Some(output_name),
-        ).await {
-            Ok(()) => {
-                log::info!("Set wallpaper for {}: {:?}", output_name, image_path);
-                return;
+/// Drop images whose file disappeared from every queue, so they stop coming
+/// around for the rest of the session. Queues that were showing one move on.
+fn drop_missing_images(state: &mut DaemonState, config: &Config) {
+    let missing: HashSet<PathBuf> = state.changes.take_missing_images().into_iter().collect();
+    for image in &missing {
+        log::warn!("{:?} no longer exists, dropping it from the queues", image);
+    }
+    if !missing.is_empty() {
+        drop_from_queues(state, config, |image| missing.contains(image));
+    }
+}
+
+/// Remove the images `drop` picks from every queue. Queues that were showing
+/// one move on. Returns how many images were removed, counted per queue.
+fn drop_from_queues(state: &mut DaemonState, config: &Config, drop: impl Fn(&Path) -> bool) -> usize {
+    let changes = Arc::clone(&state.changes);
+    let mut removed = 0;
+    for (output_name, queue) in &mut state.queues {
+        if let Some(current) = remove_from_queue(queue, &drop, &mut removed) {
+            request_change(&changes, config, output_name, &current, ChangeReason::Refresh);
+        }
+    }
+    if let Some(shared_queue) = &mut state.shared_queue {
+        if let Some(current) = remove_from_queue(shared_queue, &drop, &mut removed) {
+            request_shared_change(&changes, config, state.outputs.live(), &current, ChangeReason::Refresh);
+        }
+    }
+    for group in &mut state.groups {
+        if let Some(current) = remove_from_queue(&mut group.queue, &drop, &mut removed) {
+            request_shared_change(&changes, config, &group.outputs, &current, ChangeReason::Refresh);
+        }
+    }
+    removed
+}
+
+/// Wallpaper directories the queues draw from right now, for the directory watcher
+fn watched_paths(state: &DaemonState, config: &Config) -> Vec<PathBuf> {
+    if !config.global.watch_paths {
+        return Vec::new();
+    }
+    let individual = state.queues.keys()
+        .filter_map(|output| oriented_config(config, &state.outputs, output).active_profile().path);
+    let shared = state.shared_queue.as_ref()
+        .and(state.outputs.live().first())
+        .and_then(|first_output| oriented_config(config, &state.outputs, first_output).active_profile().path);
+    let groups = state.groups.iter()
+        .filter_map(|group| group_profile(config, &state.outputs, &group.outputs))
+        .filter_map(|(_, profile)| profile.path);
+    individual.chain(shared).chain(groups).map(PathBuf::from).collect()
+}
+
+/// The directories the watcher saw change, rescanned with the state lock held
+/// only to work out the paths and options of the queues drawing from them
+async fn scan_for_changed_paths(state: &Mutex<DaemonState>, watcher: &mut DirectoryWatcher, startup_config: &Config) -> (Vec<PathBuf>, Scans) {
+    let roots = {
+        let state = tokio::task::block_in_place(|| state.lock().unwrap());
+        watched_paths(&state, state.live_config.as_deref().unwrap_or(startup_config))
+    };
+    watcher.set_roots(roots);
+    let changed = watcher.poll(Instant::now(), WATCH_DEBOUNCE);
+    if changed.is_empty() {
+        return (changed, Scans::default());
+    }
+
+    let paths = {
+        let mut state = tokio::task::block_in_place(|| state.lock().unwrap());
+        let live_config = state.live_config.clone();
+        let mut paths = Vec::new();
+        resync_queues(&mut state, live_config.as_deref().unwrap_or(startup_config), None, |path, options| {
+            if changed.contains(&path) {
+                paths.push((path, options));
             }
-            Err(e) => {
-                if attempt < MAX_RETRIES - 1 {
-                    log::warn!("Failed to set wallpaper for {} (attempt {}/{}): {}. Retrying in {}ms...", 
-                        output_name, attempt + 1, MAX_RETRIES, e.user_friendly_message(), 
-                        RETRY_DELAY.as_millis());
-                    tokio::time::sleep(RETRY_DELAY).await;
-                } else {
-                    log::error!("Failed to set wallpaper for {} after {} attempts: {}", 
-                        output_name, MAX_RETRIES, e.user_friendly_message());
+            None
+        });
+        paths
+    };
+    // Walked even if the discovery cache thinks nothing changed
+    let scans = Scans::rescan(paths).await;
+    (changed, scans)
+}
+
+/// Bring the queues drawing from `changed` directories up to date with what
+/// `scans` found there: new images join them and deleted ones leave, moving
+/// on where one was on screen
+fn reconcile_changed_paths(state: &mut DaemonState, config: &Config, changed: &[PathBuf], scans: &Scans) {
+    resync_queues(state, config, None, |path, options| {
+        if !changed.contains(&path) {
+            return None;
+        }
+        scans.images(&path, &options)
+            .map_err(|e| log::warn!("Rescanning {:?} failed, keeping its queues as they are: {}", path, e))
+            .ok()
+    });
+}
+
+/// Images added to and removed from queues by `sync_queue`
+#[derive(Debug, Default, Clone, Copy)]
+struct Resynced {
+    added: usize,
+    removed: usize,
+}
+
+/// Sync every queue `rescan` returns images for with them, or only those
+/// showing on `only`. `rescan` gets each queue's path and discovery options.
+fn resync_queues(
+    state: &mut DaemonState,
+    config: &Config,
+    only: Option<&str>,
+    mut rescan: impl FnMut(PathBuf, DiscoveryOptions) -> Option<Vec<PathBuf>>,
+) -> Resynced {
+    let mut resynced = Resynced::default();
+    let blacklist = state.persistent_state.blacklist.clone();
+    let mut rescan = |path: Option<String>, options| rescan(PathBuf::from(path?), options).map(|images| blacklist.filter(images));
+    let covers = |outputs: &[String]| only.is_none_or(|only| outputs.iter().any(|output| output == only));
+
+    let changes = Arc::clone(&state.changes);
+    let outputs: Vec<String> = state.queues.keys().cloned().collect();
+    for output_name in outputs {
+        if !covers(std::slice::from_ref(&output_name)) {
+            continue;
+        }
+        let output_config = oriented_config(config, &state.outputs, &output_name);
+        let options = discovery_options(&output_config, &state.outputs, &[&output_name]);
+        let Some(images) = rescan(output_config.active_profile().path, options) else { continue };
+        if let Some(queue) = state.queues.get_mut(&output_name) {
+            if let Some(image) = sync_queue(queue, &output_name, &images, &mut resynced) {
+                request_change(&changes, config, &output_name, &image, ChangeReason::Refresh);
+            }
+        }
+    }
+
+    let shared = state.outputs.live().first().map(|first_output| {
+        let output_config = oriented_config(config, &state.outputs, first_output);
+        let options = discovery_options(&output_config, &state.outputs, state.outputs.live());
+        (output_config.active_profile().path, options)
+    });
+    if let (Some(shared_queue), Some((path, options))) = (&mut state.shared_queue, shared) {
+        if covers(state.outputs.live()) {
+            if let Some(images) = rescan(path, options) {
+                if let Some(image) = sync_queue(shared_queue, "synchronized outputs", &images, &mut resynced) {
+                    request_shared_change(&changes, config, state.outputs.live(), &image, ChangeReason::Refresh);
                 }
             }
         }
     }
+
+    for group in &mut state.groups {
+        if !covers(&group.outputs) {
+            continue;
+        }
+        let Some((group_config, profile)) = group_profile(config, &state.outputs, &group.outputs) else { continue };
+        let options = discovery_options(&group_config, &state.outputs, &group.outputs);
+        let Some(images) = rescan(profile.path, options) else { continue };
+        if let Some(image) = sync_queue(&mut group.queue, &group.name, &images, &mut resynced) {
+            request_shared_change(&changes, config, &group.outputs, &image, ChangeReason::Refresh);
+        }
+    }
+    resynced
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    env_logger::init();
+/// Make `queue` hold exactly `images`, keeping its place, and count what
+/// changed into `resynced`. Returns the image to show instead if the current
+/// one was deleted.
+fn sync_queue(queue: &mut Queue, owner: &str, images: &[PathBuf], resynced: &mut Resynced) -> Option<PathBuf> {
+    let shown = queue.current_image().cloned();
+    let added = queue.add_images(images.to_vec());
+    let keep: HashSet<&PathBuf> = images.iter().collect();
+    let gone: Vec<PathBuf> = queue.get_all_images().into_iter()
+        .filter(|image| !keep.contains(image))
+        .collect();
+    for image in &gone {
+        queue.remove_image(image);
+    }
+    if added > 0 || !gone.is_empty() {
+        log::info!("Wallpapers for {} changed on disk: {} added, {} removed", owner, added, gone.len());
+    }
+    resynced.added += added;
+    resynced.removed += gone.len();
 
-    log::info!("Starting swwws daemon...");
+    queue.current_image()
+        .filter(|current| Some(*current) != shown.as_ref())
+        .cloned()
+}
 
-    // Load configuration
-    let config = Config::load()
-        .map_err(|e| {
-            log::error!("Configuration error: {}", e.user_friendly_message());
-            anyhow::anyhow!("Configuration error: {}", e.user_friendly_message())
-        })?;
+/// Get ready to exit: save state and, with clear_on_exit, clear the managed
+/// outputs. The IPC server removes the socket and ends the main loop once
+/// the answer is sent.
+fn stop_daemon(state: &mut DaemonState, config: &Config) -> IpcResponse {
+    log::info!("Stopping on request");
+    shut_down(state, config);
+    IpcResponse::Success { message: "Stopping swwws daemon".to_string() }
+}
 
-    log::info!("Configuration loaded successfully");
+/// Save the state and, with clear_on_exit, clear the wallpapers, on the way out
+fn shut_down(state: &mut DaemonState, config: &Config) {
+    state.notifier.stopping();
+    if let Err(e) = state.save_state() {
+        log::error!("Failed to save state before stopping: {}", e);
+    }
+    if config.global.clear_on_exit {
+        if let Err(e) = state.swww.clear_outputs(state.outputs.live()) {
+            log::warn!("Failed to clear wallpapers: {}", e.user_friendly_message());
+        }
+    }
+}
 
-    // Check if swww daemon is running
-    ProcessExecutor::check_swww_daemon()
-        .map_err(|e| {
-            log::error!("swww daemon check failed: {}", e.user_friendly_message());
-            anyhow::anyhow!("swww daemon check failed: {}", e.user_friendly_message())
-        })?;
+/// Paths and options of the queues a rescan of `output` covers, or of every
+/// queue, scanned with the state lock held only to read them. They come from
+/// the config the daemon runs with, which the queues were built from.
+fn scan_for_rescan(state: &Mutex<DaemonState>, output: Option<&str>) -> Scans {
+    let (paths, runtime) = {
+        let mut state = state.lock().unwrap();
+        let config = match state.live_config.clone() {
+            Some(config) => config,
+            None => match load_config(&state) {
+                Ok(config) => Arc::new(config),
+                Err(_) => return Scans::default(),
+            },
+        };
+        let output = match output {
+            Some(output) => match state.outputs.resolve(output) {
+                Some(output_name) => Some(output_name),
+                None => return Scans::default(),
+            },
+            None => None,
+        };
+        let mut paths = Vec::new();
+        resync_queues(&mut state, &config, output.as_deref(), |path, options| {
+            paths.push((path, options));
+            None
+        });
+        (paths, state.runtime.clone())
+    };
+    runtime.block_on(Scans::rescan(paths))
+}
 
-    // Get swww outputs
-    let swww_outputs = ProcessExecutor::get_swww_outputs()
-        .map_err(|e| {
-            log::error!("Failed to get swww outputs: {}", e.user_friendly_message());
-            anyhow::anyhow!("Failed to get swww outputs: {}", e.user_friendly_message())
-        })?;
+/// Sync the queues showing on `output`, or every queue, with what `scans`
+/// found in their directories
+fn rescan_queues(state: &mut DaemonState, config: &Config, output: Option<&str>, scans: &Scans) -> IpcResponse {
+    let output = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => Some(output_name),
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => None,
+    };
 
-    if swww_outputs.is_empty() {
-        return Err(anyhow::anyhow!("No swww outputs found"));
+    let resynced = resync_queues(state, config, output.as_deref(), |path, options| {
+        scans.images(&path, &options)
+            .map_err(|e| log::warn!("Rescanning {:?} failed, keeping its queues as they are: {}", path, e))
+            .ok()
+    });
+    let scope = output.unwrap_or_else(|| "every output".to_string());
+    IpcResponse::Success { message: format!("Rescanned wallpapers for {}: added: {}, removed: {}", scope, resynced.added, resynced.removed) }
+}
+
+/// Remove the images `drop` picks from `queue`, counting them into `removed`.
+/// Returns the image to show instead if the current one was removed.
+fn remove_from_queue(queue: &mut Queue, drop: &impl Fn(&Path) -> bool, removed: &mut usize) -> Option<PathBuf> {
+    let shown = queue.current_image().cloned();
+    let dropped: Vec<PathBuf> = queue.get_all_images().into_iter().filter(|image| drop(image)).collect();
+    for image in &dropped {
+        queue.remove_image(image);
     }
+    *removed += dropped.len();
+    queue.current_image()
+        .filter(|current| Some(*current) != shown.as_ref())
+        .cloned()
+}
 
-    log::info!("Found swww outputs: {:?}", swww_outputs);
+/// Blacklist `path`, or the image on screen on `output`, and take it out of
+/// every queue. Without either, the image every output shows is meant.
+fn blacklist_image(state: &mut DaemonState, config: &Config, output: Option<&str>, path: Option<PathBuf>) -> IpcResponse {
+    let image = match (path, output) {
+        (Some(_), Some(_)) => return IpcResponse::error(ErrorKind::InvalidArgument, "Give an image or an output, not both"),
+        (Some(path), None) => path,
+        (None, Some(output)) => {
+            let Some(output_name) = state.outputs.resolve(output) else {
+                return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output));
+            };
+            match state.shown_image(&output_name) {
+                Some(image) => image,
+                None => return IpcResponse::error(ErrorKind::Unavailable, format!("{} shows no wallpaper yet", output_name)),
+            }
+        }
+        (None, None) => {
+            let shown: BTreeSet<PathBuf> = state.outputs.live().iter()
+                .filter_map(|output_name| state.shown_image(output_name))
+                .collect();
+            match &shown.into_iter().collect::<Vec<_>>()[..] {
+                [] => return IpcResponse::error(ErrorKind::Unavailable, "No wallpaper is shown yet"),
+                [image] => image.clone(),
+                _ => return IpcResponse::error(ErrorKind::InvalidArgument, "Outputs show different wallpapers; name one with --output"),
+            }
+        }
+    };
 
-    // Initialize daemon state
-    let mut state = DaemonState::new()
-        .map_err(|e| {
-            log::error!("Failed to initialize daemon state: {}", e);
-            anyhow::anyhow!("Failed to initialize daemon state: {}", e)
-        })?;
+    let added = state.persistent_state.blacklist.add(&image);
+    let blacklist = state.persistent_state.blacklist.clone();
+    let pins = state.changes.pins();
+    for (output_name, pinned) in pins.pinned() {
+        if blacklist.contains(&pinned) {
+            pins.unpin(&output_name);
+            // Queues showing it move on below; the rest go back to their image
+            if let Some(current) = state.current_image_for(&output_name).filter(|current| !blacklist.contains(current)).cloned() {
+                request_change(&state.changes, config, &output_name, &current, ChangeReason::Refresh);
+            }
+        }
+    }
+    let removed = drop_from_queues(state, config, |image| blacklist.contains(image));
+    if let Err(e) = state.save_state() {
+        log::error!("Failed to save state: {}", e);
+    }
 
+    let name = file_name(&image);
+    log::info!("Blacklisted {:?}, removed {} times from the queues", image, removed);
+    let message = match (added, removed) {
+        (false, 0) => format!("{} is blacklisted already", name),
+        (_, 0) => format!("Blacklisted {}; it is in no queue right now", name),
+        _ => format!("Blacklisted {}; it won't be shown again", name),
+    };
+    IpcResponse::Success { message }
+}
+
+/// Take `path` off the blacklist. It comes back into the queues with the next rescan.
+fn unblacklist_image(state: &mut DaemonState, path: &Path) -> IpcResponse {
+    if !state.persistent_state.blacklist.remove(path) {
+        return IpcResponse::error(ErrorKind::NotFound, format!("{:?} is not blacklisted", path));
+    }
+    if let Err(e) = state.save_state() {
+        log::error!("Failed to save state: {}", e);
+    }
+    IpcResponse::Success { message: format!("Removed {:?} from the blacklist; `swwws-cli rescan` puts it back in the queues", path) }
+}
+
+/// Hold `output`, or every managed output, on the image it shows now
+fn pin_outputs(state: &mut DaemonState, output: Option<&str>) -> IpcResponse {
+    let outputs = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if state.outputs.is_disabled(&output_name) => {
+                return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+            }
+            Some(output_name) => vec![output_name],
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => state.outputs.live().to_vec(),
+    };
+
+    let pins = state.changes.pins();
+    let mut pinned = Vec::new();
+    for output_name in &outputs {
+        let Some(image) = state.shown_image(output_name) else { continue };
+        if pins.pin(output_name, &image) {
+            log::info!("{}: pinned to {:?}", output_name, image);
+            pinned.push((output_name.as_str(), image));
+        }
+    }
+    if let Err(e) = state.save_state() {
+        log::error!("Failed to save state: {}", e);
+    }
+
+    let message = match (&outputs[..], &pinned[..]) {
+        ([output_name], []) => match pins.image(output_name) {
+            Some(image) => format!("{} is pinned to {} already", output_name, file_name(&image)),
+            None => return IpcResponse::error(ErrorKind::Unavailable, format!("{} shows no wallpaper yet", output_name)),
+        },
+        (_, [(output_name, image)]) => format!("Pinned {} on {}", file_name(image), output_name),
+        (_, []) if outputs.iter().all(|output_name| pins.is_pinned(output_name)) => "Every output is pinned already".to_string(),
+        (_, []) => return IpcResponse::error(ErrorKind::Unavailable, "No wallpaper is shown yet"),
+        _ => format!("Pinned {} outputs to their wallpapers", pinned.len()),
+    };
+    IpcResponse::Success { message }
+}
+
+/// Let `output`, or every pinned output, rotate again from where its queue is.
+/// Its timer starts over.
+fn unpin_outputs(state: &mut DaemonState, config: &Config, output: Option<&str>) -> IpcResponse {
+    let pins = state.changes.pins();
+    let outputs = match output {
+        Some(output) => match state.outputs.resolve(output) {
+            Some(output_name) if pins.is_pinned(&output_name) => vec![output_name],
+            Some(output_name) => return IpcResponse::error(ErrorKind::NotFound, format!("{} is not pinned", output_name)),
+            None => return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", output)),
+        },
+        None => pins.pinned().into_keys().collect(),
+    };
+    if outputs.is_empty() {
+        return IpcResponse::Success { message: "No output is pinned".to_string() };
+    }
+
+    for output_name in &outputs {
+        let Some(pinned) = pins.unpin(output_name) else { continue };
+        if let Some(timer) = state.timers.get_mut(output_name) {
+            *timer = Instant::now();
+        }
+        // Its queue may have moved on while it was pinned
+        if let Some(current) = state.current_image_for(output_name).filter(|current| **current != pinned).cloned() {
+            request_change(&state.changes, config, output_name, &current, ChangeReason::Refresh);
+        }
+    }
+    if let Err(e) = state.save_state() {
+        log::error!("Failed to save state: {}", e);
+    }
+
+    let message = match &outputs[..] {
+        [output_name] => format!("Unpinned {}", output_name),
+        _ => format!("Unpinned {} outputs", outputs.len()),
+    };
+    IpcResponse::Success { message }
+}
+
+fn file_name(image: &Path) -> std::borrow::Cow<'_, str> {
+    image.file_name().unwrap_or(image.as_os_str()).to_string_lossy()
+}
+
+/// The outputs connected now and, when they changed since the last look, the
+/// directories their queues are going to draw from, scanned with the state
+/// lock held only to read what is needed
+async fn scan_for_hotplug(state: &Mutex<DaemonState>, swww: &ProcessExecutor, startup_config: &Config) -> Option<(Vec<QueriedOutput>, Scans)> {
+    let queried = swww.query_swww_outputs().ok()?;
+    // swww answers with no outputs while it starts up, which is no unplug
+    if queried.is_empty() {
+        return None;
+    }
+
+    let paths = {
+        let state = tokio::task::block_in_place(|| state.lock().unwrap());
+        let connected: Vec<String> = queried.iter().map(|output| output.name.clone()).collect();
+        let unchanged = connected.len() == state.outputs.connected().len()
+            && connected.iter().all(|output| state.outputs.is_connected(output));
+        if unchanged {
+            return Some((queried, Scans::default()));
+        }
+        let config = state.live_config.as_deref().unwrap_or(startup_config);
+        let mut outputs = state.outputs.clone();
+        outputs.set_live(connected, config);
+        outputs.set_geometry(&queried);
+        wallpaper_paths(config, &outputs)
+    };
+    Some((queried, Scans::run(paths).await))
+}
+
+/// Bring the daemon in line with `queried`, the outputs connected now. A new
+/// output can join a group through a glob member, so the queues are rebuilt as
+/// on a reload, with outputs that stay keeping their image. Disconnected
+/// outputs lose their queue and timer; their saved queue resumes when they
+/// come back.
+fn follow_outputs(state: &mut DaemonState, config: &Config, queried: &[QueriedOutput], scans: &Scans) {
+    let connected: Vec<String> = queried.iter().map(|output| output.name.clone()).collect();
+    let added: Vec<String> = connected.iter()
+        .filter(|output| !state.outputs.is_connected(output))
+        .cloned()
+        .collect();
+    let removed: Vec<String> = state.outputs.connected().iter()
+        .filter(|output| !connected.contains(output))
+        .cloned()
+        .collect();
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+    if !added.is_empty() {
+        log::info!("Outputs connected: {:?}", added);
+    }
+    if !removed.is_empty() {
+        log::info!("Outputs disconnected: {:?}", removed);
+    }
+
+    // Record where the outputs going away were, for when they return
+    if let Err(e) = state.save_state() {
+        log::warn!("Failed to save state before following outputs: {}", e);
+    }
+    let start_at: HashMap<String, PathBuf> = state.outputs.live().iter()
+        .filter(|output| connected.contains(output))
+        .filter_map(|output| Some((output.clone(), state.mode_of(output)?.1.current_image()?.clone())))
+        .collect();
+
+    state.outputs.set_live(connected, config);
+    state.outputs.set_geometry(queried);
+    if let Err(e) = reinitialize_daemon_state_sync(state, config, scans, &start_at, &added) {
+        log::error!("Failed to set up the connected outputs: {}", e);
+    }
+}
+
+/// Whether any output has reapply_on_wake set, which takes a `swww query` every second
+fn wants_wake_checks(state: &DaemonState, config: &Config) -> bool {
+    state.outputs.live().iter().any(|output| config.get_output_config(output).reapply_on_wake)
+}
+
+/// Re-apply the current image on outputs that just came back on, for those
+/// with reapply_on_wake set. Does nothing when no output has it set.
+fn reapply_on_wake(state: &DaemonState, config: &Config, power: &mut PowerTracker) {
+    if !wants_wake_checks(state, config) {
+        return;
+    }
+
+    let outputs = match state.swww.get_swww_outputs() {
+        Ok(outputs) => outputs,
+        Err(e) => {
+            log::debug!("Skipping wake check, swww query failed: {}", e.user_friendly_message());
+            return;
+        }
+    };
+
+    for output_name in power.observe(&outputs, Instant::now()) {
+        if !state.outputs.is_live(&output_name) || !config.get_output_config(&output_name).reapply_on_wake {
+            continue;
+        }
+        if let Some(image) = state.current_image_for(&output_name) {
+            log::info!("{} came back on, re-applying {:?}", output_name, image);
+            request_change(&state.changes, config, &output_name, image, ChangeReason::Wake);
+        }
+    }
+}
+
+/// Put the current image back on every live output after swww-daemon came
+/// back from a restart without them. No queue moves.
+fn reapply_after_restart(state: &DaemonState, config: &Config) {
+    for output_name in state.outputs.live() {
+        if let Some(image) = state.current_image_for(output_name) {
+            log::info!("Re-applying {:?} on {}", image, output_name);
+            request_change(&state.changes, config, output_name, image, ChangeReason::Wake);
+        }
+    }
+}
+
+/// Inode of swww-daemon's socket, which a restarted swww-daemon replaces
+fn swww_socket() -> Option<u64> {
+    std::fs::metadata(SwwwClient::get_socket_path()).ok().map(|metadata| metadata.ino())
+}
+
+/// Set up groups, the shared queue and individual queues for `config`, from
+/// images scanned beforehand, and show their first images
+fn initialize_queues(state: &mut DaemonState, config: &Config, scans: &Scans) -> anyhow::Result<()> {
     // Initialize monitor behavior (groups, synchronized, etc.)
-    if let Err(e) = initialize_monitor_behavior(&mut state, &config, &swww_outputs) {
+    if let Err(e) = initialize_monitor_behavior(state, config, scans) {
         log::error!("Failed to initialize monitor behavior: {}", e);
         return Err(e);
     }
 
+    // Outputs with enabled = false are left alone from here on
+    let managed = state.outputs.live().to_vec();
+
     // Initialize individual queues based on monitor behavior
     let behavior = config.get_effective_monitor_behavior();
     log::info!("Detected monitor behavior: {:?}", behavior);
@@ -619,47 +2114,16 @@ async fn main() -> anyhow::Result<()> {
         MonitorBehavior::Independent => {
             // Initialize queues for each output independently
             log::info!("Initializing individual queues for Independent mode");
-            for output_name in &swww_outputs {
-                initialize_output_queue(&mut state, output_name, &config).await;
+            for output_name in &managed {
+                initialize_output_queue(state, output_name, config, scans);
             }
         }
         MonitorBehavior::Synchronized => {
-            // For synchronized mode, set initial wallpaper on all outputs from shared queue
-            log::info!("Setting initial synchronized wallpapers (no individual queues)");
-            if let Some(shared_queue) = &state.shared_queue {
-                if let Some(current_image) = shared_queue.current_image() {
-                    let command_builder = CommandBuilder::new(PathBuf::from("swww"));
-                    let executor = ProcessExecutor::new(command_builder);
-                    
-                    for output_name in &swww_outputs {
-                        let output_config = config.get_output_config(output_name);
-                        let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-                        
-                        if let Err(e) = executor.execute_swww_command(
-                            current_image,
-                            &common_config,
-                            Some(output_name),
-                        ).await {
-                            log::error!("Failed to set initial synchronized wallpaper for {}: {}", 
-                                output_name, e.user_friendly_message());
-                        } else {
-                            log::info!("Set initial synchronized wallpaper for {}: {:?}", output_name, current_image);
-                        }
-                    }
+            // For synchronized mode, set initial wallpaper on all outputs from shared queue
+            log::info!("Setting initial synchronized wallpapers (no individual queues)");
+            if let Some(shared_queue) = &state.shared_queue {
+                if let Some(current_image) = shared_queue.current_image() {
+                    request_shared_change(&state.changes, config, &managed, current_image, ChangeReason::Startup);
                 }
             } else {
                 log::error!("Synchronized mode enabled but no shared queue created!");
@@ -667,48 +2131,15 @@ async fn main() -> anyhow::Result<()> {
         }
         MonitorBehavior::Grouped(_) => {
             // For grouped mode, set initial wallpaper for each group
-            let command_builder = CommandBuilder::new(PathBuf::from("swww"));
-            let executor = ProcessExecutor::new(command_builder);
-            
             for group in &state.groups {
                 if let Some(current_image) = group.queue.current_image() {
-                    for output_name in &group.outputs {
-                        let output_config = config.get_output_config(output_name);
-                        let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-                        
-                        if let Err(e) = executor.execute_swww_command(
-                            current_image,
-                            &common_config,
-                            Some(output_name),
-                        ).await {
-                            log::error!("Failed to set initial group wallpaper for {}: {}", 
-                                output_name, e.user_friendly_message());
-                        } else {
-                            log::info!("Set initial group wallpaper for {}: {:?}", output_name, current_image);
-                        }
-                    }
+                    request_shared_change(&state.changes, config, &group.outputs, current_image, ChangeReason::Startup);
                 }
             }
             
             // Also initialize independent queues for outputs not in any group
-            for output_name in &swww_outputs {
-                if !state.groups.iter().any(|g| g.outputs.contains(output_name)) {
-                    initialize_output_queue(&mut state, output_name, &config).await;
-                }
+            for output_name in state.outputs.ungrouped() {
+                initialize_output_queue(state, &output_name, config, scans);
             }
         }
     }
@@ -729,7 +2160,7 @@ async fn main() -> anyhow::Result<()> {
         log::error!("Possible causes:");
         log::error!("  - No wallpaper paths configured in config file");
         log::error!("  - Configured paths don't exist or contain no valid images");
-        log::error!("  - Display outputs don't match configuration (found: {:?})", swww_outputs);
+        log::error!("  - Display outputs don't match configuration (found: {:?})", state.outputs.connected());
         
         // Give specific hints based on monitor behavior
         match config.get_effective_monitor_behavior() {
@@ -740,7 +2171,7 @@ async fn main() -> anyhow::Result<()> {
                 log::error!("  - For Synchronized mode: ensure [any] section has a valid 'path' setting");
             },
             MonitorBehavior::Grouped(_) => {
-                log::error!("  - For Grouped mode: ensure monitor_groups are configured with valid paths");
+                log::error!("  - For Grouped mode: ensure monitor_groups or [groups] sections are configured with valid paths");
             },
         }
         
@@ -750,378 +2181,1028 @@ async fn main() -> anyhow::Result<()> {
     log::info!("Daemon initialized successfully: {} individual queues, {} groups, shared queue: {}", 
         state.queues.len(), state.groups.len(), has_shared_queue);
 
-    // Create shared state for IPC
+    Ok(())
+}
+
+/// Build every queue and move each one image on, for `--oneshot`. Queues
+/// restored from the state file go on to their next image. Shared queues are
+/// not saved, so they go on from the image the change log last has for their
+/// first output. New queues and static outputs show their current image.
+/// Returns how many outputs were asked to change.
+fn oneshot(state: &mut DaemonState, config: &Config, scans: &Scans) -> anyhow::Result<usize> {
+    initialize_monitor_behavior(state, config, scans)?;
+    let changes = Arc::clone(&state.changes);
+    let live = state.outputs.live().to_vec();
+    let mut requested = 0;
+
+    if let Some(queue) = &mut state.shared_queue {
+        requested += step_shared_queue(queue, &changes, config, &live);
+    }
+    for group in &mut state.groups {
+        requested += step_shared_queue(&mut group.queue, &changes, config, &group.outputs);
+    }
+
+    let single = match config.get_effective_monitor_behavior() {
+        MonitorBehavior::Independent => live,
+        MonitorBehavior::Synchronized => Vec::new(),
+        MonitorBehavior::Grouped(_) => state.outputs.ungrouped(),
+    };
+    for output_name in single {
+        let new = build_output_queue(state, &output_name, config, scans);
+        let (image, reason) = if new || config.get_output_config(&output_name).is_static() {
+            (state.queues.get(&output_name).and_then(|queue| queue.current_image().cloned()), ChangeReason::Startup)
+        } else {
+            (state.advance_output(&output_name, config), ChangeReason::Timer)
+        };
+        if let Some(image) = image {
+            request_change(&changes, config, &output_name, &image, reason);
+            requested += 1;
+        }
+    }
+    Ok(requested)
+}
+
+/// `oneshot` for a queue `outputs` share
+fn step_shared_queue(queue: &mut Queue, changes: &ChangePipeline, config: &Config, outputs: &[String]) -> usize {
+    let last_set = outputs.first()
+        .and_then(|output| changes.change_log().latest(Some(output), 1).pop())
+        .map(|(_, change)| PathBuf::from(change.image));
+    let resumed = last_set.is_some_and(|image| jump_to(queue, &image));
+    let (image, reason) = if resumed && !shared_is_static(config, outputs) {
+        (queue.next().cloned(), ChangeReason::Timer)
+    } else {
+        (queue.current_image().cloned(), ChangeReason::Startup)
+    };
+    let Some(image) = image else { return 0 };
+    request_shared_change(changes, config, outputs, &image, reason);
+    outputs.len()
+}
+
+/// Pause after the `attempt`th look for swww at startup came up empty
+fn startup_backoff(attempt: u32) -> Duration {
+    STARTUP_BACKOFF.saturating_mul(2u32.saturating_pow(attempt)).min(STARTUP_MAX_BACKOFF)
+}
+
+/// Wait up to `timeout` for swww-daemon to answer with at least one output.
+/// Started along with the compositor, swwws can come up before swww-daemon
+/// does, or before the compositor has announced its outputs.
+async fn wait_for_swww(swww: &ProcessExecutor, timeout: Duration) -> anyhow::Result<Vec<QueriedOutput>> {
+    let started = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let problem = match swww.check_swww_daemon().and_then(|()| swww.query_swww_outputs()) {
+            Ok(outputs) if !outputs.is_empty() => return Ok(outputs),
+            Ok(_) => "no swww outputs found".to_string(),
+            Err(e) => e.user_friendly_message(),
+        };
+        let waited = started.elapsed();
+        if waited >= timeout {
+            log::error!("swww not ready after {}: {}", humantime::format_duration(timeout), problem);
+            anyhow::bail!("swww not ready after {}: {}", humantime::format_duration(timeout), problem);
+        }
+        let pause = startup_backoff(attempt).min(timeout - waited);
+        log::info!("Waiting for swww-daemon ({}), trying again in {:?}", problem, pause);
+        tokio::time::sleep(pause).await;
+        attempt += 1;
+    }
+}
+
+/// Refuse to start while another daemon answers on `socket`, which would
+/// remove its socket and fight it over the wallpapers. With `replace`, ask it
+/// to stop instead and wait until it is gone.
+fn claim_socket(socket: &Path, replace: bool) -> anyhow::Result<()> {
+    let client = IpcClient::new().with_timeout(INSTANCE_TIMEOUT);
+    let (pid, problem) = match client.send_command(IpcCommand::Ping) {
+        Ok(IpcResponse::Pong { pid, .. }) => (pid, None),
+        Ok(IpcResponse::Error { message, .. }) => (None, Some(message)),
+        Ok(_) => (None, None),
+        Err(e) => {
+            let kind = e.chain().find_map(|cause| cause.downcast_ref::<std::io::Error>()).map(std::io::Error::kind);
+            if matches!(kind, Some(std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused)) {
+                // Nobody there; a socket left behind is replaced when the server binds
+                return Ok(());
+            }
+            // Something holds the socket without answering
+            (None, Some(format!("{:#}", e)))
+        }
+    };
+    let mut running = match pid {
+        Some(pid) => format!("swwws-daemon is already running (pid {}) on {:?}", pid, socket),
+        None => format!("swwws-daemon is already running on {:?}", socket),
+    };
+    if let Some(problem) = problem {
+        running = format!("{} and fails to answer ({})", running, problem);
+    }
+    if !replace {
+        anyhow::bail!("{}; stop it with `swwws-cli stop` or start with --replace", running);
+    }
+
+    log::info!("{}, asking it to stop", running);
+    match client.send_command(IpcCommand::Shutdown) {
+        Ok(IpcResponse::Error { message, .. }) => anyhow::bail!("The running daemon failed to stop: {}", message),
+        Ok(_) => {}
+        Err(e) => anyhow::bail!("Failed to stop the running daemon: {:#}", e),
+    }
+    // Its socket goes first, then the process, once it cleared its wallpapers
+    let gone = || {
+        !socket.exists()
+            // SAFETY: signal 0 only checks that the process exists
+            && pid.is_none_or(|pid| unsafe { libc::kill(pid as libc::pid_t, 0) } != 0)
+    };
+    let asked = Instant::now();
+    while !gone() {
+        if asked.elapsed() > INSTANCE_TIMEOUT {
+            anyhow::bail!("{} and did not stop within {:?}", running, INSTANCE_TIMEOUT);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    log::info!("The previous daemon stopped");
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let logs = logging::init(args.log_level);
+
+    log::info!("Starting swwws daemon...");
+    let started = Instant::now();
+
+    let socket = SocketLocation::current();
+    if let Some(reason) = &socket.fallback_reason {
+        log::warn!("⚠ {}: running in a degraded setup", reason);
+        log::warn!("⚠   The IPC socket goes to {:?} instead; swwws-cli looks there too", socket.path);
+        log::warn!("⚠   swww keeps its socket in the runtime dir as well, so it may not be found");
+        log::warn!("⚠   Start swwws from a login session that sets XDG_RUNTIME_DIR to fix this");
+    }
+    claim_socket(&socket.path, args.replace)?;
+
+    // Load configuration
+    let config_path = match args.config {
+        Some(path) => path,
+        None => Config::config_path()
+            .map_err(|e| anyhow::anyhow!("Configuration error: {}", e.user_friendly_message()))?,
+    };
+    log::info!("Using configuration file: {:?}", config_path);
+    let loaded_mtime = file_mtime(&config_path);
+    let config = Config::load_from(&config_path)
+        .map(Arc::new)
+        .map_err(|e| {
+            log::error!("Configuration error: {}", e.user_friendly_message());
+            anyhow::anyhow!("Configuration error: {}", e.user_friendly_message())
+        })?;
+
+    log::info!("Configuration loaded successfully");
+
+    if let Some(log_file) = args.log_file.clone().or_else(|| config.global.log_file.as_ref().map(PathBuf::from)) {
+        // Validated with the config
+        let level = args.log_level
+            .or_else(|| config.global.log_level.as_deref().and_then(|level| level.parse().ok()))
+            .unwrap_or(log::LevelFilter::Info);
+        match logs.open(&log_file, level, config.global.log_max_size * 1024 * 1024, config.global.log_keep) {
+            Ok(()) => log::info!("Logging to {:?} at {} level", log_file, level),
+            Err(e) => log::warn!("Can't log to {:?}, logging to stderr only: {}", log_file, e),
+        }
+    }
+
+    let state_file = PersistentState::get_state_file(config.global.state_file.as_deref().map(Path::new));
+    log::info!("Using state file: {:?}", state_file);
+
+    let swww_path = ProcessExecutor::find_swww(config.global.swww_binary.as_deref().map(Path::new))
+        .map_err(|e| {
+            log::error!("{}", e.user_friendly_message());
+            anyhow::anyhow!("{}", e.user_friendly_message())
+        })?;
+    log::info!("Using swww at {:?}", swww_path);
+    let dry_run = args.dry_run || config.global.dry_run;
+    if dry_run {
+        log::warn!("Dry run: swww commands are printed instead of run, so wallpapers won't change");
+    }
+    let swww = Arc::new(ProcessExecutor::new(CommandBuilder::new(swww_path)).with_dry_run(dry_run));
+
+    // Get swww outputs, with sizes for orientation-specific paths
+    let queried = wait_for_swww(&swww, config.global.startup_timeout).await?;
+    let swww_outputs: Vec<String> = queried.iter().map(|output| output.name.clone()).collect();
+
+    log::info!("Found swww outputs: {:?}", swww_outputs);
+
+    // All swww invocations are funneled through one pipeline so a burst of changes
+    // across many outputs can't spawn an unbounded number of processes. Its lanes
+    // run on this runtime, so IPC threads only hand changes over.
+    let changes = Arc::new(ChangePipeline::new(tokio::runtime::Handle::current(), swww.clone(), config.global.max_concurrent_changes));
+    changes.change_log().set_limit(config.global.change_log_size);
+
+    // Initialize daemon state
+    let mut outputs = OutputRegistry::new(swww_outputs.clone(), &config);
+    outputs.set_geometry(&queried);
+    let mut state = DaemonState::new(config_path, state_file, loaded_mtime, Arc::clone(&changes), Arc::clone(&swww), outputs)
+        .map_err(|e| {
+            log::error!("Failed to initialize daemon state: {}", e);
+            anyhow::anyhow!("Failed to initialize daemon state: {}", e)
+        })?;
+    // What a reload compares the file against
+    state.live_config = Some(Arc::clone(&config));
+
+    if args.oneshot {
+        let scans = Scans::run(wallpaper_paths(&config, &state.outputs)).await;
+        let requested = oneshot(&mut state, &config, &scans)?;
+        tokio::task::block_in_place(|| changes.wait_idle());
+        if let Err(e) = state.save_state() {
+            log::error!("Failed to save state: {}", e);
+        }
+        let metrics = changes.metrics();
+        if metrics.completed == 0 {
+            anyhow::bail!("No wallpaper could be set ({} changes asked for, {} failed)", requested, metrics.failed);
+        }
+        log::info!("Set {} wallpapers, exiting", metrics.completed);
+        return Ok(());
+    }
+
+    let notifier = Arc::new(Notifier::from_env());
+    state.notifier = Arc::clone(&notifier);
+
+    // Serve IPC right away, so `status` answers while big collections are scanned
     let shared_state = Arc::new(Mutex::new(state));
-    let command_builder = CommandBuilder::new(PathBuf::from("swww"));
-    let executor = ProcessExecutor::new(command_builder);
 
     // Start IPC server
     let ipc_state = Arc::clone(&shared_state);
-    let ipc_executor = executor.clone();
+    let subscribers = changes.subscribers();
+    // Notified by the IPC server once `swwws-cli stop` has its answer
+    let shutdown = Arc::new(Notify::new());
+    let ipc_shutdown = Arc::clone(&shutdown);
+    // Notified after every command, which may have moved, paused or resumed a
+    // timer, so the timer loop works out its next deadline again
+    let reschedule = Arc::new(Notify::new());
+    let ipc_reschedule = Arc::clone(&reschedule);
     
     std::thread::spawn(move || {
-        let server = IpcServer::new();
-        if let Err(e) = server.start(move |cmd| {
-            Ok(handle_ipc_command(cmd, Arc::clone(&ipc_state), ipc_executor.clone()))
+        let server = IpcServer::new().with_subscribers(subscribers).with_shutdown(ipc_shutdown);
+        if let Err(e) = server.start(move |cmd| match cmd {
+            // Without the state lock, so a daemon in the middle of a long reload still answers
+            IpcCommand::Ping => Ok(pong(started.elapsed().as_secs())),
+            cmd => {
+                let response = handle_ipc_command(cmd, Arc::clone(&ipc_state));
+                ipc_reschedule.notify_one();
+                Ok(response)
+            }
         }) {
             log::error!("IPC server error: {}", e);
         }
     });
 
+    // Installed before the first scan, so a signal arriving during it waits for
+    // the loop below instead of killing the daemon without saving
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    // Scan without holding the lock, then build every queue in one go
+    let paths = wallpaper_paths(&config, &shared_state.lock().unwrap().outputs);
+    let scans = Scans::run(paths).await;
+    initialize_queues(&mut shared_state.lock().unwrap(), &config, &scans)?;
+
+    // Units ordered after swwws start once the first wallpapers are on screen
+    tokio::task::block_in_place(|| changes.wait_idle());
+    notifier.ready(&service_status(&shared_state.lock().unwrap()));
     log::info!("Daemon started successfully with {} outputs", shared_state.lock().unwrap().queues.len());
 
-    // Main timer loop with error recovery
-    let mut interval = interval(Duration::from_secs(1));
-    let mut save_counter = 0;
-    let mut swww_check_counter = 0;
+    // The loop sleeps until the next wallpaper change is due. Everything else
+    // it looks after runs on its own, slower schedule
+    let mut housekeeping = every(HOUSEKEEPING_PERIOD);
+    let mut saves = every(SAVE_PERIOD);
+    let mut health_checks = every(HEALTH_CHECK_PERIOD);
+    let mut wake_checks = every(Duration::from_secs(1));
+    // Pinged from the loop itself, so systemd restarts a daemon stuck in it
+    let watchdog_period = notifier.watchdog_interval();
+    let mut watchdog = every(watchdog_period.unwrap_or(HOUSEKEEPING_PERIOD));
+    let mut power = PowerTracker::new(WAKE_DEBOUNCE);
+    power.observe(&swww_outputs, Instant::now());
+    let mut watcher = DirectoryWatcher::new()
+        .map_err(|e| log::warn!("Can't watch wallpaper directories, new images are only picked up on reload: {}", e))
+        .ok();
+    let mut suspend = SuspendDetector::new(SUSPEND_THRESHOLD);
+    let mut restarts = SwwwRestartDetector::default();
+    restarts.check(true, swww_socket());
+    let mut resuming = false;
+    // Set while a failed health check waits to be retried
+    let mut health_retry: Option<Instant> = None;
+    let (mut deadline, mut check_wakes) = {
+        let state = shared_state.lock().unwrap();
+        (next_deadline(&state, &config), wants_wake_checks(&state, &config))
+    };
 
-    loop {
-        interval.tick().await;
-        save_counter += 1;
-        swww_check_counter += 1;
-
-        // Periodically check if swww daemon is still running (every 30 seconds)
-        if swww_check_counter >= 30 {
-            swww_check_counter = 0;
-            match ProcessExecutor::check_swww_daemon() {
+    let stopped_by = loop {
+        // Housekeeping wakes the loop every few seconds anyway. A timer is not
+        // looked at again within a second, so a random interval that picked
+        // zero can't spin the loop.
+        let now = Instant::now();
+        let sleep_until = deadline.map_or(now + HOUSEKEEPING_PERIOD, |deadline: Instant| deadline.max(now + Duration::from_secs(1)));
+        let task = tokio::select! {
+            _ = tokio::time::sleep_until(sleep_until.into()) => Task::Timers,
+            _ = reschedule.notified() => Task::Timers,
+            _ = housekeeping.tick() => Task::Housekeeping,
+            _ = saves.tick() => Task::Save,
+            _ = health_checks.tick() => Task::HealthCheck,
+            _ = tokio::time::sleep_until(health_retry.unwrap_or(now).into()), if health_retry.is_some() => Task::HealthRetry,
+            _ = wake_checks.tick(), if check_wakes => Task::WakeCheck,
+            _ = watchdog.tick(), if watchdog_period.is_some() => {
+                notifier.watchdog();
+                continue;
+            }
+            _ = shutdown.notified() => break None,
+            _ = sigterm.recv() => break Some("SIGTERM"),
+            _ = sigint.recv() => break Some("SIGINT"),
+            _ = sighup.recv() => {
+                // The same as `swwws-cli reload`, off the timer loop like IPC commands
+                log::info!("Received SIGHUP, reloading configuration");
+                let state = Arc::clone(&shared_state);
+                let reschedule = Arc::clone(&reschedule);
+                tokio::task::spawn_blocking(move || {
+                    if let IpcResponse::Error { message, .. } = handle_ipc_command(IpcCommand::Reload, state) {
+                        log::error!("Reload on SIGHUP failed: {}", message);
+                    }
+                    reschedule.notify_one();
+                });
+                continue;
+            }
+        };
+
+        // After a resume nothing is sent to swww until it answers again
+        if let Some(asleep) = suspend.tick(SystemTime::now()) {
+            log::info!("System resumed after {:?}, checking swww before going on", Duration::from_secs(asleep.as_secs()));
+            resuming = true;
+        }
+        // A swww-daemon that came back shows nothing until it is told again
+        let mut restarted = false;
+        if resuming {
+            if let Err(e) = swww.check_swww_daemon() {
+                log::debug!("swww not answering yet after resume: {}", e.user_friendly_message());
+                restarts.check(false, None);
+                continue;
+            }
+            restarted = restarts.check(true, swww_socket());
+        }
+
+        if matches!(task, Task::HealthCheck | Task::HealthRetry) {
+            let retrying = health_retry.take().is_some();
+            match swww.check_swww_daemon() {
                 Ok(()) => {
-                    // swww daemon is running, all good
+                    if retrying {
+                        log::info!("swww daemon recovered successfully");
+                    }
+                    restarted = restarts.check(true, swww_socket());
+                }
+                Err(e) if retrying => {
+                    log::error!("swww daemon still not available after retry: {}. Continuing to monitor...", e.user_friendly_message());
+                    // Don't exit, just keep trying - user might restart swww daemon
+                    continue;
                 }
                 Err(e) => {
                     log::error!("swww daemon check failed: {}. Attempting to recover...", e.user_friendly_message());
-                    // Wait a bit and try again
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    match ProcessExecutor::check_swww_daemon() {
-                        Ok(()) => {
-                            log::info!("swww daemon recovered successfully");
-                        }
-                        Err(e2) => {
-                            log::error!("swww daemon still not available after retry: {}. Continuing to monitor...", e2.user_friendly_message());
-                            // Don't exit, just keep trying - user might restart swww daemon
-                            continue;
-                        }
-                    }
+                    restarts.check(false, None);
+                    // Try again shortly, without holding up signals, IPC or timers
+                    health_retry = Some(Instant::now() + HEALTH_RETRY_DELAY);
+                    continue;
                 }
             }
         }
 
-        let mut state_guard = match shared_state.try_lock() {
-            Ok(guard) => guard,
-            Err(_) => {
-                log::warn!("Failed to acquire state lock, skipping this cycle");
-                continue;
-            }
+        // Scanning for changed directories, outputs plugged in or out and
+        // schedule switches doesn't hold up IPC commands
+        let housekeeping = task == Task::Housekeeping;
+        let (changed, watch_scans) = match &mut watcher {
+            Some(watcher) if housekeeping => scan_for_changed_paths(&shared_state, watcher, &config).await,
+            _ => (Vec::new(), Scans::default()),
         };
+        let hotplug = if housekeeping { scan_for_hotplug(&shared_state, &swww, &config).await } else { None };
+        let schedule_scans = if housekeeping { scan_for_schedules(&shared_state, &config).await } else { Scans::default() };
 
-        // Skip processing if paused
-        if state_guard.paused {
-            continue;
-        }
+        // IPC commands only hold the lock to pick images; swww runs in the change
+        // pipeline. Wait for them instead of skipping the wakeup, so no timer is missed.
+        let mut state_guard = tokio::task::block_in_place(|| shared_state.lock().unwrap());
 
-        // Check for expired timers
-        let mut expired_outputs = Vec::new();
-        for (output_name, timer) in &state_guard.timers {
-            let output_config = config.get_output_config(output_name);
-            let target_duration: Duration = output_config.duration;
-            
-            if timer.elapsed() >= target_duration {
-                expired_outputs.push(output_name.clone());
-            }
+        // Pick up settings changed by a reload or `output configure`
+        let live_config = state_guard.live_config.clone();
+        let config: &Config = live_config.as_deref().unwrap_or(&config);
+
+        if std::mem::take(&mut resuming) {
+            resume_timers(&mut state_guard, config, Instant::now());
+        }
+        if restarted {
+            log::info!("swww-daemon restarted, putting the wallpapers back");
+            reapply_after_restart(&state_guard, config);
         }
 
-        // Process timers based on monitor behavior
-        let behavior = config.get_effective_monitor_behavior();
-        
-        match behavior {
-            MonitorBehavior::Independent => {
-                // Process individual output timers
-                if !expired_outputs.is_empty() {
-                    for output_name in expired_outputs {
-                        if let Some(queue) = state_guard.queues.get_mut(&output_name) {
-                            if let Some(next_image) = queue.next() {
-                                change_wallpaper(&output_name, &next_image, &config, &executor).await;
-                                state_guard.timers.insert(output_name.clone(), Instant::now());
-                            }
-                        }
-                    }
+        drop_missing_images(&mut state_guard, config);
+
+        match task {
+            Task::Housekeeping => {
+                // Follow images added to or deleted from the wallpaper directories
+                if !changed.is_empty() {
+                    reconcile_changed_paths(&mut state_guard, config, &changed, &watch_scans);
                 }
-            }
-            MonitorBehavior::Synchronized => {
-                // Check shared timer
-                if let Some(shared_timer) = &state_guard.shared_timer {
-                    let target_duration = config.get_output_config(&swww_outputs[0]).duration;
-                    if shared_timer.elapsed() >= target_duration {
-                        if let Some(shared_queue) = &mut state_guard.shared_queue {
-                            if let Some(next_image) = shared_queue.next() {
-                                log::info!("Synchronized mode: Setting same image on all outputs: {:?}", next_image);
-                                // Set the same image on all outputs
-                                for output_name in &swww_outputs {
-                                    change_wallpaper(output_name, &next_image, &config, &executor).await;
-                                }
-                                state_guard.shared_timer = Some(Instant::now());
-                            }
-                        }
-                    }
+
+                if let Some((queried, scans)) = &hotplug {
+                    follow_outputs(&mut state_guard, config, queried, scans);
                 }
-            }
-            MonitorBehavior::Grouped(_) => {
-                // Check group timers
-                for group in &mut state_guard.groups {
-                    let target_duration = if let Some(first_output) = group.outputs.first() {
-                        config.get_output_config(first_output).duration
-                    } else {
-                        Duration::from_secs(300) // fallback
-                    };
-                    
-                    if group.timer.elapsed() >= target_duration {
-                        if let Some(next_image) = group.queue.next() {
-                            log::info!("Group '{}': Setting image on grouped outputs: {:?}", group.name, next_image);
-                            // Set the same image on all outputs in this group
-                            for output_name in &group.outputs {
-                                change_wallpaper(output_name, &next_image, &config, &executor).await;
-                            }
-                            group.timer = Instant::now();
-                        }
-                    }
+
+                // Follow schedule windows; paused outputs switch once resumed
+                if !state_guard.paused {
+                    apply_schedules(&mut state_guard, config, &schedule_scans);
                 }
-                
-                // Also process individual output timers for outputs not in any group
-                if !expired_outputs.is_empty() {
-                    for output_name in expired_outputs {
-                        // Only process if output is not in any group
-                        let is_in_group = state_guard.groups.iter().any(|g| g.outputs.contains(&output_name));
-                        if !is_in_group {
-                            if let Some(queue) = state_guard.queues.get_mut(&output_name) {
-                                if let Some(next_image) = queue.next() {
-                                    change_wallpaper(&output_name, &next_image, &config, &executor).await;
-                                    state_guard.timers.insert(output_name.clone(), Instant::now());
-                                }
-                            }
-                        }
-                    }
+            }
+            Task::WakeCheck => reapply_on_wake(&state_guard, config, &mut power),
+            Task::Save => {
+                if let Err(e) = state_guard.save_state() {
+                    log::error!("Failed to save state: {}", e);
                 }
             }
+            Task::Timers | Task::HealthCheck | Task::HealthRetry => {}
         }
 
-        // Save state periodically (every 30 seconds)
-        if save_counter >= 30 {
-            if let Err(e) = state_guard.save_state() {
-                log::error!("Failed to save state: {}", e);
-            }
-            save_counter = 0;
+        // Whatever woke the loop, timers that are due by now fire
+        run_due_timers(&mut state_guard, config, Instant::now());
+        deadline = next_deadline(&state_guard, config);
+        check_wakes = wants_wake_checks(&state_guard, config);
+    };
+
+    // `swwws-cli stop` saved the state and removed the socket before answering
+    if let Some(signal) = stopped_by {
+        log::info!("Received {}, stopping", signal);
+        let mut state_guard = tokio::task::block_in_place(|| shared_state.lock().unwrap());
+        let live_config = state_guard.live_config.clone();
+        shut_down(&mut state_guard, live_config.as_deref().unwrap_or(&config));
+        if let Err(e) = std::fs::remove_file(&socket.path) {
+            log::warn!("Failed to remove socket {:?}: {}", socket.path, e);
         }
     }
+
+    log::info!("swwws daemon stopped");
+    Ok(())
 }
 
 fn handle_next_for_output(
     state: &mut DaemonState,
     output_name: &str,
     config: &Config,
-    executor: &ProcessExecutor,
+) {
+    if let Some(next_image) = state.advance_output(output_name, config) {
+        request_change(&state.changes, config, output_name, &next_image, ChangeReason::Manual);
+        state.timers.insert(output_name.to_string(), Instant::now());
+    }
+}
+
+/// Move the group at `index` one image forward or back on all of its unpinned outputs
+fn step_group(state: &mut DaemonState, config: &Config, index: usize, forward: bool) -> IpcResponse {
+    let group = &mut state.groups[index];
+    let targets = state.changes.pins().unpinned(&group.outputs);
+    if targets.is_empty() {
+        return IpcResponse::error(ErrorKind::Unavailable, format!("Every output in group '{}' is pinned", group.name));
+    }
+    let image = if forward { group.queue.next() } else { group.queue.previous() };
+    let Some(image) = image.cloned() else {
+        return IpcResponse::error(ErrorKind::Unavailable, format!("Group '{}' has no {} image", group.name, if forward { "next" } else { "previous" }));
+    };
+    log::info!("IPC Group '{}': Setting image {:?} on group outputs", group.name, image);
+    request_shared_change(&state.changes, config, &targets, &image, ChangeReason::Manual);
+    group.timer = Instant::now();
+    let direction = if forward { "Next" } else { "Previous" };
+    IpcResponse::Success { message: format!("{} wallpaper set for group '{}'", direction, group.name) }
+}
+
+/// Pause or resume the whole slideshow. Resuming also resumes paused groups,
+/// and every timer goes on with the time it had left when it was paused.
+fn set_paused(state: &mut DaemonState, config: &Config, paused: bool) {
+    let now = Instant::now();
+    if paused {
+        freeze_timers(state, config, now, |_| true);
+    } else {
+        let all = state.paused;
+        let groups: HashSet<String> = state.paused_groups.drain().map(|name| group_timer(&name)).collect();
+        thaw_timers(state, config, now, |key| all || groups.contains(key));
+    }
+    state.paused = paused;
+}
+
+/// Stop or restart the timer of one group, leaving the others as they are
+fn pause_group(state: &mut DaemonState, config: &Config, selector: &str, pause: bool) -> IpcResponse {
+    let index = match state.resolve_group(selector) {
+        Ok(index) => index,
+        Err((kind, message)) => return IpcResponse::error(kind, message),
+    };
+    let name = state.groups[index].name.clone();
+    let key = group_timer(&name);
+    let message = if pause {
+        freeze_timers(state, config, Instant::now(), |timer| timer == key);
+        state.paused_groups.insert(name.clone());
+        format!("Group '{}' paused", name)
+    } else if state.paused {
+        state.paused_groups.remove(&name);
+        format!("Group '{}' resumed, but the whole slideshow is paused", name)
+    } else {
+        thaw_timers(state, config, Instant::now(), |timer| timer == key);
+        state.paused_groups.remove(&name);
+        format!("Group '{}' resumed", name)
+    };
+    log::info!("{}", message);
+    IpcResponse::Success { message }
+}
+
+fn handle_previous_for_output(
+    state: &mut DaemonState,
+    output_name: &str,
+    config: &Config,
 ) {
     if let Some(queue) = state.queues.get_mut(output_name) {
-        if let Some(next_image) = queue.next() {
-            change_wallpaper_sync(output_name, &next_image, config, executor);
+        if let Some(prev_image) = queue.previous() {
+            request_change(&state.changes, config, output_name, prev_image, ChangeReason::Manual);
             state.timers.insert(output_name.to_string(), Instant::now());
         }
     }
 }
 
-fn handle_previous_for_output(
-    state: &mut DaemonState,
-    output_name: &str,
-    config: &Config,
-    executor: &ProcessExecutor,
-) {
-    if let Some(queue) = state.queues.get_mut(output_name) {
-        if let Some(prev_image) = queue.previous() {
-            let output_config = config.get_output_config(output_name);
-            let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-            execute_wallpaper_change(output_name, &prev_image, &common_config, executor);
-            state.timers.insert(output_name.to_string(), Instant::now());
-        }
+/// The config file with runtime output settings applied on top
+fn load_config(state: &DaemonState) -> swwws_common::Result<Config> {
+    load_config_with(state, &state.overrides)
+}
+
+/// The config file with `overrides` for outputs, and the monitor behavior
+/// from `swwws-cli behavior` if one was set
+fn load_config_with(state: &DaemonState, overrides: &BTreeMap<String, OutputSettings>) -> swwws_common::Result<Config> {
+    let mut config = Config::load_with_overrides(&state.config_path, overrides)?;
+    if let Some((behavior, groups)) = &state.behavior {
+        config.set_monitor_behavior(behavior.clone(), groups.clone())?;
+    }
+    Ok(config)
+}
+
+/// The config with `behavior` in place of the file's, checked against the
+/// connected outputs
+fn behavior_config(state: &DaemonState, behavior: &str, groups: &Option<Vec<Vec<String>>>) -> Result<(MonitorBehavior, Config), (ErrorKind, String)> {
+    let behavior = match behavior.to_ascii_lowercase().as_str() {
+        "independent" => MonitorBehavior::Independent,
+        "synchronized" => MonitorBehavior::Synchronized,
+        "grouped" => MonitorBehavior::Grouped(vec![]),
+        other => {
+            return Err((ErrorKind::InvalidArgument, format!("Unknown monitor behavior '{}', expected independent, synchronized or grouped", other)));
+        }
+    };
+    if groups.is_some() && !matches!(behavior, MonitorBehavior::Grouped(_)) {
+        return Err((ErrorKind::InvalidArgument, "Groups can only be given for grouped behavior".to_string()));
+    }
+    let live = state.outputs.live().to_vec();
+    if let Some(output) = groups.iter().flatten().flatten().find(|output| !pattern::is_pattern(output) && !live.contains(output)) {
+        return Err((ErrorKind::NotFound, format!("Output '{}' is not connected", output)));
+    }
+
+    let mut config = match Config::load_with_overrides(&state.config_path, &state.overrides) {
+        Ok(config) => config,
+        Err(e) => return Err((ErrorKind::Failed, format!("Failed to load config: {}", e.user_friendly_message()))),
+    };
+    if let Err(e) = config.set_monitor_behavior(behavior.clone(), groups.clone()) {
+        return Err((ErrorKind::Failed, e.user_friendly_message()));
+    }
+    if matches!(behavior, MonitorBehavior::Grouped(_)) {
+        let resolved = match config.resolve_groups(&live) {
+            Ok(resolved) => resolved,
+            Err(e) => return Err((ErrorKind::Failed, e.user_friendly_message())),
+        };
+        if resolved.is_empty() {
+            return Err((ErrorKind::Unavailable, "No monitor groups in the config; give them with --group".to_string()));
+        }
+        // Configured groups may name outputs that are unplugged for now
+        let unmatched = resolved.iter().find(|(_, outputs)| !outputs.iter().any(|output| live.contains(output)));
+        if let (Some((name, _)), Some(_)) = (unmatched, groups) {
+            return Err((ErrorKind::NotFound, format!("Group '{}' matches no connected output", name)));
+        }
+    }
+    Ok((behavior, config))
+}
+
+/// Switch to `behavior` until the next reload, rebuilding the queues from
+/// `scans`. New queues start from the image each output shows where they have it.
+fn set_behavior(state: &mut DaemonState, behavior: &str, groups: Option<Vec<Vec<String>>>, scans: &Scans) -> IpcResponse {
+    let (behavior, config) = match behavior_config(state, behavior, &groups) {
+        Ok(checked) => checked,
+        Err((kind, message)) => return IpcResponse::error(kind, message),
+    };
+    let live = state.outputs.live().to_vec();
+    let start_at: HashMap<String, PathBuf> = live.iter()
+        .filter_map(|output| Some((output.clone(), state.mode_of(output)?.1.current_image()?.clone())))
+        .collect();
+    let previous = state.behavior.replace((behavior, groups));
+    state.outputs.set_live(state.outputs.connected().to_vec(), &config);
+    if let Err(e) = reinitialize_daemon_state_sync(state, &config, scans, &start_at, &[]) {
+        // Go back to the queues there were
+        state.behavior = previous;
+        if let Ok(config) = load_config(state) {
+            state.outputs.set_live(state.outputs.connected().to_vec(), &config);
+            if let Err(e) = reinitialize_daemon_state_sync(state, &config, &Scans::default(), &start_at, &[]) {
+                log::error!("Failed to restore the previous monitor behavior: {}", e);
+            }
+        }
+        return IpcResponse::error(ErrorKind::Failed, format!("Failed to switch monitor behavior: {}", e));
+    }
+    state.live_config = Some(Arc::new(config));
+    if let Err(e) = state.save_state() {
+        log::error!("Failed to save state: {}", e);
+    }
+
+    let description = match state.current_behavior() {
+        MonitorBehavior::Independent => "independent".to_string(),
+        MonitorBehavior::Synchronized => "synchronized".to_string(),
+        MonitorBehavior::Grouped(_) => {
+            let groups: Vec<String> = state.groups.iter()
+                .map(|group| format!("{}: {}", group.name, group.outputs.join(", ")))
+                .collect();
+            format!("grouped ({})", groups.join("; "))
+        }
+    };
+    log::info!("Monitor behavior is now {}", description);
+    IpcResponse::Success { message: format!("Monitor behavior is now {} until the next reload", description) }
+}
+
+/// Output settings from `swwws-cli output configure`, by output name
+type OutputOverrides = BTreeMap<String, OutputSettings>;
+
+/// The name `output` resolves to, with the runtime overrides and the config
+/// once `settings` are set for it
+fn configured_output(state: &DaemonState, output: &str, settings: &OutputSettings) -> Result<(String, OutputOverrides, Config), (ErrorKind, String)> {
+    if settings.is_empty() {
+        return Err((ErrorKind::InvalidArgument, "Nothing to configure: give at least one setting".to_string()));
+    }
+    let Some(output_name) = state.outputs.resolve(output) else {
+        return Err((ErrorKind::NotFound, format!("Unknown output '{}'", output)));
+    };
+
+    let mut overrides = state.overrides.clone();
+    overrides.entry(output_name.clone()).or_default().update(settings);
+    match load_config_with(state, &overrides) {
+        Ok(config) => Ok((output_name, overrides, config)),
+        Err(e) => Err((ErrorKind::InvalidArgument, format!("Invalid settings for {}: {}", output_name, e.user_friendly_message()))),
+    }
+}
+
+/// Set `settings` for `output` at runtime, and with `persist` also in its
+/// section of the config file, then rebuild the queues with them from `scans`
+fn configure_output(state: &mut DaemonState, output: &str, settings: &OutputSettings, persist: bool, scans: &Scans) -> IpcResponse {
+    let (output_name, mut overrides, config) = match configured_output(state, output, settings) {
+        Ok(configured) => configured,
+        Err((kind, message)) => return IpcResponse::error(kind, message),
+    };
+
+    let message = if persist {
+        if let Err(e) = edit::persist_output_section(&state.config_path, &output_name, &overrides[&output_name]) {
+            return IpcResponse::error(ErrorKind::Failed, e.user_friendly_message());
+        }
+        // The file now has everything the daemon is running with
+        overrides.remove(&output_name);
+        state.reloads.mark_loaded(file_mtime(&state.config_path));
+        format!("Configured {} and saved it to {:?}", output_name, state.config_path)
+    } else {
+        format!("Configured {} until the daemon restarts (use --persist to save it to the config file)", output_name)
+    };
+
+    log::info!("{}", message);
+    state.overrides = overrides;
+    drop_replaced_intervals(state, &config);
+    let connected = state.outputs.connected().to_vec();
+    state.outputs.set_live(connected, &config);
+    if let Err(e) = reinitialize_daemon_state_sync(state, &config, scans, &HashMap::new(), &[]) {
+        return IpcResponse::error(ErrorKind::Failed, format!("Failed to rebuild queues for {}: {}", output_name, e));
+    }
+    state.live_config = Some(Arc::new(config));
+    IpcResponse::Success { message }
+}
+
+/// Scan the directories the queues are going to draw from once the config
+/// `prepare` works out runs, holding the state lock only to work it out.
+/// Nothing is scanned when there is no config; the command says why itself.
+fn scan_for_config(state: &Mutex<DaemonState>, prepare: impl FnOnce(&DaemonState) -> Option<Config>) -> Scans {
+    let (paths, runtime) = {
+        let state = state.lock().unwrap();
+        let Some(config) = prepare(&state) else { return Scans::default() };
+        let mut outputs = state.outputs.clone();
+        outputs.set_live(outputs.connected().to_vec(), &config);
+        (wallpaper_paths(&config, &outputs), state.runtime.clone())
+    };
+    runtime.block_on(Scans::run(paths))
+}
+
+/// Scan the directories a reload is going to build queues from, holding the
+/// state lock only to read what is needed. Nothing is scanned when the reload
+/// keeps the queues it has, or is going to fail and report why itself.
+fn scan_for_reload(state: &Mutex<DaemonState>) -> Scans {
+    let (config, running_paths, behavior, mut outputs, swww, runtime) = {
+        let state = state.lock().unwrap();
+        // A reload goes back to the file's monitor behavior
+        let Ok(config) = Config::load_with_overrides(&state.config_path, &state.overrides) else { return Scans::default() };
+        let running_paths = state.live_config.as_ref().map(|running| wallpaper_paths(running, &state.outputs));
+        (config, running_paths, state.current_behavior(), state.outputs.clone(), Arc::clone(&state.swww), state.runtime.clone())
+    };
+    let Ok(queried) = swww.query_swww_outputs() else { return Scans::default() };
+
+    outputs.set_live(queried.iter().map(|output| output.name.clone()).collect(), &config);
+    outputs.set_geometry(&queried);
+    let paths = wallpaper_paths(&config, &outputs);
+    // Nothing to scan ahead while the queues draw from the same directories
+    let same_behavior = std::mem::discriminant(&behavior) == std::mem::discriminant(&config.get_effective_monitor_behavior());
+    if same_behavior && running_paths.as_ref() == Some(&paths) {
+        return Scans::default();
+    }
+    runtime.block_on(Scans::run(paths))
+}
+
+fn handle_reload(state_guard: &mut DaemonState, scans: &Scans) -> IpcResponse {
+    // Runtime output settings give way to a section written to the file since,
+    // as does a monitor behavior set with `swwws-cli behavior`. Both stay until
+    // the new config is applied, so a failed reload keeps what is running.
+    let mut overrides = state_guard.overrides.clone();
+    if let Ok(file_config) = Config::load_from(&state_guard.config_path) {
+        overrides.retain(|output, _| !file_config.outputs.contains_key(output));
+    }
+
+    // Reload configuration with comprehensive error handling
+    match Config::load_with_overrides(&state_guard.config_path, &overrides) {
+        Ok(new_config) => {
+            // Validate new config before applying
+            match new_config.get_effective_monitor_behavior() {
+                swwws_common::MonitorBehavior::Grouped(ref groups) if groups.is_empty() => {
+                    let error_msg = "Invalid config: grouped behavior with empty groups";
+                    log::error!("{}", error_msg);
+                    return IpcResponse::error(ErrorKind::Failed, error_msg);
+                }
+                _ => {}
+            }
+            
+            // Check if swww daemon is still accessible with new config
+            if let Err(e) = state_guard.swww.check_swww_daemon() {
+                let error_msg = format!("Cannot reload: swww daemon not accessible: {}", e.user_friendly_message());
+                log::error!("{}", error_msg);
+                return IpcResponse::error(ErrorKind::Failed, error_msg);
+            }
+            
+            // Try to get outputs to ensure they're still valid
+            let queried = match state_guard.swww.query_swww_outputs() {
+                Ok(outputs) => {
+                    if outputs.is_empty() {
+                        let error_msg = "Cannot reload: no swww outputs available";
+                        log::error!("{}", error_msg);
+                        return IpcResponse::error(ErrorKind::Failed, error_msg);
+                    }
+                    outputs
+                }
+                Err(e) => {
+                    let error_msg = format!("Cannot reload: failed to get swww outputs: {}", e.user_friendly_message());
+                    log::error!("{}", error_msg);
+                    return IpcResponse::error(ErrorKind::Failed, error_msg);
+                }
+            };
+            
+            let response = apply_reload(state_guard, new_config, &queried, scans);
+            if let IpcResponse::Reloaded { .. } = response {
+                state_guard.overrides = overrides;
+                state_guard.behavior = None;
+            }
+            response
+        }
+        Err(e) => {
+            log::error!("Failed to reload configuration: {}", e.user_friendly_message());
+            IpcResponse::error(ErrorKind::Failed, format!("Failed to reload configuration: {}", e.user_friendly_message()))
+        }
+    }
+}
+
+/// Apply a reloaded `config` with `queried` as the outputs swww has: rebuild
+/// the queues whose settings say how they are built, and leave the rest as
+/// they are, since durations and transitions are read on each change
+fn apply_reload(state: &mut DaemonState, config: Config, queried: &[QueriedOutput], scans: &Scans) -> IpcResponse {
+    state.changes.set_max_concurrent(config.global.max_concurrent_changes);
+    state.changes.change_log().set_limit(config.global.change_log_size);
+    let swww_outputs = queried.iter().map(|output| output.name.clone()).collect();
+    state.outputs.set_live(swww_outputs, &config);
+    state.outputs.set_geometry(queried);
+    drop_replaced_intervals(state, &config);
+
+    let diff = match &state.live_config {
+        Some(running) => match config_diff::diff(running, &config, state.outputs.connected()) {
+            Ok(diff) => diff,
+            Err(e) => return IpcResponse::error(ErrorKind::Failed, format!("Failed to compare the configs: {}", e.user_friendly_message())),
+        },
+        None => ConfigDiff::default(),
+    };
+    let mut summary = ReloadSummary {
+        changes: diff.changes.clone(),
+        rebuilt: Vec::new(),
+        outputs: diff.outputs.iter().cloned().collect(),
+    };
+
+    // Check if monitor behavior has changed by inferring current behavior from daemon state
+    let current_behavior = state.current_behavior();
+    let new_behavior = config.get_effective_monitor_behavior();
+    
+    let message = if std::mem::discriminant(&current_behavior) != std::mem::discriminant(&new_behavior) {
+        log::info!("Monitor behavior changed from {:?} to {:?}, reinitializing daemon state", 
+            current_behavior, new_behavior);
+        
+        // Reinitialize state with new behavior (using sync version)
+        if let Err(e) = reinitialize_daemon_state_sync(state, &config, scans, &HashMap::new(), &[]) {
+            let error_msg = format!("Failed to reinitialize daemon state: {}", e);
+            log::error!("{}", error_msg);
+            return IpcResponse::error(ErrorKind::Failed, error_msg);
+        }
+        
+        log::info!("Daemon state reinitialized successfully for new monitor behavior");
+        summary.rebuilt = state.queue_names();
+        "Configuration reloaded and daemon state reinitialized for new monitor behavior".to_string()
+    } else if diff.rebuild {
+        // Outputs keep the image they show if it is still in their new queue
+        let start_at: HashMap<String, PathBuf> = state.outputs.live().iter()
+            .filter_map(|output| Some((output.clone(), state.mode_of(output)?.1.current_image()?.clone())))
+            .collect();
+        if !diff.rebuild_all && diff.requeue.iter().all(|output| state.queues.contains_key(output)) {
+            // Only outputs with their own queue changed; the rest keep
+            // their queues and timers as they are
+            let requeue: Vec<String> = diff.requeue.iter().cloned().collect();
+            requeue_outputs(state, &config, scans, &requeue, &start_at);
+            summary.rebuilt = requeue;
+        } else if let Err(e) = reinitialize_daemon_state_sync(state, &config, scans, &start_at, &[]) {
+            let error_msg = format!("Failed to rebuild the queues: {}", e);
+            log::error!("{}", error_msg);
+            return IpcResponse::error(ErrorKind::Failed, error_msg);
+        } else {
+            summary.rebuilt = state.queue_names();
+        }
+        format!("Configuration reloaded: {} on {}, {} rebuilt",
+            plural(summary.changes.len(), "change"), plural(summary.outputs.len(), "output"), plural(summary.rebuilt.len(), "queue"))
+    } else if !diff.is_empty() {
+        // Durations and transitions are read on each change
+        format!("Configuration reloaded: {} on {}, applied from the next change",
+            plural(summary.changes.len(), "change"), plural(summary.outputs.len(), "output"))
+    } else {
+        "Configuration reloaded: no changes detected".to_string()
+    };
+
+    for change in &summary.changes {
+        log::info!("Reload: {}", change);
+    }
+    log::info!("{}", message);
+    state.live_config = Some(Arc::new(config));
+    IpcResponse::Reloaded { message, summary }
+}
+
+/// The line systemd shows for the service in `systemctl status`
+fn service_status(state: &DaemonState) -> String {
+    let outputs = plural(state.outputs.live().len(), "output");
+    let queues = plural(state.queue_names().len(), "queue");
+    if state.paused {
+        format!("Paused on {}, {}", outputs, queues)
+    } else {
+        format!("Showing wallpapers on {}, {}", outputs, queues)
     }
 }
 
-fn execute_wallpaper_change(
-    output_name: &str,
-    image_path: &PathBuf,
-    common_config: &swwws_common::command_builder::OutputConfig,
-    executor: &ProcessExecutor,
-) {
-    let executor_clone = executor.clone();
-    let output_name_clone = output_name.to_string();
-    let image_path_clone = image_path.clone();
-    let common_config_clone = common_config.clone();
-
-    std::thread::spawn(move || {
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            if let Err(e) = executor_clone.execute_swww_command(
-                &image_path_clone,
-                &common_config_clone,
-                Some(&output_name_clone),
-            ).await {
-                log::error!("Failed to set wallpaper for {}: {}", 
-                    output_name_clone, e.user_friendly_message());
-            } else {
-                log::info!("Set wallpaper for {}: {:?}", output_name_clone, image_path_clone);
-            }
-        });
-    });
+/// `count` followed by `noun`, with an s unless there is one
+fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
 }
 
-fn change_wallpaper_sync(
-    output_name: &str,
-    image_path: &std::path::Path,
-    config: &Config,
-    executor: &ProcessExecutor,
-) {
-    let output_config = config.get_output_config(output_name);
-    
-    let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
+/// Reload the config file, scanning the wallpaper directories before taking
+/// the lock. The outcome goes to `status`, subscribers and systemd as well,
+/// whether the reload failed on a file that doesn't parse or later on.
+fn reload(state: &Mutex<DaemonState>) -> IpcResponse {
+    // Nothing returns before READY follows, or systemd would wait on the reload
+    let notifier = Arc::clone(&state.lock().unwrap().notifier);
+    notifier.reloading();
+    let scans = scan_for_reload(state);
+    let mut state_guard = state.lock().unwrap();
+    let changes = Arc::clone(&state_guard.changes);
 
-    let executor_clone = executor.clone();
-    let output_name_clone = output_name.to_string();
-    let image_path_clone = image_path.to_path_buf();
+    // Stat before reading so an edit made mid-reload still shows up as newer
+    let mtime = file_mtime(&state_guard.config_path);
+    let response = handle_reload(&mut state_guard, &scans);
+    state_guard.notifier.ready(&service_status(&state_guard));
+    match &response {
+        IpcResponse::Success { message } | IpcResponse::Reloaded { message, .. } => {
+            state_guard.reloads.record(true, message.as_str(), mtime);
+            changes.subscribers().broadcast(&IpcEvent::Reloaded { success: true, message: message.clone() });
+        }
+        IpcResponse::Error { message, .. } => {
+            state_guard.reloads.record(false, message.as_str(), mtime);
+            changes.problems().record(ProblemCategory::Reload, None, message.as_str());
+            changes.subscribers().broadcast(&IpcEvent::Reloaded { success: false, message: message.clone() });
+        }
+        IpcResponse::Status { .. } | IpcResponse::Problems { .. } | IpcResponse::QueueList { .. }
+        | IpcResponse::Current { .. } | IpcResponse::Config { .. } | IpcResponse::History { .. }
+        | IpcResponse::Outputs { .. } | IpcResponse::Pong { .. } | IpcResponse::Blacklist { .. } => {}
+    }
+    response
+}
 
-    std::thread::spawn(move || {
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            if let Err(e) = executor_clone.execute_swww_command(
-                &image_path_clone,
-                &common_config,
-                Some(&output_name_clone),
-            ).await {
-                log::error!("Failed to set wallpaper for {}: {}", 
-                    output_name_clone, e.user_friendly_message());
-            } else {
-                log::info!("Set wallpaper for {}: {:?}", output_name_clone, image_path_clone);
-            }
-        });
-    });
+fn pong(uptime: u64) -> IpcResponse {
+    IpcResponse::Pong { version: env!("CARGO_PKG_VERSION").to_string(), uptime, pid: Some(std::process::id()) }
 }
 
 fn handle_ipc_command(
     command: IpcCommand,
     state: Arc<Mutex<DaemonState>>,
-    executor: ProcessExecutor,
 ) -> IpcResponse {
-    let mut state_guard = state.lock().unwrap();
-    
-    // Load config to check monitor behavior
-    let config = match swwws_config::Config::load() {
-        Ok(c) => c,
-        Err(e) => {
-            return IpcResponse::Error { 
-                message: format!("Failed to load config: {}", e.user_friendly_message()) 
-            };
+    // A reload reads the file itself, so one that doesn't parse is still reported
+    if let IpcCommand::Reload = command {
+        return reload(&state);
+    }
+    // Commands that rebuild queues scan the wallpaper directories before they take the lock
+    let scans = match &command {
+        IpcCommand::Rescan { output } => scan_for_rescan(&state, output.as_deref()),
+        IpcCommand::ConfigureOutput { output, settings, .. } => {
+            scan_for_config(&state, |state| configured_output(state, output, settings).ok().map(|(_, _, config)| config))
+        }
+        IpcCommand::SetBehavior { behavior, groups } => {
+            scan_for_config(&state, |state| behavior_config(state, behavior, groups).ok().map(|(_, config)| config))
         }
+        _ => Scans::default(),
+    };
+    let mut state_guard = state.lock().unwrap();
+    let changes = Arc::clone(&state_guard.changes);
+
+    // The config the daemon runs with. Edits to the file count from the next
+    // reload, so a broken one can't keep `stop` or `status` from answering.
+    let config = match state_guard.live_config.clone() {
+        Some(config) => config,
+        None => match load_config(&state_guard) {
+            Ok(config) => Arc::new(config),
+            Err(e) => {
+                return IpcResponse::error(ErrorKind::Failed, format!("Failed to load config: {}", e.user_friendly_message()));
+            }
+        },
     };
 
     match command {
-        IpcCommand::Next { output } => {
+        IpcCommand::Next { output, group } => {
+            if let Some(group) = group {
+                if output.is_some() {
+                    return IpcResponse::error(ErrorKind::InvalidArgument, "Give either an output or a group, not both");
+                }
+                return match state_guard.resolve_group(&group) {
+                    Ok(index) => step_group(&mut state_guard, &config, index, true),
+                    Err((kind, message)) => IpcResponse::error(kind, message),
+                };
+            }
             if let Some(specific_output) = output {
                 // Specific output requested - ignore monitor behavior
-                handle_next_for_output(&mut state_guard, &specific_output, &config, &executor);
+                let Some(output_name) = state_guard.outputs.resolve(&specific_output) else {
+                    return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", specific_output));
+                };
+                if state_guard.outputs.is_disabled(&output_name) {
+                    return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+                }
+                handle_next_for_output(&mut state_guard, &output_name, &config);
             } else {
                 // Handle based on current daemon state (not config, which might be out of sync)
-                let current_behavior = if state_guard.shared_queue.is_some() {
-                    MonitorBehavior::Synchronized
-                } else if !state_guard.groups.is_empty() {
-                    let groups: Vec<Vec<String>> = state_guard.groups.iter()
-                        .map(|group| group.outputs.clone())
-                        .collect();
-                    MonitorBehavior::Grouped(groups)
-                } else {
-                    MonitorBehavior::Independent
-                };
+                let current_behavior = state_guard.current_behavior();
                 match current_behavior {
                     MonitorBehavior::Independent => {
                         // Each output advances independently
-                        let outputs: Vec<_> = state_guard.queues.keys().cloned().collect();
-                        for output_name in outputs {
-                            handle_next_for_output(&mut state_guard, &output_name, &config, &executor);
+                        for output_name in state_guard.outputs.live().to_vec() {
+                            // Static and pinned outputs only change when named explicitly
+                            if !config.get_output_config(&output_name).is_static() && !changes.pins().is_pinned(&output_name) {
+                                handle_next_for_output(&mut state_guard, &output_name, &config);
+                            }
                         }
                     }
                     MonitorBehavior::Synchronized => {
                         // All outputs show the same next image from shared queue
-                        let next_image = if let Some(shared_queue) = &mut state_guard.shared_queue {
-                            shared_queue.next().cloned()
-                        } else {
-                            None
+                        let targets = changes.pins().unpinned(state_guard.outputs.live());
+                        let next_image = match &mut state_guard.shared_queue {
+                            Some(shared_queue) if !targets.is_empty() => shared_queue.next().cloned(),
+                            _ => None,
                         };
                         
                         if let Some(image_path) = next_image {
-                            log::info!("IPC Synchronized: Setting same image {:?} on all outputs", image_path);
-                            // Get all available outputs
-                            let swww_outputs = match ProcessExecutor::get_swww_outputs() {
-                                Ok(outputs) => outputs,
-                                Err(_) => {
-                                    // Fallback: collect queue keys without borrowing state_guard mutably
-                                    vec![] // We'll handle this case below
-                                }
-                            };
-                            
-                            let outputs_to_use = if swww_outputs.is_empty() {
-                                // Only collect if we need to, and do it separately
-                                state_guard.queues.keys().cloned().collect::<Vec<_>>()
-                            } else {
-                                swww_outputs
-                            };
-                            
-                            for output_name in &outputs_to_use {
-                                change_wallpaper_sync(output_name, &image_path, &config, &executor);
-                            }
+                            log::info!("IPC Synchronized: Setting same image {:?} on all unpinned outputs", image_path);
+                            request_shared_change(&changes, &config, &targets, &image_path, ChangeReason::Manual);
                             state_guard.shared_timer = Some(Instant::now());
                         }
                     }
                     MonitorBehavior::Grouped(_) => {
                         // Advance all groups and independent outputs
                         for group in &mut state_guard.groups {
+                            let targets = changes.pins().unpinned(&group.outputs);
+                            if targets.is_empty() {
+                                continue;
+                            }
                             if let Some(next_image) = group.queue.next() {
                                 log::info!("IPC Group '{}': Setting image {:?} on group outputs", group.name, next_image);
-                                for output_name in &group.outputs {
-                                    change_wallpaper_sync(output_name, &next_image, &config, &executor);
-                                }
+                                request_shared_change(&changes, &config, &targets, next_image, ChangeReason::Manual);
                                 group.timer = Instant::now();
                             }
                         }
                         
                         // Also advance independent outputs not in any group
-                        let outputs: Vec<_> = state_guard.queues.keys().cloned().collect();
-                        for output_name in outputs {
-                            let is_in_group = state_guard.groups.iter().any(|g| g.outputs.contains(&output_name));
-                            if !is_in_group {
-                                handle_next_for_output(&mut state_guard, &output_name, &config, &executor);
+                        for output_name in state_guard.outputs.ungrouped() {
+                            // Static and pinned outputs only change when named explicitly
+                            if !config.get_output_config(&output_name).is_static() && !changes.pins().is_pinned(&output_name) {
+                                handle_next_for_output(&mut state_guard, &output_name, &config);
                             }
                         }
                     }
@@ -1131,94 +3212,67 @@ fn handle_ipc_command(
             IpcResponse::Success { message: "Next wallpaper set".to_string() }
         }
 
-        IpcCommand::Previous { output } => {
+        IpcCommand::Previous { output, group } => {
+            if let Some(group) = group {
+                if output.is_some() {
+                    return IpcResponse::error(ErrorKind::InvalidArgument, "Give either an output or a group, not both");
+                }
+                return match state_guard.resolve_group(&group) {
+                    Ok(index) => step_group(&mut state_guard, &config, index, false),
+                    Err((kind, message)) => IpcResponse::error(kind, message),
+                };
+            }
             if let Some(specific_output) = output {
                 // Handle specific output request
-                handle_previous_for_output(&mut state_guard, &specific_output, &config, &executor);
+                let Some(output_name) = state_guard.outputs.resolve(&specific_output) else {
+                    return IpcResponse::error(ErrorKind::NotFound, format!("Unknown output '{}'", specific_output));
+                };
+                if state_guard.outputs.is_disabled(&output_name) {
+                    return IpcResponse::error(ErrorKind::Unavailable, format!("Output '{}' is disabled in the config", output_name));
+                }
+                handle_previous_for_output(&mut state_guard, &output_name, &config);
             } else {
                 // Handle based on current daemon state (not config, which might be out of sync)
-                let current_behavior = if state_guard.shared_queue.is_some() {
-                    MonitorBehavior::Synchronized
-                } else if !state_guard.groups.is_empty() {
-                    let groups: Vec<Vec<String>> = state_guard.groups.iter()
-                        .map(|group| group.outputs.clone())
-                        .collect();
-                    MonitorBehavior::Grouped(groups)
-                } else {
-                    MonitorBehavior::Independent
-                };
+                let current_behavior = state_guard.current_behavior();
                 match current_behavior {
                     MonitorBehavior::Independent => {
-                        let outputs: Vec<_> = state_guard.queues.keys().cloned().collect();
-                        for output_name in outputs {
-                            handle_previous_for_output(&mut state_guard, &output_name, &config, &executor);
+                        for output_name in state_guard.outputs.live().to_vec() {
+                            // Static and pinned outputs only change when named explicitly
+                            if !config.get_output_config(&output_name).is_static() && !changes.pins().is_pinned(&output_name) {
+                                handle_previous_for_output(&mut state_guard, &output_name, &config);
+                            }
                         }
                     }
                     MonitorBehavior::Synchronized => {
-                        if let Some(shared_queue) = &mut state_guard.shared_queue {
-                            if let Some(prev_image) = shared_queue.previous() {
-                                log::info!("IPC Synchronized: Setting previous image {:?} on all outputs", prev_image);
-                                let swww_outputs = match ProcessExecutor::get_swww_outputs() {
-                                    Ok(outputs) => outputs,
-                                    Err(_) => vec![] // fallback
-                                };
-                                for output_name in &swww_outputs {
-                                    let output_config = config.get_output_config(output_name);
-                                    let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-                                    execute_wallpaper_change(output_name, &prev_image, &common_config, &executor);
-                                }
-                                state_guard.shared_timer = Some(Instant::now());
-                            }
+                        let targets = changes.pins().unpinned(state_guard.outputs.live());
+                        let prev_image = state_guard.shared_queue.as_mut()
+                            .filter(|_| !targets.is_empty())
+                            .and_then(|shared_queue| shared_queue.previous().cloned());
+                        if let Some(prev_image) = prev_image {
+                            log::info!("IPC Synchronized: Setting previous image {:?} on all unpinned outputs", prev_image);
+                            request_shared_change(&changes, &config, &targets, &prev_image, ChangeReason::Manual);
+                            state_guard.shared_timer = Some(Instant::now());
                         }
                     }
                     MonitorBehavior::Grouped(_) => {
                         // Handle groups
                         for group in &mut state_guard.groups {
+                            let targets = changes.pins().unpinned(&group.outputs);
+                            if targets.is_empty() {
+                                continue;
+                            }
                             if let Some(prev_image) = group.queue.previous() {
                                 log::info!("IPC Group '{}': Setting previous image {:?} on group outputs", group.name, prev_image);
-                                for output_name in &group.outputs {
-                                    let output_config = config.get_output_config(output_name);
-                                    let common_config = swwws_common::command_builder::OutputConfig {
-    path: output_config.path.as_ref().map(|p| PathBuf::from(p)),
-    mode: None,
-    transition_type: Some(output_config.transition_type.clone()),
-    transition_step: Some(output_config.transition_step as u8),
-    transition_angle: Some(output_config.transition_angle),
-    transition_pos: Some(output_config.transition_pos.clone()),
-    transition_bezier: Some(output_config.transition_bezier.clone()),
-    transition_fps: None,
-    resize: Some(output_config.resize.clone()),
-    fill_color: Some(output_config.fill_color.clone()),
-    filter: Some(output_config.filter.clone()),
-    invert_y: Some(output_config.invert_y),
-    transition_wave: Some(output_config.transition_wave.clone()),
-                };
-                                    execute_wallpaper_change(output_name, &prev_image, &common_config, &executor);
-                                }
+                                request_shared_change(&changes, &config, &targets, prev_image, ChangeReason::Manual);
                                 group.timer = Instant::now();
                             }
                         }
                         
                         // Handle independent outputs
-                        let outputs: Vec<_> = state_guard.queues.keys().cloned().collect();
-                        for output_name in outputs {
-                            let is_in_group = state_guard.groups.iter().any(|g| g.outputs.contains(&output_name));
-                            if !is_in_group {
-                                handle_previous_for_output(&mut state_guard, &output_name, &config, &executor);
+                        for output_name in state_guard.outputs.ungrouped() {
+                            // Static and pinned outputs only change when named explicitly
+                            if !config.get_output_config(&output_name).is_static() && !changes.pins().is_pinned(&output_name) {
+                                handle_previous_for_output(&mut state_guard, &output_name, &config);
                             }
                         }
                     }
@@ -1229,112 +3283,102 @@ fn handle_ipc_command(
         }
 
         IpcCommand::Pause => {
-            state_guard.paused = true;
+            set_paused(&mut state_guard, &config, true);
+            changes.subscribers().broadcast(&IpcEvent::Paused);
             IpcResponse::Success { message: "Slideshow paused".to_string() }
         }
 
         IpcCommand::Resume => {
-            state_guard.paused = false;
+            set_paused(&mut state_guard, &config, false);
+            changes.subscribers().broadcast(&IpcEvent::Resumed);
             IpcResponse::Success { message: "Slideshow resumed".to_string() }
         }
 
+        IpcCommand::PauseGroup { group } => pause_group(&mut state_guard, &config, &group, true),
+
+        IpcCommand::ResumeGroup { group } => pause_group(&mut state_guard, &config, &group, false),
+
         IpcCommand::TogglePause => {
-            state_guard.paused = !state_guard.paused;
+            let paused = !state_guard.paused;
+            set_paused(&mut state_guard, &config, paused);
+            changes.subscribers().broadcast(if state_guard.paused { &IpcEvent::Paused } else { &IpcEvent::Resumed });
             let status = if state_guard.paused { "paused" } else { "resumed" };
             IpcResponse::Success { message: format!("Slideshow {}", status) }
         }
 
-        IpcCommand::Reload => {
-            // Reload configuration with comprehensive error handling
-            match swwws_config::Config::load() {
-                Ok(new_config) => {
-                    // Validate new config before applying
-                    match new_config.get_effective_monitor_behavior() {
-                        swwws_common::MonitorBehavior::Grouped(ref groups) if groups.is_empty() => {
-                            let error_msg = "Invalid config: grouped behavior with empty groups";
-                            log::error!("{}", error_msg);
-                            return IpcResponse::Error { message: error_msg.to_string() };
-                        }
-                        _ => {}
-                    }
-                    
-                    // Check if swww daemon is still accessible with new config
-                    if let Err(e) = ProcessExecutor::check_swww_daemon() {
-                        let error_msg = format!("Cannot reload: swww daemon not accessible: {}", e.user_friendly_message());
-                        log::error!("{}", error_msg);
-                        return IpcResponse::Error { message: error_msg };
-                    }
-                    
-                    // Try to get outputs to ensure they're still valid
-                    let swww_outputs = match ProcessExecutor::get_swww_outputs() {
-                        Ok(outputs) => {
-                            if outputs.is_empty() {
-                                let error_msg = "Cannot reload: no swww outputs available";
-                                log::error!("{}", error_msg);
-                                return IpcResponse::Error { message: error_msg.to_string() };
-                            }
-                            outputs
-                        }
-                        Err(e) => {
-                            let error_msg = format!("Cannot reload: failed to get swww outputs: {}", e.user_friendly_message());
-                            log::error!("{}", error_msg);
-                            return IpcResponse::Error { message: error_msg };
-                        }
-                    };
-                    
-                    // Check if monitor behavior has changed by inferring current behavior from daemon state
-                    let current_behavior = if state_guard.shared_queue.is_some() {
-                        MonitorBehavior::Synchronized
-                    } else if !state_guard.groups.is_empty() {
-                        // For grouped mode, we need to reconstruct the groups structure
-                        let groups: Vec<Vec<String>> = state_guard.groups.iter()
-                            .map(|group| group.outputs.clone())
-                            .collect();
-                        MonitorBehavior::Grouped(groups)
-                    } else {
-                        MonitorBehavior::Independent
-                    };
-                    let new_behavior = new_config.get_effective_monitor_behavior();
-                    
-                    if std::mem::discriminant(&current_behavior) != std::mem::discriminant(&new_behavior) {
-                        log::info!("Monitor behavior changed from {:?} to {:?}, reinitializing daemon state", 
-                            current_behavior, new_behavior);
-                        
-                        // Reinitialize state with new behavior (using sync version)
-                        if let Err(e) = reinitialize_daemon_state_sync(&mut state_guard, &new_config, &swww_outputs) {
-                            let error_msg = format!("Failed to reinitialize daemon state: {}", e);
-                            log::error!("{}", error_msg);
-                            return IpcResponse::Error { message: error_msg };
-                        }
-                        
-                        log::info!("Daemon state reinitialized successfully for new monitor behavior");
-                        IpcResponse::Success { message: "Configuration reloaded and daemon state reinitialized for new monitor behavior".to_string() }
-                    } else {
-                        // Same monitor behavior, just validate and update queues if needed
-                        log::info!("Monitor behavior unchanged, configuration reloaded successfully");
-                        IpcResponse::Success { message: "Configuration reloaded successfully".to_string() }
-                    }
+        IpcCommand::Reload => unreachable!("reloads are handled before the config is needed"),
+
+        IpcCommand::ConfigureOutput { output, settings, persist } => {
+            configure_output(&mut state_guard, &output, &settings, persist, &scans)
+        }
+
+        IpcCommand::Problems => {
+            IpcResponse::Problems { categories: state_guard.changes.problems().report() }
+        }
+
+        IpcCommand::ClearProblems { category } => {
+            state_guard.changes.problems().clear(category);
+            let cleared = category.map_or("all problems".to_string(), |category| format!("{} problems", category));
+            IpcResponse::Success { message: format!("Cleared {}", cleared) }
+        }
+
+        IpcCommand::QueueList { output, count, group, recent } => {
+            list_queue(&state_guard, output.as_deref(), group.as_deref(), count, recent)
+        }
+
+        IpcCommand::SetImage { output, path } => {
+            let response = set_image(&mut state_guard, &config, output.as_deref(), &path);
+            // Keep the new position if the daemon stops before the next periodic save
+            if matches!(response, IpcResponse::Success { .. }) {
+                if let Err(e) = state_guard.save_state() {
+                    log::error!("Failed to save state: {}", e);
                 }
-                Err(e) => {
-                    log::error!("Failed to reload configuration: {}", e.user_friendly_message());
-                    IpcResponse::Error { message: format!("Failed to reload configuration: {}", e.user_friendly_message()) }
+            }
+            response
+        }
+
+        IpcCommand::Shuffle { output } => shuffle_queues(&mut state_guard, output.as_deref()),
+
+        IpcCommand::GetCurrent { output } => current_images(&state_guard, output.as_deref()),
+        IpcCommand::History { output, limit } => change_history(&state_guard, output.as_deref(), limit),
+        IpcCommand::Outputs => list_outputs(&state_guard, &config),
+        IpcCommand::Ping => pong(state_guard.reloads.info(&state_guard.config_path).uptime),
+
+        // Answered by the IPC server itself, which keeps the connection
+        IpcCommand::Subscribe => IpcResponse::error(ErrorKind::Unavailable, "Subscribing needs a newer swwws-cli"),
+
+        IpcCommand::GetConfig { output } => effective_config(&state_guard, &config, output.as_deref()),
+
+        IpcCommand::SetInterval { output, duration } => {
+            set_interval(&mut state_guard, &config, output.as_deref(), duration.as_deref())
+        }
+
+        IpcCommand::Shutdown => stop_daemon(&mut state_guard, &config),
+
+        IpcCommand::SetBehavior { behavior, groups } => set_behavior(&mut state_guard, &behavior, groups, &scans),
+
+        IpcCommand::Blacklist { output, path } => blacklist_image(&mut state_guard, &config, output.as_deref(), path),
+        IpcCommand::Unblacklist { path } => unblacklist_image(&mut state_guard, &path),
+        IpcCommand::ListBlacklist => IpcResponse::Blacklist {
+            paths: state_guard.persistent_state.blacklist.paths().map(|path| path.to_string_lossy().to_string()).collect(),
+        },
+        IpcCommand::Pin { output } => pin_outputs(&mut state_guard, output.as_deref()),
+        IpcCommand::Unpin { output } => unpin_outputs(&mut state_guard, &config, output.as_deref()),
+
+        IpcCommand::Rescan { output } => {
+            let response = rescan_queues(&mut state_guard, &config, output.as_deref(), &scans);
+            if matches!(response, IpcResponse::Success { .. }) {
+                if let Err(e) = state_guard.save_state() {
+                    log::error!("Failed to save state: {}", e);
                 }
             }
+            response
         }
 
         IpcCommand::Status => {
             let mut statuses = Vec::new();
             // Use daemon state to determine current behavior, not config
-            let behavior = if state_guard.shared_queue.is_some() {
-                MonitorBehavior::Synchronized
-            } else if !state_guard.groups.is_empty() {
-                let groups: Vec<Vec<String>> = state_guard.groups.iter()
-                    .map(|group| group.outputs.clone())
-                    .collect();
-                MonitorBehavior::Grouped(groups)
-            } else {
-                MonitorBehavior::Independent
-            };
+            let behavior = state_guard.current_behavior();
             log::debug!("Status command - detected behavior: {:?}", behavior);
             log::debug!("Status command - individual queues count: {}", state_guard.queues.len());
             log::debug!("Status command - has shared queue: {}", state_guard.shared_queue.is_some());
@@ -1342,59 +3386,66 @@ fn handle_ipc_command(
             match behavior {
                 MonitorBehavior::Independent => {
                     // Show individual queue status for each output
-                    for (output_name, queue) in &state_guard.queues {
-                        let timer = state_guard.timers.get(output_name);
-                        let elapsed = timer.map(|t| t.elapsed()).unwrap_or(Duration::ZERO);
+                    for output_name in state_guard.outputs.live() {
+                        let Some(queue) = state_guard.queues.get(output_name) else { continue };
                         let output_config = config.get_output_config(output_name);
-                        let target_duration = output_config.duration;
-                        let remaining = if elapsed >= target_duration {
-                            Duration::ZERO
-                        } else {
-                            target_duration - elapsed
-                        };
+                        let interval = state_guard.interval_overrides.interval(output_name, output_config.duration);
+                        let remaining = state_guard.timers.get(output_name)
+                            .map(|t| time_left(&state_guard, output_name, *t, state_guard.intervals.target(output_name, *t, interval), state_guard.paused))
+                            .unwrap_or(interval.min());
 
                         let current_image = queue.current_image()
-                            .map(|p| p.file_name().unwrap_or(p.as_os_str()).to_string_lossy().to_string());
+                            .map(|p| p.to_string_lossy().to_string());
 
                         statuses.push(OutputStatus {
                             name: output_name.clone(),
                             current_image,
                             queue_position: queue.current_position(),
                             queue_size: queue.size(),
-                            timer_remaining: Some(remaining.as_secs()),
+                            timer_remaining: (!output_config.is_static()).then_some(remaining.as_secs()),
                             paused: state_guard.paused,
+                            static_wallpaper: output_config.is_static(),
+                            disabled: false,
+                            mode: OutputMode::Independent,
+                            pinned: false,
                         });
                     }
                 }
                 MonitorBehavior::Synchronized => {
                     // Show synchronized status for all outputs
-                    let swww_outputs = ProcessExecutor::get_swww_outputs().unwrap_or_default();
+                    let swww_outputs = state_guard.outputs.live();
                     if let Some(shared_queue) = &state_guard.shared_queue {
-                        let timer = &state_guard.shared_timer;
-                        let elapsed = timer.map(|t| t.elapsed()).unwrap_or(Duration::ZERO);
-                        let target_duration = if let Some(first_output) = swww_outputs.first() {
-                            config.get_output_config(first_output).duration
-                        } else {
-                            Duration::from_secs(300)
+                        let interval = match swww_outputs.first() {
+                            Some(first_output) => {
+                                state_guard.interval_overrides.interval(first_output, config.get_output_config(first_output).duration)
+                            }
+                            None => Duration::from_secs(300).into(),
                         };
-                        let remaining = if elapsed >= target_duration {
-                            Duration::ZERO
-                        } else {
-                            target_duration - elapsed
+                        let target_duration = match state_guard.shared_timer {
+                            Some(t) => state_guard.intervals.target(SHARED_TIMER, t, interval),
+                            None => interval.min(),
                         };
+                        let remaining = state_guard.shared_timer
+                            .map(|t| time_left(&state_guard, SHARED_TIMER, t, target_duration, state_guard.paused))
+                            .unwrap_or(target_duration);
 
                         let current_image = shared_queue.current_image()
-                            .map(|p| p.file_name().unwrap_or(p.as_os_str()).to_string_lossy().to_string());
+                            .map(|p| p.to_string_lossy().to_string());
+                        let is_static = shared_is_static(&config, swww_outputs);
 
                         // Add status for all outputs showing they're synchronized
                         for output_name in swww_outputs {
                             statuses.push(OutputStatus {
-                                name: format!("{} (sync)", output_name),
+                                name: output_name.clone(),
                                 current_image: current_image.clone(),
                                 queue_position: shared_queue.current_position(),
                                 queue_size: shared_queue.size(),
-                                timer_remaining: Some(remaining.as_secs()),
+                                timer_remaining: (!is_static).then_some(remaining.as_secs()),
                                 paused: state_guard.paused,
+                                static_wallpaper: is_static,
+                                disabled: false,
+                                mode: OutputMode::Synchronized,
+                                pinned: false,
                             });
                         }
                     }
@@ -1402,65 +3453,652 @@ fn handle_ipc_command(
                 MonitorBehavior::Grouped(_) => {
                     // Show group status
                     for group in &state_guard.groups {
-                        let elapsed = group.timer.elapsed();
                         let target_duration = if let Some(first_output) = group.outputs.first() {
-                            config.get_output_config(first_output).duration
+                            let interval = state_guard.interval_overrides.interval(first_output, config.get_output_config(first_output).duration);
+                            state_guard.intervals.target(&group_timer(&group.name), group.timer, interval)
                         } else {
                             Duration::from_secs(300)
                         };
-                        let remaining = if elapsed >= target_duration {
-                            Duration::ZERO
-                        } else {
-                            target_duration - elapsed
-                        };
+                        let remaining = time_left(&state_guard, &group_timer(&group.name), group.timer, target_duration, state_guard.group_paused(&group.name));
 
                         let current_image = group.queue.current_image()
-                            .map(|p| p.file_name().unwrap_or(p.as_os_str()).to_string_lossy().to_string());
+                            .map(|p| p.to_string_lossy().to_string());
+                        let is_static = shared_is_static(&config, &group.outputs);
 
                         // Add status for all outputs in this group
                         for output_name in &group.outputs {
                             statuses.push(OutputStatus {
-                                name: format!("{} ({})", output_name, group.name),
+                                name: output_name.clone(),
                                 current_image: current_image.clone(),
                                 queue_position: group.queue.current_position(),
                                 queue_size: group.queue.size(),
-                                timer_remaining: Some(remaining.as_secs()),
-                                paused: state_guard.paused,
+                                timer_remaining: (!is_static).then_some(remaining.as_secs()),
+                                paused: state_guard.group_paused(&group.name),
+                                static_wallpaper: is_static,
+                                disabled: false,
+                                mode: OutputMode::Group(group.name.clone()),
+                                pinned: false,
                             });
                         }
                     }
                     
                     // Also show independent outputs not in any group
-                    for (output_name, queue) in &state_guard.queues {
-                        let is_in_group = state_guard.groups.iter().any(|g| g.outputs.contains(output_name));
-                        if !is_in_group {
-                            let timer = state_guard.timers.get(output_name);
-                            let elapsed = timer.map(|t| t.elapsed()).unwrap_or(Duration::ZERO);
+                    for output_name in &state_guard.outputs.ungrouped() {
+                        if let Some(queue) = state_guard.queues.get(output_name) {
                             let output_config = config.get_output_config(output_name);
-                            let target_duration = output_config.duration;
-                            let remaining = if elapsed >= target_duration {
-                                Duration::ZERO
-                            } else {
-                                target_duration - elapsed
-                            };
+                            let interval = state_guard.interval_overrides.interval(output_name, output_config.duration);
+                            let remaining = state_guard.timers.get(output_name)
+                                .map(|t| time_left(&state_guard, output_name, *t, state_guard.intervals.target(output_name, *t, interval), state_guard.paused))
+                                .unwrap_or(interval.min());
 
                             let current_image = queue.current_image()
-                                .map(|p| p.file_name().unwrap_or(p.as_os_str()).to_string_lossy().to_string());
+                                .map(|p| p.to_string_lossy().to_string());
 
                             statuses.push(OutputStatus {
-                                name: format!("{} (independent)", output_name),
+                                name: output_name.clone(),
                                 current_image,
                                 queue_position: queue.current_position(),
                                 queue_size: queue.size(),
-                                timer_remaining: Some(remaining.as_secs()),
+                                timer_remaining: (!output_config.is_static()).then_some(remaining.as_secs()),
                                 paused: state_guard.paused,
+                                static_wallpaper: output_config.is_static(),
+                                disabled: false,
+                                mode: OutputMode::Independent,
+                                pinned: false,
                             });
                         }
                     }
                 }
             }
 
-            IpcResponse::Status { outputs: statuses, paused: state_guard.paused }
+            // A pinned output shows its pin, whatever its queue moved on to
+            for status in &mut statuses {
+                if let Some(image) = changes.pins().image(&status.name) {
+                    status.current_image = Some(image.to_string_lossy().to_string());
+                    status.timer_remaining = None;
+                    status.pinned = true;
+                }
+            }
+
+            for output_name in state_guard.outputs.disabled() {
+                statuses.push(OutputStatus::disabled(&output_name));
+            }
+
+            let mut daemon = state_guard.reloads.info(&state_guard.config_path);
+            daemon.runtime_outputs = state_guard.overrides.keys().cloned().collect();
+            daemon.dry_run = state_guard.swww.is_dry_run();
+            IpcResponse::Status { outputs: statuses, paused: state_guard.paused, daemon: Some(daemon) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swwws_common::{WallpaperBackend, WallpaperChange};
+    use tempfile::{tempdir, TempDir};
+
+    struct NoBackend;
+
+    impl WallpaperBackend for NoBackend {
+        fn set_wallpaper<'a>(&'a self, _image: &'a Path, _config: &'a swwws_common::command_builder::OutputConfig, _output: &'a str) -> WallpaperChange<'a> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    /// Records the images it sets, each taking a little while like swww does
+    #[derive(Default)]
+    struct RecordingBackend {
+        calls: Mutex<Vec<PathBuf>>,
+    }
+
+    impl WallpaperBackend for RecordingBackend {
+        fn set_wallpaper<'a>(&'a self, image: &'a Path, _config: &'a swwws_common::command_builder::OutputConfig, _output: &'a str) -> WallpaperChange<'a> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                self.calls.lock().unwrap().push(image.to_path_buf());
+                Ok(())
+            })
+        }
+    }
+
+    /// Runtime the change pipelines of test daemons run on. The tests' own
+    /// runtime only runs while a test awaits, and they wait for changes blocking.
+    fn changes_runtime() -> tokio::runtime::Handle {
+        static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+        RUNTIME.get_or_init(|| tokio::runtime::Runtime::new().unwrap()).handle().clone()
+    }
+
+    fn images(temp_dir: &TempDir) -> Vec<PathBuf> {
+        (0..8).map(|i| temp_dir.path().join(format!("{}.png", i))).collect()
+    }
+
+    /// A daemon with `outputs` live and no queues yet
+    fn daemon(temp_dir: &TempDir, outputs: &[&str]) -> DaemonState {
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, format!("[any]\npath = {:?}\n", temp_dir.path())).unwrap();
+        let outputs = outputs.iter().map(|output| output.to_string()).collect();
+        DaemonState::new(
+            config_path,
+            temp_dir.path().join("state.json"),
+            None,
+            Arc::new(ChangePipeline::new(changes_runtime(), Arc::new(NoBackend), 1)),
+            Arc::new(ProcessExecutor::new(CommandBuilder::new(PathBuf::from("swww")))),
+            OutputRegistry::new(outputs, &Config::default()),
+        ).unwrap()
+    }
+
+    /// Upcoming images of each queue listed, as `swwws-cli preview` prints them
+    fn preview(state: &Mutex<DaemonState>, count: usize) -> Vec<Vec<String>> {
+        let IpcResponse::QueueList { queues } = list_queue(&state.lock().unwrap(), None, None, count, 0) else {
+            panic!("expected a queue listing");
+        };
+        queues.into_iter()
+            .map(|listing| listing.images.into_iter().filter(|image| image.offset > 0).map(|image| image.path).collect())
+            .collect()
+    }
+
+    fn shown(state: &Mutex<DaemonState>, output: &str) -> String {
+        state.lock().unwrap().current_image_for(output).unwrap().to_string_lossy().to_string()
+    }
+
+    fn queried(outputs: &[&str]) -> Vec<QueriedOutput> {
+        outputs.iter().map(|output| QueriedOutput { name: output.to_string(), size: None, scale: None }).collect()
+    }
+
+    /// A daemon with its queues built from `config` over images on disk
+    fn started(temp_dir: &TempDir, outputs: &[&str], config: &str) -> (DaemonState, Config) {
+        for image in images(temp_dir) {
+            std::fs::write(image, b"").unwrap();
+        }
+        let mut state = daemon(temp_dir, outputs);
+        std::fs::write(&state.config_path, format!("{}[any]\npath = {:?}\n", config, temp_dir.path())).unwrap();
+        let config = load_config(&state).unwrap();
+        state.outputs.set_live(state.outputs.connected().to_vec(), &config);
+        reinitialize_daemon_state_sync(&mut state, &config, &Scans::default(), &HashMap::new(), &[]).unwrap();
+        (state, config)
+    }
+
+    /// The image the latest change on `output` set, once the pipeline is done
+    fn last_set(state: &DaemonState, output: &str) -> Option<String> {
+        state.changes.wait_idle();
+        state.changes.change_log().latest(Some(output), 1).pop().map(|(_, change)| change.image)
+    }
+
+    /// One `--oneshot` run over images on disk, with its state saved like the daemon does
+    fn oneshot_run(temp_dir: &TempDir, outputs: &[&str], config: &str) -> DaemonState {
+        for image in images(temp_dir) {
+            std::fs::write(image, b"").unwrap();
+        }
+        let mut state = daemon(temp_dir, outputs);
+        std::fs::write(&state.config_path, format!("{}[any]\npath = {:?}\n", config, temp_dir.path())).unwrap();
+        let config = load_config(&state).unwrap();
+        state.outputs.set_live(state.outputs.connected().to_vec(), &config);
+        assert_eq!(oneshot(&mut state, &config, &Scans::default()).unwrap(), outputs.len());
+        state.changes.wait_idle();
+        state.save_state().unwrap();
+        state
+    }
+
+    #[tokio::test]
+    async fn test_oneshot_runs_continue_from_the_saved_positions() {
+        let sorted = "[global]\nsorting = \"Ascending\"\n\n";
+        let image = |temp_dir: &TempDir, i: usize| Some(temp_dir.path().join(format!("{}.png", i)).to_string_lossy().to_string());
+
+        let independent = tempdir().unwrap();
+        let first = oneshot_run(&independent, &["DP-1"], sorted);
+        assert_eq!(last_set(&first, "DP-1"), image(&independent, 0));
+        let second = oneshot_run(&independent, &["DP-1"], sorted);
+        assert_eq!(last_set(&second, "DP-1"), image(&independent, 1));
+
+        let synchronized = tempdir().unwrap();
+        let config = format!("monitor_behavior = \"Synchronized\"\n{}", sorted);
+        oneshot_run(&synchronized, &["DP-1", "HDMI-A-1"], &config);
+        let second = oneshot_run(&synchronized, &["DP-1", "HDMI-A-1"], &config);
+        assert_eq!(last_set(&second, "DP-1"), image(&synchronized, 1));
+        assert_eq!(last_set(&second, "HDMI-A-1"), image(&synchronized, 1));
+
+        let grouped = tempdir().unwrap();
+        let config = format!("monitor_behavior = \"Grouped\"\nmonitor_groups = [[\"DP-1\", \"DP-2\"]]\n{}", sorted);
+        let outputs = ["DP-1", "DP-2", "HDMI-A-1"];
+        oneshot_run(&grouped, &outputs, &config);
+        oneshot_run(&grouped, &outputs, &config);
+        let third = oneshot_run(&grouped, &outputs, &config);
+        assert_eq!(last_set(&third, "DP-1"), image(&grouped, 2));
+        assert_eq!(last_set(&third, "DP-2"), image(&grouped, 2));
+        assert_eq!(last_set(&third, "HDMI-A-1"), image(&grouped, 2));
+    }
+
+    #[tokio::test]
+    async fn test_oneshot_without_images_sets_nothing() {
+        let temp_dir = tempdir().unwrap();
+        let mut state = daemon(&temp_dir, &["DP-1"]);
+        let config = load_config(&state).unwrap();
+        state.outputs.set_live(state.outputs.connected().to_vec(), &config);
+        assert_eq!(oneshot(&mut state, &config, &Scans::default()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_startup_backoff_doubles_up_to_the_cap() {
+        let pauses: Vec<Duration> = (0..7).map(startup_backoff).collect();
+        assert_eq!(pauses[..5], [250, 500, 1000, 2000, 4000].map(Duration::from_millis));
+        assert_eq!(pauses[6], STARTUP_MAX_BACKOFF);
+        assert_eq!(startup_backoff(u32::MAX), STARTUP_MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_swww_restart_puts_the_current_images_back() {
+        let temp_dir = tempdir().unwrap();
+        let (state, config) = started(&temp_dir, &["DP-1", "HDMI-A-1"], "");
+        state.changes.wait_idle();
+        let state = Mutex::new(state);
+        let before = (shown(&state, "DP-1"), shown(&state, "HDMI-A-1"));
+        let completed = state.lock().unwrap().changes.metrics().completed;
+
+        reapply_after_restart(&state.lock().unwrap(), &config);
+        state.lock().unwrap().changes.wait_idle();
+        assert_eq!(state.lock().unwrap().changes.metrics().completed, completed + 2);
+        assert_eq!((shown(&state, "DP-1"), shown(&state, "HDMI-A-1")), before);
+    }
+
+    #[tokio::test]
+    async fn test_reload_rebuilds_only_outputs_whose_path_changed() {
+        let temp_dir = tempdir().unwrap();
+        let other = tempdir().unwrap();
+        for name in ["a.png", "b.png"] {
+            std::fs::write(other.path().join(name), b"").unwrap();
+        }
+        let (mut state, config) = started(&temp_dir, &["DP-1", "HDMI-A-1"], "");
+        state.live_config = Some(Arc::new(load_config(&state).unwrap()));
+        state.advance_output("HDMI-A-1", &config);
+        let kept = state.current_image_for("HDMI-A-1").cloned();
+        let timer = state.timers["HDMI-A-1"];
+
+        std::fs::write(&state.config_path, format!("[any]\npath = {:?}\n\n[DP-1]\npath = {:?}\n", temp_dir.path(), other.path())).unwrap();
+        let config = load_config(&state).unwrap();
+        let IpcResponse::Reloaded { summary, .. } = apply_reload(&mut state, config, &queried(&["DP-1", "HDMI-A-1"]), &Scans::default()) else {
+            panic!("expected the reload to succeed");
+        };
+        assert_eq!(summary.rebuilt, ["DP-1"]);
+        assert!(last_set(&state, "DP-1").unwrap().starts_with(&*other.path().to_string_lossy()));
+        assert_eq!(state.queues["DP-1"].get_all_images().len(), 2);
+        assert_eq!(state.current_image_for("HDMI-A-1").cloned(), kept);
+        assert_eq!(state.timers["HDMI-A-1"], timer);
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_a_new_duration_without_rebuilding() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "[global]\nduration = \"5m\"\n\n");
+        state.live_config = Some(Arc::new(load_config(&state).unwrap()));
+        state.advance_output("DP-1", &config);
+        let kept = state.current_image_for("DP-1").cloned();
+        let timer = state.timers["DP-1"];
+
+        std::fs::write(&state.config_path, format!("[global]\nduration = \"30s\"\n\n[any]\npath = {:?}\n", temp_dir.path())).unwrap();
+        let (config, reloaded) = (load_config(&state).unwrap(), load_config(&state).unwrap());
+        let IpcResponse::Reloaded { message, summary } = apply_reload(&mut state, reloaded, &queried(&["DP-1"]), &Scans::default()) else {
+            panic!("expected the reload to succeed");
+        };
+        assert!(message.ends_with("applied from the next change"), "{}", message);
+        assert!(summary.rebuilt.is_empty());
+        assert_eq!(state.current_image_for("DP-1").cloned(), kept);
+        assert_eq!(next_deadline(&state, &config), Some(timer + Duration::from_secs(30)));
+    }
+
+    #[tokio::test]
+    async fn test_rapid_next_commands_while_timers_expire() {
+        let temp_dir = tempdir().unwrap();
+        // Enough images that 40 changes never come back around to one
+        for i in 0..40 {
+            std::fs::write(temp_dir.path().join(format!("extra-{:02}.png", i)), b"").unwrap();
+        }
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "[global]\nsorting = \"Ascending\"\nduration = \"1h\"\ntransition_duration = \"10ms\"\n\n");
+        state.changes.wait_idle();
+        let backend = Arc::new(RecordingBackend::default());
+        state.changes = Arc::new(ChangePipeline::new(changes_runtime(), backend.clone(), 1));
+        let mut ahead = state.queues["DP-1"].clone();
+        let expected: Vec<PathBuf> = (0..40).map(|_| ahead.next().cloned().unwrap()).collect();
+        let state = Arc::new(Mutex::new(state));
+
+        let clients: Vec<_> = (0..4)
+            .map(|_| {
+                let state = Arc::clone(&state);
+                std::thread::spawn(move || for _ in 0..5 {
+                    let next = IpcCommand::Next { output: Some("DP-1".to_string()), group: None };
+                    assert!(matches!(handle_ipc_command(next, Arc::clone(&state)), IpcResponse::Success { .. }));
+                })
+            })
+            .collect();
+        for _ in 0..20 {
+            let mut state = state.lock().unwrap();
+            let now = Instant::now();
+            state.timers.insert("DP-1".to_string(), now - Duration::from_secs(3600));
+            run_due_timers(&mut state, &config, now);
+        }
+        for client in clients {
+            client.join().unwrap();
+        }
+        let state = state.lock().unwrap();
+        state.changes.wait_idle();
+
+        // Every change was picked in queue order under the lock, and the
+        // pipeline set them in that order, dropping only timer changes a newer
+        // one had already replaced
+        let calls = backend.calls.lock().unwrap();
+        let positions: Vec<usize> = calls.iter()
+            .map(|image| expected.iter().position(|expected| expected == image).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", positions);
+        assert_eq!(calls.last(), expected.last());
+        assert_eq!(state.current_image_for("DP-1"), expected.last());
+
+        let metrics = state.changes.metrics();
+        assert_eq!(metrics.failed, 0);
+        assert_eq!(metrics.completed, calls.len() as u64);
+        assert_eq!(metrics.completed + metrics.skipped, 40);
+        assert!(metrics.skipped <= 20, "{} skipped, but only 20 were timer changes", metrics.skipped);
+    }
+
+    #[tokio::test]
+    async fn test_a_failed_reload_keeps_the_runtime_behavior_and_settings() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, _) = started(&temp_dir, &["DP-1", "HDMI-A-1"], "");
+        let response = set_behavior(&mut state, "synchronized", None, &Scans::default());
+        assert!(matches!(response, IpcResponse::Success { .. }), "{:?}", response);
+        state.overrides.insert("DP-1".to_string(), OutputSettings { path: None, duration: Some("1m".to_string()) });
+        let state = Mutex::new(state);
+
+        // swww isn't there to reload against, and then the file doesn't parse
+        let config_path = state.lock().unwrap().config_path.clone();
+        for edited in [format!("[DP-1]\nduration = \"2m\"\n\n[any]\npath = {:?}\n", temp_dir.path()), "[any\n".to_string()] {
+            std::fs::write(&config_path, edited).unwrap();
+            assert!(matches!(reload(&state), IpcResponse::Error { .. }));
+            let state = state.lock().unwrap();
+            assert!(matches!(state.behavior, Some((MonitorBehavior::Synchronized, None))));
+            assert!(state.overrides.contains_key("DP-1"));
+            assert!(matches!(state.current_behavior(), MonitorBehavior::Synchronized));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rescan_scans_ahead_from_the_running_config_not_the_file() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "");
+        let config = Arc::new(config);
+        state.live_config = Some(Arc::clone(&config));
+        let config_path = state.config_path.clone();
+        let state = Arc::new(Mutex::new(state));
+        let elsewhere = tempdir().unwrap();
+
+        // An edit not reloaded yet points somewhere else, and then doesn't parse
+        for edited in [format!("[any]\npath = {:?}\n", elsewhere.path()), "[any\n".to_string()] {
+            std::fs::write(&config_path, edited).unwrap();
+            let scanning = Arc::clone(&state);
+            let scans = tokio::task::spawn_blocking(move || scan_for_rescan(&scanning, None)).await.unwrap();
+
+            // Added after the scan, so it only joins if the queue was scanned again
+            std::fs::write(temp_dir.path().join("late.png"), b"").unwrap();
+            let IpcResponse::Success { message } = rescan_queues(&mut state.lock().unwrap(), &config, None, &scans) else {
+                panic!("expected the rescan to succeed");
+            };
+            assert!(message.ends_with("added: 0, removed: 0"), "{}", message);
+            std::fs::remove_file(temp_dir.path().join("late.png")).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schedule_switches_use_the_images_scanned_ahead() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "");
+        assert!(schedule_paths(&state, &config).is_empty());
+
+        let active = state.profiles["DP-1"].clone();
+        state.profiles.insert("DP-1".to_string(), "night".to_string());
+        let paths = schedule_paths(&state, &config);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].0, temp_dir.path());
+        let scans = Scans::run(paths).await;
+
+        // Gone from disk after the scan, so only the scan can have it
+        let deleted = images(&temp_dir).pop().unwrap();
+        std::fs::remove_file(&deleted).unwrap();
+        apply_schedules(&mut state, &config, &scans);
+        assert_eq!(state.profiles["DP-1"], active);
+        assert!(state.queues["DP-1"].get_all_images().contains(&deleted));
+        assert!(schedule_paths(&state, &config).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disconnected_outputs_resume_their_queue_when_they_return() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["eDP-1", "HDMI-A-1"], "");
+        state.advance_output("HDMI-A-1", &config);
+        let left_at = state.current_image_for("HDMI-A-1").cloned().unwrap();
+        let kept = state.current_image_for("eDP-1").cloned().unwrap();
+
+        follow_outputs(&mut state, &config, &queried(&["eDP-1"]), &Scans::default());
+        assert_eq!(state.outputs.live(), ["eDP-1"]);
+        assert!(!state.queues.contains_key("HDMI-A-1"));
+        assert!(!state.timers.contains_key("HDMI-A-1"));
+        assert_eq!(state.current_image_for("eDP-1"), Some(&kept));
+
+        follow_outputs(&mut state, &config, &queried(&["eDP-1", "HDMI-A-1"]), &Scans::default());
+        assert!(state.timers.contains_key("HDMI-A-1"));
+        assert_eq!(state.current_image_for("HDMI-A-1"), Some(&left_at));
+        // The screen it comes back on is blank, so the image is set again
+        assert_eq!(last_set(&state, "HDMI-A-1"), Some(left_at.to_string_lossy().to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_the_loop_sleeps_until_the_next_deadline() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["eDP-1", "HDMI-A-1"], "[global]\nduration = \"10m\"\n\n");
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        let now = Instant::now();
+        state.timers.insert("eDP-1".to_string(), now - minutes(4));
+        state.timers.insert("HDMI-A-1".to_string(), now - minutes(7));
+        assert_eq!(next_deadline(&state, &config), Some(now + minutes(3)));
+
+        // Nothing is due yet, so nothing moves
+        let shown = state.current_image_for("HDMI-A-1").cloned();
+        run_due_timers(&mut state, &config, now);
+        assert_eq!(state.current_image_for("HDMI-A-1").cloned(), shown);
+
+        let due = now + minutes(3);
+        run_due_timers(&mut state, &config, due);
+        assert_ne!(state.current_image_for("HDMI-A-1").cloned(), shown);
+        assert_eq!(state.timers["HDMI-A-1"], due);
+        assert_eq!(next_deadline(&state, &config), Some(now + minutes(6)));
+
+        // Pinned outputs and a paused daemon have nothing to wake up for
+        state.changes.pins().pin("eDP-1", state.current_image_for("eDP-1").unwrap());
+        assert_eq!(next_deadline(&state, &config), Some(due + minutes(10)));
+        state.paused = true;
+        assert_eq!(next_deadline(&state, &config), None);
+    }
+
+    #[tokio::test]
+    async fn test_resume_resets_timers_by_on_resume() {
+        let outputs = ["eDP-1", "HDMI-A-1", "DP-1"];
+        let due = |state: &DaemonState, config: &Config| -> Vec<Instant> {
+            outputs.iter()
+                .map(|output| {
+                    let started = state.timers[*output];
+                    started + state.intervals.target(output, started, config.get_output_config(output).duration)
+                })
+                .collect()
+        };
+
+        for (action, spacing) in [("restart", None), ("change", Some(Duration::ZERO)), ("stagger", Some(RESUME_STAGGER))] {
+            let temp_dir = tempdir().unwrap();
+            let (mut state, config) = started(&temp_dir, &outputs, &format!("[global]\non_resume = {:?}\n\n", action));
+            let now = Instant::now() + Duration::from_secs(8 * 3600);
+            resume_timers(&mut state, &config, now);
+
+            match spacing {
+                None => assert!(state.timers.values().all(|timer| *timer == now), "{}", action),
+                Some(spacing) => {
+                    let expected: Vec<Instant> = (0..outputs.len() as u32).map(|i| now + spacing * i).collect();
+                    assert_eq!(due(&state, &config), expected, "{}", action);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connected_outputs_join_the_synchronized_image() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["eDP-1"], "monitor_behavior = \"Synchronized\"\n");
+        let shared = state.current_image_for("eDP-1").cloned().unwrap();
+
+        follow_outputs(&mut state, &config, &queried(&["eDP-1", "HDMI-A-1"]), &Scans::default());
+        assert_eq!(state.current_image_for("HDMI-A-1"), Some(&shared));
+        assert_eq!(last_set(&state, "HDMI-A-1"), Some(shared.to_string_lossy().to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_paused_timers_resume_with_the_time_they_had_left() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "[global]\nduration = \"5m\"\n\n");
+        let now = Instant::now();
+        state.timers.insert("DP-1".to_string(), now - Duration::from_secs(180));
+
+        freeze_timers(&mut state, &config, now, |_| true);
+        state.paused = true;
+        assert_eq!(next_deadline(&state, &config), None);
+
+        // An hour later, long past the deadline the timer had
+        let later = now + Duration::from_secs(3600);
+        state.paused = false;
+        thaw_timers(&mut state, &config, later, |_| true);
+        let shown = state.current_image_for("DP-1").cloned();
+        run_due_timers(&mut state, &config, later);
+        assert_eq!(state.current_image_for("DP-1").cloned(), shown);
+        assert_eq!(next_deadline(&state, &config), Some(later + Duration::from_secs(120)));
+        assert!(state.frozen.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_status_shows_the_frozen_time_while_paused() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, _) = started(&temp_dir, &["DP-1"], "[global]\nduration = \"5m\"\n\n");
+        let started_at = Instant::now() - Duration::from_secs(180);
+        state.timers.insert("DP-1".to_string(), started_at);
+        let state = Arc::new(Mutex::new(state));
+        let timer_remaining = || match handle_ipc_command(IpcCommand::Status, Arc::clone(&state)) {
+            IpcResponse::Status { outputs, .. } => outputs[0].timer_remaining.unwrap(),
+            response => panic!("expected a status, got {:?}", response),
+        };
+
+        handle_ipc_command(IpcCommand::Pause, Arc::clone(&state));
+        let left = state.lock().unwrap().frozen["DP-1"].1;
+        assert!((119..=120).contains(&left.as_secs()), "{:?}", left);
+        assert_eq!(timer_remaining(), left.as_secs());
+
+        handle_ipc_command(IpcCommand::Resume, Arc::clone(&state));
+        let state_guard = state.lock().unwrap();
+        assert!(state_guard.frozen.is_empty());
+        // Moved on by as long as it stood still
+        assert!(state_guard.timers["DP-1"] > started_at);
+        assert!(state_guard.timers["DP-1"] < started_at + Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_config_shows_the_settings_in_use_not_the_file() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "[global]\nduration = \"5m\"\n\n");
+        state.live_config = Some(Arc::new(config));
+        let state = Arc::new(Mutex::new(state));
+        let duration = || match handle_ipc_command(IpcCommand::GetConfig { output: None }, Arc::clone(&state)) {
+            IpcResponse::Config { outputs, .. } => outputs[0].toml.lines()
+                .find(|line| line.starts_with("duration"))
+                .unwrap()
+                .to_string(),
+            response => panic!("expected the config, got {:?}", response),
+        };
+
+        let config_path = state.lock().unwrap().config_path.clone();
+        std::fs::write(&config_path, format!("[global]\nduration = \"10m\"\n\n[any]\npath = {:?}\n", temp_dir.path())).unwrap();
+        assert_eq!(duration(), "duration = \"5m\"");
+        std::fs::write(&config_path, "[global\n").unwrap();
+        assert_eq!(duration(), "duration = \"5m\"");
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_a_reload_of_a_broken_config() {
+        let temp_dir = tempdir().unwrap();
+        let (mut state, config) = started(&temp_dir, &["DP-1"], "");
+        state.live_config = Some(Arc::new(config));
+        state.reloads = ReloadStats::new(file_mtime(&state.config_path));
+        std::fs::write(&state.config_path, "[global\nduration = \n").unwrap();
+        // Newer than the loaded file however coarse the file times are
+        std::fs::File::options().write(true).open(&state.config_path).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        let state = Arc::new(Mutex::new(state));
+
+        let reloaded = handle_ipc_command(IpcCommand::Reload, Arc::clone(&state));
+        assert!(matches!(reloaded, IpcResponse::Error { .. }), "{:?}", reloaded);
+        let IpcResponse::Status { outputs, daemon: Some(daemon), .. } = handle_ipc_command(IpcCommand::Status, Arc::clone(&state)) else {
+            panic!("expected a status");
+        };
+        assert_eq!(outputs.len(), 1);
+        assert!(daemon.config_modified);
+        assert_eq!((daemon.reloads_succeeded, daemon.reloads_failed), (0, 1));
+        let last = daemon.last_reload.unwrap();
+        assert!(!last.success);
+        assert!(last.message.starts_with("Failed to reload configuration"), "{}", last.message);
+    }
+
+    #[tokio::test]
+    async fn test_preview_leaves_the_synchronized_queue_alone() {
+        let temp_dir = tempdir().unwrap();
+        let mut state = daemon(&temp_dir, &["DP-1", "HDMI-A-1"]);
+        state.shared_queue = Queue::new(4, Sorting::Random, images(&temp_dir));
+        let state = Arc::new(Mutex::new(state));
+
+        let first = preview(&state, 3);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].len(), 3);
+        for _ in 0..5 {
+            assert_eq!(preview(&state, 3), first);
+        }
+
+        handle_ipc_command(IpcCommand::Next { output: None, group: None }, Arc::clone(&state));
+        assert_eq!(shown(&state, "DP-1"), first[0][0]);
+        assert_eq!(shown(&state, "HDMI-A-1"), first[0][0]);
+        assert_eq!(preview(&state, 2)[0], first[0][1..]);
+    }
+
+    #[tokio::test]
+    async fn test_preview_leaves_group_queues_alone() {
+        let temp_dir = tempdir().unwrap();
+        let mut state = daemon(&temp_dir, &["DP-1", "DP-2", "HDMI-A-1"]);
+        let outputs = vec!["DP-1".to_string(), "DP-2".to_string()];
+        state.outputs.assign_group("desk", &outputs);
+        state.groups.push(MonitorGroup {
+            name: "desk".to_string(),
+            outputs,
+            queue: Queue::new(4, Sorting::Random, images(&temp_dir)).unwrap(),
+            timer: Instant::now(),
+            profile: DEFAULT_PROFILE.to_string(),
+        });
+        state.queues.insert("HDMI-A-1".to_string(), Queue::new(4, Sorting::Random, images(&temp_dir)).unwrap());
+        let state = Arc::new(Mutex::new(state));
+
+        let first = preview(&state, 2);
+        assert_eq!(first.len(), 2);
+        for _ in 0..5 {
+            assert_eq!(preview(&state, 2), first);
+        }
+
+        handle_ipc_command(IpcCommand::Next { output: None, group: None }, Arc::clone(&state));
+        let listed = list_queue(&state.lock().unwrap(), None, None, 0, 0);
+        let IpcResponse::QueueList { queues } = listed else { panic!("expected a queue listing") };
+        for (listing, upcoming) in queues.iter().zip(&first) {
+            for output in &listing.outputs {
+                assert_eq!(shown(&state, output), upcoming[0], "{}", output);
+            }
         }
     }
 }
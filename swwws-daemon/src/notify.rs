@@ -0,0 +1,140 @@
+use std::ffi::OsString;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+/// Tells systemd how the daemon is doing, for `Type=notify` services: when it
+/// is ready, when it reloads, a status line and watchdog pings.
+///
+/// Messages go to the datagram socket in `NOTIFY_SOCKET`, as `sd_notify` does.
+/// Without that variable, as outside systemd, every call does nothing.
+#[derive(Debug, Default)]
+pub struct Notifier {
+    socket: Option<(UnixDatagram, SocketAddr)>,
+    watchdog: Option<Duration>,
+}
+
+impl Notifier {
+    /// A notifier for the service manager that started this process, if any
+    pub fn from_env() -> Self {
+        let watchdog = watchdog_interval(
+            std::env::var("WATCHDOG_USEC").ok().as_deref(),
+            std::env::var("WATCHDOG_PID").ok().as_deref(),
+            std::process::id(),
+        );
+        match std::env::var_os("NOTIFY_SOCKET") {
+            Some(path) => Self::new(path, watchdog),
+            None => Self::default(),
+        }
+    }
+
+    /// A notifier sending to the socket at `path`; one starting with `@` is
+    /// in the abstract namespace
+    pub fn new(path: OsString, watchdog: Option<Duration>) -> Self {
+        let address = match path.as_encoded_bytes() {
+            [b'@', name @ ..] => <SocketAddr as SocketAddrExt>::from_abstract_name(name),
+            _ => SocketAddr::from_pathname(&path),
+        };
+        let socket = address.and_then(|address| Ok((UnixDatagram::unbound()?, address)));
+        match socket {
+            Ok(socket) => Self { socket: Some(socket), watchdog },
+            Err(e) => {
+                log::warn!("Can't reach the service manager at {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// How often to ping the watchdog, if systemd expects it to be pinged
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        self.socket.as_ref().and(self.watchdog)
+    }
+
+    /// The initial wallpapers are set, or a reload is done
+    pub fn ready(&self, status: &str) {
+        self.send(&format!("READY=1\nSTATUS={}", status));
+    }
+
+    /// A reload started; `ready` follows once it is done
+    pub fn reloading(&self) {
+        // systemd wants the time of the reload along for Type=notify-reload
+        self.send(&format!("RELOADING=1\nMONOTONIC_USEC={}", monotonic_usec()));
+    }
+
+    pub fn stopping(&self) {
+        self.send("STOPPING=1");
+    }
+
+    pub fn watchdog(&self) {
+        self.send("WATCHDOG=1");
+    }
+
+    fn send(&self, message: &str) {
+        let Some((socket, address)) = &self.socket else { return };
+        if let Err(e) = socket.send_to_addr(message.as_bytes(), address) {
+            log::debug!("Failed to notify the service manager: {}", e);
+        }
+    }
+}
+
+/// Half the `WatchdogSec=` systemd passed on, as its docs advise, when the
+/// watchdog is meant for this process
+fn watchdog_interval(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if pid.is_some_and(|pid| pid.parse() != Ok(own_pid)) {
+        return None;
+    }
+    let usec: u64 = usec?.parse().ok().filter(|usec| *usec > 0)?;
+    Some(Duration::from_micros(usec) / 2)
+}
+
+/// `CLOCK_MONOTONIC` in microseconds, the clock systemd compares reloads by
+fn monotonic_usec() -> u64 {
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `now` is a valid timespec for the call to fill in
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    now.tv_sec as u64 * 1_000_000 + now.tv_nsec as u64 / 1_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_messages_reach_the_notify_socket() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("notify");
+        let systemd = UnixDatagram::bind(&path).unwrap();
+        let notifier = Notifier::new(path.into_os_string(), Some(Duration::from_secs(15)));
+        assert_eq!(notifier.watchdog_interval(), Some(Duration::from_secs(15)));
+
+        let mut buffer = [0; 256];
+        let mut received = || {
+            let length = systemd.recv(&mut buffer).unwrap();
+            String::from_utf8(buffer[..length].to_vec()).unwrap()
+        };
+        notifier.ready("2 outputs, 2 queues");
+        assert_eq!(received(), "READY=1\nSTATUS=2 outputs, 2 queues");
+        notifier.reloading();
+        assert!(received().starts_with("RELOADING=1\nMONOTONIC_USEC="));
+        notifier.watchdog();
+        assert_eq!(received(), "WATCHDOG=1");
+    }
+
+    #[test]
+    fn test_nothing_happens_outside_systemd() {
+        let notifier = Notifier::default();
+        assert_eq!(notifier.watchdog_interval(), None);
+        notifier.ready("ready");
+        notifier.watchdog();
+    }
+
+    #[test]
+    fn test_watchdog_interval_is_half_the_timeout_for_this_process() {
+        assert_eq!(watchdog_interval(Some("30000000"), None, 42), Some(Duration::from_secs(15)));
+        assert_eq!(watchdog_interval(Some("30000000"), Some("42"), 42), Some(Duration::from_secs(15)));
+        assert_eq!(watchdog_interval(Some("30000000"), Some("7"), 42), None);
+        assert_eq!(watchdog_interval(Some("0"), None, 42), None);
+        assert_eq!(watchdog_interval(None, None, 42), None);
+    }
+}
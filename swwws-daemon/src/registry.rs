@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use swwws_common::{Orientation, QueriedOutput};
+use swwws_config::Config;
+
+/// The outputs the daemon drives. The timer loop, IPC handlers and status all
+/// take their output list, group membership and name lookups from here, so
+/// they can't disagree about which outputs exist.
+#[derive(Debug, Clone, Default)]
+pub struct OutputRegistry {
+    /// Outputs reported by `swww query`, in its order
+    connected: Vec<String>,
+    /// The connected outputs swwws manages, i.e. those not set to `enabled = false`
+    live: Vec<String>,
+    /// Output names that have a section of their own in the config
+    sections: HashSet<String>,
+    /// Group of each output, for groups that were actually set up
+    groups: HashMap<String, String>,
+    /// Orientation of outputs whose size swww reported
+    orientations: HashMap<String, Orientation>,
+    /// Width and height of outputs whose size swww reported
+    sizes: HashMap<String, (u32, u32)>,
+    /// Scale of outputs whose scale swww reported, as it printed it
+    scales: HashMap<String, String>,
+}
+
+impl OutputRegistry {
+    pub fn new(live: Vec<String>, config: &Config) -> Self {
+        let mut registry = Self::default();
+        registry.set_live(live, config);
+        registry
+    }
+
+    /// Replace the connected outputs and config sections, keeping group membership.
+    /// Outputs disabled in `config` are connected but not live.
+    pub fn set_live(&mut self, connected: Vec<String>, config: &Config) {
+        self.live = connected.iter()
+            .filter(|output| config.get_output_config(output).enabled)
+            .cloned()
+            .collect();
+        self.connected = connected;
+        self.sections = config.outputs.keys().cloned().collect();
+    }
+
+    /// Record the size and orientation of each queried output that came with a size
+    pub fn set_geometry(&mut self, outputs: &[QueriedOutput]) {
+        for output in outputs {
+            if let Some(orientation) = output.orientation() {
+                self.orientations.insert(output.name.clone(), orientation);
+            }
+            if let Some(size) = output.size {
+                self.sizes.insert(output.name.clone(), size);
+            }
+            if let Some(scale) = &output.scale {
+                self.scales.insert(output.name.clone(), scale.clone());
+            }
+        }
+    }
+
+    pub fn size(&self, name: &str) -> Option<(u32, u32)> {
+        self.sizes.get(name).copied()
+    }
+
+    pub fn scale(&self, name: &str) -> Option<&str> {
+        self.scales.get(name).map(String::as_str)
+    }
+
+    pub fn orientation(&self, name: &str) -> Option<Orientation> {
+        self.orientations.get(name).copied()
+    }
+
+    /// Size of the largest of `outputs`, by area, among those with a known size
+    pub fn largest_size(&self, outputs: &[impl AsRef<str>]) -> Option<(u32, u32)> {
+        outputs.iter()
+            .filter_map(|output| self.sizes.get(output.as_ref()).copied())
+            .max_by_key(|(width, height)| u64::from(*width) * u64::from(*height))
+    }
+
+    pub fn live(&self) -> &[String] {
+        &self.live
+    }
+
+    pub fn is_live(&self, name: &str) -> bool {
+        self.live.iter().any(|output| output == name)
+    }
+
+    /// Every output `swww query` reported, managed or not
+    pub fn connected(&self) -> &[String] {
+        &self.connected
+    }
+
+    pub fn is_connected(&self, name: &str) -> bool {
+        self.connected.iter().any(|output| output == name)
+    }
+
+    /// Connected outputs left alone because of `enabled = false`
+    pub fn disabled(&self) -> Vec<String> {
+        self.connected.iter()
+            .filter(|output| !self.is_live(output))
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_disabled(&self, name: &str) -> bool {
+        self.is_connected(name) && !self.is_live(name)
+    }
+
+    /// Whether the output is configured by its own section rather than `[any]`
+    pub fn has_section(&self, name: &str) -> bool {
+        self.sections.contains(name)
+    }
+
+    /// Output sections naming no connected output, sorted
+    pub fn unmatched_sections(&self) -> Vec<String> {
+        let mut unmatched: Vec<String> = self.sections.iter()
+            .filter(|section| !self.is_connected(section))
+            .cloned()
+            .collect();
+        unmatched.sort();
+        unmatched
+    }
+
+    /// Record that `outputs` are driven together by `group`
+    pub fn assign_group(&mut self, group: &str, outputs: &[String]) {
+        for output in outputs {
+            self.groups.insert(output.clone(), group.to_string());
+        }
+    }
+
+    pub fn clear_groups(&mut self) {
+        self.groups.clear();
+    }
+
+    pub fn group_of(&self, name: &str) -> Option<&str> {
+        self.groups.get(name).map(String::as_str)
+    }
+
+    /// Live outputs that are not part of any group, in `swww query` order
+    pub fn ungrouped(&self) -> Vec<String> {
+        self.live.iter()
+            .filter(|output| !self.groups.contains_key(*output))
+            .cloned()
+            .collect()
+    }
+
+    /// Resolve an output name given by the user: an exact connected name first, then
+    /// a connected name differing only in case, then an output with a config section.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        if self.is_connected(name) {
+            return Some(name.to_string());
+        }
+        if let Some(output) = self.connected.iter().find(|output| output.eq_ignore_ascii_case(name)) {
+            return Some(output.clone());
+        }
+        self.has_section(name).then(|| name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swwws_config::OutputConfig;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn registry(live: &[&str], sections: &[&str]) -> OutputRegistry {
+        let mut config = Config::default();
+        for section in sections {
+            config.outputs.insert(section.to_string(), OutputConfig::default());
+        }
+        OutputRegistry::new(names(live), &config)
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let registry = registry(&["DP-1", "dp-1", "HDMI-A-1"], &["eDP-1"]);
+
+        // Exact beats case-insensitive
+        assert_eq!(registry.resolve("dp-1").as_deref(), Some("dp-1"));
+        assert_eq!(registry.resolve("hdmi-a-1").as_deref(), Some("HDMI-A-1"));
+        // Configured but not connected
+        assert_eq!(registry.resolve("eDP-1").as_deref(), Some("eDP-1"));
+        assert!(!registry.is_live("eDP-1"));
+        assert_eq!(registry.resolve("DP-9"), None);
+        assert_eq!(registry.unmatched_sections(), names(&["eDP-1"]));
+    }
+
+    #[test]
+    fn test_groups_and_ungrouped_cover_live_outputs() {
+        let mut registry = registry(&["DP-1", "DP-2", "HDMI-A-1"], &["DP-1"]);
+        registry.assign_group("desk", &names(&["DP-1", "DP-2"]));
+
+        assert_eq!(registry.group_of("DP-2"), Some("desk"));
+        assert_eq!(registry.ungrouped(), names(&["HDMI-A-1"]));
+        let covered = registry.live().iter()
+            .filter(|output| registry.group_of(output).is_some() || registry.ungrouped().contains(output))
+            .count();
+        assert_eq!(covered, registry.live().len());
+
+        registry.clear_groups();
+        assert_eq!(registry.ungrouped(), registry.live());
+    }
+
+    #[test]
+    fn test_disabled_outputs_are_connected_but_not_live() {
+        let mut config = Config::default();
+        config.outputs.insert("HDMI-A-1".to_string(), OutputConfig { enabled: false, ..Default::default() });
+        let mut registry = OutputRegistry::new(names(&["DP-1", "HDMI-A-1"]), &config);
+        registry.assign_group("desk", &names(&["DP-1"]));
+
+        assert_eq!(registry.live(), names(&["DP-1"]));
+        assert_eq!(registry.connected(), names(&["DP-1", "HDMI-A-1"]));
+        assert_eq!(registry.disabled(), names(&["HDMI-A-1"]));
+        assert!(registry.is_disabled("HDMI-A-1"));
+        assert!(registry.ungrouped().is_empty());
+        // Still resolvable, so IPC can say why it won't act on it
+        assert_eq!(registry.resolve("hdmi-a-1").as_deref(), Some("HDMI-A-1"));
+    }
+
+    #[test]
+    fn test_geometry() {
+        let mut registry = registry(&["DP-1", "DP-2", "DP-3"], &[]);
+        registry.set_geometry(&[
+            QueriedOutput { name: "DP-1".to_string(), size: Some((1080, 1920)), scale: Some("1.5".to_string()) },
+            QueriedOutput { name: "DP-2".to_string(), size: None, scale: None },
+            QueriedOutput { name: "DP-3".to_string(), size: Some((3840, 2160)), scale: None },
+        ]);
+
+        assert_eq!(registry.orientation("DP-1"), Some(Orientation::Portrait));
+        assert_eq!(registry.orientation("DP-2"), None);
+        assert_eq!(registry.largest_size(&["DP-1", "DP-2", "DP-3"]), Some((3840, 2160)));
+        assert_eq!(registry.largest_size(&["DP-1", "DP-2"]), Some((1080, 1920)));
+        assert_eq!(registry.largest_size(&["DP-2"]), None);
+        assert_eq!(registry.size("DP-3"), Some((3840, 2160)));
+        assert_eq!(registry.scale("DP-1"), Some("1.5"));
+        assert_eq!(registry.scale("DP-2"), None);
+    }
+
+    #[test]
+    fn test_set_live_keeps_groups() {
+        let mut registry = registry(&["DP-1", "DP-2"], &[]);
+        registry.assign_group("desk", &names(&["DP-1", "DP-2"]));
+
+        let mut config = Config::default();
+        config.outputs.insert("DP-3".to_string(), OutputConfig::default());
+        registry.set_live(names(&["DP-1", "DP-2", "DP-3"]), &config);
+
+        assert_eq!(registry.group_of("DP-1"), Some("desk"));
+        assert_eq!(registry.ungrouped(), names(&["DP-3"]));
+        assert!(registry.has_section("DP-3"));
+    }
+}
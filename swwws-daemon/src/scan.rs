@@ -0,0 +1,89 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use swwws_common::{DiscoveryOptions, ErrorReporting, ImageDiscovery};
+use tokio::task::JoinSet;
+
+/// Images found in each wallpaper path, scanned ahead of building queues so
+/// that a slow scan never runs while the daemon state is locked
+#[derive(Debug, Default)]
+pub struct Scans {
+    results: HashMap<(PathBuf, DiscoveryOptions), Result<Vec<PathBuf>, String>>,
+}
+
+impl Scans {
+    /// Scan `paths` side by side on blocking threads, each path once per
+    /// set of options it is scanned with
+    pub async fn run(paths: Vec<(PathBuf, DiscoveryOptions)>) -> Self {
+        Self::scan_all(paths, false).await
+    }
+
+    /// Like `run`, but walks every tree even where the discovery cache
+    /// thinks nothing changed
+    pub async fn rescan(paths: Vec<(PathBuf, DiscoveryOptions)>) -> Self {
+        Self::scan_all(paths, true).await
+    }
+
+    async fn scan_all(paths: Vec<(PathBuf, DiscoveryOptions)>, force: bool) -> Self {
+        let paths: HashSet<(PathBuf, DiscoveryOptions)> = paths.into_iter().collect();
+
+        let mut scans = JoinSet::new();
+        for (path, options) in paths {
+            scans.spawn(async move {
+                let result = ImageDiscovery::discover_images_async(path.clone(), options.clone(), force).await;
+                ((path, options), result.map_err(|e| e.user_friendly_message()))
+            });
+        }
+
+        let mut results = HashMap::new();
+        while let Some(scanned) = scans.join_next().await {
+            match scanned {
+                Ok((key, result)) => {
+                    results.insert(key, result);
+                }
+                Err(e) => log::error!("Image scan task failed: {}", e),
+            }
+        }
+        Self { results }
+    }
+
+    /// Images in `path`, or why there are none. A path that was not scanned
+    /// ahead, e.g. because the config changed in between, is scanned now.
+    pub fn images(&self, path: &Path, options: &DiscoveryOptions) -> Result<Vec<PathBuf>, String> {
+        match self.results.get(&(path.to_path_buf(), options.clone())) {
+            Some(result) => result.clone(),
+            None => {
+                log::debug!("{:?} was not scanned ahead, scanning it now", path);
+                ImageDiscovery::discover_images(path, options, false).map_err(|e| e.user_friendly_message())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_scans_cover_each_path_once() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("a.png"), "fake png").unwrap();
+        let walls = temp_dir.path().to_path_buf();
+        let missing = temp_dir.path().join("missing");
+
+        let options = DiscoveryOptions::default();
+        let scans = Scans::run(vec![
+            (walls.clone(), options.clone()),
+            (missing.clone(), options.clone()),
+            (walls.clone(), options.clone()),
+        ]).await;
+        assert_eq!(scans.results.len(), 2);
+        assert_eq!(scans.images(&walls, &options), Ok(vec![walls.join("a.png")]));
+        assert!(scans.images(&missing, &options).is_err());
+
+        // Not scanned ahead, but still found
+        std::fs::create_dir(temp_dir.path().join("later")).unwrap();
+        std::fs::write(temp_dir.path().join("later/b.png"), "fake png").unwrap();
+        assert_eq!(scans.images(&temp_dir.path().join("later"), &options), Ok(vec![temp_dir.path().join("later/b.png")]));
+    }
+}
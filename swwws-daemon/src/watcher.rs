@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{FromRawFd, AsRawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Events that can add an image to a directory or take one away. A file only
+/// counts as added once it has been written in full, or moved into place.
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_TO
+    | libc::IN_MOVED_FROM
+    | libc::IN_DELETE
+    | libc::IN_CREATE
+    | libc::IN_ONLYDIR;
+
+/// How long a watched directory has to be quiet before its queues are updated
+pub const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Size of `struct inotify_event` without its trailing name
+const EVENT_HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+
+/// Watches wallpaper directories, and everything below them, with inotify.
+///
+/// Nothing runs in the background: the daemon calls `poll` from its timer loop,
+/// which reads whatever events are pending and reports a directory once it has
+/// been quiet for a while, so copying in a whole folder causes one rescan.
+pub struct DirectoryWatcher {
+    inotify: File,
+    /// Directories given to `set_roots`
+    roots: Vec<PathBuf>,
+    /// Watched directory of each watch descriptor
+    watches: HashMap<i32, PathBuf>,
+    /// Roots with changes not reported yet
+    changed: HashSet<PathBuf>,
+    last_event: Option<Instant>,
+}
+
+impl DirectoryWatcher {
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            inotify: unsafe { File::from_raw_fd(fd) },
+            roots: Vec::new(),
+            watches: HashMap::new(),
+            changed: HashSet::new(),
+            last_event: None,
+        })
+    }
+
+    /// Watch `roots` and every directory below them, and nothing else. Roots
+    /// that are not directories, like a single configured image, are skipped.
+    pub fn set_roots(&mut self, mut roots: Vec<PathBuf>) {
+        roots.sort();
+        roots.dedup();
+        roots.retain(|root| root.is_dir());
+        if roots == self.roots {
+            return;
+        }
+
+        for wd in self.watches.keys() {
+            unsafe { libc::inotify_rm_watch(self.inotify.as_raw_fd(), *wd) };
+        }
+        self.watches.clear();
+        self.changed.retain(|root| roots.contains(root));
+        self.roots = roots;
+        for root in self.roots.clone() {
+            self.watch_tree(&root);
+        }
+        if !self.roots.is_empty() {
+            log::info!("Watching {} wallpaper directories ({} with subdirectories)", self.roots.len(), self.watches.len());
+        }
+    }
+
+    fn watch_tree(&mut self, dir: &Path) {
+        match self.add_watch(dir) {
+            Ok(true) => {}
+            // Reached again through a link
+            Ok(false) => return,
+            Err(e) => {
+                log::warn!("Can't watch {:?} for new wallpapers: {}", dir, e);
+                return;
+            }
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            // Follows links, whether or not discovery does; an extra watch is harmless
+            if entry.path().is_dir() {
+                self.watch_tree(&entry.path());
+            }
+        }
+    }
+
+    /// Returns false if the directory was already watched
+    fn add_watch(&mut self, dir: &Path) -> io::Result<bool> {
+        let path = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let wd = unsafe { libc::inotify_add_watch(self.inotify.as_raw_fd(), path.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if self.watches.contains_key(&wd) {
+            return Ok(false);
+        }
+        self.watches.insert(wd, dir.to_path_buf());
+        Ok(true)
+    }
+
+    /// Read pending events and return the roots that changed, once no event has
+    /// come in for `debounce`. Returns nothing while changes are still coming in.
+    pub fn poll(&mut self, now: Instant, debounce: Duration) -> Vec<PathBuf> {
+        let mut buffer = [0u8; 4096];
+        loop {
+            match self.inotify.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => {
+                    self.handle_events(&buffer[..read]);
+                    self.last_event = Some(now);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    if e.kind() != io::ErrorKind::WouldBlock {
+                        log::warn!("Failed to read wallpaper directory changes: {}", e);
+                    }
+                    break;
+                }
+            }
+        }
+
+        match self.last_event {
+            Some(last_event) if now.duration_since(last_event) >= debounce && !self.changed.is_empty() => {
+                self.last_event = None;
+                self.changed.drain().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn handle_events(&mut self, mut events: &[u8]) {
+        while events.len() >= EVENT_HEADER {
+            let field = |at: usize| [events[at], events[at + 1], events[at + 2], events[at + 3]];
+            let wd = i32::from_ne_bytes(field(0));
+            let mask = u32::from_ne_bytes(field(4));
+            let name_len = u32::from_ne_bytes(field(12)) as usize;
+            let name = &events[EVENT_HEADER..(EVENT_HEADER + name_len).min(events.len())];
+            let name = Path::new(std::ffi::OsStr::from_bytes(name.split(|&b| b == 0).next().unwrap_or_default()));
+            events = &events[(EVENT_HEADER + name_len).min(events.len())..];
+
+            if mask & libc::IN_Q_OVERFLOW != 0 {
+                log::warn!("Missed wallpaper directory changes, rescanning every watched directory");
+                self.changed.extend(self.roots.iter().cloned());
+                continue;
+            }
+            if mask & libc::IN_IGNORED != 0 {
+                self.watches.remove(&wd);
+                continue;
+            }
+            let Some(dir) = self.watches.get(&wd).cloned() else { continue };
+            let path = dir.join(name);
+
+            // A new subdirectory gets watched too; files in it count as added
+            if mask & libc::IN_ISDIR != 0 && mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                self.watch_tree(&path);
+            }
+            if mask & libc::IN_CREATE != 0 && mask & libc::IN_ISDIR == 0 {
+                // Wait for IN_CLOSE_WRITE, the file is still being written
+                continue;
+            }
+
+            log::debug!("Wallpaper directory change: {:?}", path);
+            let roots: Vec<PathBuf> = self.roots.iter()
+                .filter(|root| dir.starts_with(root))
+                .cloned()
+                .collect();
+            self.changed.extend(roots);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_changes_are_reported_once_quiet() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        let mut watcher = DirectoryWatcher::new().unwrap();
+        watcher.set_roots(vec![temp_dir.path().to_path_buf(), temp_dir.path().join("missing")]);
+
+        let start = Instant::now();
+        let debounce = Duration::from_secs(2);
+        assert!(watcher.poll(start, debounce).is_empty());
+
+        std::fs::write(nested.join("a.png"), b"png").unwrap();
+        // Still settling
+        assert!(watcher.poll(start, debounce).is_empty());
+        assert_eq!(watcher.poll(start + debounce, debounce), vec![temp_dir.path().to_path_buf()]);
+        assert!(watcher.poll(start + debounce * 2, debounce).is_empty());
+
+        std::fs::remove_file(nested.join("a.png")).unwrap();
+        assert_eq!(watcher.poll(start + debounce * 3, Duration::ZERO), vec![temp_dir.path().to_path_buf()]);
+
+        watcher.set_roots(Vec::new());
+        std::fs::write(nested.join("b.png"), b"png").unwrap();
+        assert!(watcher.poll(start + debounce * 4, Duration::ZERO).is_empty());
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+use swwws_common::{IpcClient, IpcCommand, IpcEvent, IpcResponse};
+use tempfile::tempdir;
+
+mod common;
+use common::{start_daemon, wait_for};
+
+#[test]
+fn test_a_broken_config_file_is_reported_and_stop_still_works() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let mut daemon = start_daemon(temp_dir.path(), &socket, &temp_dir.path().join("state.json"));
+    if !wait_for(|| socket.exists()) {
+        daemon.kill().unwrap();
+        panic!("the daemon never opened its socket");
+    }
+
+    // The only test in this file, so nothing else reads the variable meanwhile
+    std::env::set_var("SWWWS_SOCKET", &socket);
+    let client = IpcClient::new().with_timeout(Duration::from_secs(5));
+    let (subscribed, mut events) = client.subscribe().unwrap();
+    assert!(matches!(subscribed, IpcResponse::Success { .. }), "{:?}", subscribed);
+    std::fs::write(temp_dir.path().join("config.toml"), "[global\nduration = \n").unwrap();
+
+    let reloaded = client.send_command(IpcCommand::Reload).unwrap();
+    let IpcResponse::Error { message, .. } = reloaded else { panic!("expected the reload to fail, got {:?}", reloaded) };
+    assert_eq!(events.next().unwrap().unwrap(), IpcEvent::Reloaded { success: false, message });
+    let status = client.send_command(IpcCommand::Status).unwrap();
+    assert!(matches!(status, IpcResponse::Status { .. }), "{:?}", status);
+    let stopped = client.send_command(IpcCommand::Shutdown).unwrap();
+    assert!(matches!(stopped, IpcResponse::Success { .. }), "{:?}", stopped);
+    assert!(daemon.wait().unwrap().success());
+    assert!(!socket.exists());
+}
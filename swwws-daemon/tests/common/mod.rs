@@ -0,0 +1,45 @@
+// Each test file uses its own share of these
+#![allow(dead_code)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+/// Wait up to five seconds for `done`
+pub fn wait_for(mut done: impl FnMut() -> bool) -> bool {
+    let started = Instant::now();
+    while !done() {
+        if started.elapsed() > Duration::from_secs(5) {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    true
+}
+
+/// The daemon on a stand-in swww that reports one output and accepts every image,
+/// with a few images that pass as PNG files
+pub fn daemon_command(dir: &Path, socket: &Path, state_file: &Path) -> Command {
+    let swww = dir.join("swww");
+    std::fs::write(&swww, "#!/bin/sh\n[ \"$1\" = query ] && echo 'DP-1: 1920x1080, scale: 1, currently displaying: color: 000000'\nexit 0\n").unwrap();
+    std::fs::set_permissions(&swww, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let walls = dir.join("walls");
+    std::fs::create_dir_all(&walls).unwrap();
+    for name in ["a.png", "b.png", "c.png"] {
+        std::fs::write(walls.join(name), [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]).unwrap();
+    }
+    let config = dir.join("config.toml");
+    std::fs::write(&config, format!(
+        "[global]\nswww_binary = {:?}\nstate_file = {:?}\n\n[any]\npath = {:?}\n",
+        swww, state_file, walls,
+    )).unwrap();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_swwws-daemon"));
+    command.arg("--config").arg(&config).env("SWWWS_SOCKET", socket);
+    command
+}
+
+pub fn start_daemon(dir: &Path, socket: &Path, state_file: &Path) -> Child {
+    daemon_command(dir, socket, state_file).spawn().unwrap()
+}
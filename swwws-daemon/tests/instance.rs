@@ -0,0 +1,57 @@
+use std::process::Stdio;
+use tempfile::tempdir;
+
+mod common;
+use common::{daemon_command, start_daemon, wait_for};
+
+#[test]
+fn test_a_second_daemon_refuses_to_start_and_replace_takes_over() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let state_file = temp_dir.path().join("state.json");
+    let mut first = start_daemon(temp_dir.path(), &socket, &state_file);
+    if !wait_for(|| socket.exists()) {
+        first.kill().unwrap();
+        panic!("the daemon never opened its socket");
+    }
+
+    let second = daemon_command(temp_dir.path(), &socket, &state_file).stderr(Stdio::piped()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&second.stderr);
+    assert!(!second.status.success());
+    assert!(stderr.contains(&format!("already running (pid {})", first.id())), "{}", stderr);
+    assert!(first.try_wait().unwrap().is_none(), "the first daemon stopped");
+    assert!(socket.exists());
+
+    let mut replacement = daemon_command(temp_dir.path(), &socket, &state_file).arg("--replace").spawn().unwrap();
+    assert!(first.wait().unwrap().success());
+    assert!(wait_for(|| socket.exists()), "the replacement never opened its socket");
+    assert!(replacement.try_wait().unwrap().is_none(), "the replacement stopped");
+
+    unsafe { libc::kill(replacement.id() as libc::pid_t, libc::SIGTERM) };
+    assert!(replacement.wait().unwrap().success());
+}
+
+#[test]
+fn test_replace_stops_a_daemon_whose_config_no_longer_parses() {
+    let temp_dir = tempdir().unwrap();
+    let fixed = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let state_file = temp_dir.path().join("state.json");
+    let mut first = start_daemon(temp_dir.path(), &socket, &state_file);
+    if !wait_for(|| socket.exists()) {
+        first.kill().unwrap();
+        panic!("the daemon never opened its socket");
+    }
+    std::fs::write(temp_dir.path().join("config.toml"), "[global\nduration = \n").unwrap();
+
+    let mut replacement = daemon_command(fixed.path(), &socket, &state_file).arg("--replace").stderr(Stdio::piped()).spawn().unwrap();
+    assert!(first.wait().unwrap().success());
+    if !wait_for(|| socket.exists()) {
+        replacement.kill().unwrap();
+        let output = replacement.wait_with_output().unwrap();
+        panic!("the replacement never opened its socket: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    unsafe { libc::kill(replacement.id() as libc::pid_t, libc::SIGTERM) };
+    assert!(replacement.wait().unwrap().success());
+}
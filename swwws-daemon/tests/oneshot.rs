@@ -0,0 +1,36 @@
+use std::os::unix::fs::PermissionsExt;
+use tempfile::tempdir;
+
+mod common;
+use common::daemon_command;
+
+#[test]
+fn test_oneshot_sets_wallpapers_saves_and_exits() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let state_file = temp_dir.path().join("state.json");
+
+    let output = daemon_command(temp_dir.path(), &socket, &state_file).arg("--oneshot").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!socket.exists(), "a oneshot run opened the IPC socket");
+    let state: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&state_file).unwrap()).unwrap();
+    let current = state["outputs"]["DP-1"]["current_image"].as_str().unwrap();
+    assert!(current.ends_with(".png"), "{}", current);
+}
+
+#[test]
+fn test_oneshot_fails_when_no_wallpaper_could_be_set() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let state_file = temp_dir.path().join("state.json");
+    let mut command = daemon_command(temp_dir.path(), &socket, &state_file);
+    // swww answers queries but refuses every image
+    let swww = temp_dir.path().join("swww");
+    std::fs::write(&swww, "#!/bin/sh\n[ \"$1\" = query ] && echo 'DP-1: 1920x1080, scale: 1, currently displaying: color: 000000' && exit 0\nexit 1\n").unwrap();
+    std::fs::set_permissions(&swww, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = command.arg("--oneshot").output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("No wallpaper could be set"), "{}", stderr);
+}
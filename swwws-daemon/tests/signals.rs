@@ -0,0 +1,64 @@
+use std::os::unix::net::UnixDatagram;
+use std::time::{Duration, SystemTime};
+use tempfile::tempdir;
+
+mod common;
+use common::{daemon_command, start_daemon, wait_for};
+
+#[test]
+fn test_sigterm_saves_state_and_removes_the_socket() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let state_file = temp_dir.path().join("state.json");
+    let mut daemon = start_daemon(temp_dir.path(), &socket, &state_file);
+    if !wait_for(|| socket.exists()) {
+        daemon.kill().unwrap();
+        panic!("the daemon never opened its socket");
+    }
+
+    // File times are coarser than the clock, so compare with the file itself
+    let modified = || std::fs::metadata(&state_file).and_then(|metadata| metadata.modified()).ok();
+    let before = modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    std::thread::sleep(Duration::from_millis(50));
+    unsafe { libc::kill(daemon.id() as libc::pid_t, libc::SIGTERM) };
+    let status = daemon.wait().unwrap();
+
+    assert!(status.success(), "{:?}", status);
+    assert!(!socket.exists());
+    let written = modified().expect("no state file after SIGTERM");
+    assert!(written > before, "state file was not written again on SIGTERM");
+}
+
+#[test]
+fn test_systemd_hears_ready_reloading_and_stopping() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let systemd = UnixDatagram::bind(temp_dir.path().join("notify")).unwrap();
+    systemd.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut daemon = daemon_command(temp_dir.path(), &socket, &temp_dir.path().join("state.json"))
+        .env("NOTIFY_SOCKET", temp_dir.path().join("notify"))
+        .spawn()
+        .unwrap();
+
+    let mut buffer = [0; 256];
+    let mut received = || match systemd.recv(&mut buffer) {
+        Ok(length) => String::from_utf8_lossy(&buffer[..length]).to_string(),
+        Err(e) => format!("nothing: {}", e),
+    };
+    let ready = received();
+    assert_eq!(ready, "READY=1\nSTATUS=Showing wallpapers on 1 output, 1 queue");
+
+    unsafe { libc::kill(daemon.id() as libc::pid_t, libc::SIGHUP) };
+    assert!(received().starts_with("RELOADING=1\n"));
+    assert!(received().starts_with("READY=1\n"));
+
+    // A reload that fails is over too, and the daemon carries on as it was
+    std::fs::write(temp_dir.path().join("config.toml"), "[global\nduration = \n").unwrap();
+    unsafe { libc::kill(daemon.id() as libc::pid_t, libc::SIGHUP) };
+    assert!(received().starts_with("RELOADING=1\n"));
+    assert_eq!(received(), "READY=1\nSTATUS=Showing wallpapers on 1 output, 1 queue");
+
+    unsafe { libc::kill(daemon.id() as libc::pid_t, libc::SIGTERM) };
+    assert_eq!(received(), "STOPPING=1");
+    assert!(daemon.wait().unwrap().success());
+}
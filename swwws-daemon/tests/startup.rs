@@ -0,0 +1,61 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tempfile::tempdir;
+
+mod common;
+use common::{daemon_command, wait_for};
+
+/// Replace the stand-in swww with one that fails until `ready` exists
+fn swww_ready_after(dir: &Path, ready: &Path) {
+    let swww = dir.join("swww");
+    std::fs::write(&swww, format!(
+        "#!/bin/sh\n[ -e {:?} ] || exit 1\n[ \"$1\" = query ] && echo 'DP-1: 1920x1080, scale: 1, currently displaying: color: 000000'\nexit 0\n",
+        ready,
+    )).unwrap();
+    std::fs::set_permissions(&swww, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn set_startup_timeout(dir: &Path, timeout: &str) {
+    let config = dir.join("config.toml");
+    let toml = std::fs::read_to_string(&config).unwrap();
+    std::fs::write(&config, toml.replace("[global]\n", &format!("[global]\nstartup_timeout = {:?}\n", timeout))).unwrap();
+}
+
+#[test]
+fn test_daemon_waits_for_swww_to_come_up() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let ready = temp_dir.path().join("ready");
+    let mut command = daemon_command(temp_dir.path(), &socket, &temp_dir.path().join("state.json"));
+    swww_ready_after(temp_dir.path(), &ready);
+
+    let mut daemon = command.spawn().unwrap();
+    std::thread::sleep(Duration::from_secs(1));
+    assert!(daemon.try_wait().unwrap().is_none(), "the daemon gave up on swww");
+    assert!(!socket.exists());
+
+    std::fs::write(&ready, b"").unwrap();
+    if !wait_for(|| socket.exists()) {
+        daemon.kill().unwrap();
+        panic!("the daemon never started once swww answered");
+    }
+    unsafe { libc::kill(daemon.id() as libc::pid_t, libc::SIGTERM) };
+    assert!(daemon.wait().unwrap().success());
+}
+
+#[test]
+fn test_daemon_gives_up_after_the_startup_timeout() {
+    let temp_dir = tempdir().unwrap();
+    let socket = temp_dir.path().join("swwws.sock");
+    let mut command = daemon_command(temp_dir.path(), &socket, &temp_dir.path().join("state.json"));
+    swww_ready_after(temp_dir.path(), &temp_dir.path().join("never"));
+    set_startup_timeout(temp_dir.path(), "1s");
+
+    let output = command.env("RUST_LOG", "info").stderr(Stdio::piped()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("swww not ready after 1s"), "{}", stderr);
+    assert!(stderr.contains("Waiting for swww-daemon"), "{}", stderr);
+}